@@ -14,3 +14,14 @@ pub enum Error {
     #[error("json error: {0}")]
     Json(#[from] serde_json::Error),
 }
+
+impl Error {
+    /// The machine-readable `code` of an API error response, if any.
+    pub fn code(&self) -> Option<String> {
+        let Error::Status(_, body) = self else {
+            return None;
+        };
+        let body: serde_json::Value = serde_json::from_str(body).ok()?;
+        body.get("code")?.as_str().map(String::from)
+    }
+}
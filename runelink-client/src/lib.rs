@@ -1,5 +1,6 @@
 pub mod error;
 pub mod requests;
+pub mod resolver;
 pub mod util;
 
 pub use error::*;
@@ -0,0 +1,40 @@
+use log::info;
+use reqwest::{Client, Response};
+use runelink_types::{ARCHIVE_CONTENT_TYPE, ArchiveImport};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{fetch_response_authed, post_bytes_authed};
+
+/// Start exporting a server. The archive is the body of the returned
+/// response, to be read chunk by chunk.
+pub async fn export(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+) -> Result<Response> {
+    let url = format!("{api_url}/servers/{server_id}/export");
+    info!("exporting server: {url}");
+    fetch_response_authed(client, &url, access_token).await
+}
+
+/// Import a server archive as a new server (host admin only).
+pub async fn import(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    archive: Vec<u8>,
+) -> Result<ArchiveImport> {
+    let url = format!("{api_url}/admin/servers/import");
+    info!("importing server: {url}");
+    post_bytes_authed::<ArchiveImport>(
+        client,
+        &url,
+        access_token,
+        ARCHIVE_CONTENT_TYPE,
+        archive,
+    )
+    .await
+}
@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use log::info;
+use reqwest::{
+    Client,
+    multipart::{Form, Part},
+};
+use runelink_types::{Attachment, AttachmentUpload};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    fetch_bytes_authed, fetch_bytes_federated, fetch_json_authed,
+    fetch_json_federated, post_multipart_authed, post_multipart_federated,
+};
+
+/// How long uploading or downloading an attachment may take, so that large
+/// files still get through over slow links.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The multipart form for an upload, with the file in its `file` field.
+fn upload_form(upload: &AttachmentUpload) -> Result<Form> {
+    let part = Part::bytes(upload.data.clone())
+        .file_name(upload.name.clone())
+        .mime_str(&upload.mime_type)?;
+    Ok(Form::new().part("file", part))
+}
+
+/// Upload a file to a channel. Send it by listing the returned attachment's
+/// ID in `NewMessage::attachment_ids`.
+pub async fn upload(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    upload: &AttachmentUpload,
+    target_host: Option<&str>,
+) -> Result<Attachment> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/attachments"
+    );
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("uploading attachment: {url}");
+    post_multipart_authed::<Attachment>(
+        client,
+        &url,
+        access_token,
+        upload_form(upload)?,
+        TRANSFER_TIMEOUT,
+    )
+    .await
+}
+
+pub async fn fetch_by_id(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    attachment_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Attachment> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}"
+    );
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching attachment: {url}");
+    fetch_json_authed::<Attachment>(client, &url, access_token).await
+}
+
+pub async fn fetch_content(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    attachment_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<u8>> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content"
+    );
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching attachment content: {url}");
+    fetch_bytes_authed(client, &url, access_token, TRANSFER_TIMEOUT).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// POST /federation/v1/servers/{server_id}/channels/{channel_id}/attachments
+    pub async fn upload(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+        upload: &AttachmentUpload,
+    ) -> Result<Attachment> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/attachments"
+        );
+        info!("uploading attachment (federation): {url}");
+        post_multipart_federated::<Attachment>(
+            client,
+            &url,
+            token,
+            upload_form(upload)?,
+            TRANSFER_TIMEOUT,
+        )
+        .await
+    }
+
+    /// GET /federation/v1/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}
+    pub async fn fetch_by_id(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<Attachment> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}"
+        );
+        info!("fetching attachment (federation): {url}");
+        fetch_json_federated::<Attachment>(client, &url, token).await
+    }
+
+    /// GET /federation/v1/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content
+    pub async fn fetch_content(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+        attachment_id: Uuid,
+    ) -> Result<Vec<u8>> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content"
+        );
+        info!("fetching attachment content (federation): {url}");
+        fetch_bytes_federated(client, &url, token, TRANSFER_TIMEOUT).await
+    }
+}
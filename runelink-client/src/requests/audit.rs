@@ -0,0 +1,94 @@
+use log::info;
+use reqwest::{Client, Url};
+use runelink_types::{AuditEntry, AuditLogQuery};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{fetch_json_authed, fetch_json_federated};
+
+/// Append audit log filters (and any extra parameters) to a URL.
+fn with_query(
+    url: &str,
+    query: &AuditLogQuery,
+    extra: &[(&str, String)],
+) -> String {
+    let mut params: Vec<(&str, String)> = extra.to_vec();
+    if let Some(action) = &query.action {
+        params.push(("action", action.clone()));
+    }
+    if let Some(actor_host) = &query.actor_host {
+        params.push(("actor_host", actor_host.clone()));
+    }
+    if let Some(before) = query.before {
+        params.push(("before", before.to_string()));
+    }
+    if let Some(limit) = query.limit {
+        params.push(("limit", limit.to_string()));
+    }
+    match Url::parse_with_params(url, &params) {
+        Ok(url) => url.to_string(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// GET /servers/{server_id}/audit-log
+pub async fn fetch_by_server(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    query: &AuditLogQuery,
+    target_host: Option<&str>,
+) -> Result<Vec<AuditEntry>> {
+    let extra: Vec<(&str, String)> = target_host
+        .map(|host| ("target_host", host.to_string()))
+        .into_iter()
+        .collect();
+    let url = with_query(
+        &format!("{api_url}/servers/{server_id}/audit-log"),
+        query,
+        &extra,
+    );
+    info!("fetching server audit log: {url}");
+    fetch_json_authed::<Vec<AuditEntry>>(client, &url, access_token).await
+}
+
+/// GET /admin/audit-log
+pub async fn fetch_all(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Option<Uuid>,
+    query: &AuditLogQuery,
+) -> Result<Vec<AuditEntry>> {
+    let extra: Vec<(&str, String)> = server_id
+        .map(|id| ("server_id", id.to_string()))
+        .into_iter()
+        .collect();
+    let url = with_query(&format!("{api_url}/admin/audit-log"), query, &extra);
+    info!("fetching host audit log: {url}");
+    fetch_json_authed::<Vec<AuditEntry>>(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// GET /federation/v1/servers/{server_id}/audit-log
+    pub async fn fetch_by_server(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        query: &AuditLogQuery,
+    ) -> Result<Vec<AuditEntry>> {
+        let url = with_query(
+            &format!("{federation_url}/servers/{server_id}/audit-log"),
+            query,
+            &[],
+        );
+        info!("fetching server audit log (federation): {url}");
+        fetch_json_federated::<Vec<AuditEntry>>(client, &url, token).await
+    }
+}
@@ -1,11 +1,16 @@
 use log::info;
 use reqwest::Client;
-use runelink_types::{SignupRequest, TokenResponse, User};
+use runelink_types::{
+    ChangePasswordRequest, IssuedPasswordReset, NewPasswordReset,
+    PasswordResetRequest, RecoveryCodes, SignupRequest, TokenResponse,
+    TotpConfirmRequest, TotpDisableRequest, TotpEnrollment, TotpStatus, User,
+    UserRef,
+};
 use std::collections::HashMap;
 
 use crate::error::{Error, Result};
 
-use super::post_json;
+use super::{fetch_json_authed, post_json, post_json_authed};
 
 /// Create a new user account.
 ///
@@ -28,6 +33,7 @@ pub async fn token_password(
     api_url: &str,
     username: &str,
     password: &str,
+    otp: Option<&str>,
     scope: Option<&str>,
     client_id: Option<&str>,
 ) -> Result<TokenResponse> {
@@ -38,6 +44,9 @@ pub async fn token_password(
     form.insert("grant_type", "password");
     form.insert("username", username);
     form.insert("password", password);
+    if let Some(otp) = otp {
+        form.insert("otp", otp);
+    }
     if let Some(scope) = scope {
         form.insert("scope", scope);
     }
@@ -91,3 +100,146 @@ pub async fn token_refresh(
     let data = response.json::<TokenResponse>().await?;
     Ok(data)
 }
+
+/// Change the password of the authenticated account. All of its refresh
+/// tokens except `req.keep_refresh_token` are revoked.
+///
+/// POST /auth/password
+pub async fn change_password(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    req: &ChangePasswordRequest,
+) -> Result<()> {
+    let url = format!("{api_url}/auth/password");
+    info!("changing password: {url}");
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .json(req)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(())
+}
+
+/// Set a new password using a one-time reset token from a host admin.
+///
+/// POST /auth/password/reset
+pub async fn reset_password(
+    client: &Client,
+    api_url: &str,
+    req: &PasswordResetRequest,
+) -> Result<UserRef> {
+    let url = format!("{api_url}/auth/password/reset");
+    info!("resetting password: {url}");
+    post_json::<PasswordResetRequest, UserRef>(client, &url, req).await
+}
+
+/// Mint a one-time password reset token for a local account (host admin
+/// only).
+///
+/// POST /admin/users/{name}/password-reset
+pub async fn create_password_reset(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    name: &str,
+    new_reset: &NewPasswordReset,
+) -> Result<IssuedPasswordReset> {
+    let url = format!("{api_url}/admin/users/{name}/password-reset");
+    info!("creating password reset: {url}");
+    post_json_authed::<_, IssuedPasswordReset>(
+        client,
+        &url,
+        access_token,
+        new_reset,
+    )
+    .await
+}
+
+/// Whether the authenticated account has 2FA enabled.
+///
+/// GET /auth/totp
+pub async fn totp_status(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<TotpStatus> {
+    let url = format!("{api_url}/auth/totp");
+    info!("fetching 2FA status: {url}");
+    fetch_json_authed::<TotpStatus>(client, &url, access_token).await
+}
+
+/// Start TOTP enrollment for the authenticated account.
+///
+/// POST /auth/totp/enroll
+pub async fn totp_enroll(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<TotpEnrollment> {
+    let url = format!("{api_url}/auth/totp/enroll");
+    info!("enrolling in 2FA: {url}");
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<TotpEnrollment>().await?;
+    Ok(data)
+}
+
+/// Confirm TOTP enrollment with a first code, returning recovery codes.
+///
+/// POST /auth/totp/confirm
+pub async fn totp_confirm(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    req: &TotpConfirmRequest,
+) -> Result<RecoveryCodes> {
+    let url = format!("{api_url}/auth/totp/confirm");
+    info!("confirming 2FA: {url}");
+    post_json_authed::<_, RecoveryCodes>(client, &url, access_token, req).await
+}
+
+/// Turn 2FA off for the authenticated account.
+///
+/// POST /auth/totp/disable
+pub async fn totp_disable(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    req: &TotpDisableRequest,
+) -> Result<()> {
+    let url = format!("{api_url}/auth/totp/disable");
+    info!("disabling 2FA: {url}");
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .json(req)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(())
+}
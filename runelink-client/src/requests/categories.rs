@@ -0,0 +1,162 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{
+    ChannelCategory, ChannelCategoryUpdate, NewChannelCategory,
+};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    delete_authed, delete_federated, fetch_json_authed, fetch_json_federated,
+    patch_json_authed, patch_json_federated, post_json_authed,
+    post_json_federated,
+};
+
+pub async fn create(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    new_category: &NewChannelCategory,
+    target_host: Option<&str>,
+) -> Result<ChannelCategory> {
+    let mut url = format!("{api_url}/servers/{server_id}/categories");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("creating category: {url}");
+    post_json_authed::<NewChannelCategory, ChannelCategory>(
+        client,
+        &url,
+        access_token,
+        new_category,
+    )
+    .await
+}
+
+pub async fn fetch_by_server(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<ChannelCategory>> {
+    let mut url = format!("{api_url}/servers/{server_id}/categories");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching categories by server: {url}");
+    fetch_json_authed::<Vec<ChannelCategory>>(client, &url, access_token).await
+}
+
+pub async fn update(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    category_id: Uuid,
+    update: &ChannelCategoryUpdate,
+    target_host: Option<&str>,
+) -> Result<ChannelCategory> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/categories/{category_id}");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("updating category: {url}");
+    patch_json_authed::<ChannelCategoryUpdate, ChannelCategory>(
+        client,
+        &url,
+        access_token,
+        update,
+    )
+    .await
+}
+
+pub async fn delete(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    category_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<()> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/categories/{category_id}");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("deleting category: {url}");
+    delete_authed(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// POST /federation/v1/servers/{server_id}/categories
+    pub async fn create(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        new_category: &NewChannelCategory,
+    ) -> Result<ChannelCategory> {
+        let url = format!("{federation_url}/servers/{server_id}/categories");
+        info!("creating category (federation): {url}");
+        post_json_federated::<NewChannelCategory, ChannelCategory>(
+            client,
+            &url,
+            token,
+            new_category,
+        )
+        .await
+    }
+
+    /// GET /federation/v1/servers/{server_id}/categories
+    pub async fn fetch_by_server(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+    ) -> Result<Vec<ChannelCategory>> {
+        let url = format!("{federation_url}/servers/{server_id}/categories");
+        info!("fetching categories by server (federation): {url}");
+        fetch_json_federated::<Vec<ChannelCategory>>(client, &url, token).await
+    }
+
+    /// PATCH /federation/v1/servers/{server_id}/categories/{category_id}
+    pub async fn update(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        category_id: Uuid,
+        update: &ChannelCategoryUpdate,
+    ) -> Result<ChannelCategory> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/categories/{category_id}"
+        );
+        info!("updating category (federation): {url}");
+        patch_json_federated::<ChannelCategoryUpdate, ChannelCategory>(
+            client, &url, token, update,
+        )
+        .await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}/categories/{category_id}
+    pub async fn delete(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        category_id: Uuid,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/categories/{category_id}"
+        );
+        info!("deleting category (federation): {url}");
+        delete_federated(client, &url, token).await
+    }
+}
@@ -1,12 +1,14 @@
 use log::info;
 use reqwest::Client;
-use runelink_types::{Channel, NewChannel};
+use runelink_types::{Channel, ChannelUpdate, NewChannel};
 use uuid::Uuid;
 
 use crate::error::Result;
 
 use super::{
-    delete_authed, delete_federated, fetch_json_authed, fetch_json_federated,
+    delete_authed, delete_federated, delete_json_authed, delete_json_federated,
+    fetch_json_authed, fetch_json_federated, patch_json_authed,
+    patch_json_federated, post_for_json_authed, post_for_json_federated,
     post_json_authed, post_json_federated,
 };
 
@@ -51,11 +53,14 @@ pub async fn fetch_by_server(
     api_url: &str,
     access_token: &str,
     server_id: Uuid,
+    include_archived: bool,
     target_host: Option<&str>,
 ) -> Result<Vec<Channel>> {
-    let mut url = format!("{api_url}/servers/{server_id}/channels");
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels?include_archived={include_archived}"
+    );
     if let Some(host) = target_host {
-        url = format!("{url}?target_host={host}");
+        url = format!("{url}&target_host={host}");
     }
     info!("fetching channels by server: {url}");
     fetch_json_authed::<Vec<Channel>>(client, &url, access_token).await
@@ -78,6 +83,30 @@ pub async fn fetch_by_id(
     fetch_json_authed::<Channel>(client, &url, access_token).await
 }
 
+pub async fn update(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    update: &ChannelUpdate,
+    target_host: Option<&str>,
+) -> Result<Channel> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/channels/{channel_id}");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("updating channel: {url}");
+    patch_json_authed::<ChannelUpdate, Channel>(
+        client,
+        &url,
+        access_token,
+        update,
+    )
+    .await
+}
+
 pub async fn delete(
     client: &Client,
     api_url: &str,
@@ -95,19 +124,70 @@ pub async fn delete(
     delete_authed(client, &url, access_token).await
 }
 
+pub async fn archive(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Channel> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/channels/{channel_id}/archive");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("archiving channel: {url}");
+    post_for_json_authed::<Channel>(client, &url, access_token).await
+}
+
+pub async fn unarchive(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Channel> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/channels/{channel_id}/archive");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("unarchiving channel: {url}");
+    delete_json_authed::<Channel>(client, &url, access_token).await
+}
+
+pub async fn restore(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Channel> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/channels/{channel_id}/restore");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("restoring channel: {url}");
+    post_for_json_authed::<Channel>(client, &url, access_token).await
+}
+
 /// Federation endpoints (server-to-server authentication required).
 pub mod federated {
     use super::*;
 
-    /// POST /federation/servers/{server_id}/channels
+    /// POST /federation/v1/servers/{server_id}/channels
     pub async fn create(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         new_channel: &NewChannel,
     ) -> Result<Channel> {
-        let url = format!("{api_url}/federation/servers/{server_id}/channels");
+        let url = format!("{federation_url}/servers/{server_id}/channels");
         info!("creating channel (federation): {url}");
         post_json_federated::<NewChannel, Channel>(
             client,
@@ -118,56 +198,123 @@ pub mod federated {
         .await
     }
 
-    /// GET /federation/channels
+    /// GET /federation/v1/channels
     pub async fn fetch_all(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
     ) -> Result<Vec<Channel>> {
-        let url = format!("{api_url}/federation/channels");
+        let url = format!("{federation_url}/channels");
         info!("fetching all channels (federation): {url}");
         fetch_json_federated::<Vec<Channel>>(client, &url, token).await
     }
 
-    /// GET /federation/servers/{server_id}/channels
+    /// GET /federation/v1/servers/{server_id}/channels
     pub async fn fetch_by_server(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
+        include_archived: bool,
     ) -> Result<Vec<Channel>> {
-        let url = format!("{api_url}/federation/servers/{server_id}/channels");
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels?include_archived={include_archived}"
+        );
         info!("fetching channels by server (federation): {url}");
         fetch_json_federated::<Vec<Channel>>(client, &url, token).await
     }
 
-    /// GET /federation/servers/{server_id}/channels/{channel_id}
+    /// GET /federation/v1/servers/{server_id}/channels/{channel_id}
     pub async fn fetch_by_id(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         channel_id: Uuid,
     ) -> Result<Channel> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/channels/{channel_id}"
+            "{federation_url}/servers/{server_id}/channels/{channel_id}"
         );
         info!("fetching channel (federation): {url}");
         fetch_json_federated::<Channel>(client, &url, token).await
     }
 
-    /// DELETE /federation/servers/{server_id}/channels/{channel_id}
+    /// PATCH /federation/v1/servers/{server_id}/channels/{channel_id}
+    pub async fn update(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+        update: &ChannelUpdate,
+    ) -> Result<Channel> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}"
+        );
+        info!("updating channel (federation): {url}");
+        patch_json_federated::<ChannelUpdate, Channel>(
+            client, &url, token, update,
+        )
+        .await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}/channels/{channel_id}
     pub async fn delete(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         channel_id: Uuid,
     ) -> Result<()> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/channels/{channel_id}"
+            "{federation_url}/servers/{server_id}/channels/{channel_id}"
         );
         info!("deleting channel (federation): {url}");
         delete_federated(client, &url, token).await
     }
+
+    /// POST /federation/v1/servers/{server_id}/channels/{channel_id}/archive
+    pub async fn archive(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Channel> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/archive"
+        );
+        info!("archiving channel (federation): {url}");
+        post_for_json_federated::<Channel>(client, &url, token).await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}/channels/{channel_id}/archive
+    pub async fn unarchive(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Channel> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/archive"
+        );
+        info!("unarchiving channel (federation): {url}");
+        delete_json_federated::<Channel>(client, &url, token).await
+    }
+
+    /// POST /federation/v1/servers/{server_id}/channels/{channel_id}/restore
+    pub async fn restore(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Channel> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/restore"
+        );
+        info!("restoring channel (federation): {url}");
+        post_for_json_federated::<Channel>(client, &url, token).await
+    }
 }
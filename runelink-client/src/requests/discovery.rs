@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use log::info;
+use reqwest::Client;
+use runelink_types::{HostDiscovery, WELL_KNOWN_RUNELINK_PATH};
+
+use crate::error::{Error, Result};
+
+/// How long to wait for a host's discovery document before falling back.
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fetch a host's discovery document from an origin URL.
+///
+/// GET /.well-known/runelink
+pub async fn fetch(client: &Client, origin: &str) -> Result<HostDiscovery> {
+    let url = format!("{origin}{WELL_KNOWN_RUNELINK_PATH}");
+    info!("fetching host discovery: {url}");
+    let response = client.get(&url).timeout(DISCOVERY_TIMEOUT).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let discovery = response.json::<HostDiscovery>().await?;
+    Ok(discovery)
+}
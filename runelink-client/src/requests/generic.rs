@@ -1,5 +1,7 @@
+use std::time::Duration;
+
 use log::debug;
-use reqwest::Client;
+use reqwest::{Body, Client, Response, multipart::Form};
 use serde::{Serialize, de::DeserializeOwned};
 
 use crate::error::{Error, Result};
@@ -199,6 +201,106 @@ where
     Ok(data)
 }
 
+/// Helper to post without a body (or response body) with client access
+/// token.
+pub async fn post_authed(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+) -> Result<()> {
+    debug!("posting (authenticated): {url}");
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(())
+}
+
+/// Helper to post without a body (or response body) with federation auth
+/// token.
+pub async fn post_federated(
+    client: &Client,
+    url: &str,
+    token: &str,
+) -> Result<()> {
+    debug!("posting (federation): {url}");
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(())
+}
+
+/// Helper to post without a body with client access token, reading a JSON
+/// response.
+pub async fn post_for_json_authed<T>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    debug!("posting for json (authenticated): {url}");
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<T>().await?;
+    Ok(data)
+}
+
+/// Helper to post without a body with federation auth token, reading a
+/// JSON response.
+pub async fn post_for_json_federated<T>(
+    client: &Client,
+    url: &str,
+    token: &str,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    debug!("posting for json (federation): {url}");
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<T>().await?;
+    Ok(data)
+}
+
 /// Helper to delete with client access token.
 pub async fn delete_authed(
     client: &Client,
@@ -242,3 +344,426 @@ pub async fn delete_federated(
     }
     Ok(())
 }
+
+/// Helper to delete with client access token, reading a JSON response.
+pub async fn delete_json_authed<T>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    debug!("deleting json (authenticated): {url}");
+    let response = client
+        .delete(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<T>().await?;
+    Ok(data)
+}
+
+/// Helper to delete with federation auth token, reading a JSON response.
+pub async fn delete_json_federated<T>(
+    client: &Client,
+    url: &str,
+    token: &str,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    debug!("deleting json (federation): {url}");
+    let response = client
+        .delete(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<T>().await?;
+    Ok(data)
+}
+
+/// Helper to patch JSON with client access token.
+pub async fn patch_json_authed<I, O>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+    request_body: &I,
+) -> Result<O>
+where
+    I: Serialize,
+    O: DeserializeOwned,
+{
+    debug!(
+        "patching json (authenticated): {url}\n{}",
+        serde_json::to_string_pretty(request_body).unwrap()
+    );
+    let response = client
+        .patch(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .json(request_body)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<O>().await?;
+    Ok(data)
+}
+
+/// Helper to patch JSON with federation auth token.
+pub async fn patch_json_federated<I, O>(
+    client: &Client,
+    url: &str,
+    token: &str,
+    request_body: &I,
+) -> Result<O>
+where
+    I: Serialize,
+    O: DeserializeOwned,
+{
+    debug!(
+        "patching json (federation): {url}\n{}",
+        serde_json::to_string_pretty(request_body).unwrap()
+    );
+    let response = client
+        .patch(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .json(request_body)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<O>().await?;
+    Ok(data)
+}
+
+/// Helper to put JSON with client access token.
+pub async fn put_json_authed<I, O>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+    request_body: &I,
+) -> Result<O>
+where
+    I: Serialize,
+    O: DeserializeOwned,
+{
+    debug!(
+        "putting json (authenticated): {url}\n{}",
+        serde_json::to_string_pretty(request_body).unwrap()
+    );
+    let response = client
+        .put(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .json(request_body)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<O>().await?;
+    Ok(data)
+}
+
+/// Helper to put JSON with federation auth token, expecting no response
+/// body.
+pub async fn put_json_federated<I>(
+    client: &Client,
+    url: &str,
+    token: &str,
+    request_body: &I,
+) -> Result<()>
+where
+    I: Serialize,
+{
+    debug!(
+        "putting json (federation): {url}\n{}",
+        serde_json::to_string_pretty(request_body).unwrap()
+    );
+    let response = client
+        .put(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .json(request_body)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(())
+}
+
+/// Helper to put without a body (or response body) with client access
+/// token.
+pub async fn put_authed(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+) -> Result<()> {
+    debug!("putting (authenticated): {url}");
+    let response = client
+        .put(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(())
+}
+
+/// Helper to put without a body (or response body) with federation auth
+/// token.
+pub async fn put_federated(
+    client: &Client,
+    url: &str,
+    token: &str,
+) -> Result<()> {
+    debug!("putting (federation): {url}");
+    let response = client
+        .put(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(())
+}
+
+/// Helper to fetch raw bytes with client access token.
+pub async fn fetch_bytes_authed(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+    timeout: Duration,
+) -> Result<Vec<u8>> {
+    debug!("fetching bytes (authenticated): {url}");
+    let response = client
+        .get(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .timeout(timeout)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.bytes().await?;
+    Ok(data.to_vec())
+}
+
+/// Helper to fetch raw bytes with federation auth token.
+pub async fn fetch_bytes_federated(
+    client: &Client,
+    url: &str,
+    token: &str,
+    timeout: Duration,
+) -> Result<Vec<u8>> {
+    debug!("fetching bytes (federation): {url}");
+    let response = client
+        .get(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .timeout(timeout)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.bytes().await?;
+    Ok(data.to_vec())
+}
+
+/// Helper to start a download with client access token. The response
+/// body is left unread, so it can be consumed chunk by chunk.
+pub async fn fetch_response_authed(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+) -> Result<Response> {
+    debug!("fetching response (authenticated): {url}");
+    let response = client
+        .get(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    Ok(response)
+}
+
+/// Helper to post a multipart form with client access token.
+pub async fn post_multipart_authed<O>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+    form: Form,
+    timeout: Duration,
+) -> Result<O>
+where
+    O: DeserializeOwned,
+{
+    debug!("posting multipart (authenticated): {url}");
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .multipart(form)
+        .timeout(timeout)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<O>().await?;
+    Ok(data)
+}
+
+/// Helper to post a multipart form with federation auth token.
+pub async fn post_multipart_federated<O>(
+    client: &Client,
+    url: &str,
+    token: &str,
+    form: Form,
+    timeout: Duration,
+) -> Result<O>
+where
+    O: DeserializeOwned,
+{
+    debug!("posting multipart (federation): {url}");
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .multipart(form)
+        .timeout(timeout)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<O>().await?;
+    Ok(data)
+}
+
+/// Helper to post raw bytes with client access token.
+pub async fn post_bytes_authed<O>(
+    client: &Client,
+    url: &str,
+    access_token: &str,
+    content_type: &str,
+    body: Vec<u8>,
+) -> Result<O>
+where
+    O: DeserializeOwned,
+{
+    debug!("posting {} bytes (authenticated): {url}", body.len());
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .header("Content-Type", content_type)
+        .body(body)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<O>().await?;
+    Ok(data)
+}
+
+/// Helper to post a (possibly streamed) body with federation auth token.
+///
+/// `timeout` replaces the client's own, since large bodies can take longer
+/// to send than an ordinary request.
+pub async fn post_body_federated<O>(
+    client: &Client,
+    url: &str,
+    token: &str,
+    content_type: &str,
+    body: Body,
+    timeout: Duration,
+) -> Result<O>
+where
+    O: DeserializeOwned,
+{
+    debug!("posting body (federation): {url}");
+    let response = client
+        .post(url)
+        .header("Authorization", format!("Bearer {token}"))
+        .header("Content-Type", content_type)
+        .body(body)
+        .timeout(timeout)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_else(|e| {
+            format!("Failed to get error message body: {e}")
+        });
+        return Err(Error::Status(status, message));
+    }
+    let data = response.json::<O>().await?;
+    Ok(data)
+}
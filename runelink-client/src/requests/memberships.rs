@@ -1,15 +1,17 @@
 use log::info;
 use reqwest::Client;
 use runelink_types::{
-    FullServerMembership, NewServerMembership, ServerMembership, UserRef,
+    FullServerMembership, NewServerMembership, OwnershipTransfer, ServerMember,
+    ServerMembership, ServerRoleUpdate, UserRef,
 };
 use uuid::Uuid;
 
 use crate::error::Result;
 
 use super::{
-    delete_authed, delete_federated, fetch_json, post_json_authed,
-    post_json_federated,
+    delete_authed, delete_federated, fetch_json, patch_json_authed,
+    patch_json_federated, post_json_authed, post_json_federated,
+    put_json_federated,
 };
 
 pub async fn fetch_by_user(
@@ -99,6 +101,57 @@ pub async fn delete(
     delete_authed(client, &url, access_token).await
 }
 
+/// PATCH /servers/{server_id}/users/{host}/{name}
+pub async fn update_role(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    user: UserRef,
+    update: &ServerRoleUpdate,
+    target_host: Option<&str>,
+) -> Result<ServerMember> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/users/{host}/{name}",
+        host = user.host,
+        name = user.name
+    );
+    if let Some(d) = target_host {
+        url = format!("{url}?target_host={d}");
+    }
+    info!("updating member role: {url}");
+    patch_json_authed::<ServerRoleUpdate, ServerMember>(
+        client,
+        &url,
+        access_token,
+        update,
+    )
+    .await
+}
+
+/// POST /servers/{server_id}/owner
+pub async fn transfer_ownership(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    transfer: &OwnershipTransfer,
+    target_host: Option<&str>,
+) -> Result<ServerMember> {
+    let mut url = format!("{api_url}/servers/{server_id}/owner");
+    if let Some(d) = target_host {
+        url = format!("{url}?target_host={d}");
+    }
+    info!("transferring server ownership: {url}");
+    post_json_authed::<OwnershipTransfer, ServerMember>(
+        client,
+        &url,
+        access_token,
+        transfer,
+    )
+    .await
+}
+
 /// Federation endpoints (server-to-server authentication required).
 pub mod federated {
     use super::*;
@@ -106,12 +159,12 @@ pub mod federated {
     /// Create a remote membership via federation.
     pub async fn create(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         new_membership: &NewServerMembership,
     ) -> Result<FullServerMembership> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/users",
+            "{federation_url}/servers/{server_id}/users",
             server_id = new_membership.server_id
         );
         info!("creating membership (federation): {url}");
@@ -127,17 +180,90 @@ pub mod federated {
     /// Delete a remote server membership via federation.
     pub async fn delete(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         user: UserRef,
     ) -> Result<()> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/users/{host}/{name}",
+            "{federation_url}/servers/{server_id}/users/{host}/{name}",
             host = user.host,
             name = user.name
         );
         info!("deleting server membership (federation): {url}");
         delete_federated(client, &url, token).await
     }
+
+    /// Change a member's role in a remote server via federation.
+    pub async fn update_role(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        user: UserRef,
+        update: &ServerRoleUpdate,
+    ) -> Result<ServerMember> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/users/{host}/{name}",
+            host = user.host,
+            name = user.name
+        );
+        info!("updating member role (federation): {url}");
+        patch_json_federated::<ServerRoleUpdate, ServerMember>(
+            client, &url, token, update,
+        )
+        .await
+    }
+
+    /// Transfer ownership of a remote server via federation.
+    pub async fn transfer_ownership(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        transfer: &OwnershipTransfer,
+    ) -> Result<ServerMember> {
+        let url = format!("{federation_url}/servers/{server_id}/owner");
+        info!("transferring server ownership (federation): {url}");
+        post_json_federated::<OwnershipTransfer, ServerMember>(
+            client, &url, token, transfer,
+        )
+        .await
+    }
+
+    /// Tell a user's home host that their membership in one of our servers
+    /// changed, so it can update its cached copy.
+    pub async fn sync(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        membership: &ServerMembership,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/users/{host}/{name}/servers/{server_id}",
+            host = membership.user_ref.host,
+            name = membership.user_ref.name,
+            server_id = membership.server.id
+        );
+        info!("syncing membership (federation): {url}");
+        put_json_federated(client, &url, token, membership).await
+    }
+
+    /// Tell a user's home host that they were removed from one of our
+    /// servers, so it can drop its cached copy of the membership.
+    pub async fn unsync(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        user: &UserRef,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/users/{host}/{name}/servers/{server_id}",
+            host = user.host,
+            name = user.name
+        );
+        info!("removing synced membership (federation): {url}");
+        delete_federated(client, &url, token).await
+    }
 }
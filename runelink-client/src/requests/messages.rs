@@ -55,11 +55,14 @@ pub async fn fetch_by_server(
     api_url: &str,
     access_token: &str,
     server_id: Uuid,
+    include_deleted: bool,
     target_host: Option<&str>,
 ) -> Result<Vec<Message>> {
-    let mut url = format!("{api_url}/servers/{server_id}/messages");
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/messages?include_deleted={include_deleted}"
+    );
     if let Some(host) = target_host {
-        url = format!("{url}?target_host={host}");
+        url = format!("{url}&target_host={host}");
     }
     info!("fetching messages by server: {url}");
     fetch_json_authed::<Vec<Message>>(client, &url, access_token).await
@@ -71,12 +74,14 @@ pub async fn fetch_by_channel(
     access_token: &str,
     server_id: Uuid,
     channel_id: Uuid,
+    include_deleted: bool,
     target_host: Option<&str>,
 ) -> Result<Vec<Message>> {
-    let mut url =
-        format!("{api_url}/servers/{server_id}/channels/{channel_id}/messages");
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/messages?include_deleted={include_deleted}"
+    );
     if let Some(host) = target_host {
-        url = format!("{url}?target_host={host}");
+        url = format!("{url}&target_host={host}");
     }
     info!("fetching messages by channel: {url}");
     fetch_json_authed::<Vec<Message>>(client, &url, access_token).await
@@ -124,17 +129,17 @@ pub async fn delete(
 pub mod federated {
     use super::*;
 
-    /// POST /federation/servers/{server_id}/channels/{channel_id}/messages
+    /// POST /federation/v1/servers/{server_id}/channels/{channel_id}/messages
     pub async fn create(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         channel_id: Uuid,
         new_message: &NewMessage,
     ) -> Result<Message> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/channels/{channel_id}/messages"
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/messages"
         );
         info!("creating message (federation): {url}");
         post_json_federated::<NewMessage, Message>(
@@ -146,71 +151,75 @@ pub mod federated {
         .await
     }
 
-    /// GET /federation/messages
+    /// GET /federation/v1/messages
     pub async fn fetch_all(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
     ) -> Result<Vec<Message>> {
-        let url = format!("{api_url}/federation/messages");
+        let url = format!("{federation_url}/messages");
         info!("fetching all messages (federation): {url}");
         fetch_json_federated::<Vec<Message>>(client, &url, token).await
     }
 
-    /// GET /federation/servers/{server_id}/messages
+    /// GET /federation/v1/servers/{server_id}/messages
     pub async fn fetch_by_server(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
+        include_deleted: bool,
     ) -> Result<Vec<Message>> {
-        let url = format!("{api_url}/federation/servers/{server_id}/messages");
+        let url = format!(
+            "{federation_url}/servers/{server_id}/messages?include_deleted={include_deleted}"
+        );
         info!("fetching messages by server (federation): {url}");
         fetch_json_federated::<Vec<Message>>(client, &url, token).await
     }
 
-    /// GET /federation/servers/{server_id}/channels/{channel_id}/messages
+    /// GET /federation/v1/servers/{server_id}/channels/{channel_id}/messages
     pub async fn fetch_by_channel(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         channel_id: Uuid,
+        include_deleted: bool,
     ) -> Result<Vec<Message>> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/channels/{channel_id}/messages"
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/messages?include_deleted={include_deleted}"
         );
         info!("fetching messages by channel (federation): {url}");
         fetch_json_federated::<Vec<Message>>(client, &url, token).await
     }
 
-    /// GET /federation/servers/{server_id}/channels/{channel_id}/messages/{message_id}
+    /// GET /federation/v1/servers/{server_id}/channels/{channel_id}/messages/{message_id}
     pub async fn fetch_by_id(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         channel_id: Uuid,
         message_id: Uuid,
     ) -> Result<Message> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/channels/{channel_id}/messages/{message_id}"
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/messages/{message_id}"
         );
         info!("fetching message (federation): {url}");
         fetch_json_federated::<Message>(client, &url, token).await
     }
 
-    /// DELETE /federation/servers/{server_id}/channels/{channel_id}/messages/{message_id}
+    /// DELETE /federation/v1/servers/{server_id}/channels/{channel_id}/messages/{message_id}
     pub async fn delete(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
         channel_id: Uuid,
         message_id: Uuid,
     ) -> Result<()> {
         let url = format!(
-            "{api_url}/federation/servers/{server_id}/channels/{channel_id}/messages/{message_id}"
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/messages/{message_id}"
         );
         info!("deleting message (federation): {url}");
         delete_federated(client, &url, token).await
@@ -2,12 +2,26 @@ use reqwest::Client;
 
 use crate::error::Result;
 
+pub mod archive;
+pub mod attachments;
+pub mod audit;
 pub mod auth;
+pub mod categories;
 pub mod channels;
+pub mod discovery;
 pub mod generic;
 pub mod memberships;
 pub mod messages;
+pub mod moderation;
+pub mod notifications;
+pub mod pins;
+pub mod presence;
+pub mod read_markers;
+pub mod retention;
+pub mod scheduled_messages;
+pub mod server_migrations;
 pub mod servers;
+pub mod signups;
 pub mod users;
 
 pub use generic::*;
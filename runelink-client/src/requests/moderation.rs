@@ -0,0 +1,270 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{
+    DeletedMessages, NewServerBan, NewServerTimeout, ServerBan, ServerTimeout,
+    UserRef,
+};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    delete_authed, delete_federated, delete_json_authed, delete_json_federated,
+    fetch_json_authed, fetch_json_federated, post_json_authed,
+    post_json_federated,
+};
+
+fn with_target_host(url: String, target_host: Option<&str>) -> String {
+    match target_host {
+        Some(host) => format!("{url}?target_host={host}"),
+        None => url,
+    }
+}
+
+/// GET /servers/{server_id}/bans
+pub async fn fetch_bans(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<ServerBan>> {
+    let url = with_target_host(
+        format!("{api_url}/servers/{server_id}/bans"),
+        target_host,
+    );
+    info!("fetching bans: {url}");
+    fetch_json_authed::<Vec<ServerBan>>(client, &url, access_token).await
+}
+
+/// POST /servers/{server_id}/bans
+pub async fn create_ban(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    new_ban: &NewServerBan,
+    target_host: Option<&str>,
+) -> Result<ServerBan> {
+    let url = with_target_host(
+        format!("{api_url}/servers/{server_id}/bans"),
+        target_host,
+    );
+    info!("creating ban: {url}");
+    post_json_authed::<NewServerBan, ServerBan>(
+        client,
+        &url,
+        access_token,
+        new_ban,
+    )
+    .await
+}
+
+/// DELETE /servers/{server_id}/bans/{ban_id}
+pub async fn delete_ban(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    ban_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<()> {
+    let url = with_target_host(
+        format!("{api_url}/servers/{server_id}/bans/{ban_id}"),
+        target_host,
+    );
+    info!("deleting ban: {url}");
+    delete_authed(client, &url, access_token).await
+}
+
+/// GET /servers/{server_id}/timeouts
+pub async fn fetch_timeouts(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<ServerTimeout>> {
+    let url = with_target_host(
+        format!("{api_url}/servers/{server_id}/timeouts"),
+        target_host,
+    );
+    info!("fetching timeouts: {url}");
+    fetch_json_authed::<Vec<ServerTimeout>>(client, &url, access_token).await
+}
+
+/// POST /servers/{server_id}/timeouts
+pub async fn create_timeout(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    new_timeout: &NewServerTimeout,
+    target_host: Option<&str>,
+) -> Result<ServerTimeout> {
+    let url = with_target_host(
+        format!("{api_url}/servers/{server_id}/timeouts"),
+        target_host,
+    );
+    info!("creating timeout: {url}");
+    post_json_authed::<NewServerTimeout, ServerTimeout>(
+        client,
+        &url,
+        access_token,
+        new_timeout,
+    )
+    .await
+}
+
+/// DELETE /servers/{server_id}/timeouts/{host}/{name}
+pub async fn delete_timeout(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    user: UserRef,
+    target_host: Option<&str>,
+) -> Result<()> {
+    let url = with_target_host(
+        format!(
+            "{api_url}/servers/{server_id}/timeouts/{host}/{name}",
+            host = user.host,
+            name = user.name
+        ),
+        target_host,
+    );
+    info!("deleting timeout: {url}");
+    delete_authed(client, &url, access_token).await
+}
+
+/// DELETE /servers/{server_id}/users/{host}/{name}/messages
+pub async fn delete_messages_by_user(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    user: UserRef,
+    target_host: Option<&str>,
+) -> Result<DeletedMessages> {
+    let url = with_target_host(
+        format!(
+            "{api_url}/servers/{server_id}/users/{host}/{name}/messages",
+            host = user.host,
+            name = user.name
+        ),
+        target_host,
+    );
+    info!("deleting messages by user: {url}");
+    delete_json_authed::<DeletedMessages>(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// GET /federation/v1/servers/{server_id}/bans
+    pub async fn fetch_bans(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+    ) -> Result<Vec<ServerBan>> {
+        let url = format!("{federation_url}/servers/{server_id}/bans");
+        info!("fetching bans (federation): {url}");
+        fetch_json_federated::<Vec<ServerBan>>(client, &url, token).await
+    }
+
+    /// POST /federation/v1/servers/{server_id}/bans
+    pub async fn create_ban(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        new_ban: &NewServerBan,
+    ) -> Result<ServerBan> {
+        let url = format!("{federation_url}/servers/{server_id}/bans");
+        info!("creating ban (federation): {url}");
+        post_json_federated::<NewServerBan, ServerBan>(
+            client, &url, token, new_ban,
+        )
+        .await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}/bans/{ban_id}
+    pub async fn delete_ban(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        ban_id: Uuid,
+    ) -> Result<()> {
+        let url = format!("{federation_url}/servers/{server_id}/bans/{ban_id}");
+        info!("deleting ban (federation): {url}");
+        delete_federated(client, &url, token).await
+    }
+
+    /// GET /federation/v1/servers/{server_id}/timeouts
+    pub async fn fetch_timeouts(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+    ) -> Result<Vec<ServerTimeout>> {
+        let url = format!("{federation_url}/servers/{server_id}/timeouts");
+        info!("fetching timeouts (federation): {url}");
+        fetch_json_federated::<Vec<ServerTimeout>>(client, &url, token).await
+    }
+
+    /// POST /federation/v1/servers/{server_id}/timeouts
+    pub async fn create_timeout(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        new_timeout: &NewServerTimeout,
+    ) -> Result<ServerTimeout> {
+        let url = format!("{federation_url}/servers/{server_id}/timeouts");
+        info!("creating timeout (federation): {url}");
+        post_json_federated::<NewServerTimeout, ServerTimeout>(
+            client,
+            &url,
+            token,
+            new_timeout,
+        )
+        .await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}/timeouts/{host}/{name}
+    pub async fn delete_timeout(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        user: UserRef,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/timeouts/{host}/{name}",
+            host = user.host,
+            name = user.name
+        );
+        info!("deleting timeout (federation): {url}");
+        delete_federated(client, &url, token).await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}/users/{host}/{name}/messages
+    pub async fn delete_messages_by_user(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        user: UserRef,
+    ) -> Result<DeletedMessages> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/users/{host}/{name}/messages",
+            host = user.host,
+            name = user.name
+        );
+        info!("deleting messages by user (federation): {url}");
+        delete_json_federated::<DeletedMessages>(client, &url, token).await
+    }
+}
@@ -0,0 +1,83 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{NewNotification, Notification, UserRef};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    delete_federated, fetch_json_authed, post_authed, put_json_federated,
+};
+
+/// Fetch the notifications of the token's user, newest first. Read ones are
+/// only included when `all` is set.
+pub async fn fetch(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    all: bool,
+) -> Result<Vec<Notification>> {
+    let url = format!("{api_url}/notifications?all={all}");
+    info!("fetching notifications: {url}");
+    fetch_json_authed::<Vec<Notification>>(client, &url, access_token).await
+}
+
+pub async fn mark_read(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    notification_id: Uuid,
+) -> Result<()> {
+    let url = format!("{api_url}/notifications/{notification_id}/read");
+    info!("marking notification as read: {url}");
+    post_authed(client, &url, access_token).await
+}
+
+pub async fn mark_all_read(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<()> {
+    let url = format!("{api_url}/notifications/read");
+    info!("marking all notifications as read: {url}");
+    post_authed(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// Relay a mention to the home host of the user mentioned.
+    pub async fn create(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        new_notification: &NewNotification,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/users/{host}/{name}/notifications/{message_id}",
+            host = new_notification.user.host,
+            name = new_notification.user.name,
+            message_id = new_notification.message_id
+        );
+        info!("relaying notification (federation): {url}");
+        put_json_federated(client, &url, token, new_notification).await
+    }
+
+    /// Withdraw a relayed mention once its message was deleted.
+    pub async fn delete(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        user: &UserRef,
+        message_id: Uuid,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/users/{host}/{name}/notifications/{message_id}",
+            host = user.host,
+            name = user.name
+        );
+        info!("withdrawing notification (federation): {url}");
+        delete_federated(client, &url, token).await
+    }
+}
@@ -0,0 +1,121 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::PinnedMessage;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    delete_authed, delete_federated, fetch_json_authed, fetch_json_federated,
+    put_authed, put_federated,
+};
+
+/// Fetch the messages pinned in a channel, most recently pinned first.
+pub async fn fetch_by_channel(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<PinnedMessage>> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/channels/{channel_id}/pins");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching pins: {url}");
+    fetch_json_authed::<Vec<PinnedMessage>>(client, &url, access_token).await
+}
+
+/// Pin a message in its channel.
+pub async fn pin(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    message_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<()> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/pins/{message_id}"
+    );
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("pinning message: {url}");
+    put_authed(client, &url, access_token).await
+}
+
+/// Unpin a message.
+pub async fn unpin(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    message_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<()> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/pins/{message_id}"
+    );
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("unpinning message: {url}");
+    delete_authed(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// Fetch the messages pinned in a channel.
+    pub async fn fetch_by_channel(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Vec<PinnedMessage>> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/pins"
+        );
+        info!("fetching pins (federation): {url}");
+        fetch_json_federated::<Vec<PinnedMessage>>(client, &url, token).await
+    }
+
+    /// Pin a message in its channel.
+    pub async fn pin(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/pins/{message_id}"
+        );
+        info!("pinning message (federation): {url}");
+        put_federated(client, &url, token).await
+    }
+
+    /// Unpin a message.
+    pub async fn unpin(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+        message_id: Uuid,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/pins/{message_id}"
+        );
+        info!("unpinning message (federation): {url}");
+        delete_federated(client, &url, token).await
+    }
+}
@@ -0,0 +1,145 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{MemberPresence, PresenceUpdate, UserRef};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    fetch_json_authed, fetch_json_federated, post_authed, post_federated,
+    put_json_authed, put_json_federated,
+};
+
+/// Report the presence of the token's user. Clients should repeat this
+/// about every 30 seconds while connected.
+pub async fn update(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    update: &PresenceUpdate,
+) -> Result<PresenceUpdate> {
+    let url = format!("{api_url}/presence");
+    info!("updating presence: {url}");
+    put_json_authed::<PresenceUpdate, PresenceUpdate>(
+        client,
+        &url,
+        access_token,
+        update,
+    )
+    .await
+}
+
+/// Fetch the presence of every member of a server.
+pub async fn fetch_by_server(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<MemberPresence>> {
+    let mut url = format!("{api_url}/servers/{server_id}/presence");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching presence: {url}");
+    fetch_json_authed::<Vec<MemberPresence>>(client, &url, access_token).await
+}
+
+/// Show the token's user as typing in a channel.
+pub async fn start_typing(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<()> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/channels/{channel_id}/typing");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("sending typing event: {url}");
+    post_authed(client, &url, access_token).await
+}
+
+/// Fetch the users typing in a channel.
+pub async fn fetch_typing(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<UserRef>> {
+    let mut url =
+        format!("{api_url}/servers/{server_id}/channels/{channel_id}/typing");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching typing users: {url}");
+    fetch_json_authed::<Vec<UserRef>>(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// Publish a user's presence to the host of one of their servers.
+    pub async fn update(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        user_ref: &UserRef,
+        update: &PresenceUpdate,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/users/{}/{}/presence",
+            user_ref.host, user_ref.name
+        );
+        info!("publishing presence (federation): {url}");
+        put_json_federated::<PresenceUpdate>(client, &url, token, update).await
+    }
+
+    /// Fetch the presence of every member of a server.
+    pub async fn fetch_by_server(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+    ) -> Result<Vec<MemberPresence>> {
+        let url = format!("{federation_url}/servers/{server_id}/presence");
+        info!("fetching presence (federation): {url}");
+        fetch_json_federated::<Vec<MemberPresence>>(client, &url, token).await
+    }
+
+    /// Show the token's user as typing in a channel.
+    pub async fn start_typing(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/typing"
+        );
+        info!("sending typing event (federation): {url}");
+        post_federated(client, &url, token).await
+    }
+
+    /// Fetch the users typing in a channel.
+    pub async fn fetch_typing(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Uuid,
+    ) -> Result<Vec<UserRef>> {
+        let url = format!(
+            "{federation_url}/servers/{server_id}/channels/{channel_id}/typing"
+        );
+        info!("fetching typing users (federation): {url}");
+        fetch_json_federated::<Vec<UserRef>>(client, &url, token).await
+    }
+}
@@ -0,0 +1,79 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{ChannelUnread, NewReadMarker, ReadMarker};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{fetch_json_authed, post_json_federated, put_json_authed};
+
+/// Fetch all read markers of the token's user.
+pub async fn fetch_all(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<Vec<ReadMarker>> {
+    let url = format!("{api_url}/read-markers");
+    info!("fetching read markers: {url}");
+    fetch_json_authed::<Vec<ReadMarker>>(client, &url, access_token).await
+}
+
+/// Mark a channel as read up to a message.
+pub async fn set(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    new_marker: &NewReadMarker,
+    target_host: Option<&str>,
+) -> Result<ReadMarker> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/read-marker"
+    );
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("setting read marker: {url}");
+    put_json_authed::<NewReadMarker, ReadMarker>(
+        client,
+        &url,
+        access_token,
+        new_marker,
+    )
+    .await
+}
+
+/// Fetch unread counts for every channel of every server the token's user
+/// is a member of.
+pub async fn fetch_unread(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<Vec<ChannelUnread>> {
+    let url = format!("{api_url}/unread");
+    info!("fetching unread counts: {url}");
+    fetch_json_authed::<Vec<ChannelUnread>>(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// Count a user's unread messages in a server, given their markers in
+    /// it.
+    pub async fn count_unread(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        markers: &[ReadMarker],
+    ) -> Result<Vec<ChannelUnread>> {
+        let url = format!("{federation_url}/servers/{server_id}/unread");
+        info!("counting unread messages (federation): {url}");
+        post_json_federated::<&[ReadMarker], Vec<ChannelUnread>>(
+            client, &url, token, &markers,
+        )
+        .await
+    }
+}
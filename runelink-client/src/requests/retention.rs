@@ -0,0 +1,128 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{RetentionPolicy, RetentionPolicyUpdate};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    delete_authed, delete_federated, fetch_json_authed, fetch_json_federated,
+    patch_json_authed, patch_json_federated,
+};
+
+/// Path of the retention policy of a server, or of one of its channels.
+fn policy_path(server_id: Uuid, channel_id: Option<Uuid>) -> String {
+    match channel_id {
+        Some(channel_id) => {
+            format!("/servers/{server_id}/channels/{channel_id}/retention")
+        }
+        None => format!("/servers/{server_id}/retention"),
+    }
+}
+
+pub async fn fetch_by_server(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<Vec<RetentionPolicy>> {
+    let mut url = format!("{api_url}/servers/{server_id}/retention");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching retention policies by server: {url}");
+    fetch_json_authed::<Vec<RetentionPolicy>>(client, &url, access_token).await
+}
+
+/// Change the retention policy of a server, or of one of its channels when
+/// `channel_id` is given.
+pub async fn update(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Option<Uuid>,
+    update: &RetentionPolicyUpdate,
+    target_host: Option<&str>,
+) -> Result<RetentionPolicy> {
+    let mut url = format!("{api_url}{}", policy_path(server_id, channel_id));
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("updating retention policy: {url}");
+    patch_json_authed::<RetentionPolicyUpdate, RetentionPolicy>(
+        client,
+        &url,
+        access_token,
+        update,
+    )
+    .await
+}
+
+/// Remove the retention policy of a server, or of one of its channels when
+/// `channel_id` is given.
+pub async fn delete(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Option<Uuid>,
+    target_host: Option<&str>,
+) -> Result<()> {
+    let mut url = format!("{api_url}{}", policy_path(server_id, channel_id));
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("deleting retention policy: {url}");
+    delete_authed(client, &url, access_token).await
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// GET /federation/v1/servers/{server_id}/retention
+    pub async fn fetch_by_server(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+    ) -> Result<Vec<RetentionPolicy>> {
+        let url = format!("{federation_url}/servers/{server_id}/retention");
+        info!("fetching retention policies by server (federation): {url}");
+        fetch_json_federated::<Vec<RetentionPolicy>>(client, &url, token).await
+    }
+
+    /// PATCH /federation/v1/servers/{server_id}[/channels/{channel_id}]/retention
+    pub async fn update(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Option<Uuid>,
+        update: &RetentionPolicyUpdate,
+    ) -> Result<RetentionPolicy> {
+        let url =
+            format!("{federation_url}{}", policy_path(server_id, channel_id));
+        info!("updating retention policy (federation): {url}");
+        patch_json_federated::<RetentionPolicyUpdate, RetentionPolicy>(
+            client, &url, token, update,
+        )
+        .await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}[/channels/{channel_id}]/retention
+    pub async fn delete(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        channel_id: Option<Uuid>,
+    ) -> Result<()> {
+        let url =
+            format!("{federation_url}{}", policy_path(server_id, channel_id));
+        info!("deleting retention policy (federation): {url}");
+        delete_federated(client, &url, token).await
+    }
+}
@@ -0,0 +1,57 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{NewScheduledMessage, ScheduledMessage};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{delete_authed, fetch_json_authed, post_json_authed};
+
+/// Schedule a message to be sent to a channel later.
+pub async fn create(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    channel_id: Uuid,
+    new_scheduled: &NewScheduledMessage,
+    target_host: Option<&str>,
+) -> Result<ScheduledMessage> {
+    let mut url = format!(
+        "{api_url}/servers/{server_id}/channels/{channel_id}/scheduled-messages"
+    );
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("scheduling message: {url}");
+    post_json_authed::<NewScheduledMessage, ScheduledMessage>(
+        client,
+        &url,
+        access_token,
+        new_scheduled,
+    )
+    .await
+}
+
+/// Fetch the scheduled messages of the token's user, soonest first.
+pub async fn fetch_all(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<Vec<ScheduledMessage>> {
+    let url = format!("{api_url}/scheduled-messages");
+    info!("fetching scheduled messages: {url}");
+    fetch_json_authed::<Vec<ScheduledMessage>>(client, &url, access_token).await
+}
+
+/// Cancel a scheduled message.
+pub async fn cancel(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    scheduled_id: Uuid,
+) -> Result<()> {
+    let url = format!("{api_url}/scheduled-messages/{scheduled_id}");
+    info!("cancelling scheduled message: {url}");
+    delete_authed(client, &url, access_token).await
+}
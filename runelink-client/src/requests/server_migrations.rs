@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use log::info;
+use reqwest::{Body, Client};
+use runelink_types::{
+    ARCHIVE_CONTENT_TYPE, ArchiveImport, NewServerMigration, ServerMigration,
+};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{
+    post_body_federated, post_json_authed, post_json_federated,
+    put_json_federated,
+};
+
+/// How long the new host may take to import a handed-off server.
+const HAND_OFF_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Move a server to another host (server owners only).
+pub async fn migrate(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    new_migration: &NewServerMigration,
+    target_host: Option<&str>,
+) -> Result<ServerMigration> {
+    let mut url = format!("{api_url}/servers/{server_id}/migration");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("migrating server: {url}");
+    post_json_authed::<NewServerMigration, ServerMigration>(
+        client,
+        &url,
+        access_token,
+        new_migration,
+    )
+    .await
+}
+
+pub mod federated {
+    use super::*;
+
+    /// Move a server to another host, on behalf of its owner.
+    pub async fn migrate(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        new_migration: &NewServerMigration,
+    ) -> Result<ServerMigration> {
+        let url = format!("{federation_url}/servers/{server_id}/migration");
+        info!("migrating server (federation): {url}");
+        post_json_federated::<NewServerMigration, ServerMigration>(
+            client,
+            &url,
+            token,
+            new_migration,
+        )
+        .await
+    }
+
+    /// Hand a server over to the host it moves to, as an archive.
+    pub async fn hand_off(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        archive: Body,
+    ) -> Result<ArchiveImport> {
+        let url = format!("{federation_url}/servers/{server_id}/handoff");
+        info!("handing off server (federation): {url}");
+        post_body_federated::<ArchiveImport>(
+            client,
+            &url,
+            token,
+            ARCHIVE_CONTENT_TYPE,
+            archive,
+            HAND_OFF_TIMEOUT,
+        )
+        .await
+    }
+
+    /// Tell the host of a server's members that the server moved.
+    pub async fn notify(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        migration: &ServerMigration,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/servers/{}/migration",
+            migration.server_id
+        );
+        info!("sending server migration (federation): {url}");
+        put_json_federated(client, &url, token, migration).await
+    }
+}
@@ -1,12 +1,15 @@
 use log::info;
 use reqwest::Client;
-use runelink_types::{NewServer, Server, ServerWithChannels, UserRef};
+use runelink_types::{
+    NewServer, Server, ServerUpdate, ServerWithChannels, UserRef,
+};
 use uuid::Uuid;
 
 use crate::{error::Result, requests};
 
 use super::{
-    delete_authed, delete_federated, fetch_json, fetch_json_federated,
+    delete_authed, delete_federated, fetch_json, fetch_json_authed,
+    fetch_json_federated, patch_json_authed, patch_json_federated,
     post_json_authed, post_json_federated,
 };
 
@@ -52,6 +55,21 @@ pub async fn fetch_by_id(
     fetch_json::<Server>(client, &url).await
 }
 
+pub async fn fetch_with_channels(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> Result<ServerWithChannels> {
+    let mut url = format!("{api_url}/servers/{server_id}/with_channels");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("fetching server with channels: {url}");
+    fetch_json_authed::<ServerWithChannels>(client, &url, access_token).await
+}
+
 pub async fn fetch_by_user(
     client: &Client,
     api_url: &str,
@@ -67,6 +85,28 @@ pub async fn fetch_by_user(
     Ok(servers)
 }
 
+pub async fn update(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    server_id: Uuid,
+    update: &ServerUpdate,
+    target_host: Option<&str>,
+) -> Result<Server> {
+    let mut url = format!("{api_url}/servers/{server_id}");
+    if let Some(host) = target_host {
+        url = format!("{url}?target_host={host}");
+    }
+    info!("updating server: {url}");
+    patch_json_authed::<ServerUpdate, Server>(
+        client,
+        &url,
+        access_token,
+        update,
+    )
+    .await
+}
+
 pub async fn delete(
     client: &Client,
     api_url: &str,
@@ -86,14 +126,14 @@ pub async fn delete(
 pub mod federated {
     use super::*;
 
-    /// POST /federation/servers
+    /// POST /federation/v1/servers
     pub async fn create(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         new_server: &NewServer,
     ) -> Result<Server> {
-        let url = format!("{api_url}/federation/servers");
+        let url = format!("{federation_url}/servers");
         info!("creating server (federation): {url}");
         post_json_federated::<NewServer, Server>(
             client, &url, token, new_server,
@@ -101,27 +141,42 @@ pub mod federated {
         .await
     }
 
-    /// GET /federation/servers/{server_id}/with_channels
+    /// GET /federation/v1/servers/{server_id}/with_channels
     pub async fn fetch_with_channels(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
     ) -> Result<ServerWithChannels> {
-        let url =
-            format!("{api_url}/federation/servers/{server_id}/with_channels");
+        let url = format!("{federation_url}/servers/{server_id}/with_channels");
         info!("fetching server with channels (federation): {url}");
         fetch_json_federated::<ServerWithChannels>(client, &url, token).await
     }
 
-    /// DELETE /federation/servers/{server_id}
+    /// PATCH /federation/v1/servers/{server_id}
+    pub async fn update(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        server_id: Uuid,
+        update: &ServerUpdate,
+    ) -> Result<Server> {
+        let url = format!("{federation_url}/servers/{server_id}");
+        info!("updating server (federation): {url}");
+        patch_json_federated::<ServerUpdate, Server>(
+            client, &url, token, update,
+        )
+        .await
+    }
+
+    /// DELETE /federation/v1/servers/{server_id}
     pub async fn delete(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         server_id: Uuid,
     ) -> Result<()> {
-        let url = format!("{api_url}/federation/servers/{server_id}");
+        let url = format!("{federation_url}/servers/{server_id}");
         info!("deleting server (federation): {url}");
         delete_federated(client, &url, token).await
     }
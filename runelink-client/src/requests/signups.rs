@@ -0,0 +1,84 @@
+use log::info;
+use reqwest::Client;
+use runelink_types::{IssuedSignupInvite, NewSignupInvite, SignupInvite, User};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+use super::{delete_authed, fetch_json_authed, post_authed, post_json_authed};
+
+/// POST /admin/invites
+pub async fn create_invite(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    new_invite: &NewSignupInvite,
+) -> Result<IssuedSignupInvite> {
+    let url = format!("{api_url}/admin/invites");
+    info!("creating signup invite: {url}");
+    post_json_authed::<_, IssuedSignupInvite>(
+        client,
+        &url,
+        access_token,
+        new_invite,
+    )
+    .await
+}
+
+/// GET /admin/invites
+pub async fn fetch_invites(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<Vec<SignupInvite>> {
+    let url = format!("{api_url}/admin/invites");
+    info!("fetching signup invites: {url}");
+    fetch_json_authed::<Vec<SignupInvite>>(client, &url, access_token).await
+}
+
+/// DELETE /admin/invites/{invite_id}
+pub async fn delete_invite(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    invite_id: Uuid,
+) -> Result<()> {
+    let url = format!("{api_url}/admin/invites/{invite_id}");
+    info!("deleting signup invite: {url}");
+    delete_authed(client, &url, access_token).await
+}
+
+/// GET /admin/signups
+pub async fn fetch_pending(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+) -> Result<Vec<User>> {
+    let url = format!("{api_url}/admin/signups");
+    info!("fetching pending signups: {url}");
+    fetch_json_authed::<Vec<User>>(client, &url, access_token).await
+}
+
+/// POST /admin/signups/{name}/approve
+pub async fn approve(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    name: &str,
+) -> Result<()> {
+    let url = format!("{api_url}/admin/signups/{name}/approve");
+    info!("approving signup: {url}");
+    post_authed(client, &url, access_token).await
+}
+
+/// DELETE /admin/signups/{name}
+pub async fn reject(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    name: &str,
+) -> Result<()> {
+    let url = format!("{api_url}/admin/signups/{name}");
+    info!("rejecting signup: {url}");
+    delete_authed(client, &url, access_token).await
+}
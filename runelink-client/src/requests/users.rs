@@ -1,10 +1,13 @@
 use log::info;
 use reqwest::Client;
-use runelink_types::{NewUser, User, UserRef};
+use runelink_types::{NewUser, User, UserProfileUpdate, UserRef};
 
 use crate::error::Result;
 
-use super::{delete_authed, delete_federated, fetch_json, post_json_authed};
+use super::{
+    delete_authed, delete_federated, fetch_json, patch_json_authed,
+    post_json_authed, put_json_federated,
+};
 
 pub async fn create(
     client: &Client,
@@ -60,6 +63,29 @@ pub async fn delete(
     delete_authed(client, &url, access_token).await
 }
 
+/// Edit the profile of the token's user.
+pub async fn update_profile(
+    client: &Client,
+    api_url: &str,
+    access_token: &str,
+    user: UserRef,
+    update: &UserProfileUpdate,
+) -> Result<User> {
+    let url = format!(
+        "{api_url}/users/{host}/{name}",
+        host = user.host,
+        name = user.name
+    );
+    info!("updating user profile: {url}");
+    patch_json_authed::<UserProfileUpdate, User>(
+        client,
+        &url,
+        access_token,
+        update,
+    )
+    .await
+}
+
 pub async fn fetch_associated_hosts(
     client: &Client,
     api_url: &str,
@@ -82,19 +108,35 @@ pub async fn fetch_associated_hosts(
 pub mod federated {
     use super::*;
 
-    /// DELETE /federation/users/{host}/{name}
+    /// DELETE /federation/v1/users/{host}/{name}
     pub async fn delete(
         client: &Client,
-        api_url: &str,
+        federation_url: &str,
         token: &str,
         user: UserRef,
     ) -> Result<()> {
         let url = format!(
-            "{api_url}/federation/users/{host}/{name}",
+            "{federation_url}/users/{host}/{name}",
             host = user.host,
             name = user.name
         );
         info!("deleting user (federation): {url}");
         delete_federated(client, &url, token).await
     }
+
+    /// Send a user's profile to a host caching it.
+    pub async fn sync(
+        client: &Client,
+        federation_url: &str,
+        token: &str,
+        user: &User,
+    ) -> Result<()> {
+        let url = format!(
+            "{federation_url}/users/{host}/{name}",
+            host = user.host,
+            name = user.name
+        );
+        info!("syncing user (federation): {url}");
+        put_json_federated(client, &url, token, user).await
+    }
 }
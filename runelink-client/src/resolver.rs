@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+use reqwest::{Client, Url};
+use runelink_types::{
+    FEDERATION_LEGACY_PATH, HostDiscovery, PROTOCOL_VERSION, capabilities,
+};
+
+use crate::{
+    requests,
+    util::{
+        Scheme, api_url_with_scheme, discovery_origins, is_allowed_api_url,
+        pad_host,
+    },
+};
+
+/// How long a fetched discovery document is trusted.
+const DEFAULT_TTL: Duration = Duration::from_secs(10 * 60);
+/// How long to remember that a host has no discovery document.
+const FALLBACK_TTL: Duration = Duration::from_secs(60);
+
+/// Where a host's APIs live.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedHost {
+    pub api_url: String,
+    pub federation_url: String,
+    /// `None` if the host serves no discovery document and the default
+    /// `host:7000` layout is assumed
+    pub discovery: Option<HostDiscovery>,
+}
+
+impl ResolvedHost {
+    fn from_discovery(discovery: HostDiscovery) -> Self {
+        Self {
+            api_url: discovery.api_base_url.trim_end_matches('/').into(),
+            federation_url: discovery
+                .federation_url
+                .trim_end_matches('/')
+                .into(),
+            discovery: Some(discovery),
+        }
+    }
+
+    /// Whether the host speaks our federation protocol version.
+    pub fn speaks_protocol(&self) -> bool {
+        match &self.discovery {
+            Some(discovery) if !discovery.protocol_versions.is_empty() => {
+                discovery
+                    .protocol_versions
+                    .iter()
+                    .any(|v| v == PROTOCOL_VERSION)
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the host advertises the given federation capability.
+    ///
+    /// Hosts that predate capability negotiation are assumed to support
+    /// [`capabilities::BASELINE`].
+    pub fn supports(&self, capability: &str) -> bool {
+        if !self.speaks_protocol() {
+            return false;
+        }
+        match &self.discovery {
+            Some(discovery) if !discovery.capabilities.is_empty() => {
+                discovery.capabilities.iter().any(|c| c == capability)
+            }
+            _ => capabilities::BASELINE.contains(&capability),
+        }
+    }
+
+    fn fallback(host: &str, scheme: Scheme) -> Self {
+        let api_url = api_url_with_scheme(host, scheme);
+        Self {
+            federation_url: format!("{api_url}{FEDERATION_LEGACY_PATH}"),
+            api_url,
+            discovery: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    resolved: ResolvedHost,
+    /// `None` for pinned entries
+    expires_at: Option<Instant>,
+}
+
+/// Resolves hosts through their `/.well-known/runelink` document and caches
+/// the result, falling back to [`api_url_with_scheme`] for hosts that don't
+/// serve one.
+#[derive(Clone, Debug)]
+pub struct HostResolver {
+    cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
+    ttl: Duration,
+    /// Scheme for hosts that don't specify one
+    scheme: Scheme,
+}
+
+impl Default for HostResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HostResolver {
+    pub fn new() -> Self {
+        Self::with_ttl(DEFAULT_TTL)
+    }
+
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            cache: Arc::new(RwLock::new(HashMap::new())),
+            ttl,
+            scheme: Scheme::Https,
+        }
+    }
+
+    /// Reach hosts that don't specify a scheme over `scheme` rather than
+    /// `https`. Plaintext `http` is only meant for local development.
+    pub fn with_scheme(mut self, scheme: Scheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Scheme used for hosts that don't specify one.
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// Permanently resolve `host` to `discovery` without fetching anything.
+    ///
+    /// Servers use this for their own host.
+    pub fn pin(&self, host: &str, discovery: HostDiscovery) {
+        let entry = CacheEntry {
+            resolved: ResolvedHost::from_discovery(discovery),
+            expires_at: None,
+        };
+        self.cache.write().unwrap().insert(host.to_string(), entry);
+    }
+
+    /// Forget any cached resolution for `host`.
+    pub fn invalidate(&self, host: &str) {
+        self.cache.write().unwrap().remove(host);
+    }
+
+    pub async fn resolve(&self, client: &Client, host: &str) -> ResolvedHost {
+        if let Some(resolved) = self.get_cached(host) {
+            return resolved;
+        }
+        let (resolved, ttl) = match discover(client, host, self.scheme).await {
+            Some(discovery) => {
+                (ResolvedHost::from_discovery(discovery), self.ttl)
+            }
+            None => (
+                ResolvedHost::fallback(host, self.scheme),
+                FALLBACK_TTL.min(self.ttl),
+            ),
+        };
+        let entry = CacheEntry {
+            resolved: resolved.clone(),
+            expires_at: Some(Instant::now() + ttl),
+        };
+        self.cache.write().unwrap().insert(host.to_string(), entry);
+        resolved
+    }
+
+    /// Shorthand for the API base URL of [`HostResolver::resolve`].
+    pub async fn api_url(&self, client: &Client, host: &str) -> String {
+        self.resolve(client, host).await.api_url
+    }
+
+    fn get_cached(&self, host: &str) -> Option<ResolvedHost> {
+        let cache = self.cache.read().unwrap();
+        let entry = cache.get(host)?;
+        match entry.expires_at {
+            Some(expires_at) if expires_at <= Instant::now() => None,
+            _ => Some(entry.resolved.clone()),
+        }
+    }
+}
+
+async fn discover(
+    client: &Client,
+    host: &str,
+    scheme: Scheme,
+) -> Option<HostDiscovery> {
+    for origin in discovery_origins(host, scheme) {
+        let discovery = match requests::discovery::fetch(client, &origin).await
+        {
+            Ok(discovery) => discovery,
+            Err(e) => {
+                debug!("no discovery document at {origin}: {e}");
+                continue;
+            }
+        };
+        if let Err(reason) = check_discovery(&discovery, host, &origin, scheme)
+        {
+            warn!("ignoring discovery document at {origin}: {reason}");
+            continue;
+        }
+        return Some(discovery);
+    }
+    None
+}
+
+/// Why a discovery document fetched from `origin` can't be used for
+/// `host`, if it can't.
+fn check_discovery(
+    discovery: &HostDiscovery,
+    host: &str,
+    origin: &str,
+    scheme: Scheme,
+) -> Result<(), String> {
+    if pad_host(&discovery.host) != pad_host(host) {
+        return Err(format!("describes host {}", discovery.host));
+    }
+    for url in [&discovery.api_base_url, &discovery.federation_url] {
+        if !is_allowed_api_url(url, scheme) {
+            return Err(format!("insecure API URL {url}"));
+        }
+        if !is_url_of_host(url, host, origin) {
+            return Err(format!("API URL {url} is not on {host}"));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `url` is served by `host` itself: the same host name, on the
+/// port the discovery document was fetched from (`origin`) or on the
+/// host's own port. Tokens we issue to a host are addressed to its API
+/// URL, so a host must not be able to claim another host's.
+fn is_url_of_host(url: &str, host: &str, origin: &str) -> bool {
+    let own = format!("https://{}", pad_host(host));
+    let (Ok(url), Ok(origin), Ok(own)) =
+        (Url::parse(url), Url::parse(origin), Url::parse(&own))
+    else {
+        return false;
+    };
+    let Some(name) = url.host_str() else {
+        return false;
+    };
+    let port = url.port_or_known_default();
+    Some(name) == own.host_str()
+        && (port == origin.port_or_known_default()
+            || port == own.port_or_known_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovery(host: &str, api_base_url: &str) -> HostDiscovery {
+        HostDiscovery {
+            host: host.into(),
+            api_base_url: api_base_url.into(),
+            federation_url: format!("{api_base_url}/federation"),
+            protocol_versions: vec![PROTOCOL_VERSION.into()],
+            capabilities: Vec::new(),
+            software: None,
+        }
+    }
+
+    #[test]
+    fn test_discovery_with_another_hosts_api_url_rejected() {
+        let origin = "https://evil.example:7000";
+        let own = discovery("evil.example", "https://evil.example:7000");
+        assert!(
+            check_discovery(&own, "evil.example", origin, Scheme::Https)
+                .is_ok()
+        );
+        let stolen = discovery("evil.example", "https://victim.example:7000");
+        assert!(
+            check_discovery(&stolen, "evil.example", origin, Scheme::Https)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_url_of_host_on_either_port() {
+        let origin = "https://example.com";
+        assert!(is_url_of_host(
+            "https://example.com/runelink",
+            "example.com",
+            origin
+        ));
+        assert!(is_url_of_host(
+            "https://EXAMPLE.com:7000",
+            "example.com",
+            origin
+        ));
+        assert!(!is_url_of_host(
+            "https://example.com:8443",
+            "example.com",
+            origin
+        ));
+    }
+
+    #[test]
+    fn test_url_of_another_host_rejected() {
+        let origin = "https://evil.example:7000";
+        assert!(!is_url_of_host(
+            "https://victim.example:7000",
+            "evil.example",
+            origin
+        ));
+        assert!(!is_url_of_host(
+            "https://evil.example.victim.example:7000",
+            "evil.example",
+            origin
+        ));
+        assert!(!is_url_of_host("not a url", "evil.example", origin));
+    }
+}
@@ -1,4 +1,46 @@
+use std::fmt;
+
+/// Port assumed when a host does not specify one.
+pub const DEFAULT_PORT: u16 = 7000;
+
+/// Transport scheme used to reach a host's API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scheme {
+    Http,
+    Https,
+}
+
+impl Scheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scheme::Http => "http",
+            Scheme::Https => "https",
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Split an optional `http://` or `https://` prefix off a host string.
+pub fn split_scheme(host: &str) -> (Option<Scheme>, &str) {
+    if let Some(rest) = host.strip_prefix("https://") {
+        (Some(Scheme::Https), rest)
+    } else if let Some(rest) = host.strip_prefix("http://") {
+        (Some(Scheme::Http), rest)
+    } else {
+        (None, host)
+    }
+}
+
+/// Normalize a host to `host:port`, dropping any scheme prefix and adding
+/// the default port when none is given.
 pub fn pad_host(host: &str) -> String {
+    let (_, host) = split_scheme(host);
+    let host = host.trim_end_matches('/');
     if host.starts_with('[') {
         // IPv6 literal
         match host.find(']') {
@@ -7,24 +49,67 @@ pub fn pad_host(host: &str) -> String {
                 if after.starts_with(':') {
                     host.to_string()
                 } else {
-                    format!("{host}:7000")
+                    format!("{host}:{DEFAULT_PORT}")
                 }
             }
             None => {
                 // malformed IPv6, just append
-                format!("{host}:7000")
+                format!("{host}:{DEFAULT_PORT}")
             }
         }
     } else if host.contains(':') {
         host.to_string()
     } else {
-        format!("{host}:7000")
+        format!("{host}:{DEFAULT_PORT}")
     }
 }
 
-pub fn get_api_url(host: &str) -> String {
+/// Build the API base URL for a host, using `default_scheme` unless the host
+/// carries an explicit scheme prefix.
+pub fn api_url_with_scheme(host: &str, default_scheme: Scheme) -> String {
+    let (scheme, _) = split_scheme(host);
+    let scheme = scheme.unwrap_or(default_scheme);
     let host_with_port = pad_host(host);
-    format!("http://{host_with_port}")
+    format!("{scheme}://{host_with_port}")
+}
+
+/// Build the API base URL for a host, over `https` unless the host carries
+/// an explicit scheme prefix.
+pub fn get_api_url(host: &str) -> String {
+    api_url_with_scheme(host, Scheme::Https)
+}
+
+/// Origins to probe for a host's `/.well-known/runelink` document, in order,
+/// using `default_scheme` unless the host carries an explicit scheme prefix.
+///
+/// A host without an explicit port is first looked up on the scheme's
+/// standard port (where a reverse proxy would usually sit), then on
+/// [`DEFAULT_PORT`].
+pub fn discovery_origins(host: &str, default_scheme: Scheme) -> Vec<String> {
+    let (scheme, bare) = split_scheme(host);
+    let scheme = scheme.unwrap_or(default_scheme);
+    let bare = bare.trim_end_matches('/');
+    let padded = pad_host(bare);
+    let mut origins = Vec::with_capacity(2);
+    if padded != bare {
+        origins.push(format!("{scheme}://{bare}"));
+    }
+    origins.push(format!("{scheme}://{padded}"));
+    origins
+}
+
+/// Whether `url` may be used as a peer's API base URL (e.g. as a JWT issuer
+/// whose JWKS we are about to fetch), when hosts are reached over
+/// `default_scheme`.
+///
+/// `https` is always allowed; `http` only in insecure development mode,
+/// where `default_scheme` is `http` too.
+pub fn is_allowed_api_url(url: &str, default_scheme: Scheme) -> bool {
+    match split_scheme(url) {
+        (Some(Scheme::Https), _) => true,
+        (Some(Scheme::Http), _) => default_scheme == Scheme::Http,
+        (None, _) => false,
+    }
 }
 
 #[cfg(test)]
@@ -33,32 +118,84 @@ mod tests {
 
     #[test]
     fn test_no_port() {
-        let url = get_api_url("example.com");
-        assert_eq!(url, "http://example.com:7000");
+        let url = api_url_with_scheme("example.com", Scheme::Https);
+        assert_eq!(url, "https://example.com:7000");
     }
 
     #[test]
     fn test_with_port() {
-        let url = get_api_url("example.com:8080");
-        assert_eq!(url, "http://example.com:8080");
+        let url = api_url_with_scheme("example.com:8080", Scheme::Https);
+        assert_eq!(url, "https://example.com:8080");
     }
 
     #[test]
     fn test_ipv6_no_port() {
-        let url = get_api_url("[::1]");
-        assert_eq!(url, "http://[::1]:7000");
+        let url = api_url_with_scheme("[::1]", Scheme::Https);
+        assert_eq!(url, "https://[::1]:7000");
     }
 
     #[test]
     fn test_ipv6_with_port() {
-        let url = get_api_url("[::1]:4321");
-        assert_eq!(url, "http://[::1]:4321");
+        let url = api_url_with_scheme("[::1]:4321", Scheme::Https);
+        assert_eq!(url, "https://[::1]:4321");
     }
 
     #[test]
     fn test_malformed_ipv6() {
         // no closing ']', treated as no port
-        let url = get_api_url("[::1");
-        assert_eq!(url, "http://[::1:7000");
+        let url = api_url_with_scheme("[::1", Scheme::Https);
+        assert_eq!(url, "https://[::1:7000");
+    }
+
+    #[test]
+    fn test_http_default_scheme() {
+        let url = api_url_with_scheme("localhost", Scheme::Http);
+        assert_eq!(url, "http://localhost:7000");
+    }
+
+    #[test]
+    fn test_explicit_scheme_overrides_default() {
+        let url = api_url_with_scheme("http://localhost:7001", Scheme::Https);
+        assert_eq!(url, "http://localhost:7001");
+        let url = api_url_with_scheme("https://example.com", Scheme::Http);
+        assert_eq!(url, "https://example.com:7000");
+    }
+
+    #[test]
+    fn test_pad_host_strips_scheme() {
+        assert_eq!(pad_host("https://example.com/"), "example.com:7000");
+        assert_eq!(pad_host("http://[::1]:4321"), "[::1]:4321");
+    }
+
+    #[test]
+    fn test_discovery_origins() {
+        assert_eq!(
+            discovery_origins("https://example.com", Scheme::Https),
+            vec!["https://example.com", "https://example.com:7000"]
+        );
+        assert_eq!(
+            discovery_origins("http://localhost:7001", Scheme::Https),
+            vec!["http://localhost:7001"]
+        );
+        assert_eq!(
+            discovery_origins("localhost:7001", Scheme::Http),
+            vec!["http://localhost:7001"]
+        );
+    }
+
+    #[test]
+    fn test_unprefixed_url_not_allowed() {
+        assert!(is_allowed_api_url(
+            "https://example.com:7000",
+            Scheme::Https
+        ));
+        assert!(!is_allowed_api_url("example.com:7000", Scheme::Https));
+        assert!(!is_allowed_api_url("ftp://example.com", Scheme::Https));
+    }
+
+    #[test]
+    fn test_http_url_allowed_only_in_insecure_mode() {
+        assert!(!is_allowed_api_url("http://localhost:7000", Scheme::Https));
+        assert!(is_allowed_api_url("http://localhost:7000", Scheme::Http));
     }
 }
@@ -0,0 +1,12 @@
+-- Fixed-window request counters shared by all server instances when
+-- RATE_LIMIT_BACKEND=postgres
+
+CREATE TABLE rate_limit_counters (
+    bucket_key TEXT NOT NULL,
+    window_start TIMESTAMPTZ NOT NULL,
+    hits INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (bucket_key, window_start)
+);
+
+CREATE INDEX idx_rate_limit_counters_window_start
+    ON rate_limit_counters (window_start);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS signup_invites;
+ALTER TABLE local_accounts DROP COLUMN IF EXISTS approved;
@@ -0,0 +1,21 @@
+-- Accounts created while SIGNUP_MODE=approval stay unapproved until a host
+-- admin approves them
+ALTER TABLE local_accounts
+    ADD COLUMN approved BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Invite tokens for SIGNUP_MODE=invite (only a hash of the token is stored)
+CREATE TABLE signup_invites (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    token_hash TEXT NOT NULL UNIQUE,
+    created_by_name TEXT,
+    created_by_host TEXT,
+    note TEXT,
+    max_uses INTEGER,
+    uses INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    CONSTRAINT signup_invites_created_by_fkey
+        FOREIGN KEY (created_by_name, created_by_host)
+        REFERENCES users(name, host)
+        ON DELETE SET NULL
+);
@@ -0,0 +1,23 @@
+-- One-time password reset tokens minted by host admins (only a hash of the
+-- token is stored)
+CREATE TABLE password_resets (
+    token_hash TEXT PRIMARY KEY,
+    user_name TEXT NOT NULL,
+    user_host TEXT NOT NULL,
+    created_by_name TEXT,
+    created_by_host TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    CONSTRAINT password_resets_account_fkey
+        FOREIGN KEY (user_name, user_host)
+        REFERENCES local_accounts(user_name, user_host)
+        ON DELETE CASCADE,
+    CONSTRAINT password_resets_created_by_fkey
+        FOREIGN KEY (created_by_name, created_by_host)
+        REFERENCES users(name, host)
+        ON DELETE SET NULL
+);
+
+CREATE INDEX idx_password_resets_user
+    ON password_resets(user_name, user_host);
@@ -0,0 +1,29 @@
+-- TOTP second factor for local accounts. A row with enabled = FALSE is an
+-- enrollment waiting to be confirmed with a first code.
+CREATE TABLE totp_credentials (
+    user_name TEXT NOT NULL,
+    user_host TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Last accepted time step, so a code can't be replayed
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_name, user_host),
+    CONSTRAINT totp_credentials_account_fkey
+        FOREIGN KEY (user_name, user_host)
+        REFERENCES local_accounts(user_name, user_host)
+        ON DELETE CASCADE
+);
+
+-- One-time recovery codes (only a hash of each code is stored)
+CREATE TABLE totp_recovery_codes (
+    user_name TEXT NOT NULL,
+    user_host TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    used_at TIMESTAMPTZ,
+    PRIMARY KEY (user_name, user_host, code_hash),
+    CONSTRAINT totp_recovery_codes_account_fkey
+        FOREIGN KEY (user_name, user_host)
+        REFERENCES local_accounts(user_name, user_host)
+        ON DELETE CASCADE
+);
@@ -0,0 +1,29 @@
+-- Append-only record of privileged actions. Actors and servers aren't
+-- foreign keys so entries outlive what they refer to.
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_name TEXT,
+    actor_host TEXT,
+    -- Host that issued the credentials the action was authorized with (NULL
+    -- for actions the host takes on its own)
+    issuer_host TEXT,
+    action TEXT NOT NULL,
+    server_id UUID,
+    target_kind TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_server ON audit_log(server_id, id);
+
+CREATE FUNCTION audit_log_append_only()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW
+    EXECUTE FUNCTION audit_log_append_only();
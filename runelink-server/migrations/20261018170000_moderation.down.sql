@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS server_timeouts;
+DROP TABLE IF EXISTS server_bans;
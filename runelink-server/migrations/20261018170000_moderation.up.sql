@@ -0,0 +1,43 @@
+-- Users (or whole hosts) barred from joining and posting in a server. The
+-- banned user isn't a foreign key: a user can be banned before this host has
+-- ever seen them.
+CREATE TABLE server_bans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    server_id UUID NOT NULL
+        REFERENCES servers (id)
+        ON DELETE CASCADE,
+    -- NULL bans every user of user_host
+    user_name TEXT,
+    user_host TEXT NOT NULL,
+    reason TEXT,
+    created_by_name TEXT,
+    created_by_host TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,
+    CONSTRAINT server_bans_created_by_fkey
+        FOREIGN KEY (created_by_name, created_by_host)
+        REFERENCES users(name, host)
+        ON DELETE SET NULL
+);
+
+CREATE UNIQUE INDEX idx_server_bans_target
+    ON server_bans(server_id, user_host, COALESCE(user_name, ''));
+
+-- Members temporarily barred from posting in a server
+CREATE TABLE server_timeouts (
+    server_id UUID NOT NULL
+        REFERENCES servers (id)
+        ON DELETE CASCADE,
+    user_name TEXT NOT NULL,
+    user_host TEXT NOT NULL,
+    reason TEXT,
+    created_by_name TEXT,
+    created_by_host TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    until TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (server_id, user_name, user_host),
+    CONSTRAINT server_timeouts_created_by_fkey
+        FOREIGN KEY (created_by_name, created_by_host)
+        REFERENCES users(name, host)
+        ON DELETE SET NULL
+);
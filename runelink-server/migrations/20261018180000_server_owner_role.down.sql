@@ -0,0 +1,7 @@
+ALTER TYPE server_role RENAME TO server_role_old;
+CREATE TYPE server_role AS ENUM ('member', 'admin');
+ALTER TABLE server_users
+    ALTER COLUMN role TYPE server_role USING role::TEXT::server_role;
+ALTER TABLE user_remote_server_memberships
+    ALTER COLUMN role TYPE server_role USING role::TEXT::server_role;
+DROP TYPE server_role_old;
@@ -0,0 +1,3 @@
+-- A new enum value can't be used in the transaction that adds it, so the
+-- owners are backfilled by the next migration
+ALTER TYPE server_role ADD VALUE 'owner';
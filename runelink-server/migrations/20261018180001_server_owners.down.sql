@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_server_users_owner;
+UPDATE server_users SET role = 'admin' WHERE role = 'owner';
+UPDATE user_remote_server_memberships SET role = 'admin' WHERE role = 'owner';
@@ -0,0 +1,17 @@
+-- The longest-standing admin of each server becomes its owner
+UPDATE server_users su
+SET role = 'owner'
+FROM (
+    SELECT DISTINCT ON (server_id) server_id, user_name, user_host
+    FROM server_users
+    WHERE role = 'admin'
+    ORDER BY server_id, created_at
+) first_admin
+WHERE su.server_id = first_admin.server_id
+    AND su.user_name = first_admin.user_name
+    AND su.user_host = first_admin.user_host;
+
+-- At most one owner per server
+CREATE UNIQUE INDEX idx_server_users_owner
+    ON server_users(server_id)
+    WHERE role = 'owner';
@@ -0,0 +1,17 @@
+-- Channels are listed by position within their server; existing channels
+-- keep their creation order
+ALTER TABLE channels
+    ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE channels c
+SET position = ordered.position
+FROM (
+    SELECT id,
+           ROW_NUMBER() OVER (PARTITION BY server_id ORDER BY created_at) - 1
+               AS position
+    FROM channels
+) ordered
+WHERE c.id = ordered.id;
+
+CREATE INDEX idx_channels_server_position
+    ON channels (server_id, position);
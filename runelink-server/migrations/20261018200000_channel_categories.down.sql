@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_channels_category_id;
+
+ALTER TABLE channels
+    DROP COLUMN IF EXISTS category_id;
+
+DROP TABLE IF EXISTS channel_categories;
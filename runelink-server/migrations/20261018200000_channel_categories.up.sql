@@ -0,0 +1,29 @@
+-- Named groups of channels within a server, ordered by position
+CREATE TABLE channel_categories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    server_id UUID NOT NULL
+        REFERENCES servers (id)
+        ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_channel_categories_server_position
+    ON channel_categories (server_id, position);
+
+CREATE TRIGGER channel_categories_set_updated_at
+    BEFORE UPDATE ON channel_categories
+    FOR EACH ROW
+    EXECUTE FUNCTION set_updated_at();
+
+-- Deleting a category leaves its channels uncategorized
+ALTER TABLE channels
+    ADD COLUMN category_id UUID
+        REFERENCES channel_categories (id)
+        ON DELETE SET NULL;
+
+CREATE INDEX idx_channels_category_id
+    ON channels (category_id)
+    WHERE category_id IS NOT NULL;
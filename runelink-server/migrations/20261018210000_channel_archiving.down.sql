@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_channels_deleted_at;
+
+ALTER TABLE channels
+    DROP COLUMN IF EXISTS deleted_at,
+    DROP COLUMN IF EXISTS archived_at;
@@ -0,0 +1,9 @@
+-- Archived channels are read-only and hidden from default listings.
+-- Deleted channels sit in the trash until a background job purges them.
+ALTER TABLE channels
+    ADD COLUMN archived_at TIMESTAMPTZ,
+    ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_channels_deleted_at
+    ON channels (deleted_at)
+    WHERE deleted_at IS NOT NULL;
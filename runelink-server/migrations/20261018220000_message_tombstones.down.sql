@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS idx_messages_deleted_at;
+
+DELETE FROM messages WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE messages
+    DROP COLUMN IF EXISTS deleted_by_host,
+    DROP COLUMN IF EXISTS deleted_by_name,
+    DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,11 @@
+-- Deleted messages leave a tombstone (the row with an empty body) so that
+-- clients and peers can find out about the deletion. Tombstones are purged
+-- by a background job after a retention window.
+ALTER TABLE messages
+    ADD COLUMN deleted_at TIMESTAMPTZ,
+    ADD COLUMN deleted_by_name TEXT,
+    ADD COLUMN deleted_by_host TEXT;
+
+CREATE INDEX idx_messages_deleted_at
+    ON messages (deleted_at)
+    WHERE deleted_at IS NOT NULL;
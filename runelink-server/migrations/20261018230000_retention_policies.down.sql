@@ -0,0 +1,3 @@
+ALTER TABLE audit_log DROP COLUMN IF EXISTS detail;
+
+DROP TABLE IF EXISTS retention_policies;
@@ -0,0 +1,19 @@
+-- Per-server and per-channel message retention. A channel's policy replaces
+-- its server's; the server-wide policy has no channel_id.
+CREATE TABLE retention_policies (
+    server_id UUID NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    channel_id UUID REFERENCES channels(id) ON DELETE CASCADE,
+    max_age_days INTEGER CHECK (max_age_days > 0),
+    max_messages INTEGER CHECK (max_messages > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE NULLS NOT DISTINCT (server_id, channel_id)
+);
+
+CREATE TRIGGER retention_policies_set_updated_at
+    BEFORE UPDATE ON retention_policies
+    FOR EACH ROW
+    EXECUTE FUNCTION set_updated_at();
+
+-- Free-form details of an audited action, e.g. how much a purge removed
+ALTER TABLE audit_log ADD COLUMN detail TEXT;
@@ -0,0 +1,26 @@
+-- Files attached to messages. The contents live in the blob store under the
+-- attachment's ID. Rows outlive their message or channel (the references
+-- are cleared instead), so that a background job can remove the blob along
+-- with the row.
+CREATE TABLE attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    channel_id UUID REFERENCES channels(id) ON DELETE SET NULL,
+    -- NULL until the attachment is sent with a message
+    message_id UUID REFERENCES messages(id) ON DELETE SET NULL,
+    uploader_name TEXT NOT NULL,
+    uploader_host TEXT NOT NULL,
+    name TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    size BIGINT NOT NULL CHECK (size >= 0),
+    sha256 TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_attachments_message_id ON attachments (message_id);
+
+CREATE INDEX idx_attachments_uploader
+    ON attachments (uploader_host, uploader_name);
+
+CREATE INDEX idx_attachments_unattached
+    ON attachments (created_at)
+    WHERE message_id IS NULL;
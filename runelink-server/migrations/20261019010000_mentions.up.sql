@@ -0,0 +1,36 @@
+-- Users mentioned in messages of this host's servers
+CREATE TABLE message_mentions (
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    user_name TEXT NOT NULL,
+    user_host TEXT NOT NULL,
+    PRIMARY KEY (message_id, user_name, user_host)
+);
+
+CREATE INDEX idx_message_mentions_user
+    ON message_mentions (user_host, user_name);
+
+-- Inboxes of this host's users: mentions of them in messages of any server,
+-- local or on another host
+CREATE TABLE notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_name TEXT NOT NULL,
+    user_host TEXT NOT NULL,
+    server_id UUID NOT NULL,
+    server_host TEXT NOT NULL,
+    channel_id UUID NOT NULL,
+    message_id UUID NOT NULL,
+    author_name TEXT NOT NULL,
+    author_host TEXT NOT NULL,
+    excerpt TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    read_at TIMESTAMPTZ,
+    UNIQUE (user_name, user_host, message_id),
+    CONSTRAINT notifications_user_fkey
+        FOREIGN KEY (user_name, user_host)
+        REFERENCES users(name, host)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_notifications_unread
+    ON notifications (user_host, user_name, created_at)
+    WHERE read_at IS NULL;
@@ -0,0 +1,17 @@
+-- The last message this host's users have read in each channel, on any
+-- server, local or on another host
+CREATE TABLE read_markers (
+    user_name TEXT NOT NULL,
+    user_host TEXT NOT NULL,
+    server_id UUID NOT NULL,
+    server_host TEXT NOT NULL,
+    channel_id UUID NOT NULL,
+    message_id UUID NOT NULL,
+    message_created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_name, user_host, channel_id),
+    CONSTRAINT read_markers_user_fkey
+        FOREIGN KEY (user_name, user_host)
+        REFERENCES users(name, host)
+        ON DELETE CASCADE
+);
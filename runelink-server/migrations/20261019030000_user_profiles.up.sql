@@ -0,0 +1,7 @@
+-- Profile fields, set by users on their home host and cached by other hosts
+-- along with the rest of the user
+ALTER TABLE users
+    ADD COLUMN display_name TEXT,
+    ADD COLUMN bio TEXT,
+    ADD COLUMN avatar_url TEXT,
+    ADD COLUMN status_text TEXT;
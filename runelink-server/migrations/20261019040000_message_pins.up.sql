@@ -0,0 +1,11 @@
+-- Messages pinned in their channels by server admins
+CREATE TABLE message_pins (
+    message_id UUID PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    channel_id UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    pinned_by_name TEXT NOT NULL,
+    pinned_by_host TEXT NOT NULL,
+    pinned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_message_pins_channel
+    ON message_pins (channel_id, pinned_at);
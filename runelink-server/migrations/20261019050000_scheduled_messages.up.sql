@@ -0,0 +1,26 @@
+-- Messages of this host's users waiting to be sent, to servers of any host
+CREATE TABLE scheduled_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    server_id UUID NOT NULL,
+    server_host TEXT NOT NULL,
+    channel_id UUID NOT NULL,
+    author_name TEXT NOT NULL,
+    author_host TEXT NOT NULL,
+    body TEXT NOT NULL,
+    send_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    failed_at TIMESTAMPTZ,
+    CONSTRAINT scheduled_messages_author_fkey
+        FOREIGN KEY (author_name, author_host)
+        REFERENCES users(name, host)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_scheduled_messages_due
+    ON scheduled_messages (send_at)
+    WHERE failed_at IS NULL;
+
+CREATE INDEX idx_scheduled_messages_author
+    ON scheduled_messages (author_host, author_name, send_at);
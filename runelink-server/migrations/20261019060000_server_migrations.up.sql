@@ -0,0 +1,8 @@
+-- Servers that moved to another host, kept so requests for them can be
+-- pointed to their new host
+CREATE TABLE server_migrations (
+    server_id UUID PRIMARY KEY,
+    from_host TEXT NOT NULL,
+    to_host TEXT NOT NULL,
+    migrated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
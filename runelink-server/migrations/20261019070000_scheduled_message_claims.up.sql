@@ -0,0 +1,2 @@
+-- Set while a scheduled message is being sent, so it is never sent twice
+ALTER TABLE scheduled_messages ADD COLUMN claimed_at TIMESTAMPTZ;
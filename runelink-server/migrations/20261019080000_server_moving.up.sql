@@ -0,0 +1,9 @@
+-- Local servers that are being moved to another host. They can't be changed
+-- until the move is done, so nothing written during the export is lost.
+CREATE TABLE server_moves (
+    server_id UUID PRIMARY KEY
+        REFERENCES servers (id)
+        ON DELETE CASCADE,
+    to_host TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
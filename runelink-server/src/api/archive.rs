@@ -0,0 +1,54 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    body::Body,
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::ARCHIVE_CONTENT_TYPE;
+use uuid::Uuid;
+
+/// GET /servers/{server_id}/export
+pub async fn export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /servers/{server_id}/export");
+    authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::archive::auth::export(server_id),
+    )
+    .await?;
+    let archive = ops::archive::export(&state, server_id).await?;
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, ARCHIVE_CONTENT_TYPE)],
+        Body::from_stream(archive),
+    ))
+}
+
+/// POST /admin/servers/import
+pub async fn import(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /admin/servers/import");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::archive::auth::import(),
+    )
+    .await?;
+    let summary =
+        ops::archive::import(&state, &session, body.into_data_stream()).await?;
+    Ok((StatusCode::CREATED, Json(summary)))
+}
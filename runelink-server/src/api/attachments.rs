@@ -0,0 +1,259 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::{ApiError, ApiResult},
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{Attachment, AttachmentUpload};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct AttachmentQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// Read the `file` field of an upload, refusing anything larger than the
+/// configured maximum before it is fully received.
+async fn read_upload(
+    state: &AppState,
+    mut multipart: Multipart,
+) -> ApiResult<AttachmentUpload> {
+    let bad_form = |e: axum::extract::multipart::MultipartError| {
+        ApiError::BadRequest(format!("Invalid upload: {e}"))
+    };
+    while let Some(mut field) =
+        multipart.next_field().await.map_err(bad_form)?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let name = field.file_name().unwrap_or_default().to_string();
+        let mime_type = field.content_type().unwrap_or_default().to_string();
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(bad_form)? {
+            data.extend_from_slice(&chunk);
+            if data.len() as u64 > state.config.attachment_max_bytes {
+                return Err(ApiError::PayloadTooLarge(format!(
+                    "Attachments may be at most {} bytes",
+                    state.config.attachment_max_bytes
+                )));
+            }
+        }
+        return Ok(AttachmentUpload {
+            name,
+            mime_type,
+            data,
+        });
+    }
+    Err(ApiError::BadRequest("Upload has no `file` field".into()))
+}
+
+/// Serve attachment contents as a download, never to be rendered inline.
+fn content_response(
+    attachment: Attachment,
+    data: Vec<u8>,
+) -> impl IntoResponse {
+    // Header values must be visible ASCII; quotes would end the file name
+    let file_name: String = attachment
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, attachment.mime_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        data,
+    )
+}
+
+/// POST /servers/{server_id}/channels/{channel_id}/attachments
+pub async fn upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<AttachmentQueryParams>,
+    multipart: Multipart,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/channels/{channel_id}/attachments?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::attachments::auth::upload(server_id),
+    )
+    .await?;
+    let upload = read_upload(&state, multipart).await?;
+    let attachment = ops::attachments::upload(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        &upload,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(attachment)))
+}
+
+/// GET /servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}
+pub async fn get_by_id(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id, attachment_id)): Path<(Uuid, Uuid, Uuid)>,
+    Query(params): Query<AttachmentQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::attachments::auth::get_by_id(server_id),
+    )
+    .await?;
+    let attachment = ops::attachments::get_by_id(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        attachment_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(attachment)))
+}
+
+/// GET /servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content
+pub async fn get_content(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id, attachment_id)): Path<(Uuid, Uuid, Uuid)>,
+    Query(params): Query<AttachmentQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::attachments::auth::get_content(server_id),
+    )
+    .await?;
+    let (attachment, data) = ops::attachments::get_content(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        attachment_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(content_response(attachment, data))
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// POST /federation/servers/{server_id}/channels/{channel_id}/attachments
+    pub async fn upload(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+        multipart: Multipart,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/channels/{channel_id}/attachments"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::attachments::auth::federated::upload(server_id),
+        )
+        .await?;
+        let upload = read_upload(&state, multipart).await?;
+        let attachment = ops::attachments::upload(
+            &state, &session, server_id, channel_id, &upload, None,
+        )
+        .await?;
+        Ok((StatusCode::CREATED, Json(attachment)))
+    }
+
+    /// GET /federation/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}
+    pub async fn get_by_id(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id, attachment_id)): Path<(Uuid, Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "GET /federation/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::attachments::auth::federated::get_by_id(server_id),
+        )
+        .await?;
+        let attachment = ops::attachments::get_by_id(
+            &state,
+            &session,
+            server_id,
+            channel_id,
+            attachment_id,
+            None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(attachment)))
+    }
+
+    /// GET /federation/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content
+    pub async fn get_content(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id, attachment_id)): Path<(Uuid, Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "GET /federation/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::attachments::auth::federated::get_content(server_id),
+        )
+        .await?;
+        let (attachment, data) = ops::attachments::get_content(
+            &state,
+            &session,
+            server_id,
+            channel_id,
+            attachment_id,
+            None,
+        )
+        .await?;
+        Ok(content_response(attachment, data))
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::AuditLogQuery;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct AuditQueryParams {
+    pub target_host: Option<String>,
+    /// Only for the host-wide log
+    pub server_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub actor_host: Option<String>,
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl AuditQueryParams {
+    fn audit_query(&self) -> AuditLogQuery {
+        AuditLogQuery {
+            action: self.action.clone(),
+            actor_host: self.actor_host.clone(),
+            before: self.before,
+            limit: self.limit,
+        }
+    }
+}
+
+/// GET /servers/{server_id}/audit-log
+pub async fn get_by_server(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<AuditQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /servers/{server_id}/audit-log?{:?}", params);
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::audit::auth::get_by_server(server_id),
+    )
+    .await?;
+    let entries = ops::audit::get_by_server(
+        &state,
+        &session,
+        server_id,
+        &params.audit_query(),
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(entries)))
+}
+
+/// GET /admin/audit-log
+pub async fn get_all(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<AuditQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /admin/audit-log?{:?}", params);
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::audit::auth::get_all(),
+    )
+    .await?;
+    let entries = ops::audit::get_all(
+        &state,
+        &session,
+        params.server_id,
+        &params.audit_query(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(entries)))
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// GET /federation/servers/{server_id}/audit-log
+    pub async fn get_by_server(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Query(params): Query<AuditQueryParams>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("GET /federation/servers/{server_id}/audit-log?{:?}", params);
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::audit::auth::federated::get_by_server(server_id),
+        )
+        .await?;
+        let entries = ops::audit::get_by_server(
+            &state,
+            &session,
+            server_id,
+            &params.audit_query(),
+            None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(entries)))
+    }
+}
@@ -1,12 +1,8 @@
 use crate::{
     error::{ApiError, ApiResult},
-    queries,
+    ops, queries, secrets,
     state::AppState,
 };
-use argon2::{
-    Argon2, PasswordHasher, PasswordVerifier,
-    password_hash::{PasswordHash, SaltString, rand_core::OsRng},
-};
 use axum::{
     Form, Json, Router,
     extract::State,
@@ -17,8 +13,8 @@ use jsonwebtoken::{Algorithm, Header};
 use log::info;
 use reqwest::StatusCode;
 use runelink_types::{
-    ClientAccessClaims, NewUser, RefreshToken, SignupRequest, TokenRequest,
-    TokenResponse, UserRef, UserRole,
+    ClientAccessClaims, RefreshToken, SignupRequest, TokenRequest,
+    TokenResponse, UserRef,
 };
 use serde_json::json;
 use time::{Duration, OffsetDateTime};
@@ -36,7 +32,16 @@ pub fn router() -> Router<AppState> {
                 .route("/token", post(token))
                 .route("/userinfo", get(userinfo))
                 .route("/register", post(register_client))
-                .route("/signup", post(signup)),
+                .route("/signup", post(signup))
+                .route("/password", post(super::passwords::change_password))
+                .route(
+                    "/password/reset",
+                    post(super::passwords::reset_password),
+                )
+                .route("/totp", get(super::totp::status))
+                .route("/totp/enroll", post(super::totp::enroll))
+                .route("/totp/confirm", post(super::totp::confirm))
+                .route("/totp/disable", post(super::totp::disable)),
         )
 }
 
@@ -103,15 +108,18 @@ pub async fn token(
                 user_ref.clone(),
             )
             .await?;
-            let parsed_hash = PasswordHash::new(&account.password_hash)
-                .map_err(|_| {
-                    ApiError::AuthError("invalid password hash".into())
-                })?;
-            Argon2::default()
-                .verify_password(password.as_bytes(), &parsed_hash)
-                .map_err(|_| {
-                    ApiError::AuthError("invalid credentials".into())
-                })?;
+            secrets::verify_password(&password, &account.password_hash)?;
+            if !account.approved {
+                return Err(ApiError::AuthError(
+                    "account is pending approval by a host admin".into(),
+                ));
+            }
+            ops::totp::check_second_factor(
+                &state,
+                &user_ref,
+                req.otp.as_deref(),
+            )
+            .await?;
 
             // Create client access JWT (valid only on this server)
             let lifetime = Duration::hours(1);
@@ -221,28 +229,6 @@ pub async fn signup(
     Json(req): Json<SignupRequest>,
 ) -> ApiResult<impl IntoResponse> {
     info!("POST /auth/signup\nsignup_request = {:#?}", req);
-    // Insert user
-    let new_user = NewUser {
-        name: req.name,
-        host: state.config.local_host(),
-        role: UserRole::User,
-    };
-    let user = queries::users::insert(&state.db_pool, &new_user).await?;
-
-    // Hash password
-    let salt = SaltString::generate(&mut OsRng);
-    let password_hash = Argon2::default()
-        .hash_password(req.password.as_bytes(), &salt)
-        .map_err(|e| ApiError::Internal(format!("hashing error: {e}")))?
-        .to_string();
-
-    // Insert local account
-    let _ = queries::accounts::insert(
-        &state.db_pool,
-        user.as_ref(),
-        &password_hash,
-    )
-    .await?;
-
+    let user = ops::signups::signup(&state, &req).await?;
     Ok((StatusCode::CREATED, Json(user)))
 }
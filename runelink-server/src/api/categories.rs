@@ -0,0 +1,235 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{ChannelCategoryUpdate, NewChannelCategory};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct CategoryQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// POST /servers/{server_id}/categories
+pub async fn create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<CategoryQueryParams>,
+    Json(new_category): Json<NewChannelCategory>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/categories?target_host={:?}\nnew_category = {:#?}",
+        params.target_host, new_category
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::categories::auth::create(server_id),
+    )
+    .await?;
+    let category = ops::categories::create(
+        &state,
+        &session,
+        server_id,
+        &new_category,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+/// GET /servers/{server_id}/categories
+pub async fn get_by_server(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<CategoryQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/categories?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::categories::auth::get_by_server(server_id),
+    )
+    .await?;
+    let categories = ops::categories::get_by_server(
+        &state,
+        &session,
+        server_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(categories)))
+}
+
+/// PATCH /servers/{server_id}/categories/{category_id}
+pub async fn update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, category_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<CategoryQueryParams>,
+    Json(update): Json<ChannelCategoryUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PATCH /servers/{server_id}/categories/{category_id}?target_host={:?}\nupdate = {:#?}",
+        params.target_host, update
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::categories::auth::update(server_id),
+    )
+    .await?;
+    let category = ops::categories::update(
+        &state,
+        &session,
+        server_id,
+        category_id,
+        &update,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(category)))
+}
+
+/// DELETE /servers/{server_id}/categories/{category_id}
+pub async fn delete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, category_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<CategoryQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/categories/{category_id}?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::categories::auth::delete(server_id),
+    )
+    .await?;
+    ops::categories::delete(
+        &state,
+        &session,
+        server_id,
+        category_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// POST /federation/servers/{server_id}/categories
+    pub async fn create(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(new_category): Json<NewChannelCategory>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/categories\nnew_category = {:#?}",
+            new_category
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::categories::auth::federated::create(server_id),
+        )
+        .await?;
+        let category = ops::categories::create(
+            &state,
+            &session,
+            server_id,
+            &new_category,
+            None,
+        )
+        .await?;
+        Ok((StatusCode::CREATED, Json(category)))
+    }
+
+    /// GET /federation/servers/{server_id}/categories
+    pub async fn get_by_server(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("GET /federation/servers/{server_id}/categories");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::categories::auth::federated::get_by_server(server_id),
+        )
+        .await?;
+        let categories =
+            ops::categories::get_by_server(&state, &session, server_id, None)
+                .await?;
+        Ok((StatusCode::OK, Json(categories)))
+    }
+
+    /// PATCH /federation/servers/{server_id}/categories/{category_id}
+    pub async fn update(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, category_id)): Path<(Uuid, Uuid)>,
+        Json(update): Json<ChannelCategoryUpdate>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PATCH /federation/servers/{server_id}/categories/{category_id}\nupdate = {:#?}",
+            update
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::categories::auth::federated::update(server_id),
+        )
+        .await?;
+        let category = ops::categories::update(
+            &state,
+            &session,
+            server_id,
+            category_id,
+            &update,
+            None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(category)))
+    }
+
+    /// DELETE /federation/servers/{server_id}/categories/{category_id}
+    pub async fn delete(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, category_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "DELETE /federation/servers/{server_id}/categories/{category_id}"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::categories::auth::federated::delete(server_id),
+        )
+        .await?;
+        ops::categories::delete(&state, &session, server_id, category_id, None)
+            .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
@@ -10,13 +10,16 @@ use axum::{
     response::IntoResponse,
 };
 use log::info;
-use runelink_types::NewChannel;
+use runelink_types::{ChannelUpdate, NewChannel};
 use serde::Deserialize;
 use uuid::Uuid;
 
 #[derive(Deserialize, Debug)]
 pub struct ChannelQueryParams {
     pub target_host: Option<String>,
+    /// Also list archived channels
+    #[serde(default)]
+    pub include_archived: bool,
 }
 
 /// POST /servers/{server_id}/channels
@@ -61,12 +64,9 @@ pub async fn get_all(
         ops::channels::auth::get_all(),
     )
     .await?;
-    let channels = ops::channels::get_all(
-        &state,
-        &session,
-        params.target_host.as_deref(),
-    )
-    .await?;
+    let channels =
+        ops::channels::get_all(&state, &session, params.target_host.as_deref())
+            .await?;
     Ok((StatusCode::OK, Json(channels)))
 }
 
@@ -78,8 +78,8 @@ pub async fn get_by_server(
     Query(params): Query<ChannelQueryParams>,
 ) -> ApiResult<impl IntoResponse> {
     info!(
-        "GET /servers/{server_id}/channels?target_host={:?}",
-        params.target_host
+        "GET /servers/{server_id}/channels?target_host={:?}&include_archived={}",
+        params.target_host, params.include_archived
     );
     let session = authorize(
         &state,
@@ -91,6 +91,7 @@ pub async fn get_by_server(
         &state,
         &session,
         server_id,
+        params.include_archived,
         params.target_host.as_deref(),
     )
     .await?;
@@ -125,6 +126,36 @@ pub async fn get_by_id(
     Ok((StatusCode::OK, Json(channel)))
 }
 
+/// PATCH /servers/{server_id}/channels/{channel_id}
+pub async fn update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<ChannelQueryParams>,
+    Json(update): Json<ChannelUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PATCH /servers/{server_id}/channels/{channel_id}?target_host={:?}\nupdate = {:#?}",
+        params.target_host, update
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::channels::auth::update(server_id),
+    )
+    .await?;
+    let channel = ops::channels::update(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        &update,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(channel)))
+}
+
 /// DELETE /servers/{server_id}/channels/{channel_id}
 pub async fn delete(
     State(state): State<AppState>,
@@ -153,6 +184,92 @@ pub async fn delete(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// POST /servers/{server_id}/channels/{channel_id}/archive
+pub async fn archive(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<ChannelQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/channels/{channel_id}/archive?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::channels::auth::archive(server_id),
+    )
+    .await?;
+    let channel = ops::channels::set_archived(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        true,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(channel)))
+}
+
+/// DELETE /servers/{server_id}/channels/{channel_id}/archive
+pub async fn unarchive(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<ChannelQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/channels/{channel_id}/archive?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::channels::auth::archive(server_id),
+    )
+    .await?;
+    let channel = ops::channels::set_archived(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        false,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(channel)))
+}
+
+/// POST /servers/{server_id}/channels/{channel_id}/restore
+pub async fn restore(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<ChannelQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/channels/{channel_id}/restore?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::channels::auth::restore(server_id),
+    )
+    .await?;
+    let channel = ops::channels::restore(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(channel)))
+}
+
 /// Federation endpoints (server-to-server authentication required).
 pub mod federated {
     use super::*;
@@ -206,17 +323,26 @@ pub mod federated {
         State(state): State<AppState>,
         headers: HeaderMap,
         Path(server_id): Path<Uuid>,
+        Query(params): Query<ChannelQueryParams>,
     ) -> ApiResult<impl IntoResponse> {
-        info!("GET /federation/servers/{server_id}/channels");
+        info!(
+            "GET /federation/servers/{server_id}/channels?include_archived={}",
+            params.include_archived
+        );
         let session = authorize(
             &state,
             Principal::from_federation_headers(&headers, &state).await?,
             ops::channels::auth::federated::get_by_server(server_id),
         )
         .await?;
-        let channels =
-            ops::channels::get_by_server(&state, &session, server_id, None)
-                .await?;
+        let channels = ops::channels::get_by_server(
+            &state,
+            &session,
+            server_id,
+            params.include_archived,
+            None,
+        )
+        .await?;
         Ok((StatusCode::OK, Json(channels)))
     }
 
@@ -240,6 +366,30 @@ pub mod federated {
         Ok((StatusCode::OK, Json(channel)))
     }
 
+    /// PATCH /federation/servers/{server_id}/channels/{channel_id}
+    pub async fn update(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+        Json(update): Json<ChannelUpdate>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PATCH /federation/servers/{server_id}/channels/{channel_id}\nupdate = {:#?}",
+            update
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::channels::auth::federated::update(server_id),
+        )
+        .await?;
+        let channel = ops::channels::update(
+            &state, &session, server_id, channel_id, &update, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(channel)))
+    }
+
     /// DELETE /federation/servers/{server_id}/channels/{channel_id}
     pub async fn delete(
         State(state): State<AppState>,
@@ -257,4 +407,70 @@ pub mod federated {
             .await?;
         Ok(StatusCode::NO_CONTENT)
     }
+
+    /// POST /federation/servers/{server_id}/channels/{channel_id}/archive
+    pub async fn archive(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/channels/{channel_id}/archive"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::channels::auth::federated::archive(server_id),
+        )
+        .await?;
+        let channel = ops::channels::set_archived(
+            &state, &session, server_id, channel_id, true, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(channel)))
+    }
+
+    /// DELETE /federation/servers/{server_id}/channels/{channel_id}/archive
+    pub async fn unarchive(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "DELETE /federation/servers/{server_id}/channels/{channel_id}/archive"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::channels::auth::federated::archive(server_id),
+        )
+        .await?;
+        let channel = ops::channels::set_archived(
+            &state, &session, server_id, channel_id, false, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(channel)))
+    }
+
+    /// POST /federation/servers/{server_id}/channels/{channel_id}/restore
+    pub async fn restore(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/channels/{channel_id}/restore"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::channels::auth::federated::restore(server_id),
+        )
+        .await?;
+        let channel = ops::channels::restore(
+            &state, &session, server_id, channel_id, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(channel)))
+    }
 }
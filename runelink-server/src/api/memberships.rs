@@ -1,7 +1,7 @@
 use crate::{
     auth::{Principal, authorize},
     error::{ApiError, ApiResult},
-    ops,
+    ops, queries,
     state::AppState,
 };
 use axum::{
@@ -10,7 +10,11 @@ use axum::{
     response::IntoResponse,
 };
 use log::info;
-use runelink_types::{NewServerMembership, UserRef};
+use runelink_client::util::pad_host;
+use runelink_types::{
+    NewServerMembership, OwnershipTransfer, ServerMembership, ServerRoleUpdate,
+    UserRef,
+};
 use serde::Deserialize;
 use uuid::Uuid;
 
@@ -125,6 +129,65 @@ pub async fn delete(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// PATCH /servers/{server_id}/users/{host}/{name}
+pub async fn update_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, host, name)): Path<(Uuid, String, String)>,
+    Query(params): Query<MembershipQueryParams>,
+    Json(update): Json<ServerRoleUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PATCH /servers/{server_id}/users/{host}/{name}?target_host={:?}\nupdate = {:#?}",
+        params.target_host, update
+    );
+    let mut session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::memberships::auth::update_role(server_id),
+    )
+    .await?;
+    let member = ops::memberships::update_role(
+        &state,
+        &mut session,
+        server_id,
+        UserRef::new(name, host),
+        &update,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(member)))
+}
+
+/// POST /servers/{server_id}/owner
+pub async fn transfer_ownership(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<MembershipQueryParams>,
+    Json(transfer): Json<OwnershipTransfer>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/owner?target_host={:?}\ntransfer = {:#?}",
+        params.target_host, transfer
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::memberships::auth::transfer_ownership(server_id),
+    )
+    .await?;
+    let member = ops::memberships::transfer_ownership(
+        &state,
+        &session,
+        server_id,
+        &transfer,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(member)))
+}
+
 /// Federation endpoints (server-to-server authentication required).
 pub mod federated {
     use super::*;
@@ -151,8 +214,7 @@ pub mod federated {
             .is_remote_host(Some(&new_membership.user_ref.host))
         {
             return Err(ApiError::BadRequest(
-                "User host in membership should not match local host"
-                    .into(),
+                "User host in membership should not match local host".into(),
             ));
         }
         let mut session = authorize(
@@ -197,4 +259,123 @@ pub mod federated {
         .await?;
         Ok(StatusCode::NO_CONTENT)
     }
+
+    /// PATCH /federation/servers/{server_id}/users/{host}/{name}
+    pub async fn update_role(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, host, name)): Path<(Uuid, String, String)>,
+        Json(update): Json<ServerRoleUpdate>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PATCH /federation/servers/{server_id}/users/{host}/{name}\nupdate = {:#?}",
+            update
+        );
+        let mut session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::memberships::auth::federated::update_role(server_id),
+        )
+        .await?;
+        let member = ops::memberships::update_role(
+            &state,
+            &mut session,
+            server_id,
+            UserRef::new(name, host),
+            &update,
+            None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(member)))
+    }
+
+    /// POST /federation/servers/{server_id}/owner
+    pub async fn transfer_ownership(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(transfer): Json<OwnershipTransfer>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/owner\ntransfer = {:#?}",
+            transfer
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::memberships::auth::federated::transfer_ownership(server_id),
+        )
+        .await?;
+        let member = ops::memberships::transfer_ownership(
+            &state, &session, server_id, &transfer, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(member)))
+    }
+
+    /// PUT /federation/users/{host}/{name}/servers/{server_id}
+    pub async fn sync(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((host, name, server_id)): Path<(String, String, Uuid)>,
+        Json(membership): Json<ServerMembership>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PUT /federation/users/{host}/{name}/servers/{server_id}\nmembership = {:#?}",
+            membership
+        );
+        if membership.server.id != server_id
+            || membership.user_ref != UserRef::new(name, host)
+        {
+            return Err(ApiError::BadRequest(
+                "Membership does not match path".into(),
+            ));
+        }
+        // Trust the host the server is cached under, not the one claimed
+        let server_host = queries::server_migrations::get_cached_remote_host(
+            &state.db_pool,
+            server_id,
+        )
+        .await?;
+        if pad_host(&membership.server.host) != pad_host(&server_host) {
+            return Err(ApiError::BadRequest(
+                "Membership is not for a server of that host".into(),
+            ));
+        }
+        authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::memberships::auth::federated::sync(server_host),
+        )
+        .await?;
+        ops::memberships::sync_remote(&state, &membership).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// DELETE /federation/users/{host}/{name}/servers/{server_id}
+    pub async fn unsync(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((host, name, server_id)): Path<(String, String, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("DELETE /federation/users/{host}/{name}/servers/{server_id}");
+        let server_host = queries::server_migrations::get_cached_remote_host(
+            &state.db_pool,
+            server_id,
+        )
+        .await?;
+        authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::memberships::auth::federated::sync(server_host),
+        )
+        .await?;
+        ops::memberships::unsync_remote(
+            &state,
+            server_id,
+            UserRef::new(name, host),
+        )
+        .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
 }
@@ -17,6 +17,9 @@ use uuid::Uuid;
 #[derive(Deserialize, Debug)]
 pub struct MessageQueryParams {
     pub target_host: Option<String>,
+    /// Also list tombstones of deleted messages
+    #[serde(default)]
+    pub include_deleted: bool,
 }
 
 /// POST /servers/{server_id}/channels/{channel_id}/messages
@@ -62,12 +65,9 @@ pub async fn get_all(
         ops::messages::auth::get_all(),
     )
     .await?;
-    let messages = ops::messages::get_all(
-        &state,
-        &session,
-        params.target_host.as_deref(),
-    )
-    .await?;
+    let messages =
+        ops::messages::get_all(&state, &session, params.target_host.as_deref())
+            .await?;
     Ok((StatusCode::OK, Json(messages)))
 }
 
@@ -79,8 +79,8 @@ pub async fn get_by_server(
     Query(params): Query<MessageQueryParams>,
 ) -> ApiResult<impl IntoResponse> {
     info!(
-        "GET /servers/{server_id}/messages?target_host={:?}",
-        params.target_host
+        "GET /servers/{server_id}/messages?target_host={:?}&include_deleted={}",
+        params.target_host, params.include_deleted
     );
     let session = authorize(
         &state,
@@ -92,6 +92,7 @@ pub async fn get_by_server(
         &state,
         &session,
         server_id,
+        params.include_deleted,
         params.target_host.as_deref(),
     )
     .await?;
@@ -106,8 +107,8 @@ pub async fn get_by_channel(
     Query(params): Query<MessageQueryParams>,
 ) -> ApiResult<impl IntoResponse> {
     info!(
-        "GET /servers/{server_id}/channels/{channel_id}/messages?target_host={:?}",
-        params.target_host
+        "GET /servers/{server_id}/channels/{channel_id}/messages?target_host={:?}&include_deleted={}",
+        params.target_host, params.include_deleted
     );
     let session = authorize(
         &state,
@@ -120,6 +121,7 @@ pub async fn get_by_channel(
         &session,
         server_id,
         channel_id,
+        params.include_deleted,
         params.target_host.as_deref(),
     )
     .await?;
@@ -238,17 +240,26 @@ pub mod federated {
         State(state): State<AppState>,
         headers: HeaderMap,
         Path(server_id): Path<Uuid>,
+        Query(params): Query<MessageQueryParams>,
     ) -> ApiResult<impl IntoResponse> {
-        info!("GET /federation/servers/{server_id}/messages");
+        info!(
+            "GET /federation/servers/{server_id}/messages?include_deleted={}",
+            params.include_deleted
+        );
         let session = authorize(
             &state,
             Principal::from_federation_headers(&headers, &state).await?,
             ops::messages::auth::federated::get_by_server(server_id),
         )
         .await?;
-        let messages =
-            ops::messages::get_by_server(&state, &session, server_id, None)
-                .await?;
+        let messages = ops::messages::get_by_server(
+            &state,
+            &session,
+            server_id,
+            params.include_deleted,
+            None,
+        )
+        .await?;
         Ok((StatusCode::OK, Json(messages)))
     }
 
@@ -257,9 +268,11 @@ pub mod federated {
         State(state): State<AppState>,
         headers: HeaderMap,
         Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+        Query(params): Query<MessageQueryParams>,
     ) -> ApiResult<impl IntoResponse> {
         info!(
-            "GET /federation/servers/{server_id}/channels/{channel_id}/messages"
+            "GET /federation/servers/{server_id}/channels/{channel_id}/messages?include_deleted={}",
+            params.include_deleted
         );
         let session = authorize(
             &state,
@@ -268,7 +281,12 @@ pub mod federated {
         )
         .await?;
         let messages = ops::messages::get_by_channel(
-            &state, &session, server_id, channel_id, None,
+            &state,
+            &session,
+            server_id,
+            channel_id,
+            params.include_deleted,
+            None,
         )
         .await?;
         Ok((StatusCode::OK, Json(messages)))
@@ -1,18 +1,37 @@
 use crate::state::AppState;
 use axum::{
-    Router,
-    extract::Query,
+    Json, Router,
+    extract::{DefaultBodyLimit, Query, State},
     response::IntoResponse,
-    routing::{delete, get, post},
+    routing::{delete, get, patch, post, put},
 };
 use log::info;
+use runelink_types::{
+    FEDERATION_LEGACY_PATH, FEDERATION_V1_PATH, HostDiscovery,
+    WELL_KNOWN_RUNELINK_PATH,
+};
 use serde::Deserialize;
 
+mod archive;
+mod attachments;
+mod audit;
 mod auth;
+mod categories;
 mod channels;
 mod memberships;
 mod messages;
+mod moderation;
+mod notifications;
+mod passwords;
+mod pins;
+mod presence;
+mod read_markers;
+mod retention;
+mod scheduled_messages;
+mod server_migrations;
 mod servers;
+mod signups;
+mod totp;
 mod users;
 
 /// Creates a router for all API endpoints.
@@ -20,14 +39,20 @@ pub fn router() -> Router<AppState> {
     Router::new()
         // Mount auth router (includes OIDC discovery and auth endpoints)
         .merge(auth::router())
-        // Mount federation router (server-to-server endpoints)
-        .nest("/federation", federation_router())
+        // Mount federation router (server-to-server endpoints). The
+        // unversioned prefix is kept for hosts that predate versioning.
+        .nest(FEDERATION_V1_PATH, federation_router())
+        .nest(FEDERATION_LEGACY_PATH, federation_router())
+        // Host discovery must be at root level, next to the OIDC documents
+        .route(WELL_KNOWN_RUNELINK_PATH, get(discovery))
         // API routes
         .route("/ping", get(ping))
         .route("/users", get(users::get_all).post(users::create))
         .route(
             "/users/{host}/{name}",
-            get(users::get_by_ref).delete(users::delete),
+            get(users::get_by_ref)
+                .patch(users::update_profile)
+                .delete(users::delete),
         )
         .route(
             "/users/{host}/{name}/hosts",
@@ -38,28 +63,111 @@ pub fn router() -> Router<AppState> {
             get(memberships::get_by_user),
         )
         .route("/messages", get(messages::get_all))
+        .route("/notifications", get(notifications::get_inbox))
+        .route("/notifications/read", post(notifications::mark_all_read))
+        .route(
+            "/notifications/{notification_id}/read",
+            post(notifications::mark_read),
+        )
+        .route("/presence", put(presence::update))
+        .route("/servers/{server_id}/presence", get(presence::get_by_server))
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/typing",
+            get(presence::get_typing).post(presence::start_typing),
+        )
+        .route("/read-markers", get(read_markers::get_all))
+        .route("/unread", get(read_markers::get_unread))
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/read-marker",
+            put(read_markers::set),
+        )
         .route(
             "/servers/{server_id}/channels/{channel_id}/messages/{message_id}",
             get(messages::get_by_id).delete(messages::delete),
         )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/scheduled-messages",
+            post(scheduled_messages::create),
+        )
+        .route("/scheduled-messages", get(scheduled_messages::get_all))
+        .route(
+            "/scheduled-messages/{scheduled_id}",
+            delete(scheduled_messages::cancel),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/pins",
+            get(pins::get_by_channel),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/pins/{message_id}",
+            put(pins::pin).delete(pins::unpin),
+        )
         .route("/channels", get(channels::get_all))
         .route(
             "/servers/{server_id}/channels/{channel_id}",
-            get(channels::get_by_id).delete(channels::delete),
+            get(channels::get_by_id)
+                .patch(channels::update)
+                .delete(channels::delete),
         )
         .route(
             "/servers/{server_id}/channels/{channel_id}/messages",
             get(messages::get_by_channel).post(messages::create),
         )
+        // Uploads are limited by the attachment size limit instead
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/attachments",
+            post(attachments::upload).layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}",
+            get(attachments::get_by_id),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content",
+            get(attachments::get_content),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/archive",
+            post(channels::archive).delete(channels::unarchive),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/restore",
+            post(channels::restore),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/retention",
+            patch(retention::update_channel).delete(retention::delete_channel),
+        )
+        .route(
+            "/servers/{server_id}/retention",
+            get(retention::get_by_server)
+                .patch(retention::update_server)
+                .delete(retention::delete_server),
+        )
         .route("/servers", get(servers::get_all).post(servers::create))
         .route(
             "/servers/{server_id}",
-            get(servers::get_by_id).delete(servers::delete),
+            get(servers::get_by_id)
+                .patch(servers::update)
+                .delete(servers::delete),
+        )
+        .route("/servers/{server_id}/export", get(archive::export))
+        .route(
+            "/servers/{server_id}/migration",
+            post(server_migrations::migrate),
         )
         .route(
             "/servers/{server_id}/channels",
             get(channels::get_by_server).post(channels::create),
         )
+        .route(
+            "/servers/{server_id}/categories",
+            get(categories::get_by_server).post(categories::create),
+        )
+        .route(
+            "/servers/{server_id}/categories/{category_id}",
+            patch(categories::update).delete(categories::delete),
+        )
         .route(
             "/servers/{server_id}/messages",
             get(messages::get_by_server),
@@ -75,8 +183,49 @@ pub fn router() -> Router<AppState> {
         .route(
             "/servers/{server_id}/users/{host}/{name}",
             get(memberships::get_by_user_and_server)
+                .patch(memberships::update_role)
                 .delete(memberships::delete),
         )
+        .route(
+            "/servers/{server_id}/owner",
+            post(memberships::transfer_ownership),
+        )
+        .route("/servers/{server_id}/audit-log", get(audit::get_by_server))
+        .route(
+            "/servers/{server_id}/bans",
+            get(moderation::get_bans).post(moderation::create_ban),
+        )
+        .route(
+            "/servers/{server_id}/bans/{ban_id}",
+            delete(moderation::delete_ban),
+        )
+        .route(
+            "/servers/{server_id}/timeouts",
+            get(moderation::get_timeouts).post(moderation::create_timeout),
+        )
+        .route(
+            "/servers/{server_id}/timeouts/{host}/{name}",
+            delete(moderation::delete_timeout),
+        )
+        .route(
+            "/servers/{server_id}/users/{host}/{name}/messages",
+            delete(moderation::delete_messages_by_user),
+        )
+        // Host admin routes
+        .route("/admin/audit-log", get(audit::get_all))
+        .route(
+            "/admin/invites",
+            get(signups::get_invites).post(signups::create_invite),
+        )
+        .route("/admin/servers/import", post(archive::import))
+        .route("/admin/invites/{invite_id}", delete(signups::delete_invite))
+        .route("/admin/signups", get(signups::get_pending))
+        .route("/admin/signups/{name}", delete(signups::reject))
+        .route("/admin/signups/{name}/approve", post(signups::approve))
+        .route(
+            "/admin/users/{name}/password-reset",
+            post(passwords::create_reset),
+        )
 }
 
 /// Creates a router for all federation endpoints (server-to-server).
@@ -88,10 +237,38 @@ pub fn federation_router() -> Router<AppState> {
         )
         .route(
             "/servers/{server_id}/users/{host}/{name}",
-            delete(memberships::federated::delete),
+            patch(memberships::federated::update_role)
+                .delete(memberships::federated::delete),
+        )
+        .route(
+            "/servers/{server_id}/owner",
+            post(memberships::federated::transfer_ownership),
+        )
+        .route(
+            "/users/{host}/{name}/servers/{server_id}",
+            put(memberships::federated::sync)
+                .delete(memberships::federated::unsync),
+        )
+        .route(
+            "/users/{host}/{name}/notifications/{message_id}",
+            put(notifications::federated::create)
+                .delete(notifications::federated::delete),
         )
         .route("/servers", post(servers::federated::create))
-        .route("/servers/{server_id}", delete(servers::federated::delete))
+        .route(
+            "/servers/{server_id}",
+            patch(servers::federated::update)
+                .delete(servers::federated::delete),
+        )
+        .route(
+            "/servers/{server_id}/migration",
+            post(server_migrations::federated::migrate)
+                .put(server_migrations::federated::apply),
+        )
+        .route(
+            "/servers/{server_id}/handoff",
+            post(server_migrations::federated::receive),
+        )
         .route(
             "/servers/{server_id}/with_channels",
             get(servers::federated::get_with_channels),
@@ -101,12 +278,73 @@ pub fn federation_router() -> Router<AppState> {
             post(channels::federated::create)
                 .get(channels::federated::get_by_server),
         )
+        .route(
+            "/servers/{server_id}/categories",
+            post(categories::federated::create)
+                .get(categories::federated::get_by_server),
+        )
+        .route(
+            "/servers/{server_id}/categories/{category_id}",
+            patch(categories::federated::update)
+                .delete(categories::federated::delete),
+        )
         .route("/channels", get(channels::federated::get_all))
         .route(
             "/servers/{server_id}/channels/{channel_id}",
             get(channels::federated::get_by_id)
+                .patch(channels::federated::update)
                 .delete(channels::federated::delete),
         )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/attachments",
+            post(attachments::federated::upload)
+                .layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}",
+            get(attachments::federated::get_by_id),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/attachments/{attachment_id}/content",
+            get(attachments::federated::get_content),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/archive",
+            post(channels::federated::archive)
+                .delete(channels::federated::unarchive),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/restore",
+            post(channels::federated::restore),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/retention",
+            patch(retention::federated::update_channel)
+                .delete(retention::federated::delete_channel),
+        )
+        .route(
+            "/servers/{server_id}/retention",
+            get(retention::federated::get_by_server)
+                .patch(retention::federated::update_server)
+                .delete(retention::federated::delete_server),
+        )
+        .route(
+            "/servers/{server_id}/unread",
+            post(read_markers::federated::count_unread),
+        )
+        .route(
+            "/users/{host}/{name}/presence",
+            put(presence::federated::update),
+        )
+        .route(
+            "/servers/{server_id}/presence",
+            get(presence::federated::get_by_server),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/typing",
+            get(presence::federated::get_typing)
+                .post(presence::federated::start_typing),
+        )
         .route("/messages", get(messages::federated::get_all))
         .route(
             "/servers/{server_id}/messages",
@@ -122,7 +360,50 @@ pub fn federation_router() -> Router<AppState> {
             get(messages::federated::get_by_id)
                 .delete(messages::federated::delete),
         )
-        .route("/users/{host}/{name}", delete(users::federated::delete))
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/pins",
+            get(pins::federated::get_by_channel),
+        )
+        .route(
+            "/servers/{server_id}/channels/{channel_id}/pins/{message_id}",
+            put(pins::federated::pin).delete(pins::federated::unpin),
+        )
+        .route(
+            "/users/{host}/{name}",
+            put(users::federated::sync).delete(users::federated::delete),
+        )
+        .route(
+            "/servers/{server_id}/audit-log",
+            get(audit::federated::get_by_server),
+        )
+        .route(
+            "/servers/{server_id}/bans",
+            get(moderation::federated::get_bans)
+                .post(moderation::federated::create_ban),
+        )
+        .route(
+            "/servers/{server_id}/bans/{ban_id}",
+            delete(moderation::federated::delete_ban),
+        )
+        .route(
+            "/servers/{server_id}/timeouts",
+            get(moderation::federated::get_timeouts)
+                .post(moderation::federated::create_timeout),
+        )
+        .route(
+            "/servers/{server_id}/timeouts/{host}/{name}",
+            delete(moderation::federated::delete_timeout),
+        )
+        .route(
+            "/servers/{server_id}/users/{host}/{name}/messages",
+            delete(moderation::federated::delete_messages_by_user),
+        )
+}
+
+/// GET /.well-known/runelink
+pub async fn discovery(State(state): State<AppState>) -> Json<HostDiscovery> {
+    info!("GET {WELL_KNOWN_RUNELINK_PATH}");
+    Json(state.config.discovery())
 }
 
 #[derive(Deserialize, Debug)]
@@ -0,0 +1,378 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{NewServerBan, NewServerTimeout, UserRef};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct ModerationQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// GET /servers/{server_id}/bans
+pub async fn get_bans(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<ModerationQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/bans?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::moderation::auth::manage(server_id),
+    )
+    .await?;
+    let bans = ops::moderation::get_bans(
+        &state,
+        &session,
+        server_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(bans)))
+}
+
+/// POST /servers/{server_id}/bans
+pub async fn create_ban(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<ModerationQueryParams>,
+    Json(new_ban): Json<NewServerBan>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/bans?target_host={:?}\nnew_ban = {:#?}",
+        params.target_host, new_ban
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::moderation::auth::manage(server_id),
+    )
+    .await?;
+    let ban = ops::moderation::create_ban(
+        &state,
+        &session,
+        server_id,
+        &new_ban,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(ban)))
+}
+
+/// DELETE /servers/{server_id}/bans/{ban_id}
+pub async fn delete_ban(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, ban_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<ModerationQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/bans/{ban_id}?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::moderation::auth::manage(server_id),
+    )
+    .await?;
+    ops::moderation::delete_ban(
+        &state,
+        &session,
+        server_id,
+        ban_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /servers/{server_id}/timeouts
+pub async fn get_timeouts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<ModerationQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/timeouts?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::moderation::auth::manage(server_id),
+    )
+    .await?;
+    let timeouts = ops::moderation::get_timeouts(
+        &state,
+        &session,
+        server_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(timeouts)))
+}
+
+/// POST /servers/{server_id}/timeouts
+pub async fn create_timeout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<ModerationQueryParams>,
+    Json(new_timeout): Json<NewServerTimeout>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/timeouts?target_host={:?}\nnew_timeout = {:#?}",
+        params.target_host, new_timeout
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::moderation::auth::manage(server_id),
+    )
+    .await?;
+    let timeout = ops::moderation::create_timeout(
+        &state,
+        &session,
+        server_id,
+        &new_timeout,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(timeout)))
+}
+
+/// DELETE /servers/{server_id}/timeouts/{host}/{name}
+pub async fn delete_timeout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, host, name)): Path<(Uuid, String, String)>,
+    Query(params): Query<ModerationQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/timeouts/{host}/{name}?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::moderation::auth::manage(server_id),
+    )
+    .await?;
+    ops::moderation::delete_timeout(
+        &state,
+        &session,
+        server_id,
+        UserRef::new(name, host),
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /servers/{server_id}/users/{host}/{name}/messages
+pub async fn delete_messages_by_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, host, name)): Path<(Uuid, String, String)>,
+    Query(params): Query<ModerationQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/users/{host}/{name}/messages?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::moderation::auth::manage(server_id),
+    )
+    .await?;
+    let deleted = ops::moderation::delete_messages_by_user(
+        &state,
+        &session,
+        server_id,
+        UserRef::new(name, host),
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(deleted)))
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// GET /federation/servers/{server_id}/bans
+    pub async fn get_bans(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("GET /federation/servers/{server_id}/bans");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::moderation::auth::federated::manage(server_id),
+        )
+        .await?;
+        let bans = ops::moderation::get_bans(&state, &session, server_id, None)
+            .await?;
+        Ok((StatusCode::OK, Json(bans)))
+    }
+
+    /// POST /federation/servers/{server_id}/bans
+    pub async fn create_ban(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(new_ban): Json<NewServerBan>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/bans\nnew_ban = {:#?}",
+            new_ban
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::moderation::auth::federated::manage(server_id),
+        )
+        .await?;
+        let ban = ops::moderation::create_ban(
+            &state, &session, server_id, &new_ban, None,
+        )
+        .await?;
+        Ok((StatusCode::CREATED, Json(ban)))
+    }
+
+    /// DELETE /federation/servers/{server_id}/bans/{ban_id}
+    pub async fn delete_ban(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, ban_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("DELETE /federation/servers/{server_id}/bans/{ban_id}");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::moderation::auth::federated::manage(server_id),
+        )
+        .await?;
+        ops::moderation::delete_ban(&state, &session, server_id, ban_id, None)
+            .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// GET /federation/servers/{server_id}/timeouts
+    pub async fn get_timeouts(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("GET /federation/servers/{server_id}/timeouts");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::moderation::auth::federated::manage(server_id),
+        )
+        .await?;
+        let timeouts =
+            ops::moderation::get_timeouts(&state, &session, server_id, None)
+                .await?;
+        Ok((StatusCode::OK, Json(timeouts)))
+    }
+
+    /// POST /federation/servers/{server_id}/timeouts
+    pub async fn create_timeout(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(new_timeout): Json<NewServerTimeout>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/timeouts\nnew_timeout = {:#?}",
+            new_timeout
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::moderation::auth::federated::manage(server_id),
+        )
+        .await?;
+        let timeout = ops::moderation::create_timeout(
+            &state,
+            &session,
+            server_id,
+            &new_timeout,
+            None,
+        )
+        .await?;
+        Ok((StatusCode::CREATED, Json(timeout)))
+    }
+
+    /// DELETE /federation/servers/{server_id}/timeouts/{host}/{name}
+    pub async fn delete_timeout(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, host, name)): Path<(Uuid, String, String)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("DELETE /federation/servers/{server_id}/timeouts/{host}/{name}");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::moderation::auth::federated::manage(server_id),
+        )
+        .await?;
+        ops::moderation::delete_timeout(
+            &state,
+            &session,
+            server_id,
+            UserRef::new(name, host),
+            None,
+        )
+        .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// DELETE /federation/servers/{server_id}/users/{host}/{name}/messages
+    pub async fn delete_messages_by_user(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, host, name)): Path<(Uuid, String, String)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "DELETE /federation/servers/{server_id}/users/{host}/{name}/messages"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::moderation::auth::federated::manage(server_id),
+        )
+        .await?;
+        let deleted = ops::moderation::delete_messages_by_user(
+            &state,
+            &session,
+            server_id,
+            UserRef::new(name, host),
+            None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(deleted)))
+    }
+}
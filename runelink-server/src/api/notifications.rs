@@ -0,0 +1,140 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::{ApiError, ApiResult},
+    ops, queries,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{NewNotification, UserRef};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct NotificationQueryParams {
+    /// Include notifications that were already read
+    #[serde(default)]
+    pub all: bool,
+}
+
+/// GET /notifications
+pub async fn get_inbox(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<NotificationQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /notifications?all={}", params.all);
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::notifications::auth::inbox(),
+    )
+    .await?;
+    let notifications =
+        ops::notifications::get_inbox(&state, &session, params.all).await?;
+    Ok((StatusCode::OK, Json(notifications)))
+}
+
+/// POST /notifications/read
+pub async fn mark_all_read(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /notifications/read");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::notifications::auth::inbox(),
+    )
+    .await?;
+    ops::notifications::mark_all_read(&state, &session).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /notifications/{notification_id}/read
+pub async fn mark_read(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(notification_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /notifications/{notification_id}/read");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::notifications::auth::inbox(),
+    )
+    .await?;
+    ops::notifications::mark_read(&state, &session, notification_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// PUT /federation/users/{host}/{name}/notifications/{message_id}
+    pub async fn create(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((host, name, message_id)): Path<(String, String, Uuid)>,
+        Json(new_notification): Json<NewNotification>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PUT /federation/users/{host}/{name}/notifications/{message_id}\nnew_notification = {:#?}",
+            new_notification
+        );
+        if new_notification.message_id != message_id
+            || new_notification.user != UserRef::new(name, host)
+        {
+            return Err(ApiError::BadRequest(
+                "Notification does not match path".into(),
+            ));
+        }
+        authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::notifications::auth::federated::create(
+                new_notification.server_host.clone(),
+            ),
+        )
+        .await?;
+        ops::notifications::receive(&state, &new_notification).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// DELETE /federation/users/{host}/{name}/notifications/{message_id}
+    pub async fn delete(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((host, name, message_id)): Path<(String, String, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "DELETE /federation/users/{host}/{name}/notifications/{message_id}"
+        );
+        let user_ref = UserRef::new(name, host);
+        let server_host = queries::notifications::get_server_host(
+            &state.db_pool,
+            &user_ref,
+            message_id,
+        )
+        .await?;
+        authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::notifications::auth::federated::delete(server_host.clone()),
+        )
+        .await?;
+        ops::notifications::withdraw(
+            &state,
+            &server_host,
+            &user_ref,
+            message_id,
+        )
+        .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
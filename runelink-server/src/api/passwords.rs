@@ -0,0 +1,65 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{
+    ChangePasswordRequest, NewPasswordReset, PasswordResetRequest,
+};
+
+/// POST /auth/password
+pub async fn change_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<ChangePasswordRequest>,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /auth/password");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::passwords::auth::change_password(),
+    )
+    .await?;
+    ops::passwords::change_password(&state, &session, &req).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /auth/password/reset
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(req): Json<PasswordResetRequest>,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /auth/password/reset");
+    let user_ref = ops::passwords::reset_password(&state, &req).await?;
+    Ok((StatusCode::OK, Json(user_ref)))
+}
+
+/// POST /admin/users/{name}/password-reset
+pub async fn create_reset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(new_reset): Json<NewPasswordReset>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /admin/users/{name}/password-reset\nnew_reset = {:#?}",
+        new_reset
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::passwords::auth::create_reset(),
+    )
+    .await?;
+    let reset =
+        ops::passwords::create_reset(&state, &session, &name, &new_reset)
+            .await?;
+    Ok((StatusCode::CREATED, Json(reset)))
+}
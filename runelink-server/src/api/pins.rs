@@ -0,0 +1,174 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct PinQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// GET /servers/{server_id}/channels/{channel_id}/pins
+pub async fn get_by_channel(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<PinQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/channels/{channel_id}/pins?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::pins::auth::get_by_channel(server_id),
+    )
+    .await?;
+    let pins = ops::pins::get_by_channel(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(pins)))
+}
+
+/// PUT /servers/{server_id}/channels/{channel_id}/pins/{message_id}
+pub async fn pin(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id, message_id)): Path<(Uuid, Uuid, Uuid)>,
+    Query(params): Query<PinQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PUT /servers/{server_id}/channels/{channel_id}/pins/{message_id}?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::pins::auth::pin(server_id),
+    )
+    .await?;
+    ops::pins::pin(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        message_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /servers/{server_id}/channels/{channel_id}/pins/{message_id}
+pub async fn unpin(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id, message_id)): Path<(Uuid, Uuid, Uuid)>,
+    Query(params): Query<PinQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/channels/{channel_id}/pins/{message_id}?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::pins::auth::unpin(server_id),
+    )
+    .await?;
+    ops::pins::unpin(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        message_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// GET /federation/servers/{server_id}/channels/{channel_id}/pins
+    pub async fn get_by_channel(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("GET /federation/servers/{server_id}/channels/{channel_id}/pins");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::pins::auth::federated::get_by_channel(server_id),
+        )
+        .await?;
+        let pins = ops::pins::get_by_channel(
+            &state, &session, server_id, channel_id, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(pins)))
+    }
+
+    /// PUT /federation/servers/{server_id}/channels/{channel_id}/pins/{message_id}
+    pub async fn pin(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id, message_id)): Path<(Uuid, Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PUT /federation/servers/{server_id}/channels/{channel_id}/pins/{message_id}"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::pins::auth::federated::pin(server_id),
+        )
+        .await?;
+        ops::pins::pin(
+            &state, &session, server_id, channel_id, message_id, None,
+        )
+        .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// DELETE /federation/servers/{server_id}/channels/{channel_id}/pins/{message_id}
+    pub async fn unpin(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id, message_id)): Path<(Uuid, Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "DELETE /federation/servers/{server_id}/channels/{channel_id}/pins/{message_id}"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::pins::auth::federated::unpin(server_id),
+        )
+        .await?;
+        ops::pins::unpin(
+            &state, &session, server_id, channel_id, message_id, None,
+        )
+        .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
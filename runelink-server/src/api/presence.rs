@@ -0,0 +1,209 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{PresenceUpdate, UserRef};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct PresenceQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// PUT /presence
+pub async fn update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(update): Json<PresenceUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    info!("PUT /presence\nupdate = {update:#?}");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::presence::auth::update(),
+    )
+    .await?;
+    let presence = ops::presence::update(&state, &session, &update).await?;
+    Ok((StatusCode::OK, Json(presence)))
+}
+
+/// GET /servers/{server_id}/presence
+pub async fn get_by_server(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<PresenceQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/presence?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::presence::auth::get_by_server(server_id),
+    )
+    .await?;
+    let presence = ops::presence::get_by_server(
+        &state,
+        &session,
+        server_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(presence)))
+}
+
+/// POST /servers/{server_id}/channels/{channel_id}/typing
+pub async fn start_typing(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<PresenceQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/channels/{channel_id}/typing?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::presence::auth::typing(server_id),
+    )
+    .await?;
+    ops::presence::start_typing(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /servers/{server_id}/channels/{channel_id}/typing
+pub async fn get_typing(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<PresenceQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/channels/{channel_id}/typing?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::presence::auth::typing(server_id),
+    )
+    .await?;
+    let typing = ops::presence::get_typing(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(typing)))
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// PUT /federation/users/{host}/{name}/presence
+    pub async fn update(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((host, name)): Path<(String, String)>,
+        Json(update): Json<PresenceUpdate>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PUT /federation/users/{host}/{name}/presence\nupdate = {update:#?}"
+        );
+        let user_ref = UserRef::new(name, host);
+        authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::presence::auth::federated::update(user_ref.host.clone()),
+        )
+        .await?;
+        ops::presence::receive(&state, &user_ref, &update).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// GET /federation/servers/{server_id}/presence
+    pub async fn get_by_server(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("GET /federation/servers/{server_id}/presence");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::presence::auth::federated::get_by_server(server_id),
+        )
+        .await?;
+        let presence =
+            ops::presence::get_by_server(&state, &session, server_id, None)
+                .await?;
+        Ok((StatusCode::OK, Json(presence)))
+    }
+
+    /// POST /federation/servers/{server_id}/channels/{channel_id}/typing
+    pub async fn start_typing(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/channels/{channel_id}/typing"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::presence::auth::federated::typing(server_id),
+        )
+        .await?;
+        ops::presence::start_typing(
+            &state, &session, server_id, channel_id, None,
+        )
+        .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// GET /federation/servers/{server_id}/channels/{channel_id}/typing
+    pub async fn get_typing(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "GET /federation/servers/{server_id}/channels/{channel_id}/typing"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::presence::auth::federated::typing(server_id),
+        )
+        .await?;
+        let typing = ops::presence::get_typing(
+            &state, &session, server_id, channel_id, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(typing)))
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{NewReadMarker, ReadMarker};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct ReadMarkerQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// GET /read-markers
+pub async fn get_all(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /read-markers");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::read_markers::auth::get_all(),
+    )
+    .await?;
+    let markers = ops::read_markers::get_all(&state, &session).await?;
+    Ok((StatusCode::OK, Json(markers)))
+}
+
+/// GET /unread
+pub async fn get_unread(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /unread");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::read_markers::auth::get_unread(),
+    )
+    .await?;
+    let unread = ops::read_markers::get_unread(&state, &session).await?;
+    Ok((StatusCode::OK, Json(unread)))
+}
+
+/// PUT /servers/{server_id}/channels/{channel_id}/read-marker
+pub async fn set(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<ReadMarkerQueryParams>,
+    Json(new_marker): Json<NewReadMarker>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PUT /servers/{server_id}/channels/{channel_id}/read-marker?target_host={:?}\nnew_marker = {:#?}",
+        params.target_host, new_marker
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::read_markers::auth::set(server_id),
+    )
+    .await?;
+    let marker = ops::read_markers::set(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        &new_marker,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(marker)))
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// POST /federation/servers/{server_id}/unread
+    pub async fn count_unread(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(markers): Json<Vec<ReadMarker>>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("POST /federation/servers/{server_id}/unread");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::read_markers::auth::federated::count_unread(server_id),
+        )
+        .await?;
+        let unread = ops::read_markers::count_unread(
+            &state, &session, server_id, &markers,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(unread)))
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::RetentionPolicyUpdate;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct RetentionQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// GET /servers/{server_id}/retention
+pub async fn get_by_server(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<RetentionQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "GET /servers/{server_id}/retention?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::retention::auth::get_by_server(server_id),
+    )
+    .await?;
+    let policies = ops::retention::get_by_server(
+        &state,
+        &session,
+        server_id,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(policies)))
+}
+
+/// PATCH /servers/{server_id}/retention
+pub async fn update_server(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<RetentionQueryParams>,
+    Json(update): Json<RetentionPolicyUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PATCH /servers/{server_id}/retention?target_host={:?}\nupdate = {:#?}",
+        params.target_host, update
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::retention::auth::update(server_id),
+    )
+    .await?;
+    let policy = ops::retention::update(
+        &state,
+        &session,
+        server_id,
+        None,
+        &update,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(policy)))
+}
+
+/// DELETE /servers/{server_id}/retention
+pub async fn delete_server(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<RetentionQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/retention?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::retention::auth::delete(server_id),
+    )
+    .await?;
+    ops::retention::delete(
+        &state,
+        &session,
+        server_id,
+        None,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// PATCH /servers/{server_id}/channels/{channel_id}/retention
+pub async fn update_channel(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<RetentionQueryParams>,
+    Json(update): Json<RetentionPolicyUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PATCH /servers/{server_id}/channels/{channel_id}/retention?target_host={:?}\nupdate = {:#?}",
+        params.target_host, update
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::retention::auth::update(server_id),
+    )
+    .await?;
+    let policy = ops::retention::update(
+        &state,
+        &session,
+        server_id,
+        Some(channel_id),
+        &update,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(policy)))
+}
+
+/// DELETE /servers/{server_id}/channels/{channel_id}/retention
+pub async fn delete_channel(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<RetentionQueryParams>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "DELETE /servers/{server_id}/channels/{channel_id}/retention?target_host={:?}",
+        params.target_host
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::retention::auth::delete(server_id),
+    )
+    .await?;
+    ops::retention::delete(
+        &state,
+        &session,
+        server_id,
+        Some(channel_id),
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Federation endpoints (server-to-server authentication required).
+pub mod federated {
+    use super::*;
+
+    /// GET /federation/servers/{server_id}/retention
+    pub async fn get_by_server(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("GET /federation/servers/{server_id}/retention");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::retention::auth::federated::get_by_server(server_id),
+        )
+        .await?;
+        let policies =
+            ops::retention::get_by_server(&state, &session, server_id, None)
+                .await?;
+        Ok((StatusCode::OK, Json(policies)))
+    }
+
+    /// PATCH /federation/servers/{server_id}/retention
+    pub async fn update_server(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(update): Json<RetentionPolicyUpdate>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PATCH /federation/servers/{server_id}/retention\nupdate = {:#?}",
+            update
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::retention::auth::federated::update(server_id),
+        )
+        .await?;
+        let policy = ops::retention::update(
+            &state, &session, server_id, None, &update, None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(policy)))
+    }
+
+    /// DELETE /federation/servers/{server_id}/retention
+    pub async fn delete_server(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("DELETE /federation/servers/{server_id}/retention");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::retention::auth::federated::delete(server_id),
+        )
+        .await?;
+        ops::retention::delete(&state, &session, server_id, None, None).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// PATCH /federation/servers/{server_id}/channels/{channel_id}/retention
+    pub async fn update_channel(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+        Json(update): Json<RetentionPolicyUpdate>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PATCH /federation/servers/{server_id}/channels/{channel_id}/retention\nupdate = {:#?}",
+            update
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::retention::auth::federated::update(server_id),
+        )
+        .await?;
+        let policy = ops::retention::update(
+            &state,
+            &session,
+            server_id,
+            Some(channel_id),
+            &update,
+            None,
+        )
+        .await?;
+        Ok((StatusCode::OK, Json(policy)))
+    }
+
+    /// DELETE /federation/servers/{server_id}/channels/{channel_id}/retention
+    pub async fn delete_channel(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "DELETE /federation/servers/{server_id}/channels/{channel_id}/retention"
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::retention::auth::federated::delete(server_id),
+        )
+        .await?;
+        ops::retention::delete(
+            &state,
+            &session,
+            server_id,
+            Some(channel_id),
+            None,
+        )
+        .await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::NewScheduledMessage;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct ScheduledMessageQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// POST /servers/{server_id}/channels/{channel_id}/scheduled-messages
+pub async fn create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((server_id, channel_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<ScheduledMessageQueryParams>,
+    Json(new_scheduled): Json<NewScheduledMessage>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/channels/{channel_id}/scheduled-messages?target_host={:?}\nnew_scheduled = {:#?}",
+        params.target_host, new_scheduled
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::scheduled_messages::auth::create(server_id),
+    )
+    .await?;
+    let scheduled = ops::scheduled_messages::create(
+        &state,
+        &session,
+        server_id,
+        channel_id,
+        &new_scheduled,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(scheduled)))
+}
+
+/// GET /scheduled-messages
+pub async fn get_all(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /scheduled-messages");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::scheduled_messages::auth::get_all(),
+    )
+    .await?;
+    let scheduled = ops::scheduled_messages::get_all(&state, &session).await?;
+    Ok((StatusCode::OK, Json(scheduled)))
+}
+
+/// DELETE /scheduled-messages/{scheduled_id}
+pub async fn cancel(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(scheduled_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    info!("DELETE /scheduled-messages/{scheduled_id}");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::scheduled_messages::auth::cancel(),
+    )
+    .await?;
+    ops::scheduled_messages::cancel(&state, &session, scheduled_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
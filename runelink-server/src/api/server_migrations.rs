@@ -0,0 +1,139 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::{ApiError, ApiResult},
+    ops, queries,
+    state::AppState,
+};
+use axum::{
+    body::Body,
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{NewServerMigration, ServerMigration};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct MigrationQueryParams {
+    pub target_host: Option<String>,
+}
+
+/// POST /servers/{server_id}/migration
+pub async fn migrate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<MigrationQueryParams>,
+    Json(new_migration): Json<NewServerMigration>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "POST /servers/{server_id}/migration?target_host={:?}\nnew_migration = {:#?}",
+        params.target_host, new_migration
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::server_migrations::auth::migrate(server_id),
+    )
+    .await?;
+    let migration = ops::server_migrations::migrate(
+        &state,
+        &session,
+        server_id,
+        &new_migration,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(migration)))
+}
+
+pub mod federated {
+    use super::*;
+
+    /// POST /federation/servers/{server_id}/migration
+    pub async fn migrate(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(new_migration): Json<NewServerMigration>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "POST /federation/servers/{server_id}/migration\nnew_migration = {:#?}",
+            new_migration
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::server_migrations::auth::federated::migrate(server_id),
+        )
+        .await?;
+        let migration = ops::server_migrations::migrate(
+            &state,
+            &session,
+            server_id,
+            &new_migration,
+            None,
+        )
+        .await?;
+        Ok((StatusCode::CREATED, Json(migration)))
+    }
+
+    /// POST /federation/servers/{server_id}/handoff
+    pub async fn receive(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        body: Body,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("POST /federation/servers/{server_id}/handoff");
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::server_migrations::auth::federated::receive(
+                &state.config.migration_source_hosts,
+            ),
+        )
+        .await?;
+        let imported = ops::server_migrations::receive(
+            &state,
+            &session,
+            server_id,
+            body.into_data_stream(),
+        )
+        .await?;
+        Ok((StatusCode::CREATED, Json(imported)))
+    }
+
+    /// PUT /federation/servers/{server_id}/migration
+    pub async fn apply(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(migration): Json<ServerMigration>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PUT /federation/servers/{server_id}/migration\nmigration = {:#?}",
+            migration
+        );
+        if migration.server_id != server_id {
+            return Err(ApiError::BadRequest(
+                "Migration does not match path".into(),
+            ));
+        }
+        let server_host = queries::server_migrations::get_cached_remote_host(
+            &state.db_pool,
+            server_id,
+        )
+        .await?;
+        authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::server_migrations::auth::federated::apply(server_host),
+        )
+        .await?;
+        ops::server_migrations::apply_remote(&state, &migration).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
@@ -10,7 +10,7 @@ use axum::{
     response::IntoResponse,
 };
 use log::info;
-use runelink_types::NewServer;
+use runelink_types::{NewServer, ServerUpdate};
 use serde::Deserialize;
 use uuid::Uuid;
 
@@ -103,6 +103,35 @@ pub async fn get_with_channels(
     Ok((StatusCode::OK, Json(server_with_channels)))
 }
 
+/// PATCH /servers/{server_id}
+pub async fn update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(server_id): Path<Uuid>,
+    Query(params): Query<ServerQueryParams>,
+    Json(update): Json<ServerUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    info!(
+        "PATCH /servers/{server_id}?target_host={:?}\nupdate = {:#?}",
+        params.target_host, update
+    );
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::servers::auth::update(server_id),
+    )
+    .await?;
+    let server = ops::servers::update(
+        &state,
+        &session,
+        server_id,
+        &update,
+        params.target_host.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::OK, Json(server)))
+}
+
 /// DELETE /servers/{server_id}
 pub async fn delete(
     State(state): State<AppState>,
@@ -171,6 +200,29 @@ pub mod federated {
         Ok((StatusCode::OK, Json(server_with_channels)))
     }
 
+    /// PATCH /federation/servers/{server_id}
+    pub async fn update(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path(server_id): Path<Uuid>,
+        Json(update): Json<ServerUpdate>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!(
+            "PATCH /federation/servers/{server_id}\nupdate = {:#?}",
+            update
+        );
+        let session = authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::servers::auth::federated::update(server_id),
+        )
+        .await?;
+        let server =
+            ops::servers::update(&state, &session, server_id, &update, None)
+                .await?;
+        Ok((StatusCode::OK, Json(server)))
+    }
+
     /// DELETE /federation/servers/{server_id}
     pub async fn delete(
         State(state): State<AppState>,
@@ -0,0 +1,115 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::NewSignupInvite;
+use uuid::Uuid;
+
+/// POST /admin/invites
+pub async fn create_invite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(new_invite): Json<NewSignupInvite>,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /admin/invites\nnew_invite = {:#?}", new_invite);
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::signups::auth::manage(),
+    )
+    .await?;
+    let invite =
+        ops::signups::create_invite(&state, &session, &new_invite).await?;
+    Ok((StatusCode::CREATED, Json(invite)))
+}
+
+/// GET /admin/invites
+pub async fn get_invites(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /admin/invites");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::signups::auth::manage(),
+    )
+    .await?;
+    let invites = ops::signups::get_invites(&state, &session).await?;
+    Ok((StatusCode::OK, Json(invites)))
+}
+
+/// DELETE /admin/invites/{invite_id}
+pub async fn delete_invite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(invite_id): Path<Uuid>,
+) -> ApiResult<impl IntoResponse> {
+    info!("DELETE /admin/invites/{invite_id}");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::signups::auth::manage(),
+    )
+    .await?;
+    ops::signups::delete_invite(&state, &session, invite_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /admin/signups
+pub async fn get_pending(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /admin/signups");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::signups::auth::manage(),
+    )
+    .await?;
+    let users = ops::signups::get_pending(&state, &session).await?;
+    Ok((StatusCode::OK, Json(users)))
+}
+
+/// POST /admin/signups/{name}/approve
+pub async fn approve(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /admin/signups/{name}/approve");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::signups::auth::manage(),
+    )
+    .await?;
+    ops::signups::approve(&state, &session, &name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /admin/signups/{name}
+pub async fn reject(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> ApiResult<impl IntoResponse> {
+    info!("DELETE /admin/signups/{name}");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::signups::auth::manage(),
+    )
+    .await?;
+    ops::signups::reject(&state, &session, &name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,79 @@
+use crate::{
+    auth::{Principal, authorize},
+    error::ApiResult,
+    ops,
+    state::AppState,
+};
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::info;
+use runelink_types::{TotpConfirmRequest, TotpDisableRequest};
+
+/// GET /auth/totp
+pub async fn status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("GET /auth/totp");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::totp::auth::manage(),
+    )
+    .await?;
+    let status = ops::totp::status(&state, &session).await?;
+    Ok((StatusCode::OK, Json(status)))
+}
+
+/// POST /auth/totp/enroll
+pub async fn enroll(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /auth/totp/enroll");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::totp::auth::manage(),
+    )
+    .await?;
+    let enrollment = ops::totp::enroll(&state, &session).await?;
+    Ok((StatusCode::OK, Json(enrollment)))
+}
+
+/// POST /auth/totp/confirm
+pub async fn confirm(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<TotpConfirmRequest>,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /auth/totp/confirm");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::totp::auth::manage(),
+    )
+    .await?;
+    let codes = ops::totp::confirm(&state, &session, &req).await?;
+    Ok((StatusCode::OK, Json(codes)))
+}
+
+/// POST /auth/totp/disable
+pub async fn disable(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<TotpDisableRequest>,
+) -> ApiResult<impl IntoResponse> {
+    info!("POST /auth/totp/disable");
+    let session = authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::totp::auth::manage(),
+    )
+    .await?;
+    ops::totp::disable(&state, &session, &req).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,6 +1,6 @@
 use crate::{
     auth::{Principal, authorize},
-    error::ApiResult,
+    error::{ApiError, ApiResult},
     ops,
     state::AppState,
 };
@@ -10,7 +10,7 @@ use axum::{
     response::IntoResponse,
 };
 use log::info;
-use runelink_types::{NewUser, UserRef};
+use runelink_types::{NewUser, User, UserProfileUpdate, UserRef};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
@@ -57,12 +57,9 @@ pub async fn get_by_ref(
         params.target_host
     );
     let user_ref = UserRef::new(name, host);
-    let user = ops::users::get_by_ref(
-        &state,
-        user_ref,
-        params.target_host.as_deref(),
-    )
-    .await?;
+    let user =
+        ops::users::get_by_ref(&state, user_ref, params.target_host.as_deref())
+            .await?;
     Ok((StatusCode::OK, Json(user)))
 }
 
@@ -104,10 +101,52 @@ pub async fn delete(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// PATCH /users/{host}/{name}
+pub async fn update_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((host, name)): Path<(String, String)>,
+    Json(update): Json<UserProfileUpdate>,
+) -> ApiResult<impl IntoResponse> {
+    let user_ref = UserRef::new(name.clone(), host.clone());
+    info!("PATCH /users/{host}/{name}\nupdate = {update:#?}");
+    authorize(
+        &state,
+        Principal::from_client_headers(&headers, &state)?,
+        ops::users::auth::update_profile(user_ref.clone()),
+    )
+    .await?;
+    let user = ops::users::update_profile(&state, &user_ref, &update).await?;
+    Ok((StatusCode::OK, Json(user)))
+}
+
 /// Federation endpoints (server-to-server authentication required).
 pub mod federated {
     use super::*;
 
+    /// PUT /federation/users/{host}/{name}
+    pub async fn sync(
+        State(state): State<AppState>,
+        headers: HeaderMap,
+        Path((host, name)): Path<(String, String)>,
+        Json(user): Json<User>,
+    ) -> ApiResult<impl IntoResponse> {
+        info!("PUT /federation/users/{host}/{name}\nuser = {user:#?}");
+        if user.as_ref() != UserRef::new(name, host) {
+            return Err(ApiError::BadRequest(
+                "User does not match path".into(),
+            ));
+        }
+        authorize(
+            &state,
+            Principal::from_federation_headers(&headers, &state).await?,
+            ops::users::auth::federated::sync(user.host.clone()),
+        )
+        .await?;
+        ops::users::sync_remote(&state, &user).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
     /// DELETE /federation/users/{host}/{name}
     pub async fn delete(
         State(state): State<AppState>,
@@ -7,7 +7,6 @@ use crate::{
     state::AppState,
 };
 use axum::http::HeaderMap;
-use runelink_client::util::get_api_url;
 use runelink_types::{
     FederationClaims, ServerMembership, ServerRole, User, UserRef, UserRole,
 };
@@ -18,10 +17,10 @@ use uuid::Uuid;
 #[macro_export]
 macro_rules! and {
     () => {
-        crate::auth::Requirement::And(vec![])
+        $crate::auth::Requirement::And(vec![])
     };
     ( $( $req:expr ),+ $(,)? ) => {
-        crate::auth::Requirement::And(vec![ $( $req ),+ ])
+        $crate::auth::Requirement::And(vec![ $( $req ),+ ])
     };
 }
 
@@ -30,10 +29,10 @@ macro_rules! and {
 #[macro_export]
 macro_rules! or {
     () => {
-        crate::auth::Requirement::Or(vec![])
+        $crate::auth::Requirement::Or(vec![])
     };
     ( $( $req:expr ),+ $(,)? ) => {
-        crate::auth::Requirement::Or(vec![ $( $req ),+ ])
+        $crate::auth::Requirement::Or(vec![ $( $req ),+ ])
     };
 }
 
@@ -41,6 +40,9 @@ macro_rules! or {
 pub enum Principal {
     Client(ClientAuth),
     Federation(FederationAuth),
+    /// This host acting on its own for one of its users, outside of any
+    /// request (such as when sending their scheduled messages)
+    System(UserRef),
 }
 
 impl Principal {
@@ -67,16 +69,23 @@ pub enum Requirement {
     Client,
     /// Must be authenticated with a federation token.
     Federation,
+    /// Must be this host acting on its own.
+    System,
     /// Must be a delegated federated user with the referenced identity.
     FederatedUser(UserRef),
+    /// Must be authenticated with a federation token issued by the
+    /// referenced host.
+    FederatedHost(String),
     /// Must be a user with the referenced identity.
     User(UserRef),
     /// Must be a host admin.
     HostAdmin,
     /// Must be a member of the referenced server.
     ServerMember(Uuid),
-    /// Must be an admin of the referenced server.
+    /// Must be an admin (or the owner) of the referenced server.
     ServerAdmin(Uuid),
+    /// Must be the owner of the referenced server.
+    ServerOwner(Uuid),
     /// A requirement that will always be satisfied.
     Always,
     /// A requirement that will never be satisfied.
@@ -100,6 +109,10 @@ impl Requirement {
         and!(Requirement::Federation, self)
     }
 
+    pub fn system_only(self) -> Self {
+        and!(Requirement::System, self)
+    }
+
     async fn check(
         &self,
         ctx: &mut AuthContext<'_>,
@@ -117,6 +130,12 @@ impl Requirement {
                 }
             }
 
+            Requirement::System => {
+                if !matches!(ctx.principal, Principal::System(_)) {
+                    return Ok(Some("Internal use only".into()));
+                }
+            }
+
             Requirement::FederatedUser(expected) => {
                 let (claims, user_ref) = match &ctx.principal {
                     Principal::Federation(auth) => {
@@ -129,7 +148,7 @@ impl Requirement {
                         "Federated delegated user required".into(),
                     ));
                 };
-                let expected_iss = get_api_url(&expected.host);
+                let expected_iss = ctx.state.api_url_for(&expected.host).await;
                 if claims.iss != expected_iss {
                     return Ok(Some(
                         "Federation issuer does not match delegated user host"
@@ -143,6 +162,18 @@ impl Requirement {
                 }
             }
 
+            Requirement::FederatedHost(expected) => {
+                let Principal::Federation(auth) = &ctx.principal else {
+                    return Ok(Some("Federation auth required".into()));
+                };
+                let expected_iss = ctx.state.api_url_for(expected).await;
+                if auth.claims.iss != expected_iss {
+                    return Ok(Some(format!(
+                        "Federation issuer does not match {expected}"
+                    )));
+                }
+            }
+
             Requirement::User(expected) => {
                 let user = ctx.get_user().await?;
                 if user.is_none()
@@ -168,13 +199,21 @@ impl Requirement {
 
             Requirement::ServerAdmin(server_id) => {
                 let membership = ctx.get_membership(*server_id).await?;
-                if membership.is_none()
-                    || membership.unwrap().role != ServerRole::Admin
+                if membership.is_none() || !membership.unwrap().role.is_admin()
                 {
                     return Ok(Some("Server admin only".into()));
                 }
             }
 
+            Requirement::ServerOwner(server_id) => {
+                let membership = ctx.get_membership(*server_id).await?;
+                if membership.is_none()
+                    || membership.unwrap().role != ServerRole::Owner
+                {
+                    return Ok(Some("Server owner only".into()));
+                }
+            }
+
             Requirement::Always => {
                 return Ok(None);
             }
@@ -274,7 +313,7 @@ impl<'a> AuthContext<'a> {
 /// For federation auth, the user reference may or may not exist locally.
 #[derive(Clone, Debug)]
 pub struct Session {
-    /// The authenticated principal (Client or Federation)
+    /// The authenticated principal (Client, Federation or System)
     pub principal: Principal,
     /// Optional delegated user reference (always present for client auth, optional for federation)
     pub user_ref: Option<UserRef>,
@@ -344,6 +383,7 @@ pub async fn authorize(
         Principal::Federation(auth) => {
             (auth.claims.user_ref.clone(), Some(auth.claims.clone()))
         }
+        Principal::System(user_ref) => (Some(user_ref.clone()), None),
     };
     let mut ctx = AuthContext {
         state,
@@ -356,12 +396,8 @@ pub async fn authorize(
         return Err(ApiError::AuthError(error));
     }
     let cached_user = if ctx.user_ref.is_some() {
-        if let Some(user) = ctx.user {
-            Some(Some(user))
-        } else {
-            // Not looked up yet
-            None
-        }
+        // None if not looked up yet
+        ctx.user.map(Some)
     } else {
         // Not delegated
         Some(None)
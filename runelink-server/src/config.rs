@@ -1,6 +1,24 @@
 use std::path::PathBuf;
 
-use runelink_client::util::{get_api_url, pad_host};
+use crate::blobs::{BlobStoreConfig, S3Config};
+use crate::rate_limit::{Backend, Quota, RateLimitConfig, parse_quota_setting};
+use crate::signup_policy::SignupMode;
+use runelink_client::util::{
+    DEFAULT_PORT, Scheme, api_url_with_scheme, pad_host, split_scheme,
+};
+use runelink_types::{
+    FEDERATION_V1_PATH, HostDiscovery, PROTOCOL_VERSION, SoftwareInfo,
+    capabilities,
+};
+
+/// Days a deleted channel can still be restored before it is purged.
+const DEFAULT_CHANNEL_TRASH_DAYS: u32 = 30;
+/// Days the tombstone of a deleted message is kept before it is purged.
+const DEFAULT_MESSAGE_TOMBSTONE_DAYS: u32 = 30;
+/// Largest attachment accepted, in bytes.
+const DEFAULT_ATTACHMENT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+/// Total size of the attachments one user may upload, in bytes.
+const DEFAULT_ATTACHMENT_QUOTA_BYTES: u64 = 1024 * 1024 * 1024;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ConfigError {
@@ -9,6 +27,18 @@ pub enum ConfigError {
 
     #[error("Invalid environment variable `{0}`: {1}")]
     InvalidEnvVar(String, #[source] std::num::ParseIntError),
+
+    #[error("Invalid environment variable `{0}`: {1}")]
+    InvalidValue(String, String),
+}
+
+/// Certificate and key used when the server terminates TLS itself.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// PEM-encoded certificate chain
+    pub cert_path: PathBuf,
+    /// PEM-encoded private key
+    pub key_path: PathBuf,
 }
 
 #[derive(Clone, Debug)]
@@ -17,6 +47,127 @@ pub struct ServerConfig {
     pub database_url: String,
     pub port: u16,
     pub key_dir: PathBuf,
+    /// Scheme this host is reached over (and advertises as its issuer).
+    /// Plaintext HTTP is a development-only opt-in via `INSECURE_HTTP`.
+    pub scheme: Scheme,
+    pub tls: Option<TlsConfig>,
+    /// Externally visible API base URL when running behind a reverse proxy
+    /// on a different port or path (e.g. "https://example.com/runelink")
+    pub public_api_url: Option<String>,
+    pub rate_limits: RateLimitConfig,
+    pub signup_mode: SignupMode,
+    /// Usernames nobody may sign up with, in addition to the built-in list
+    pub reserved_usernames: Vec<String>,
+    /// Days a deleted channel stays in the trash before it is purged
+    pub channel_trash_days: u32,
+    /// Days the tombstone of a deleted message is kept before it is purged
+    pub message_tombstone_days: u32,
+    /// Where the contents of attachments are stored
+    pub blob_store: BlobStoreConfig,
+    /// Hosts allowed to move their servers to this host
+    pub migration_source_hosts: Vec<String>,
+    /// Largest attachment accepted, in bytes
+    pub attachment_max_bytes: u64,
+    /// Total size of the attachments one user may upload, in bytes
+    pub attachment_quota_bytes: u64,
+}
+
+fn parse_bool_env(name: &str) -> Result<bool, ConfigError> {
+    match std::env::var(name) {
+        Err(_) => Ok(false),
+        Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(true),
+            "" | "0" | "false" | "no" | "off" => Ok(false),
+            _ => Err(ConfigError::InvalidValue(
+                name.into(),
+                format!("expected a boolean, got `{value}`"),
+            )),
+        },
+    }
+}
+
+fn parse_quota_env(
+    name: &str,
+    default: Option<Quota>,
+) -> Result<Option<Quota>, ConfigError> {
+    match std::env::var(name) {
+        Err(_) => Ok(default),
+        Ok(value) => parse_quota_setting(&value)
+            .map_err(|e| ConfigError::InvalidValue(name.into(), e)),
+    }
+}
+
+fn rate_limits_from_env() -> Result<RateLimitConfig, ConfigError> {
+    let defaults = RateLimitConfig::default();
+    let backend = match std::env::var("RATE_LIMIT_BACKEND") {
+        Err(_) => defaults.backend,
+        Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "memory" => Backend::Memory,
+            "postgres" => Backend::Postgres,
+            _ => {
+                return Err(ConfigError::InvalidValue(
+                    "RATE_LIMIT_BACKEND".into(),
+                    format!("expected `memory` or `postgres`, got `{value}`"),
+                ));
+            }
+        },
+    };
+    Ok(RateLimitConfig {
+        backend,
+        auth: parse_quota_env("RATE_LIMIT_AUTH", defaults.auth)?,
+        signup: parse_quota_env("RATE_LIMIT_SIGNUP", defaults.signup)?,
+        messages: parse_quota_env("RATE_LIMIT_MESSAGES", defaults.messages)?,
+        federation: parse_quota_env(
+            "RATE_LIMIT_FEDERATION",
+            defaults.federation,
+        )?,
+        trust_forwarded_for: parse_bool_env("RATE_LIMIT_TRUST_FORWARDED_FOR")?,
+    })
+}
+
+fn parse_u64_env(name: &str, default: u64) -> Result<u64, ConfigError> {
+    match std::env::var(name) {
+        Err(_) => Ok(default),
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|e| ConfigError::InvalidEnvVar(name.into(), e)),
+    }
+}
+
+fn required_env(name: &str) -> Result<String, ConfigError> {
+    std::env::var(name).map_err(|_| ConfigError::MissingEnvVar(name.into()))
+}
+
+/// Attachments are stored in `BLOB_DIR`, by default a `blobs` directory
+/// next to the key directory, unless `BLOB_STORE=s3`.
+fn blob_store_from_env(
+    key_dir: &std::path::Path,
+) -> Result<BlobStoreConfig, ConfigError> {
+    let backend = std::env::var("BLOB_STORE").unwrap_or_default();
+    match backend.trim().to_ascii_lowercase().as_str() {
+        "" | "local" => {
+            let dir = std::env::var("BLOB_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| match key_dir.parent() {
+                    Some(parent) => parent.join("blobs"),
+                    None => PathBuf::from("blobs"),
+                });
+            Ok(BlobStoreConfig::Local { dir })
+        }
+        "s3" => Ok(BlobStoreConfig::S3(S3Config {
+            endpoint: required_env("S3_ENDPOINT")?,
+            bucket: required_env("S3_BUCKET")?,
+            region: std::env::var("S3_REGION")
+                .unwrap_or_else(|_| "us-east-1".into()),
+            access_key_id: required_env("S3_ACCESS_KEY_ID")?,
+            secret_access_key: required_env("S3_SECRET_ACCESS_KEY")?,
+        })),
+        _ => Err(ConfigError::InvalidValue(
+            "BLOB_STORE".into(),
+            format!("expected `local` or `s3`, got `{backend}`"),
+        )),
+    }
 }
 
 impl ServerConfig {
@@ -39,18 +190,128 @@ impl ServerConfig {
                 path.extend([".local", "share", "runelink", "keys"]);
                 path
             });
+        let scheme = if parse_bool_env("INSECURE_HTTP")? {
+            Scheme::Http
+        } else {
+            Scheme::Https
+        };
+        let tls = match (
+            std::env::var("TLS_CERT_PATH").ok(),
+            std::env::var("TLS_KEY_PATH").ok(),
+        ) {
+            (Some(cert_path), Some(key_path)) => Some(TlsConfig {
+                cert_path: cert_path.into(),
+                key_path: key_path.into(),
+            }),
+            (None, None) => None,
+            _ => {
+                return Err(ConfigError::InvalidValue(
+                    "TLS_CERT_PATH".into(),
+                    "TLS_CERT_PATH and TLS_KEY_PATH must be set together"
+                        .into(),
+                ));
+            }
+        };
+        if tls.is_some() && scheme == Scheme::Http {
+            return Err(ConfigError::InvalidValue(
+                "INSECURE_HTTP".into(),
+                "cannot be enabled while TLS is configured".into(),
+            ));
+        }
+
+        let public_api_url = match std::env::var("PUBLIC_API_URL") {
+            Ok(url) if !url.trim().is_empty() => {
+                let url = url.trim().trim_end_matches('/').to_string();
+                match split_scheme(&url) {
+                    (Some(Scheme::Https), _) => {}
+                    (Some(Scheme::Http), _) if scheme == Scheme::Http => {}
+                    _ => {
+                        return Err(ConfigError::InvalidValue(
+                            "PUBLIC_API_URL".into(),
+                            format!("expected an https:// URL, got `{url}`"),
+                        ));
+                    }
+                }
+                Some(url)
+            }
+            _ => None,
+        };
+
+        let signup_mode = match std::env::var("SIGNUP_MODE") {
+            Err(_) => SignupMode::Open,
+            Ok(value) => value.parse().map_err(|e| {
+                ConfigError::InvalidValue("SIGNUP_MODE".into(), e)
+            })?,
+        };
+        let reserved_usernames = std::env::var("RESERVED_USERNAMES")
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let migration_source_hosts = std::env::var("MIGRATION_SOURCE_HOSTS")
+            .map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(|host| host.trim().to_string())
+                    .filter(|host| !host.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let channel_trash_days = match std::env::var("CHANNEL_TRASH_DAYS") {
+            Err(_) => DEFAULT_CHANNEL_TRASH_DAYS,
+            Ok(value) => value.trim().parse().map_err(|e| {
+                ConfigError::InvalidEnvVar("CHANNEL_TRASH_DAYS".into(), e)
+            })?,
+        };
+
+        let message_tombstone_days =
+            match std::env::var("MESSAGE_TOMBSTONE_DAYS") {
+                Err(_) => DEFAULT_MESSAGE_TOMBSTONE_DAYS,
+                Ok(value) => value.trim().parse().map_err(|e| {
+                    ConfigError::InvalidEnvVar(
+                        "MESSAGE_TOMBSTONE_DAYS".into(),
+                        e,
+                    )
+                })?,
+            };
+
+        let blob_store = blob_store_from_env(&key_dir)?;
 
         Ok(ServerConfig {
             local_host_raw: local_host,
             database_url,
             port,
             key_dir,
+            scheme,
+            tls,
+            public_api_url,
+            rate_limits: rate_limits_from_env()?,
+            signup_mode,
+            reserved_usernames,
+            channel_trash_days,
+            message_tombstone_days,
+            blob_store,
+            migration_source_hosts,
+            attachment_max_bytes: parse_u64_env(
+                "ATTACHMENT_MAX_BYTES",
+                DEFAULT_ATTACHMENT_MAX_BYTES,
+            )?,
+            attachment_quota_bytes: parse_u64_env(
+                "ATTACHMENT_QUOTA_BYTES",
+                DEFAULT_ATTACHMENT_QUOTA_BYTES,
+            )?,
         })
     }
 
     /// Includes port if it's not the default port (7000)
     pub fn local_host(&self) -> String {
-        if self.port == 7000 {
+        if self.port == DEFAULT_PORT {
             self.local_host_raw.clone()
         } else {
             format!("{}:{}", &self.local_host_raw, self.port)
@@ -62,8 +323,38 @@ impl ServerConfig {
         format!("{}:{}", &self.local_host_raw, self.port)
     }
 
+    /// Base URL peers and clients reach this host's API at; also used as our
+    /// token issuer
     pub fn api_url(&self) -> String {
-        get_api_url(self.local_host_with_explicit_port().as_str())
+        match &self.public_api_url {
+            Some(url) => url.clone(),
+            None => api_url_with_scheme(
+                &self.local_host_with_explicit_port(),
+                self.scheme,
+            ),
+        }
+    }
+
+    pub fn federation_url(&self) -> String {
+        format!("{}{FEDERATION_V1_PATH}", self.api_url())
+    }
+
+    /// Discovery document served at `/.well-known/runelink`
+    pub fn discovery(&self) -> HostDiscovery {
+        HostDiscovery {
+            host: self.local_host(),
+            api_base_url: self.api_url(),
+            federation_url: self.federation_url(),
+            protocol_versions: vec![PROTOCOL_VERSION.into()],
+            capabilities: capabilities::ALL
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+            software: Some(SoftwareInfo {
+                name: env!("CARGO_PKG_NAME").into(),
+                version: env!("CARGO_PKG_VERSION").into(),
+            }),
+        }
     }
 
     pub fn is_remote_host(&self, host: Option<&str>) -> bool {
@@ -1,10 +1,12 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use runelink_client::Error as ClientError;
+use runelink_types::MFA_REQUIRED;
 use serde::Serialize;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::task::JoinError;
 
@@ -27,15 +29,36 @@ pub enum ApiError {
     #[error("Unauthorized: {0}")]
     AuthError(String),
 
+    #[error("A one-time code is required to log in")]
+    MfaRequired,
+
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 
     #[error("Internal error: {0}")]
     Internal(String),
 
+    #[error("Server moved to {0}")]
+    ServerMoved(String),
+
+    #[error("Not supported by peer: {0}")]
+    Unsupported(String),
+
+    #[error("Rate limited ({scope}), retry in {}s", retry_after_secs(.retry_after))]
+    RateLimited {
+        scope: String,
+        retry_after: Duration,
+    },
+
     #[error("Upstream error: {0}")]
     Client(#[from] ClientError),
 }
@@ -65,9 +88,17 @@ impl From<JoinError> for ApiError {
     }
 }
 
+/// Whole seconds to wait, rounded up so clients never retry too early
+fn retry_after_secs(retry_after: &Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
 #[derive(Serialize)]
 struct ErrorResponse {
     error: String,
+    /// Stable identifier for errors clients are expected to act on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
 }
 
 impl IntoResponse for ApiError {
@@ -79,16 +110,33 @@ impl IntoResponse for ApiError {
             | ApiError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::UniqueViolation => StatusCode::CONFLICT,
             ApiError::NotFound => StatusCode::NOT_FOUND,
-            ApiError::AuthError(_) => StatusCode::UNAUTHORIZED,
+            ApiError::ServerMoved(_) => StatusCode::GONE,
+            ApiError::AuthError(_) | ApiError::MfaRequired => {
+                StatusCode::UNAUTHORIZED
+            }
             ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
             ApiError::Client(ref client_err) => match client_err {
                 ClientError::Status(code, _) => *code,
                 _ => StatusCode::BAD_GATEWAY,
             },
         };
+        let code = match self {
+            ApiError::MfaRequired => Some(MFA_REQUIRED),
+            _ => None,
+        };
         let body = Json(ErrorResponse {
             error: self.to_string(),
+            code,
         });
+        if let ApiError::RateLimited { retry_after, .. } = &self {
+            let retry_after = retry_after_secs(retry_after).to_string();
+            return (status, [(header::RETRY_AFTER, retry_after)], body)
+                .into_response();
+        }
         (status, body).into_response()
     }
 }
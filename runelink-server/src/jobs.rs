@@ -0,0 +1,214 @@
+//! Periodic background maintenance.
+
+use std::time::Duration;
+
+use log::{info, warn};
+use time::OffsetDateTime;
+
+use crate::{
+    error::ApiResult,
+    ops::{
+        self,
+        audit::{self, AuditAction, AuditTarget},
+    },
+    queries::{self, retention::ChannelRetention},
+    state::AppState,
+};
+
+/// How often the maintenance jobs run.
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How many messages one retention statement deletes at most, so no single
+/// statement holds its locks for long.
+const RETENTION_BATCH_SIZE: i64 = 1000;
+
+/// How many orphaned attachments one run deletes at most.
+const ORPHANED_ATTACHMENT_BATCH_SIZE: i64 = 1000;
+
+/// How often due scheduled messages are sent.
+const SCHEDULED_MESSAGE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Spawn a task that runs the maintenance jobs every [`INTERVAL`], starting
+/// right away.
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+            run(&state).await;
+        }
+    });
+}
+
+/// Spawn a task that sends due scheduled messages every
+/// [`SCHEDULED_MESSAGE_INTERVAL`].
+pub fn spawn_scheduled_messages(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SCHEDULED_MESSAGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            ops::scheduled_messages::deliver_due(&state).await;
+        }
+    });
+}
+
+async fn run(state: &AppState) {
+    purge_trashed_channels(state).await;
+    purge_message_tombstones(state).await;
+    enforce_retention(state).await;
+    purge_orphaned_attachments(state).await;
+}
+
+/// Delete channels that have been in the trash for longer than the
+/// configured trash period, along with their messages.
+async fn purge_trashed_channels(state: &AppState) {
+    let trash_period =
+        time::Duration::days(state.config.channel_trash_days.into());
+    let cutoff = OffsetDateTime::now_utc() - trash_period;
+    match queries::channels::purge_deleted(&state.db_pool, cutoff).await {
+        Ok(0) => {}
+        Ok(purged) => info!("Purged {purged} channel(s) from the trash"),
+        Err(e) => warn!("Failed to purge trashed channels: {e}"),
+    }
+}
+
+/// Delete the tombstones of messages that were deleted longer ago than the
+/// configured retention window.
+async fn purge_message_tombstones(state: &AppState) {
+    let retention =
+        time::Duration::days(state.config.message_tombstone_days.into());
+    let cutoff = OffsetDateTime::now_utc() - retention;
+    match queries::messages::purge_deleted(&state.db_pool, cutoff).await {
+        Ok(0) => {}
+        Ok(purged) => info!("Purged {purged} message tombstone(s)"),
+        Err(e) => warn!("Failed to purge message tombstones: {e}"),
+    }
+}
+
+/// Delete the messages that fall outside their channel's retention policy
+/// and record how many were purged from each channel in the audit log.
+async fn enforce_retention(state: &AppState) {
+    let channels = match queries::retention::get_effective(&state.db_pool).await
+    {
+        Ok(channels) => channels,
+        Err(e) => {
+            warn!("Failed to load retention policies: {e}");
+            return;
+        }
+    };
+    for retention in channels {
+        let purged = match purge_by_retention(state, &retention).await {
+            Ok(purged) => purged,
+            Err(e) => {
+                warn!(
+                    "Failed to enforce retention in channel {}: {e}",
+                    retention.channel_id
+                );
+                continue;
+            }
+        };
+        if purged == 0 {
+            continue;
+        }
+        info!(
+            "Purged {purged} message(s) from channel {} by retention policy",
+            retention.channel_id
+        );
+        audit::record_with_detail(
+            state,
+            None,
+            AuditAction::RetentionPurge,
+            Some(retention.server_id),
+            AuditTarget::Channel(retention.channel_id),
+            Some(&format!("{purged} message(s)")),
+        )
+        .await;
+    }
+}
+
+/// Purge one channel in batches of [`RETENTION_BATCH_SIZE`]. Returns how
+/// many messages were removed.
+async fn purge_by_retention(
+    state: &AppState,
+    retention: &ChannelRetention,
+) -> ApiResult<u64> {
+    let mut purged = 0;
+    // Limits too long to subtract from now can't have expired anything
+    let cutoff = retention.max_age_days.and_then(|days| {
+        OffsetDateTime::now_utc().checked_sub(time::Duration::days(days.into()))
+    });
+    if let Some(cutoff) = cutoff {
+        loop {
+            let batch = queries::messages::purge_older_than(
+                &state.db_pool,
+                retention.channel_id,
+                cutoff,
+                RETENTION_BATCH_SIZE,
+            )
+            .await?;
+            purged += batch;
+            if batch < RETENTION_BATCH_SIZE as u64 {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+    if let Some(keep) = retention.max_messages {
+        loop {
+            let batch = queries::messages::purge_beyond_newest(
+                &state.db_pool,
+                retention.channel_id,
+                keep,
+                RETENTION_BATCH_SIZE,
+            )
+            .await?;
+            purged += batch;
+            if batch < RETENTION_BATCH_SIZE as u64 {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+    Ok(purged)
+}
+
+/// Delete attachments that are not part of any message: uploads that were
+/// never sent, and attachments of purged messages.
+async fn purge_orphaned_attachments(state: &AppState) {
+    let cutoff = OffsetDateTime::now_utc()
+        - time::Duration::hours(ops::attachments::ORPHANED_ATTACHMENT_HOURS);
+    let ids = match queries::attachments::get_unattached_before(
+        &state.db_pool,
+        cutoff,
+        ORPHANED_ATTACHMENT_BATCH_SIZE,
+    )
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            warn!("Failed to load orphaned attachments: {e}");
+            return;
+        }
+    };
+    let mut purged = 0;
+    for id in ids {
+        // Remove the row first, so an attachment sent in the meantime keeps
+        // its contents
+        match queries::attachments::delete_unattached(&state.db_pool, id).await
+        {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                warn!("Failed to delete orphaned attachment {id}: {e}");
+                continue;
+            }
+        }
+        if let Err(e) = state.blob_store.delete(&id.to_string()).await {
+            warn!("Failed to delete contents of attachment {id}: {e}");
+        }
+        purged += 1;
+    }
+    if purged > 0 {
+        info!("Purged {purged} orphaned attachment(s)");
+    }
+}
@@ -12,7 +12,7 @@ use crate::{
     error::{ApiError, ApiResult},
     state::AppState,
 };
-use runelink_client::util::get_api_url;
+use runelink_client::util::is_allowed_api_url;
 
 #[derive(Debug, Clone)]
 pub struct CachedJwks {
@@ -97,10 +97,10 @@ async fn get_cached_jwks(state: &AppState, iss: &str) -> ApiResult<CachedJwks> {
     let ttl = Duration::minutes(10);
     {
         let cache = state.jwks_cache.read().await;
-        if let Some(entry) = cache.get(iss_key) {
-            if entry.fetched_at + ttl > OffsetDateTime::now_utc() {
-                return Ok(entry.clone());
-            }
+        if let Some(entry) = cache.get(iss_key)
+            && entry.fetched_at + ttl > OffsetDateTime::now_utc()
+        {
+            return Ok(entry.clone());
         }
     }
 
@@ -137,6 +137,7 @@ fn select_public_key_bytes<'a>(
 /// discovering the issuer's JWKS via `{iss}/.well-known/jwks.json`.
 ///
 /// Notes:
+/// - Issuers must be `https` URLs (or `http` in insecure dev mode).
 /// - This does an **unverified** parse of `iss` from the JWT payload solely to
 ///   locate the JWKS. Signature and claim validation happens after the key is
 ///   fetched.
@@ -146,10 +147,16 @@ pub async fn decode_federation_jwt(
     token: &str,
     expected_audience: &str,
 ) -> ApiResult<FederationClaims> {
-    let header = jsonwebtoken::decode_header(token).map_err(|e| {
-        ApiError::AuthError(format!("invalid JWT header: {e}"))
-    })?;
+    let header = jsonwebtoken::decode_header(token)
+        .map_err(|e| ApiError::AuthError(format!("invalid JWT header: {e}")))?;
     let iss = parse_iss_unverified(token)?;
+    // Never fetch keys over plaintext unless running in dev mode, otherwise
+    // the JWKS response could be spoofed
+    if !is_allowed_api_url(&iss, state.config.scheme) {
+        return Err(ApiError::AuthError(format!(
+            "federation issuer must use https: {iss}"
+        )));
+    }
 
     let cached = get_cached_jwks(state, &iss).await?;
     let pub_bytes = select_public_key_bytes(&cached, header.kid.as_deref())?;
@@ -175,7 +182,7 @@ pub async fn decode_federation_jwt(
 
     // Verify delegation policy: issuer can only delegate users from their own host
     if let Some(user_ref) = &claims.user_ref {
-        let expected_iss = get_api_url(&user_ref.host);
+        let expected_iss = state.api_url_for(&user_ref.host).await;
         if claims.iss != expected_iss {
             return Err(ApiError::AuthError(format!(
                 "Federation delegation mismatch: token from {} cannot delegate user from {}",
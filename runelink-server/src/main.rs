@@ -1,22 +1,40 @@
+use axum::middleware;
+use axum_server::tls_rustls::RustlsConfig;
 use config::ServerConfig;
+use rate_limit::RateLimiter;
+use runelink_client::{resolver::HostResolver, util::Scheme};
 use sqlx::migrate::Migrator;
 use state::AppState;
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 use tokio::net::TcpListener;
 
-use crate::key_manager::KeyManager;
+use crate::{
+    blobs::BlobStore, key_manager::KeyManager, presence::PresenceTracker,
+};
 
 mod api;
 mod auth;
 mod bearer_auth;
+mod blobs;
 mod config;
 mod db;
 mod error;
+mod jobs;
 mod jwks_resolver;
 mod key_manager;
 mod ops;
+mod presence;
 mod queries;
+mod rate_limit;
+mod secrets;
+mod signup_policy;
 mod state;
+mod totp;
+
+/// How long outgoing requests may take to connect to a peer.
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long outgoing requests may take as a whole, unless they say otherwise.
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
 // Embed all sql migrations in binary
 static MIGRATOR: Migrator = sqlx::migrate!();
@@ -35,9 +53,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let config = Arc::new(ServerConfig::from_env()?);
     let pool = Arc::new(db::get_pool(config.as_ref()).await?);
-    let http_client = reqwest::Client::new();
+    let http_client = reqwest::Client::builder()
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .build()?;
+    let blob_store = BlobStore::new(&config.blob_store, http_client.clone());
     let key_manager = KeyManager::load_or_generate(config.key_dir.clone())?;
 
+    // Outgoing federation requests follow our own scheme
+    let host_resolver = HostResolver::new().with_scheme(config.scheme);
+    host_resolver.pin(&config.local_host(), config.discovery());
+
     let app_state = AppState {
         config: config.clone(),
         db_pool: pool.clone(),
@@ -46,14 +72,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         jwks_cache: Arc::new(tokio::sync::RwLock::new(
             std::collections::HashMap::new(),
         )),
+        host_resolver,
+        rate_limiter: Arc::new(RateLimiter::new(config.rate_limits.clone())),
+        blob_store: Arc::new(blob_store),
+        presence: Arc::new(PresenceTracker::new()),
     };
 
     MIGRATOR.run(pool.as_ref()).await?;
     log::info!("Migrations are up to date.");
 
-    let app = api::router().with_state(app_state);
+    rate_limit::spawn_pruner(app_state.clone());
+    presence::spawn_pruner(app_state.clone());
+    jobs::spawn(app_state.clone());
+    jobs::spawn_scheduled_messages(app_state.clone());
+    let app = api::router()
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit::middleware,
+        ))
+        .with_state(app_state)
+        .into_make_service_with_connect_info::<SocketAddr>();
 
     let ip_addr = format!("0.0.0.0:{}", config.port);
+
+    if let Some(tls) = &config.tls {
+        rustls::crypto::ring::default_provider()
+            .install_default()
+            .map_err(|_| "failed to install rustls crypto provider")?;
+        let tls_config =
+            RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await?;
+        log::info!("Starting server on {ip_addr} (TLS)");
+        axum_server::bind_rustls(ip_addr.parse()?, tls_config)
+            .serve(app)
+            .await?;
+        return Ok(());
+    }
+
+    if config.scheme == Scheme::Http {
+        log::warn!(
+            "INSECURE_HTTP is enabled: federation traffic is unencrypted. \
+            Do not use this outside of local development."
+        );
+    } else {
+        log::info!(
+            "TLS is not configured; expecting a TLS-terminating proxy in \
+            front of {}",
+            config.api_url()
+        );
+    }
     let listener = TcpListener::bind(&ip_addr).await?;
 
     log::info!("Starting server on {ip_addr}");
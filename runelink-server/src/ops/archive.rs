@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use runelink_client::util::pad_host;
+use runelink_types::{
+    ARCHIVE_VERSION, ArchiveHeader, ArchiveImport, ArchiveRecord, ArchivedPin,
+    MIN_ARCHIVE_VERSION, UserRef,
+};
+use sqlx::PgConnection;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        session_user,
+    },
+    queries,
+    state::AppState,
+};
+
+/// How many messages are read from the database at a time while
+/// exporting.
+const EXPORT_PAGE_SIZE: i64 = 1000;
+
+/// The longest line accepted in an imported archive.
+const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Export a local server as an archive, one line per record. Messages are
+/// read page by page while the archive is streamed, so large servers are
+/// never held in memory.
+pub async fn export(
+    state: &AppState,
+    server_id: Uuid,
+) -> ApiResult<impl Stream<Item = ApiResult<String>> + Send + 'static> {
+    let server = queries::servers::get_by_id(state, server_id).await?;
+    let mut head = vec![
+        ArchiveRecord::Header(ArchiveHeader {
+            version: ARCHIVE_VERSION,
+            host: state.config.local_host(),
+            exported_at: OffsetDateTime::now_utc(),
+        }),
+        ArchiveRecord::Server(server),
+    ];
+    let categories =
+        queries::categories::get_by_server(&state.db_pool, server_id).await?;
+    head.extend(categories.into_iter().map(ArchiveRecord::Category));
+    let channels =
+        queries::channels::get_by_server(&state.db_pool, server_id, true)
+            .await?;
+    head.extend(channels.into_iter().map(ArchiveRecord::Channel));
+    let members =
+        queries::archive::get_members(&state.db_pool, server_id).await?;
+    head.extend(members.into_iter().map(ArchiveRecord::Member));
+    let bans = queries::moderation::get_bans(&state.db_pool, server_id).await?;
+    head.extend(bans.into_iter().map(ArchiveRecord::Ban));
+    let timeouts =
+        queries::moderation::get_timeouts(&state.db_pool, server_id).await?;
+    head.extend(timeouts.into_iter().map(ArchiveRecord::Timeout));
+    let policies =
+        queries::retention::get_by_server(&state.db_pool, server_id).await?;
+    head.extend(policies.into_iter().map(ArchiveRecord::Retention));
+    let pins = queries::archive::get_pins(&state.db_pool, server_id).await?;
+    head.extend(pins.into_iter().map(ArchiveRecord::Pin));
+
+    // `None` once the last page has been read
+    let start: Option<Option<(OffsetDateTime, Uuid)>> = Some(None);
+    let pool = state.db_pool.clone();
+    let messages = stream::try_unfold(start, move |cursor| {
+        let pool = pool.clone();
+        async move {
+            let Some(after) = cursor else {
+                return ApiResult::Ok(None);
+            };
+            let page = queries::archive::get_message_page(
+                &pool,
+                server_id,
+                after,
+                EXPORT_PAGE_SIZE,
+            )
+            .await?;
+            let next = if (page.len() as i64) < EXPORT_PAGE_SIZE {
+                None
+            } else {
+                page.last().map(|m| Some((m.created_at, m.id)))
+            };
+            let records = page
+                .into_iter()
+                .map(|m| ApiResult::Ok(ArchiveRecord::Message(m.into())));
+            Ok(Some((stream::iter(records), next)))
+        }
+    })
+    .try_flatten();
+
+    let records = stream::iter(head.into_iter().map(Ok))
+        .chain(messages)
+        .chain(stream::once(async { Ok(ArchiveRecord::End) }));
+    Ok(records.map(|record| {
+        let mut line = serde_json::to_string(&record?).map_err(|e| {
+            ApiError::Internal(format!("Failed to encode archive: {e}"))
+        })?;
+        line.push('\n');
+        Ok(line)
+    }))
+}
+
+/// Splits a request body into lines without buffering all of it.
+struct Lines<S> {
+    body: S,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl<S, E> Lines<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    fn new(body: S) -> Self {
+        Self {
+            body,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+
+    async fn next_line(&mut self) -> ApiResult<Option<Vec<u8>>> {
+        loop {
+            if let Some(end) = self.buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buf.drain(..=end).collect();
+                return Ok(Some(line));
+            }
+            if self.done {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(std::mem::take(&mut self.buf)));
+            }
+            match self.body.next().await {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    return Err(ApiError::BadRequest(format!(
+                        "Failed to read archive: {e}"
+                    )));
+                }
+                None => self.done = true,
+            }
+            if self.buf.len() > MAX_LINE_BYTES
+                && !self.buf[..MAX_LINE_BYTES].contains(&b'\n')
+            {
+                return Err(ApiError::PayloadTooLarge(format!(
+                    "Archive lines can be at most {MAX_LINE_BYTES} bytes"
+                )));
+            }
+        }
+    }
+
+    /// The next record, skipping blank lines.
+    async fn next_record(&mut self) -> ApiResult<Option<ArchiveRecord>> {
+        while let Some(line) = self.next_line().await? {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let record = serde_json::from_slice(&line).map_err(|e| {
+                ApiError::BadRequest(format!("Invalid archive record: {e}"))
+            })?;
+            return Ok(Some(record));
+        }
+        Ok(None)
+    }
+}
+
+/// How an archive is imported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportMode {
+    /// As a new server with new IDs. Only members that are users of this
+    /// host are kept, and the importing admin becomes the owner if no
+    /// owner was imported.
+    Copy,
+    /// As the server itself, moved here from `from_host`. IDs are kept, and
+    /// so are members of other hosts.
+    Migration { server_id: Uuid, from_host: String },
+}
+
+/// Recreate a server from an archive as a new server.
+pub async fn import<S, E>(
+    state: &AppState,
+    session: &Session,
+    body: S,
+) -> ApiResult<ArchiveImport>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    import_with_mode(state, session, body, ImportMode::Copy).await
+}
+
+/// Whether a user referred to by an archive can be recorded on this host.
+/// Users of other hosts are added as needed; users of this host have to
+/// exist already. Answers are remembered in `known_users`.
+async fn is_known_user(
+    state: &AppState,
+    conn: &mut PgConnection,
+    known_users: &mut HashMap<UserRef, bool>,
+    user_ref: &UserRef,
+) -> ApiResult<bool> {
+    if let Some(known) = known_users.get(user_ref) {
+        return Ok(*known);
+    }
+    let known = if state.config.is_remote_host(Some(&user_ref.host)) {
+        queries::archive::ensure_remote_user(conn, user_ref).await?;
+        true
+    } else {
+        queries::archive::user_exists(conn, user_ref).await?
+    };
+    known_users.insert(user_ref.clone(), known);
+    Ok(known)
+}
+
+/// Recreate a server from an archive. Everything is imported in one
+/// transaction, so a bad or truncated archive leaves nothing behind.
+/// Attachments are not part of archives.
+pub async fn import_with_mode<S, E>(
+    state: &AppState,
+    session: &Session,
+    body: S,
+    mode: ImportMode,
+) -> ApiResult<ArchiveImport>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let mut lines = Lines::new(body);
+
+    let header = match lines.next_record().await? {
+        Some(ArchiveRecord::Header(header)) => header,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Archive must start with a header".into(),
+            ));
+        }
+    };
+    if !(MIN_ARCHIVE_VERSION..=ARCHIVE_VERSION).contains(&header.version) {
+        return Err(ApiError::BadRequest(format!(
+            "Unsupported archive version {} (expected \
+             {MIN_ARCHIVE_VERSION} to {ARCHIVE_VERSION})",
+            header.version
+        )));
+    }
+    let server = match lines.next_record().await? {
+        Some(ArchiveRecord::Server(server)) => server,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Archive must have the server after its header".into(),
+            ));
+        }
+    };
+
+    let keep_ids = match &mode {
+        ImportMode::Copy => false,
+        ImportMode::Migration {
+            server_id,
+            from_host,
+        } => {
+            let from_host = pad_host(from_host);
+            if pad_host(&header.host) != from_host
+                || pad_host(&server.host) != from_host
+            {
+                return Err(ApiError::BadRequest(format!(
+                    "Archive is not of a server hosted by {from_host}"
+                )));
+            }
+            if server.id != *server_id {
+                return Err(ApiError::BadRequest(
+                    "Archive is not of the server being moved".into(),
+                ));
+            }
+            true
+        }
+    };
+    let id = |id: Uuid| keep_ids.then_some(id);
+
+    let mut tx = state.db_pool.begin().await?;
+    if keep_ids {
+        // Local users' memberships in the server now live in the server
+        queries::server_migrations::delete_cached_remote(&mut tx, server.id)
+            .await?;
+        queries::server_migrations::delete(&mut tx, server.id).await?;
+    }
+    let server_id =
+        queries::archive::insert_server(&mut tx, id(server.id), &server)
+            .await?;
+    let mut categories = HashMap::new();
+    let mut channels = HashMap::new();
+    // Whether each user seen so far exists on this host
+    let mut known_users: HashMap<UserRef, bool> = HashMap::new();
+    let mut members = 0;
+    let mut skipped_members = Vec::new();
+    let mut messages = 0;
+    // Pins by the ID of the message they pin, until it comes up
+    let mut pins: HashMap<Uuid, ArchivedPin> = HashMap::new();
+    loop {
+        let Some(record) = lines.next_record().await? else {
+            return Err(ApiError::BadRequest("Archive is truncated".into()));
+        };
+        match record {
+            ArchiveRecord::Header(_) | ArchiveRecord::Server(_) => {
+                return Err(ApiError::BadRequest(
+                    "Archive has more than one server".into(),
+                ));
+            }
+            ArchiveRecord::Category(category) => {
+                let new_id = queries::archive::insert_category(
+                    &mut tx,
+                    id(category.id),
+                    server_id,
+                    &category,
+                )
+                .await?;
+                categories.insert(category.id, new_id);
+            }
+            ArchiveRecord::Channel(channel) => {
+                let category_id = match channel.category_id {
+                    Some(old_id) => {
+                        Some(*categories.get(&old_id).ok_or_else(|| {
+                            ApiError::BadRequest(format!(
+                                "Channel {} is in an unknown category",
+                                channel.id
+                            ))
+                        })?)
+                    }
+                    None => None,
+                };
+                let new_id = queries::archive::insert_channel(
+                    &mut tx,
+                    id(channel.id),
+                    server_id,
+                    category_id,
+                    &channel,
+                )
+                .await?;
+                channels.insert(channel.id, new_id);
+            }
+            ArchiveRecord::Member(member) => {
+                let keep =
+                    if state.config.is_remote_host(Some(&member.user.host)) {
+                        if keep_ids {
+                            queries::archive::ensure_remote_user(
+                                &mut tx,
+                                &member.user,
+                            )
+                            .await?;
+                        }
+                        keep_ids
+                    } else {
+                        queries::archive::user_exists(&mut tx, &member.user)
+                            .await?
+                    };
+                if keep {
+                    queries::archive::insert_member(
+                        &mut tx, server_id, &member,
+                    )
+                    .await?;
+                    members += 1;
+                } else {
+                    skipped_members.push(member.user);
+                }
+            }
+            ArchiveRecord::Ban(ban) => {
+                let created_by = match ban.created_by() {
+                    Some(user_ref) => is_known_user(
+                        state,
+                        &mut tx,
+                        &mut known_users,
+                        &user_ref,
+                    )
+                    .await?
+                    .then_some(user_ref),
+                    None => None,
+                };
+                queries::archive::insert_ban(
+                    &mut tx,
+                    id(ban.id),
+                    server_id,
+                    created_by.as_ref(),
+                    &ban,
+                )
+                .await?;
+            }
+            ArchiveRecord::Timeout(timeout) => {
+                let created_by = match timeout.created_by() {
+                    Some(user_ref) => is_known_user(
+                        state,
+                        &mut tx,
+                        &mut known_users,
+                        &user_ref,
+                    )
+                    .await?
+                    .then_some(user_ref),
+                    None => None,
+                };
+                queries::archive::insert_timeout(
+                    &mut tx,
+                    server_id,
+                    created_by.as_ref(),
+                    &timeout,
+                )
+                .await?;
+            }
+            ArchiveRecord::Retention(policy) => {
+                let channel_id = match policy.channel_id {
+                    Some(old_id) => {
+                        Some(*channels.get(&old_id).ok_or_else(|| {
+                            ApiError::BadRequest(
+                                "Retention policy of an unknown channel".into(),
+                            )
+                        })?)
+                    }
+                    None => None,
+                };
+                queries::archive::insert_retention(
+                    &mut tx, server_id, channel_id, &policy,
+                )
+                .await?;
+            }
+            ArchiveRecord::Pin(pin) => {
+                pins.insert(pin.message_id, pin);
+            }
+            ArchiveRecord::Message(message) => {
+                let channel_id =
+                    *channels.get(&message.channel_id).ok_or_else(|| {
+                        ApiError::BadRequest(format!(
+                            "Message {} is in an unknown channel",
+                            message.id
+                        ))
+                    })?;
+                let author = match &message.author {
+                    Some(author) => {
+                        let author = author.as_ref();
+                        is_known_user(state, &mut tx, &mut known_users, &author)
+                            .await?
+                            .then_some(author)
+                    }
+                    None => None,
+                };
+                let new_id = queries::archive::insert_message(
+                    &mut tx,
+                    id(message.id),
+                    channel_id,
+                    author.as_ref(),
+                    &message,
+                )
+                .await?;
+                if let Some(pin) = pins.remove(&message.id) {
+                    queries::archive::insert_pin(
+                        &mut tx, new_id, channel_id, &pin,
+                    )
+                    .await?;
+                }
+                messages += 1;
+            }
+            ArchiveRecord::End => break,
+        }
+    }
+    if lines.next_record().await?.is_some() {
+        return Err(ApiError::BadRequest(
+            "Archive continues after its end".into(),
+        ));
+    }
+    let action = match mode {
+        ImportMode::Copy => {
+            let user_ref = session_user(session)?;
+            queries::archive::ensure_owner(&mut tx, server_id, user_ref)
+                .await?;
+            AuditAction::ServerImport
+        }
+        ImportMode::Migration { .. } => {
+            queries::server_migrations::update_server_host(
+                &mut tx,
+                server_id,
+                &state.config.local_host(),
+            )
+            .await?;
+            AuditAction::ServerMigrate
+        }
+    };
+    tx.commit().await?;
+
+    let server = queries::servers::get_by_id(state, server_id).await?;
+    audit::record_with_detail(
+        state,
+        Some(session),
+        action,
+        Some(server_id),
+        AuditTarget::Server(server_id),
+        Some(&format!("from {}", header.host)),
+    )
+    .await;
+    Ok(ArchiveImport {
+        server,
+        categories: categories.len() as u64,
+        channels: channels.len() as u64,
+        members,
+        skipped_members,
+        messages,
+    })
+}
+
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn export(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn import() -> Req {
+        Req::HostAdmin.client_only()
+    }
+}
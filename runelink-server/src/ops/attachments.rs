@@ -0,0 +1,398 @@
+use runelink_client::requests;
+use runelink_types::{Attachment, AttachmentUpload, capabilities};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{check_writable, moderation, require_capability},
+    queries::{self, attachments::StoredAttachment},
+    state::AppState,
+};
+
+/// How long an uploaded attachment may go without being sent with a message
+/// before it is deleted. Older uploads can no longer be sent.
+pub(crate) const ORPHANED_ATTACHMENT_HOURS: i64 = 24;
+
+const MAX_NAME_CHARS: usize = 255;
+const DEFAULT_NAME: &str = "attachment";
+const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+/// Keep only the last path component of an uploaded file name, without
+/// control characters.
+fn sanitize_name(name: &str) -> String {
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        DEFAULT_NAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Accept a `type/subtype` MIME type, falling back to
+/// `application/octet-stream` for anything else.
+fn sanitize_mime_type(mime_type: &str) -> String {
+    let is_token = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$&^_.+-".contains(c))
+    };
+    // Parameters such as "; charset=utf-8" are dropped
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+    match essence.split_once('/') {
+        Some((kind, subtype)) if is_token(kind) && is_token(subtype) => {
+            essence.to_ascii_lowercase()
+        }
+        _ => DEFAULT_MIME_TYPE.to_string(),
+    }
+}
+
+fn user_ref_required(action: &str) -> ApiError {
+    ApiError::Internal(format!("User reference required for {action}"))
+}
+
+/// Look up an attachment of a channel. Attachments that are not sent with a
+/// message yet are only visible to whoever uploaded them.
+async fn get_stored(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    attachment_id: Uuid,
+) -> ApiResult<StoredAttachment> {
+    let stored =
+        queries::attachments::get_by_id(&state.db_pool, attachment_id).await?;
+    if stored.channel_id != Some(channel_id) {
+        return Err(ApiError::NotFound);
+    }
+    let channel =
+        queries::channels::get_by_id(&state.db_pool, channel_id).await?;
+    if channel.server_id != server_id {
+        return Err(ApiError::NotFound);
+    }
+    if stored.attachment.message_id.is_none()
+        && session.user_ref.as_ref() != Some(&stored.uploader)
+    {
+        return Err(ApiError::NotFound);
+    }
+    Ok(stored)
+}
+
+/// Upload a file to a channel, to be sent with a message later on.
+pub async fn upload(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    upload: &AttachmentUpload,
+    target_host: Option<&str>,
+) -> ApiResult<Attachment> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        let uploader = session
+            .user_ref
+            .as_ref()
+            .ok_or_else(|| user_ref_required("attachment upload"))?;
+        let channel =
+            queries::channels::get_by_id(&state.db_pool, channel_id).await?;
+        if channel.server_id != server_id {
+            return Err(ApiError::NotFound);
+        }
+        if channel.is_archived() {
+            return Err(ApiError::Forbidden(
+                "Channel is archived and read-only".into(),
+            ));
+        }
+        moderation::check_can_post(state, server_id, uploader).await?;
+        let size = upload.data.len() as u64;
+        if size > state.config.attachment_max_bytes {
+            return Err(ApiError::PayloadTooLarge(format!(
+                "Attachments may be at most {} bytes",
+                state.config.attachment_max_bytes
+            )));
+        }
+        // Concurrent uploads by the same user wait here, so they can't
+        // all pass the quota check before any of them is counted
+        let mut tx = state.db_pool.begin().await?;
+        queries::attachments::lock_uploader(&mut tx, uploader).await?;
+        let used =
+            queries::attachments::total_size_by_uploader(&mut tx, uploader)
+                .await? as u64;
+        if used + size > state.config.attachment_quota_bytes {
+            return Err(ApiError::PayloadTooLarge(format!(
+                "Attachment quota of {} bytes exceeded ({used} bytes used)",
+                state.config.attachment_quota_bytes
+            )));
+        }
+        let attachment = queries::attachments::insert(
+            &mut tx,
+            channel_id,
+            uploader,
+            &sanitize_name(&upload.name),
+            &sanitize_mime_type(&upload.mime_type),
+            size as i64,
+            &hex::encode(Sha256::digest(&upload.data)),
+        )
+        .await?;
+        tx.commit().await?;
+        if let Err(e) = state
+            .blob_store
+            .put(&attachment.id.to_string(), &upload.data)
+            .await
+        {
+            queries::attachments::delete_unattached(
+                &state.db_pool,
+                attachment.id,
+            )
+            .await?;
+            return Err(e);
+        }
+        Ok(attachment)
+    } else {
+        // Upload to remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::ATTACHMENTS)?;
+        let user_ref = session
+            .user_ref
+            .clone()
+            .ok_or_else(|| user_ref_required("federated attachment upload"))?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let attachment = requests::attachments::federated::upload(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            upload,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to upload attachment to {host}: {e}"
+            ))
+        })?;
+        Ok(attachment)
+    }
+}
+
+/// Get the metadata of an attachment.
+pub async fn get_by_id(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    attachment_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Attachment> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let stored =
+            get_stored(state, session, server_id, channel_id, attachment_id)
+                .await?;
+        Ok(stored.attachment)
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::ATTACHMENTS)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            user_ref_required("federated attachment fetching")
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let attachment = requests::attachments::federated::fetch_by_id(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            attachment_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch attachment from {host}: {e}"
+            ))
+        })?;
+        Ok(attachment)
+    }
+}
+
+/// Get the metadata and contents of an attachment. Attachments on other
+/// hosts are fetched through this one.
+pub async fn get_content(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    attachment_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<(Attachment, Vec<u8>)> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let stored =
+            get_stored(state, session, server_id, channel_id, attachment_id)
+                .await?;
+        let data = state
+            .blob_store
+            .get(&stored.attachment.id.to_string())
+            .await?;
+        Ok((stored.attachment, data))
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::ATTACHMENTS)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            user_ref_required("federated attachment fetching")
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let attachment = requests::attachments::federated::fetch_by_id(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            attachment_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch attachment from {host}: {e}"
+            ))
+        })?;
+        let data = requests::attachments::federated::fetch_content(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            attachment_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch attachment contents from {host}: {e}"
+            ))
+        })?;
+        Ok((attachment, data))
+    }
+}
+
+/// Auth requirements for attachment operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn upload(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().client_only()
+    }
+
+    pub fn get_by_id(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().client_only()
+    }
+
+    pub fn get_content(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn upload(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+
+        pub fn get_by_id(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+
+        pub fn get_content(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_name_strips_paths() {
+        assert_eq!(sanitize_name("report.pdf"), "report.pdf");
+        assert_eq!(sanitize_name("/etc/passwd"), "passwd");
+        assert_eq!(sanitize_name("../../secret.txt"), "secret.txt");
+        assert_eq!(sanitize_name("C:\\Users\\me\\a.png"), "a.png");
+        assert_eq!(sanitize_name("dir/"), DEFAULT_NAME);
+    }
+
+    #[test]
+    fn test_sanitize_name_rejects_dot_names() {
+        assert_eq!(sanitize_name(".."), DEFAULT_NAME);
+        assert_eq!(sanitize_name("a/.."), DEFAULT_NAME);
+        assert_eq!(sanitize_name("."), DEFAULT_NAME);
+        assert_eq!(sanitize_name("   "), DEFAULT_NAME);
+        assert_eq!(sanitize_name(""), DEFAULT_NAME);
+    }
+
+    #[test]
+    fn test_sanitize_name_drops_control_characters() {
+        assert_eq!(sanitize_name("a\u{0}b\nc\r.txt"), "abc.txt");
+        assert_eq!(sanitize_name(".\u{7f}."), DEFAULT_NAME);
+        let long = "x".repeat(MAX_NAME_CHARS + 10);
+        assert_eq!(sanitize_name(&long).chars().count(), MAX_NAME_CHARS);
+    }
+
+    #[test]
+    fn test_sanitize_mime_type() {
+        assert_eq!(sanitize_mime_type("image/png"), "image/png");
+        assert_eq!(sanitize_mime_type("Text/HTML"), "text/html");
+        assert_eq!(
+            sanitize_mime_type("text/plain; charset=utf-8"),
+            "text/plain"
+        );
+        assert_eq!(
+            sanitize_mime_type("application/vnd.api+json"),
+            "application/vnd.api+json"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_mime_type_rejects_malformed() {
+        for mime_type in [
+            "",
+            "image",
+            "image/",
+            "/png",
+            "text/html/x",
+            "text/ html",
+            "text/html\r\nX-Evil: 1",
+            "image/p<ng",
+            "tëxt/plain",
+        ] {
+            assert_eq!(sanitize_mime_type(mime_type), DEFAULT_MIME_TYPE);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use log::error;
+use runelink_client::{requests, util::split_scheme};
+use runelink_types::{AuditEntry, AuditLogQuery, UserRef, capabilities};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::require_capability,
+    queries,
+    state::AppState,
+};
+
+/// A privileged action worth recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    ServerCreate,
+    ServerUpdate,
+    ServerDelete,
+    ServerImport,
+    ServerMigrate,
+    ChannelCreate,
+    ChannelUpdate,
+    ChannelDelete,
+    ChannelArchive,
+    ChannelUnarchive,
+    ChannelRestore,
+    CategoryCreate,
+    CategoryUpdate,
+    CategoryDelete,
+    MessageDelete,
+    MessagePin,
+    MessageUnpin,
+    MembershipCreate,
+    MembershipDelete,
+    MembershipRoleUpdate,
+    OwnershipTransfer,
+    UserCreate,
+    UserDelete,
+    InviteCreate,
+    InviteDelete,
+    SignupApprove,
+    SignupReject,
+    PasswordResetCreate,
+    BanCreate,
+    BanDelete,
+    TimeoutCreate,
+    TimeoutDelete,
+    MessageBulkDelete,
+    RetentionUpdate,
+    RetentionDelete,
+    RetentionPurge,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::ServerCreate => "server.create",
+            AuditAction::ServerUpdate => "server.update",
+            AuditAction::ServerDelete => "server.delete",
+            AuditAction::ServerImport => "server.import",
+            AuditAction::ServerMigrate => "server.migrate",
+            AuditAction::ChannelCreate => "channel.create",
+            AuditAction::ChannelUpdate => "channel.update",
+            AuditAction::ChannelDelete => "channel.delete",
+            AuditAction::ChannelArchive => "channel.archive",
+            AuditAction::ChannelUnarchive => "channel.unarchive",
+            AuditAction::ChannelRestore => "channel.restore",
+            AuditAction::CategoryCreate => "category.create",
+            AuditAction::CategoryUpdate => "category.update",
+            AuditAction::CategoryDelete => "category.delete",
+            AuditAction::MessageDelete => "message.delete",
+            AuditAction::MessagePin => "message.pin",
+            AuditAction::MessageUnpin => "message.unpin",
+            AuditAction::MembershipCreate => "membership.create",
+            AuditAction::MembershipDelete => "membership.delete",
+            AuditAction::MembershipRoleUpdate => "membership.role_update",
+            AuditAction::OwnershipTransfer => "server.ownership_transfer",
+            AuditAction::UserCreate => "user.create",
+            AuditAction::UserDelete => "user.delete",
+            AuditAction::InviteCreate => "invite.create",
+            AuditAction::InviteDelete => "invite.delete",
+            AuditAction::SignupApprove => "signup.approve",
+            AuditAction::SignupReject => "signup.reject",
+            AuditAction::PasswordResetCreate => "password_reset.create",
+            AuditAction::BanCreate => "ban.create",
+            AuditAction::BanDelete => "ban.delete",
+            AuditAction::TimeoutCreate => "timeout.create",
+            AuditAction::TimeoutDelete => "timeout.delete",
+            AuditAction::MessageBulkDelete => "message.bulk_delete",
+            AuditAction::RetentionUpdate => "retention.update",
+            AuditAction::RetentionDelete => "retention.delete",
+            AuditAction::RetentionPurge => "retention.purge",
+        }
+    }
+}
+
+/// What an audited action was taken on.
+#[derive(Clone, Debug)]
+pub enum AuditTarget {
+    Server(Uuid),
+    Channel(Uuid),
+    Message(Uuid),
+    User(UserRef),
+    Invite(Uuid),
+    Ban(Uuid),
+    Category(Uuid),
+}
+
+impl AuditTarget {
+    fn kind(&self) -> &'static str {
+        match self {
+            AuditTarget::Server(_) => "server",
+            AuditTarget::Channel(_) => "channel",
+            AuditTarget::Message(_) => "message",
+            AuditTarget::User(_) => "user",
+            AuditTarget::Invite(_) => "invite",
+            AuditTarget::Ban(_) => "ban",
+            AuditTarget::Category(_) => "category",
+        }
+    }
+
+    fn id(&self) -> String {
+        match self {
+            AuditTarget::Server(id)
+            | AuditTarget::Channel(id)
+            | AuditTarget::Message(id)
+            | AuditTarget::Invite(id)
+            | AuditTarget::Ban(id)
+            | AuditTarget::Category(id) => id.to_string(),
+            AuditTarget::User(user_ref) => user_ref.to_string(),
+        }
+    }
+}
+
+/// Host that issued the credentials behind a session.
+fn issuer_host(state: &AppState, session: &Session) -> String {
+    match &session.federation {
+        Some(claims) => {
+            let (_, rest) = split_scheme(&claims.iss);
+            rest.split('/').next().unwrap_or(rest).to_string()
+        }
+        None => state.config.local_host(),
+    }
+}
+
+/// Append an entry to the audit log. `session` is `None` for actions the
+/// host takes on its own.
+///
+/// The action has already happened by the time it is recorded, so a failure
+/// to write the entry is logged rather than failing the request.
+pub async fn record(
+    state: &AppState,
+    session: Option<&Session>,
+    action: AuditAction,
+    server_id: Option<Uuid>,
+    target: AuditTarget,
+) {
+    record_with_detail(state, session, action, server_id, target, None).await
+}
+
+/// Like [`record`], with free-form details about the action.
+pub async fn record_with_detail(
+    state: &AppState,
+    session: Option<&Session>,
+    action: AuditAction,
+    server_id: Option<Uuid>,
+    target: AuditTarget,
+    detail: Option<&str>,
+) {
+    let actor = session.and_then(|s| s.user_ref.as_ref());
+    let issuer = session.map(|s| issuer_host(state, s));
+    let result = queries::audit::insert(
+        &state.db_pool,
+        actor,
+        issuer.as_deref(),
+        action.as_str(),
+        server_id,
+        target.kind(),
+        &target.id(),
+        detail,
+    )
+    .await;
+    if let Err(e) = result {
+        error!(
+            "Failed to record audit entry {} on {}:{}: {e}",
+            action.as_str(),
+            target.kind(),
+            target.id()
+        );
+    }
+}
+
+/// Read the audit log of a server.
+pub async fn get_by_server(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    query: &AuditLogQuery,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<AuditEntry>> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        queries::audit::get(&state.db_pool, Some(server_id), query).await
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::AUDIT_LOG)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated audit log fetching"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let entries = requests::audit::federated::fetch_by_server(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            query,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch audit log from {host}: {e}"
+            ))
+        })?;
+        Ok(entries)
+    }
+}
+
+/// Read the whole audit log of this host.
+pub async fn get_all(
+    state: &AppState,
+    _session: &Session,
+    server_id: Option<Uuid>,
+    query: &AuditLogQuery,
+) -> ApiResult<Vec<AuditEntry>> {
+    queries::audit::get(&state.db_pool, server_id, query).await
+}
+
+/// Auth requirements for audit log operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn get_by_server(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn get_all() -> Req {
+        Req::HostAdmin.client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn get_by_server(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use runelink_client::requests;
+use runelink_types::{
+    ChannelCategory, ChannelCategoryUpdate, NewChannelCategory, capabilities,
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        channels::move_to_position,
+        check_writable, require_capability,
+        servers::validate_title,
+    },
+    queries,
+    state::AppState,
+};
+
+/// Create a new category at the end of a server's list.
+pub async fn create(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    new_category: &NewChannelCategory,
+    target_host: Option<&str>,
+) -> ApiResult<ChannelCategory> {
+    validate_title(Some(&new_category.title))?;
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        let category = queries::categories::insert(
+            &state.db_pool,
+            server_id,
+            new_category,
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::CategoryCreate,
+            Some(server_id),
+            AuditTarget::Category(category.id),
+        )
+        .await;
+        Ok(category)
+    } else {
+        // Create on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::CATEGORIES)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated category creation"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let category = requests::categories::federated::create(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            new_category,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to create category on {host}: {e}"
+            ))
+        })?;
+        Ok(category)
+    }
+}
+
+/// Get the categories of a server, in order.
+pub async fn get_by_server(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<ChannelCategory>> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        queries::categories::get_by_server(&state.db_pool, server_id).await
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::CATEGORIES)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated category fetching"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let categories = requests::categories::federated::fetch_by_server(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch categories from {host}: {e}"
+            ))
+        })?;
+        Ok(categories)
+    }
+}
+
+/// Rename and/or move a category.
+pub async fn update(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    category_id: Uuid,
+    update: &ChannelCategoryUpdate,
+    target_host: Option<&str>,
+) -> ApiResult<ChannelCategory> {
+    validate_title(update.title.as_deref())?;
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        // Verify the category belongs to the server
+        let category =
+            queries::categories::get_by_id(&state.db_pool, category_id).await?;
+        if category.server_id != server_id {
+            return Err(ApiError::NotFound);
+        }
+        if let Some(position) = update.position {
+            let ids: Vec<Uuid> =
+                queries::categories::get_by_server(&state.db_pool, server_id)
+                    .await?
+                    .into_iter()
+                    .map(|c| c.id)
+                    .collect();
+            let ids = move_to_position(ids, category_id, position);
+            queries::categories::set_positions(&state.db_pool, server_id, &ids)
+                .await?;
+        }
+        let category = queries::categories::update(
+            &state.db_pool,
+            category_id,
+            update.title.as_deref(),
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::CategoryUpdate,
+            Some(server_id),
+            AuditTarget::Category(category_id),
+        )
+        .await;
+        Ok(category)
+    } else {
+        // Update on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::CATEGORIES)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated category update"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let category = requests::categories::federated::update(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            category_id,
+            update,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to update category on {host}: {e}"
+            ))
+        })?;
+        Ok(category)
+    }
+}
+
+/// Delete a category. Its channels are kept, uncategorized.
+pub async fn delete(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    category_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<()> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        // Verify the category belongs to the server
+        let category =
+            queries::categories::get_by_id(&state.db_pool, category_id).await?;
+        if category.server_id != server_id {
+            return Err(ApiError::NotFound);
+        }
+        queries::categories::delete(&state.db_pool, category_id).await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::CategoryDelete,
+            Some(server_id),
+            AuditTarget::Category(category_id),
+        )
+        .await;
+        Ok(())
+    } else {
+        // Delete on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::CATEGORIES)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated category deletion"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        requests::categories::federated::delete(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            category_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to delete category on {host}: {e}"
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+/// Auth requirements for category operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn create(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn get_by_server(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().client_only()
+    }
+
+    pub fn update(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn delete(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn create(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
+        pub fn get_by_server(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+
+        pub fn update(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
+        pub fn delete(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+    }
+}
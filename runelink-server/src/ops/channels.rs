@@ -1,10 +1,15 @@
-use runelink_client::{requests, util::get_api_url};
-use runelink_types::{Channel, NewChannel};
+use runelink_client::requests;
+use runelink_types::{Channel, ChannelUpdate, NewChannel, capabilities};
 use uuid::Uuid;
 
 use crate::{
     auth::Session,
     error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        check_writable, require_capability,
+        servers::validate_title,
+    },
     queries,
     state::AppState,
 };
@@ -19,14 +24,23 @@ pub async fn create(
 ) -> ApiResult<Channel> {
     // Handle local case
     if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
         let channel =
             queries::channels::insert(&state.db_pool, server_id, new_channel)
                 .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::ChannelCreate,
+            Some(server_id),
+            AuditTarget::Channel(channel.id),
+        )
+        .await;
         Ok(channel)
     } else {
         // Create on remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated channel creation"
@@ -35,12 +49,12 @@ pub async fn create(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         let channel = requests::channels::federated::create(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             new_channel,
@@ -68,7 +82,7 @@ pub async fn get_all(
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated channel fetching"
@@ -77,12 +91,12 @@ pub async fn get_all(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref,
         )?;
         let channels = requests::channels::federated::fetch_all(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
         )
         .await
@@ -95,20 +109,27 @@ pub async fn get_all(
     }
 }
 
-/// Get channels in a server.
+/// Get channels in a server, leaving out archived channels unless
+/// `include_archived`.
 pub async fn get_by_server(
     state: &AppState,
     session: &Session,
     server_id: Uuid,
+    include_archived: bool,
     target_host: Option<&str>,
 ) -> ApiResult<Vec<Channel>> {
     if !state.config.is_remote_host(target_host) {
         // Handle local case
-        queries::channels::get_by_server(&state.db_pool, server_id).await
+        queries::channels::get_by_server(
+            &state.db_pool,
+            server_id,
+            include_archived,
+        )
+        .await
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated channel fetching"
@@ -117,14 +138,15 @@ pub async fn get_by_server(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref,
         )?;
         let channels = requests::channels::federated::fetch_by_server(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
+            include_archived,
         )
         .await
         .map_err(|e| {
@@ -152,7 +174,7 @@ pub async fn get_by_id(
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated channel fetching"
@@ -161,12 +183,12 @@ pub async fn get_by_id(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref,
         )?;
         let channel = requests::channels::federated::fetch_by_id(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             channel_id,
@@ -181,7 +203,117 @@ pub async fn get_by_id(
     }
 }
 
-/// Delete a channel by ID.
+/// Reorder `ids` so that `moved` sits at `position` (clamped to the list),
+/// keeping the relative order of everything else.
+pub(crate) fn move_to_position(
+    mut ids: Vec<Uuid>,
+    moved: Uuid,
+    position: i32,
+) -> Vec<Uuid> {
+    ids.retain(|id| *id != moved);
+    let index = position.clamp(0, ids.len() as i32) as usize;
+    ids.insert(index, moved);
+    ids
+}
+
+/// Update a channel's title, description, position and/or category.
+pub async fn update(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    update: &ChannelUpdate,
+    target_host: Option<&str>,
+) -> ApiResult<Channel> {
+    validate_title(update.title.as_deref())?;
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        // Verify the channel belongs to the server
+        let channel =
+            queries::channels::get_by_id(&state.db_pool, channel_id).await?;
+        if channel.server_id != server_id {
+            return Err(ApiError::NotFound);
+        }
+        if let Some(Some(category_id)) = update.category_id {
+            let category =
+                queries::categories::get_by_id(&state.db_pool, category_id)
+                    .await?;
+            if category.server_id != server_id {
+                return Err(ApiError::BadRequest(
+                    "Category belongs to another server".into(),
+                ));
+            }
+        }
+        if let Some(position) = update.position {
+            let ids: Vec<Uuid> = queries::channels::get_by_server(
+                &state.db_pool,
+                server_id,
+                true,
+            )
+            .await?
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+            let ids = move_to_position(ids, channel_id, position);
+            queries::channels::set_positions(&state.db_pool, server_id, &ids)
+                .await?;
+        }
+        let channel = queries::channels::update(
+            &state.db_pool,
+            channel_id,
+            update.title.as_deref(),
+            update.description.is_some(),
+            update.description.clone().flatten().as_deref(),
+            update.category_id.is_some(),
+            update.category_id.flatten(),
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::ChannelUpdate,
+            Some(server_id),
+            AuditTarget::Channel(channel_id),
+        )
+        .await;
+        Ok(channel)
+    } else {
+        // Update on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::METADATA)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated channel update"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let channel = requests::channels::federated::update(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            update,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to update channel on {host}: {e}"
+            ))
+        })?;
+        Ok(channel)
+    }
+}
+
+/// Move a channel to the trash. It can be restored until the trash period
+/// runs out and the channel is purged along with its messages.
 pub async fn delete(
     state: &AppState,
     session: &Session,
@@ -191,6 +323,7 @@ pub async fn delete(
 ) -> ApiResult<()> {
     // Handle local case
     if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
         // Verify the channel belongs to the server
         let channel =
             queries::channels::get_by_id(&state.db_pool, channel_id).await?;
@@ -200,11 +333,19 @@ pub async fn delete(
             ));
         }
         queries::channels::delete(&state.db_pool, channel_id).await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::ChannelDelete,
+            Some(server_id),
+            AuditTarget::Channel(channel_id),
+        )
+        .await;
         Ok(())
     } else {
         // Delete on remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated channel deletion"
@@ -213,12 +354,12 @@ pub async fn delete(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref,
         )?;
         requests::channels::federated::delete(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             channel_id,
@@ -233,6 +374,145 @@ pub async fn delete(
     }
 }
 
+/// Archive a channel (making it read-only and hiding it from default
+/// listings) or unarchive it.
+pub async fn set_archived(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    archived: bool,
+    target_host: Option<&str>,
+) -> ApiResult<Channel> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        // Verify the channel belongs to the server
+        let channel =
+            queries::channels::get_by_id(&state.db_pool, channel_id).await?;
+        if channel.server_id != server_id {
+            return Err(ApiError::NotFound);
+        }
+        let channel = queries::channels::set_archived(
+            &state.db_pool,
+            channel_id,
+            archived,
+        )
+        .await?;
+        let action = if archived {
+            AuditAction::ChannelArchive
+        } else {
+            AuditAction::ChannelUnarchive
+        };
+        audit::record(
+            state,
+            Some(session),
+            action,
+            Some(server_id),
+            AuditTarget::Channel(channel_id),
+        )
+        .await;
+        Ok(channel)
+    } else {
+        // Archive on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::ARCHIVING)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated channel archiving"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let result = if archived {
+            requests::channels::federated::archive(
+                &state.http_client,
+                &peer.federation_url,
+                &token,
+                server_id,
+                channel_id,
+            )
+            .await
+        } else {
+            requests::channels::federated::unarchive(
+                &state.http_client,
+                &peer.federation_url,
+                &token,
+                server_id,
+                channel_id,
+            )
+            .await
+        };
+        let channel = result.map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to update channel archive state on {host}: {e}"
+            ))
+        })?;
+        Ok(channel)
+    }
+}
+
+/// Take a channel back out of the trash.
+pub async fn restore(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Channel> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        let channel =
+            queries::channels::restore(&state.db_pool, server_id, channel_id)
+                .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::ChannelRestore,
+            Some(server_id),
+            AuditTarget::Channel(channel_id),
+        )
+        .await;
+        Ok(channel)
+    } else {
+        // Restore on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::ARCHIVING)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated channel restore"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let channel = requests::channels::federated::restore(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to restore channel on {host}: {e}"
+            ))
+        })?;
+        Ok(channel)
+    }
+}
+
 /// Auth requirements for channel operations.
 pub mod auth {
     use super::*;
@@ -254,10 +534,22 @@ pub mod auth {
         Req::ServerMember(server_id).or_admin().client_only()
     }
 
+    pub fn update(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
     pub fn delete(server_id: Uuid) -> Req {
         Req::ServerAdmin(server_id).or_admin().client_only()
     }
 
+    pub fn archive(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn restore(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
     pub mod federated {
         use super::*;
 
@@ -277,8 +569,20 @@ pub mod auth {
             Req::ServerMember(server_id).federated_only()
         }
 
+        pub fn update(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
         pub fn delete(server_id: Uuid) -> Req {
             Req::ServerAdmin(server_id).federated_only()
         }
+
+        pub fn archive(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
+        pub fn restore(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
     }
 }
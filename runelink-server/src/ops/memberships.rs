@@ -1,13 +1,19 @@
-use runelink_client::{requests, util::get_api_url};
+use log::{info, warn};
+use runelink_client::requests;
 use runelink_types::{
-    FullServerMembership, NewServerMembership, ServerMember, ServerMembership,
-    UserRef,
+    FullServerMembership, NewServerMembership, OwnershipTransfer, ServerMember,
+    ServerMembership, ServerRole, ServerRoleUpdate, UserRef, UserRole,
+    capabilities,
 };
 use uuid::Uuid;
 
 use crate::{
     auth::Session,
     error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        check_writable, moderation, require_capability,
+    },
     queries,
     state::AppState,
 };
@@ -30,15 +36,20 @@ pub async fn create(
                 "User host in membership does not match local host".into(),
             ));
         }
-        let server_api_url = get_api_url(&new_membership.server_host);
+        let peer = state.resolve_host(&new_membership.server_host).await;
+        require_capability(
+            &peer,
+            &new_membership.server_host,
+            capabilities::MEMBERSHIPS,
+        )?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            server_api_url.clone(),
+            peer.api_url.clone(),
             new_membership.user_ref.clone(),
         )?;
         let membership = requests::memberships::federated::create(
             &state.http_client,
-            &server_api_url,
+            &peer.federation_url,
             &token,
             new_membership,
         )
@@ -56,11 +67,20 @@ pub async fn create(
         return Ok(cached_membership.as_full(user));
     }
 
+    let _writing = check_writable(state, new_membership.server_id).await?;
+    moderation::check_can_join(
+        state,
+        new_membership.server_id,
+        &new_membership.user_ref,
+    )
+    .await?;
+
     // Ensure remote user exists locally before creating membership
     if new_membership.user_ref.host != state.config.local_host() {
         let user = session.lookup_user(state).await?;
         if user.is_none() {
-            let api_url = get_api_url(&new_membership.user_ref.host);
+            let api_url =
+                state.api_url_for(&new_membership.user_ref.host).await;
             let user = requests::users::fetch_by_ref(
                 &state.http_client,
                 &api_url,
@@ -81,6 +101,14 @@ pub async fn create(
         new_membership.user_ref.clone(),
     )
     .await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::MembershipCreate,
+        Some(new_membership.server_id),
+        AuditTarget::User(new_membership.user_ref.clone()),
+    )
+    .await;
     let full_membership = FullServerMembership {
         server: membership.server,
         user: member.user,
@@ -109,7 +137,7 @@ pub async fn get_members_by_server(
     } else {
         // Fetch from remote host (public endpoint, no auth needed)
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let api_url = state.api_url_for(host).await;
         let members = requests::memberships::fetch_members_by_server(
             &state.http_client,
             &api_url,
@@ -145,7 +173,7 @@ pub async fn get_member_by_user_and_server(
     } else {
         // Fetch from remote host (public endpoint, no auth needed)
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let api_url = state.api_url_for(host).await;
         let member = requests::memberships::fetch_member_by_user_and_server(
             &state.http_client,
             &api_url,
@@ -192,37 +220,57 @@ pub async fn delete(
 
     // Handle local case
     if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
         // Verify the membership exists
-        queries::memberships::get_local_member_by_user_and_server(
+        let member = queries::memberships::get_local_member_by_user_and_server(
             &state.db_pool,
             server_id,
             user_ref.clone(),
         )
         .await?;
-        queries::memberships::delete_local(&state.db_pool, server_id, user_ref)
-            .await?;
+        match member.role {
+            ServerRole::Owner => {
+                return Err(ApiError::BadRequest(
+                    "Transfer ownership before leaving the server".into(),
+                ));
+            }
+            ServerRole::Admin => ensure_other_admin(state, server_id).await?,
+            ServerRole::Member => {}
+        }
+        queries::memberships::delete_local(
+            &state.db_pool,
+            server_id,
+            user_ref.clone(),
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::MembershipDelete,
+            Some(server_id),
+            AuditTarget::User(user_ref),
+        )
+        .await;
         Ok(())
     } else {
         // Delete on remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         requests::memberships::federated::delete(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             user_ref.clone(),
         )
         .await
         .map_err(|e| {
-            ApiError::Internal(format!(
-                "Failed to leave server on {host}: {e}"
-            ))
+            ApiError::Internal(format!("Failed to leave server on {host}: {e}"))
         })?;
         // Also delete from local cache if it exists
         let _ = queries::memberships::delete_remote(
@@ -235,6 +283,350 @@ pub async fn delete(
     }
 }
 
+/// Fail if the server has no admin (or owner) left besides the one about to
+/// step down.
+async fn ensure_other_admin(
+    state: &AppState,
+    server_id: Uuid,
+) -> ApiResult<()> {
+    let admins =
+        queries::memberships::count_local_admins(&state.db_pool, server_id)
+            .await?;
+    if admins <= 1 {
+        return Err(ApiError::BadRequest(
+            "A server must keep at least one admin".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the session's user may demote admins other than themselves: the
+/// server owner, or a host admin of this host.
+async fn can_demote_admins(
+    state: &AppState,
+    session: &mut Session,
+    server_id: Uuid,
+) -> ApiResult<bool> {
+    let owner =
+        queries::memberships::get_local_owner(&state.db_pool, server_id)
+            .await?;
+    if owner.is_some() && owner == session.user_ref {
+        return Ok(true);
+    }
+    if session.federation.is_some() {
+        return Ok(false);
+    }
+    let user = session.lookup_user(state).await?;
+    Ok(user.is_some_and(|user| user.role == UserRole::Admin))
+}
+
+/// Let a remote user's home host know that their membership in one of our
+/// servers changed, so its cached copy (which it authorizes the user with)
+/// stays current.
+///
+/// Best effort: the change has already been made here, so failures are only
+/// logged.
+pub(crate) async fn push_membership_update(
+    state: &AppState,
+    server_id: Uuid,
+    user_ref: &UserRef,
+) {
+    let host = user_ref.host.as_str();
+    if !state.config.is_remote_host(Some(host)) {
+        return;
+    }
+    let membership = match queries::memberships::get_local_by_user_and_server(
+        state,
+        server_id,
+        user_ref.clone(),
+    )
+    .await
+    {
+        Ok(membership) => membership,
+        Err(e) => {
+            warn!(
+                "Failed to load membership of {user_ref} in {server_id}: {e}"
+            );
+            return;
+        }
+    };
+    let peer = state.resolve_host(host).await;
+    if !peer.supports(capabilities::MEMBERSHIP_SYNC) {
+        info!("Skipping membership sync on {host}: not supported by peer");
+        return;
+    }
+    let token = match state.key_manager.issue_federation_jwt_server_only(
+        state.config.api_url(),
+        peer.api_url.clone(),
+    ) {
+        Ok(token) => token,
+        Err(e) => {
+            warn!("Failed to issue federation token for host {host}: {e}");
+            return;
+        }
+    };
+    let result = requests::memberships::federated::sync(
+        &state.http_client,
+        &peer.federation_url,
+        &token,
+        &membership,
+    )
+    .await;
+    if let Err(e) = result {
+        warn!(
+            "Failed to sync membership of {user_ref} in {server_id} to {host}: {e}"
+        );
+    }
+}
+
+/// Let a remote user's home host know that they were removed from one of
+/// our servers, so it stops listing the server for them.
+///
+/// Best effort, like [`push_membership_update`].
+pub(crate) async fn push_membership_removal(
+    state: &AppState,
+    server_id: Uuid,
+    user_ref: &UserRef,
+) {
+    let host = user_ref.host.as_str();
+    if !state.config.is_remote_host(Some(host)) {
+        return;
+    }
+    let peer = state.resolve_host(host).await;
+    if !peer.supports(capabilities::MEMBERSHIP_SYNC) {
+        info!("Skipping membership sync on {host}: not supported by peer");
+        return;
+    }
+    let token = match state.key_manager.issue_federation_jwt_server_only(
+        state.config.api_url(),
+        peer.api_url.clone(),
+    ) {
+        Ok(token) => token,
+        Err(e) => {
+            warn!("Failed to issue federation token for host {host}: {e}");
+            return;
+        }
+    };
+    let result = requests::memberships::federated::unsync(
+        &state.http_client,
+        &peer.federation_url,
+        &token,
+        server_id,
+        user_ref,
+    )
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to tell {host} that {user_ref} left {server_id}: {e}");
+    }
+}
+
+/// Promote a member to admin or demote an admin to member.
+pub async fn update_role(
+    state: &AppState,
+    session: &mut Session,
+    server_id: Uuid,
+    user_ref: UserRef,
+    update: &ServerRoleUpdate,
+    target_host: Option<&str>,
+) -> ApiResult<ServerMember> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        let _writing = check_writable(state, server_id).await?;
+        if update.role == ServerRole::Owner {
+            return Err(ApiError::BadRequest(
+                "Use an ownership transfer to change the owner".into(),
+            ));
+        }
+        let member = queries::memberships::get_local_member_by_user_and_server(
+            &state.db_pool,
+            server_id,
+            user_ref.clone(),
+        )
+        .await?;
+        if member.role == update.role {
+            return Ok(member);
+        }
+        match member.role {
+            ServerRole::Owner => {
+                return Err(ApiError::BadRequest(
+                    "Transfer ownership before changing the owner's role"
+                        .into(),
+                ));
+            }
+            ServerRole::Admin => {
+                // Admins may step down themselves, but only the owner demotes
+                // other admins
+                if session.user_ref.as_ref() != Some(&user_ref)
+                    && !can_demote_admins(state, session, server_id).await?
+                {
+                    return Err(ApiError::Forbidden(
+                        "Only the server owner can demote other admins".into(),
+                    ));
+                }
+                ensure_other_admin(state, server_id).await?;
+            }
+            ServerRole::Member => {}
+        }
+        queries::memberships::update_local_role(
+            &state.db_pool,
+            server_id,
+            &user_ref,
+            update.role,
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::MembershipRoleUpdate,
+            Some(server_id),
+            AuditTarget::User(user_ref.clone()),
+        )
+        .await;
+        push_membership_update(state, server_id, &user_ref).await;
+        queries::memberships::get_local_member_by_user_and_server(
+            &state.db_pool,
+            server_id,
+            user_ref,
+        )
+        .await
+    } else {
+        // Update on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::ROLES)?;
+        let session_user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated role update".to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            session_user_ref,
+        )?;
+        let member = requests::memberships::federated::update_role(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            user_ref,
+            update,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to update member role on {host}: {e}"
+            ))
+        })?;
+        Ok(member)
+    }
+}
+
+/// Hand a server over to another member. The previous owner stays an admin.
+pub async fn transfer_ownership(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    transfer: &OwnershipTransfer,
+    target_host: Option<&str>,
+) -> ApiResult<ServerMember> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        let _writing = check_writable(state, server_id).await?;
+        let new_owner = &transfer.new_owner;
+        let previous_owner =
+            queries::memberships::get_local_owner(&state.db_pool, server_id)
+                .await?;
+        if previous_owner.as_ref() != Some(new_owner) {
+            queries::memberships::transfer_local_ownership(
+                &state.db_pool,
+                server_id,
+                new_owner,
+            )
+            .await?;
+            audit::record(
+                state,
+                Some(session),
+                AuditAction::OwnershipTransfer,
+                Some(server_id),
+                AuditTarget::User(new_owner.clone()),
+            )
+            .await;
+            push_membership_update(state, server_id, new_owner).await;
+            if let Some(previous_owner) = &previous_owner {
+                push_membership_update(state, server_id, previous_owner).await;
+            }
+        }
+        queries::memberships::get_local_member_by_user_and_server(
+            &state.db_pool,
+            server_id,
+            new_owner.clone(),
+        )
+        .await
+    } else {
+        // Transfer on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::ROLES)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated ownership transfer"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let member = requests::memberships::federated::transfer_ownership(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            transfer,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to transfer server ownership on {host}: {e}"
+            ))
+        })?;
+        Ok(member)
+    }
+}
+
+/// Update the cached copy of a local user's membership in a remote server,
+/// as pushed by the server's host.
+pub async fn sync_remote(
+    state: &AppState,
+    membership: &ServerMembership,
+) -> ApiResult<()> {
+    if state.config.is_remote_host(Some(&membership.user_ref.host)) {
+        return Err(ApiError::BadRequest(
+            "Membership is not for a user of this host".into(),
+        ));
+    }
+    queries::servers::update_remote(&state.db_pool, &membership.server).await?;
+    queries::memberships::update_remote(&state.db_pool, membership).await
+}
+
+/// Drop the cached copy of a local user's membership in a remote server,
+/// after the server's host removed them.
+pub async fn unsync_remote(
+    state: &AppState,
+    server_id: Uuid,
+    user_ref: UserRef,
+) -> ApiResult<()> {
+    if state.config.is_remote_host(Some(&user_ref.host)) {
+        return Err(ApiError::BadRequest(
+            "Membership is not for a user of this host".into(),
+        ));
+    }
+    queries::memberships::delete_remote(&state.db_pool, server_id, user_ref)
+        .await
+}
+
 /// Auth requirements for membership operations.
 pub mod auth {
     use super::*;
@@ -253,6 +645,14 @@ pub mod auth {
             .client_only()
     }
 
+    pub fn update_role(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn transfer_ownership(server_id: Uuid) -> Req {
+        Req::ServerOwner(server_id).or_admin().client_only()
+    }
+
     pub mod federated {
         use super::*;
 
@@ -263,5 +663,19 @@ pub mod auth {
         pub fn delete(_server_id: Uuid, user_ref: UserRef) -> Req {
             Req::FederatedUser(user_ref).federated_only()
         }
+
+        pub fn update_role(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
+        pub fn transfer_ownership(server_id: Uuid) -> Req {
+            Req::ServerOwner(server_id).federated_only()
+        }
+
+        /// Only the host of the server may update or drop a cached
+        /// membership.
+        pub fn sync(server_host: String) -> Req {
+            Req::FederatedHost(server_host).federated_only()
+        }
     }
 }
@@ -1,14 +1,59 @@
-use runelink_client::{requests, util::get_api_url};
-use runelink_types::{Message, NewMessage};
+use runelink_client::requests;
+use runelink_types::{Message, NewMessage, markup};
+use sqlx::PgConnection;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{
     auth::Session,
     error::{ApiError, ApiResult},
+    ops::{
+        attachments,
+        audit::{self, AuditAction, AuditTarget},
+        check_writable, moderation, notifications,
+    },
     queries,
     state::AppState,
 };
 
+/// Send attachments with a new message, failing unless every one of them
+/// was uploaded to the channel by the sender and is not sent yet.
+async fn attach(
+    conn: &mut PgConnection,
+    session: &Session,
+    channel_id: Uuid,
+    message_id: Uuid,
+    attachment_ids: &[Uuid],
+) -> ApiResult<()> {
+    let uploader = session.user_ref.as_ref().ok_or_else(|| {
+        ApiError::BadRequest("Only users can send attachments".into())
+    })?;
+    let mut unique_ids = attachment_ids.to_vec();
+    unique_ids.sort();
+    unique_ids.dedup();
+    // Uploads old enough to be purged any moment are refused, and so are
+    // attachments of purged messages, which are left unattached
+    let uploaded_after = OffsetDateTime::now_utc()
+        - time::Duration::hours(attachments::ORPHANED_ATTACHMENT_HOURS);
+    let attached = queries::attachments::attach(
+        conn,
+        message_id,
+        channel_id,
+        uploader,
+        &unique_ids,
+        uploaded_after,
+    )
+    .await?;
+    if attached != unique_ids.len() as u64 {
+        return Err(ApiError::BadRequest(format!(
+            "Attachments must be uploaded to the channel within the last \
+            {} hours, and can only be sent once",
+            attachments::ORPHANED_ATTACHMENT_HOURS
+        )));
+    }
+    Ok(())
+}
+
 /// Create a new message in a channel.
 pub async fn create(
     state: &AppState,
@@ -20,6 +65,7 @@ pub async fn create(
 ) -> ApiResult<Message> {
     // Handle local case
     if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
         let channel =
             queries::channels::get_by_id(&state.db_pool, channel_id).await?;
         if channel.server_id != server_id {
@@ -27,14 +73,53 @@ pub async fn create(
                 "Channel not found in specified server".into(),
             ));
         }
-        let message =
-            queries::messages::insert(&state.db_pool, channel_id, new_message)
+        if channel.is_archived() {
+            return Err(ApiError::Forbidden(
+                "Channel is archived and read-only".into(),
+            ));
+        }
+        moderation::check_can_post(state, server_id, &new_message.author)
+            .await?;
+        if let Some(user_ref) = &session.user_ref
+            && *user_ref != new_message.author
+        {
+            moderation::check_can_post(state, server_id, user_ref).await?;
+        }
+        let body = markup::sanitize(&new_message.body);
+        markup::validate(&markup::parse(&body))
+            .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        let attachment_ids = &new_message.attachment_ids;
+        if body.is_empty() && attachment_ids.is_empty() {
+            return Err(ApiError::BadRequest("Message is empty".into()));
+        }
+        let new_message = &NewMessage {
+            body,
+            ..new_message.clone()
+        };
+        // The message and its attachments are stored together, so a
+        // message whose attachments were taken by another is never sent
+        let mut tx = state.db_pool.begin().await?;
+        let message_id =
+            queries::messages::insert(&mut tx, channel_id, new_message).await?;
+        if !attachment_ids.is_empty() {
+            attach(&mut tx, session, channel_id, message_id, attachment_ids)
                 .await?;
+        }
+        tx.commit().await?;
+        let message =
+            queries::messages::get_by_id(&state.db_pool, message_id).await?;
+        state.presence.clear_typing(channel_id, &new_message.author);
+        let state = state.clone();
+        let mentioning = message.clone();
+        tokio::spawn(async move {
+            notifications::notify_mentions(&state, server_id, &mentioning)
+                .await;
+        });
         Ok(message)
     } else {
         // Create on remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.as_ref().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated message creation"
@@ -43,12 +128,12 @@ pub async fn create(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         let message = requests::messages::federated::create(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             channel_id,
@@ -77,7 +162,7 @@ pub async fn get_all(
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.as_ref().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated message fetching"
@@ -86,12 +171,12 @@ pub async fn get_all(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         let messages = requests::messages::federated::fetch_all(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
         )
         .await
@@ -109,17 +194,22 @@ pub async fn get_by_server(
     state: &AppState,
     session: &Session,
     server_id: Uuid,
+    include_deleted: bool,
     target_host: Option<&str>,
 ) -> ApiResult<Vec<Message>> {
     // Handle local case
     if !state.config.is_remote_host(target_host) {
-        let messages =
-            queries::messages::get_by_server(&state.db_pool, server_id).await?;
+        let messages = queries::messages::get_by_server(
+            &state.db_pool,
+            server_id,
+            include_deleted,
+        )
+        .await?;
         Ok(messages)
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.as_ref().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated message fetching"
@@ -128,14 +218,15 @@ pub async fn get_by_server(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         let messages = requests::messages::federated::fetch_by_server(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
+            include_deleted,
         )
         .await
         .map_err(|e| {
@@ -153,18 +244,27 @@ pub async fn get_by_channel(
     session: &Session,
     server_id: Uuid,
     channel_id: Uuid,
+    include_deleted: bool,
     target_host: Option<&str>,
 ) -> ApiResult<Vec<Message>> {
     // Handle local case
     if !state.config.is_remote_host(target_host) {
-        let messages =
-            queries::messages::get_by_channel(&state.db_pool, channel_id)
-                .await?;
+        let channel =
+            queries::channels::get_by_id(&state.db_pool, channel_id).await?;
+        if channel.server_id != server_id {
+            return Err(ApiError::NotFound);
+        }
+        let messages = queries::messages::get_by_channel(
+            &state.db_pool,
+            channel_id,
+            include_deleted,
+        )
+        .await?;
         Ok(messages)
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.as_ref().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated message fetching"
@@ -173,15 +273,16 @@ pub async fn get_by_channel(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         let messages = requests::messages::federated::fetch_by_channel(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             channel_id,
+            include_deleted,
         )
         .await
         .map_err(|e| {
@@ -222,7 +323,7 @@ pub async fn get_by_id(
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.as_ref().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated message fetching"
@@ -231,12 +332,12 @@ pub async fn get_by_id(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         let message = requests::messages::federated::fetch_by_id(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             channel_id,
@@ -263,6 +364,7 @@ pub async fn delete(
 ) -> ApiResult<()> {
     // Handle local case
     if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
         // Verify the message belongs to the channel and server
         // TODO: This should be done with one database query
         let message =
@@ -279,12 +381,32 @@ pub async fn delete(
                 "Message not found in specified server".into(),
             ));
         }
-        queries::messages::delete(&state.db_pool, message_id).await?;
+        if message.is_deleted() {
+            return Err(ApiError::NotFound);
+        }
+        queries::messages::delete(
+            &state.db_pool,
+            message_id,
+            session.user_ref.as_ref(),
+        )
+        .await?;
+        let retracting = state.clone();
+        tokio::spawn(async move {
+            notifications::retract_mentions(&retracting, &[message_id]).await;
+        });
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::MessageDelete,
+            Some(server_id),
+            AuditTarget::Message(message_id),
+        )
+        .await;
         Ok(())
     } else {
         // Delete on remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.as_ref().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated message deletion"
@@ -293,12 +415,12 @@ pub async fn delete(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         requests::messages::federated::delete(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
             channel_id,
@@ -1,5 +1,93 @@
+pub mod archive;
+pub mod attachments;
+pub mod audit;
+pub mod categories;
 pub mod channels;
 pub mod memberships;
 pub mod messages;
+pub mod moderation;
+pub mod notifications;
+pub mod passwords;
+pub mod pins;
+pub mod presence;
+pub mod read_markers;
+pub mod retention;
+pub mod scheduled_messages;
+pub mod server_migrations;
 pub mod servers;
+pub mod signups;
+pub mod totp;
 pub mod users;
+
+use runelink_client::resolver::ResolvedHost;
+use runelink_types::UserRef;
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    queries,
+    state::AppState,
+};
+
+/// Fail if a peer host lacks a federation capability we need.
+pub fn require_capability(
+    peer: &ResolvedHost,
+    host: &str,
+    capability: &str,
+) -> ApiResult<()> {
+    if peer.supports(capability) {
+        Ok(())
+    } else {
+        Err(ApiError::Unsupported(format!(
+            "{host} does not support `{capability}`"
+        )))
+    }
+}
+
+/// The user a session acts for.
+pub fn session_user(session: &Session) -> ApiResult<&UserRef> {
+    session
+        .user_ref
+        .as_ref()
+        .ok_or_else(|| ApiError::Internal("User reference required".into()))
+}
+
+/// Fail unless a channel is part of a local server.
+pub async fn check_channel(
+    state: &AppState,
+    server_id: Uuid,
+    channel_id: Uuid,
+) -> ApiResult<()> {
+    let channel =
+        queries::channels::get_by_id(&state.db_pool, channel_id).await?;
+    if channel.server_id != server_id {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+/// Held while writing to a local server, so it can't start moving to
+/// another host until the write is done. Released when dropped.
+#[must_use = "the server can start moving as soon as this is dropped"]
+pub struct WriteGuard {
+    _tx: Transaction<'static, Postgres>,
+}
+
+/// Fail while a local server is being moved to another host, so nothing
+/// written during the move is left behind. Otherwise keep it from starting
+/// to move until the returned guard is dropped.
+pub async fn check_writable(
+    state: &AppState,
+    server_id: Uuid,
+) -> ApiResult<WriteGuard> {
+    let mut tx = state.db_pool.begin().await?;
+    queries::server_migrations::lock_server(&mut tx, server_id, false).await?;
+    let moving_to =
+        queries::server_migrations::get_moving_to(&mut tx, server_id).await?;
+    match moving_to {
+        Some(to_host) => Err(ApiError::ServerMoved(to_host)),
+        None => Ok(WriteGuard { _tx: tx }),
+    }
+}
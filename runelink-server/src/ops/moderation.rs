@@ -0,0 +1,511 @@
+use runelink_client::{requests, resolver::ResolvedHost};
+use runelink_types::{
+    DeletedMessages, NewServerBan, NewServerTimeout, ServerBan, ServerTimeout,
+    UserRef, capabilities,
+};
+use time::{Duration, OffsetDateTime, format_description::well_known::Rfc3339};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        check_writable, memberships, notifications, require_capability,
+    },
+    queries,
+    state::AppState,
+};
+
+/// Longest timeout an admin can hand out (28 days).
+const MAX_TIMEOUT_MINUTES: i64 = 28 * 24 * 60;
+
+/// Longest ban that still expires (about ten years); longer bans are
+/// permanent ones.
+const MAX_BAN_HOURS: i64 = 10 * 366 * 24;
+
+/// Withdraw the notifications of bulk deleted messages in the background.
+fn retract_mentions(state: &AppState, message_ids: Vec<Uuid>) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        notifications::retract_mentions(&state, &message_ids).await;
+    });
+}
+
+/// Fail if `user` is banned from the server. Called before a membership is
+/// created on this host, whether the request came from a client or a peer.
+pub async fn check_can_join(
+    state: &AppState,
+    server_id: Uuid,
+    user: &UserRef,
+) -> ApiResult<()> {
+    let ban =
+        queries::moderation::get_active_ban(&state.db_pool, server_id, user)
+            .await?;
+    match ban {
+        Some(_) => Err(ApiError::Forbidden(format!(
+            "{user} is banned from this server"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Fail if `user` is banned from the server or timed out in it. Called
+/// before a message is posted on this host.
+pub async fn check_can_post(
+    state: &AppState,
+    server_id: Uuid,
+    user: &UserRef,
+) -> ApiResult<()> {
+    check_can_join(state, server_id, user).await?;
+    let timeout = queries::moderation::get_active_timeout(
+        &state.db_pool,
+        server_id,
+        user,
+    )
+    .await?;
+    match timeout {
+        Some(timeout) => {
+            let until = timeout
+                .until
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| timeout.until.to_string());
+            Err(ApiError::Forbidden(format!(
+                "{user} is timed out in this server until {until}"
+            )))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Resolve a peer that supports moderation and mint a delegated token for
+/// the session's user.
+async fn federate(
+    state: &AppState,
+    session: &Session,
+    host: &str,
+) -> ApiResult<(ResolvedHost, String)> {
+    let peer = state.resolve_host(host).await;
+    require_capability(&peer, host, capabilities::MODERATION)?;
+    let user_ref = session.user_ref.clone().ok_or_else(|| {
+        ApiError::Internal(
+            "User reference required for federated moderation".to_string(),
+        )
+    })?;
+    let token = state.key_manager.issue_federation_jwt_delegated(
+        state.config.api_url(),
+        peer.api_url.clone(),
+        user_ref,
+    )?;
+    Ok((peer, token))
+}
+
+/// Ban a user, or every user of a host, from a server. Current members
+/// covered by the ban are removed from the server.
+pub async fn create_ban(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    new_ban: &NewServerBan,
+    target_host: Option<&str>,
+) -> ApiResult<ServerBan> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        let _writing = check_writable(state, server_id).await?;
+        let user_host = new_ban.user_host.trim();
+        if user_host.is_empty() {
+            return Err(ApiError::BadRequest("user_host is required".into()));
+        }
+        let user_name = new_ban.user_name.as_deref().map(str::trim);
+        if let Some(actor) = &session.user_ref
+            && actor.host == user_host
+            && user_name.is_none_or(|name| name == actor.name)
+        {
+            return Err(ApiError::BadRequest("Cannot ban yourself".into()));
+        }
+        let owner =
+            queries::memberships::get_local_owner(&state.db_pool, server_id)
+                .await?;
+        if let Some(owner) = &owner
+            && owner.host == user_host
+            && user_name.is_none_or(|name| name == owner.name)
+        {
+            return Err(ApiError::BadRequest(
+                "Cannot ban the server owner".into(),
+            ));
+        }
+        let expires_at = match new_ban.expires_in_hours {
+            Some(hours) if !(1..=MAX_BAN_HOURS).contains(&hours) => {
+                return Err(ApiError::BadRequest(format!(
+                    "expires_in_hours must be between 1 and {MAX_BAN_HOURS}"
+                )));
+            }
+            Some(hours) => {
+                Some(OffsetDateTime::now_utc() + Duration::hours(hours))
+            }
+            None => None,
+        };
+        queries::servers::get_by_id(state, server_id).await?;
+        let ban = queries::moderation::insert_ban(
+            &state.db_pool,
+            server_id,
+            user_name,
+            user_host,
+            new_ban.reason.as_deref(),
+            session.user_ref.as_ref(),
+            expires_at,
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::BanCreate,
+            Some(server_id),
+            AuditTarget::Ban(ban.id),
+        )
+        .await;
+        let removed = queries::memberships::delete_local_matching(
+            &state.db_pool,
+            server_id,
+            user_name,
+            user_host,
+        )
+        .await?;
+        for user_ref in removed {
+            memberships::push_membership_removal(state, server_id, &user_ref)
+                .await;
+            audit::record(
+                state,
+                Some(session),
+                AuditAction::MembershipDelete,
+                Some(server_id),
+                AuditTarget::User(user_ref),
+            )
+            .await;
+        }
+        if new_ban.delete_messages {
+            let deleted = queries::messages::delete_by_author_in_server(
+                &state.db_pool,
+                server_id,
+                user_name,
+                user_host,
+                session.user_ref.as_ref(),
+            )
+            .await?;
+            if !deleted.is_empty() {
+                retract_mentions(state, deleted);
+                audit::record(
+                    state,
+                    Some(session),
+                    AuditAction::MessageBulkDelete,
+                    Some(server_id),
+                    AuditTarget::Ban(ban.id),
+                )
+                .await;
+            }
+        }
+        Ok(ban)
+    } else {
+        // Create on remote host using federation
+        let host = target_host.unwrap();
+        let (peer, token) = federate(state, session, host).await?;
+        let ban = requests::moderation::federated::create_ban(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            new_ban,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!("Failed to create ban on {host}: {e}"))
+        })?;
+        Ok(ban)
+    }
+}
+
+/// List the unexpired bans of a server.
+pub async fn get_bans(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<ServerBan>> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        queries::moderation::get_bans(&state.db_pool, server_id).await
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let (peer, token) = federate(state, session, host).await?;
+        let bans = requests::moderation::federated::fetch_bans(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!("Failed to fetch bans from {host}: {e}"))
+        })?;
+        Ok(bans)
+    }
+}
+
+/// Lift a ban.
+pub async fn delete_ban(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    ban_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<()> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        let _writing = check_writable(state, server_id).await?;
+        queries::moderation::delete_ban(&state.db_pool, server_id, ban_id)
+            .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::BanDelete,
+            Some(server_id),
+            AuditTarget::Ban(ban_id),
+        )
+        .await;
+        Ok(())
+    } else {
+        // Delete on remote host using federation
+        let host = target_host.unwrap();
+        let (peer, token) = federate(state, session, host).await?;
+        requests::moderation::federated::delete_ban(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            ban_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!("Failed to delete ban on {host}: {e}"))
+        })?;
+        Ok(())
+    }
+}
+
+/// Stop a user from posting in a server for a while.
+pub async fn create_timeout(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    new_timeout: &NewServerTimeout,
+    target_host: Option<&str>,
+) -> ApiResult<ServerTimeout> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        let _writing = check_writable(state, server_id).await?;
+        if !(1..=MAX_TIMEOUT_MINUTES).contains(&new_timeout.duration_minutes) {
+            return Err(ApiError::BadRequest(format!(
+                "duration_minutes must be between 1 and {MAX_TIMEOUT_MINUTES}"
+            )));
+        }
+        if session.user_ref.as_ref() == Some(&new_timeout.user_ref) {
+            return Err(ApiError::BadRequest(
+                "Cannot time yourself out".into(),
+            ));
+        }
+        let owner =
+            queries::memberships::get_local_owner(&state.db_pool, server_id)
+                .await?;
+        if owner.as_ref() == Some(&new_timeout.user_ref) {
+            return Err(ApiError::BadRequest(
+                "Cannot time out the server owner".into(),
+            ));
+        }
+        queries::servers::get_by_id(state, server_id).await?;
+        let until = OffsetDateTime::now_utc()
+            + Duration::minutes(new_timeout.duration_minutes);
+        let timeout = queries::moderation::upsert_timeout(
+            &state.db_pool,
+            server_id,
+            &new_timeout.user_ref,
+            new_timeout.reason.as_deref(),
+            session.user_ref.as_ref(),
+            until,
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::TimeoutCreate,
+            Some(server_id),
+            AuditTarget::User(new_timeout.user_ref.clone()),
+        )
+        .await;
+        Ok(timeout)
+    } else {
+        // Create on remote host using federation
+        let host = target_host.unwrap();
+        let (peer, token) = federate(state, session, host).await?;
+        let timeout = requests::moderation::federated::create_timeout(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            new_timeout,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to create timeout on {host}: {e}"
+            ))
+        })?;
+        Ok(timeout)
+    }
+}
+
+/// List the running timeouts of a server.
+pub async fn get_timeouts(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<ServerTimeout>> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        queries::moderation::get_timeouts(&state.db_pool, server_id).await
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let (peer, token) = federate(state, session, host).await?;
+        let timeouts = requests::moderation::federated::fetch_timeouts(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch timeouts from {host}: {e}"
+            ))
+        })?;
+        Ok(timeouts)
+    }
+}
+
+/// End a user's timeout early.
+pub async fn delete_timeout(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    user_ref: UserRef,
+    target_host: Option<&str>,
+) -> ApiResult<()> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        let _writing = check_writable(state, server_id).await?;
+        queries::moderation::delete_timeout(
+            &state.db_pool,
+            server_id,
+            &user_ref,
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::TimeoutDelete,
+            Some(server_id),
+            AuditTarget::User(user_ref),
+        )
+        .await;
+        Ok(())
+    } else {
+        // Delete on remote host using federation
+        let host = target_host.unwrap();
+        let (peer, token) = federate(state, session, host).await?;
+        requests::moderation::federated::delete_timeout(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            user_ref,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to delete timeout on {host}: {e}"
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+/// Delete every message a user posted in a server.
+pub async fn delete_messages_by_user(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    user_ref: UserRef,
+    target_host: Option<&str>,
+) -> ApiResult<DeletedMessages> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        let _writing = check_writable(state, server_id).await?;
+        let deleted = queries::messages::delete_by_author_in_server(
+            &state.db_pool,
+            server_id,
+            Some(&user_ref.name),
+            &user_ref.host,
+            session.user_ref.as_ref(),
+        )
+        .await?;
+        let count = deleted.len() as u64;
+        retract_mentions(state, deleted);
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::MessageBulkDelete,
+            Some(server_id),
+            AuditTarget::User(user_ref),
+        )
+        .await;
+        Ok(DeletedMessages { deleted: count })
+    } else {
+        // Delete on remote host using federation
+        let host = target_host.unwrap();
+        let (peer, token) = federate(state, session, host).await?;
+        let deleted = requests::moderation::federated::delete_messages_by_user(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            user_ref,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to delete messages on {host}: {e}"
+            ))
+        })?;
+        Ok(deleted)
+    }
+}
+
+/// Auth requirements for moderation operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn manage(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn manage(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+    }
+}
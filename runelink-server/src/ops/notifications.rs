@@ -0,0 +1,310 @@
+use log::{info, warn};
+use runelink_client::requests;
+use runelink_types::{
+    Message, NewNotification, Notification, UserRef, capabilities, markup,
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::session_user,
+    queries,
+    state::AppState,
+};
+
+/// How many characters of a message a notification keeps.
+const EXCERPT_CHARS: usize = 200;
+
+/// The start of a message as plain text, on a single line.
+fn excerpt(body: &str) -> String {
+    let text = markup::to_plain_text(&markup::parse(body));
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= EXCERPT_CHARS {
+        return text;
+    }
+    let mut excerpt: String = text.chars().take(EXCERPT_CHARS - 1).collect();
+    excerpt.push('…');
+    excerpt
+}
+
+/// Record the members of a server that a new message mentions, and notify
+/// each of them on their home host. Failures are only logged.
+pub(crate) async fn notify_mentions(
+    state: &AppState,
+    server_id: Uuid,
+    message: &Message,
+) {
+    let Some(author) = message.author.as_ref().map(|a| a.as_ref()) else {
+        return;
+    };
+    let mentioned = markup::mentions(&markup::parse(&message.body));
+    if mentioned.is_empty() {
+        return;
+    }
+    let members = match queries::memberships::get_members_by_server(
+        &state.db_pool,
+        server_id,
+    )
+    .await
+    {
+        Ok(members) => members,
+        Err(e) => {
+            warn!("Failed to load members of {server_id}: {e}");
+            return;
+        }
+    };
+    // Mentions of non-members are not stored, and authors are not notified
+    // of their own mentions
+    let users: Vec<UserRef> = mentioned
+        .into_iter()
+        .filter(|user_ref| members.iter().any(|m| m.user.as_ref() == *user_ref))
+        .collect();
+    if users.is_empty() {
+        return;
+    }
+    if let Err(e) =
+        queries::messages::insert_mentions(&state.db_pool, message.id, &users)
+            .await
+    {
+        warn!("Failed to store mentions of message {}: {e}", message.id);
+        return;
+    }
+    let excerpt = excerpt(&message.body);
+    for user in users {
+        if user == author {
+            continue;
+        }
+        let new_notification = NewNotification {
+            user,
+            server_id,
+            server_host: state.config.local_host(),
+            channel_id: message.channel_id,
+            message_id: message.id,
+            author: author.clone(),
+            excerpt: excerpt.clone(),
+        };
+        push_notification(state, &new_notification).await;
+    }
+}
+
+/// Add a notification to the inbox of a local user, or relay it to the
+/// user's home host.
+async fn push_notification(
+    state: &AppState,
+    new_notification: &NewNotification,
+) {
+    let user_ref = &new_notification.user;
+    let host = user_ref.host.as_str();
+    if !state.config.is_remote_host(Some(host)) {
+        if let Err(e) =
+            queries::notifications::insert(&state.db_pool, new_notification)
+                .await
+        {
+            warn!("Failed to notify {user_ref}: {e}");
+        }
+        return;
+    }
+    let peer = state.resolve_host(host).await;
+    if !peer.supports(capabilities::NOTIFICATIONS) {
+        info!("Skipping notification on {host}: not supported by peer");
+        return;
+    }
+    let token = match state.key_manager.issue_federation_jwt_server_only(
+        state.config.api_url(),
+        peer.api_url.clone(),
+    ) {
+        Ok(token) => token,
+        Err(e) => {
+            warn!("Failed to issue federation token for host {host}: {e}");
+            return;
+        }
+    };
+    let result = requests::notifications::federated::create(
+        &state.http_client,
+        &peer.federation_url,
+        &token,
+        new_notification,
+    )
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to notify {user_ref} on {host}: {e}");
+    }
+}
+
+/// Withdraw the notifications of deleted messages, on this host and on the
+/// home hosts of the users they mention. Failures are only logged.
+pub(crate) async fn retract_mentions(state: &AppState, message_ids: &[Uuid]) {
+    let local_host = state.config.local_host();
+    if let Err(e) = queries::notifications::delete_by_messages(
+        &state.db_pool,
+        &local_host,
+        message_ids,
+        None,
+    )
+    .await
+    {
+        warn!("Failed to remove notifications of deleted messages: {e}");
+    }
+    let mentions = match queries::messages::get_mentions(
+        &state.db_pool,
+        message_ids,
+    )
+    .await
+    {
+        Ok(mentions) => mentions,
+        Err(e) => {
+            warn!("Failed to load mentions of deleted messages: {e}");
+            return;
+        }
+    };
+    for (message_id, user) in mentions {
+        if state.config.is_remote_host(Some(&user.host)) {
+            retract_notification(state, &user, message_id).await;
+        }
+    }
+}
+
+/// Withdraw the notification of a deleted message from the inbox of a user
+/// of another host.
+async fn retract_notification(
+    state: &AppState,
+    user_ref: &UserRef,
+    message_id: Uuid,
+) {
+    let host = user_ref.host.as_str();
+    let peer = state.resolve_host(host).await;
+    if !peer.supports(capabilities::NOTIFICATIONS) {
+        return;
+    }
+    let token = match state.key_manager.issue_federation_jwt_server_only(
+        state.config.api_url(),
+        peer.api_url.clone(),
+    ) {
+        Ok(token) => token,
+        Err(e) => {
+            warn!("Failed to issue federation token for host {host}: {e}");
+            return;
+        }
+    };
+    let result = requests::notifications::federated::delete(
+        &state.http_client,
+        &peer.federation_url,
+        &token,
+        user_ref,
+        message_id,
+    )
+    .await;
+    if let Err(e) = result {
+        warn!("Failed to retract notification of {user_ref} on {host}: {e}");
+    }
+}
+
+/// Get the notifications of the session's user, newest first.
+pub async fn get_inbox(
+    state: &AppState,
+    session: &Session,
+    include_read: bool,
+) -> ApiResult<Vec<Notification>> {
+    let user_ref = session_user(session)?;
+    queries::notifications::get_by_user(&state.db_pool, user_ref, include_read)
+        .await
+}
+
+/// Mark one of the session user's notifications as read.
+pub async fn mark_read(
+    state: &AppState,
+    session: &Session,
+    notification_id: Uuid,
+) -> ApiResult<()> {
+    let user_ref = session_user(session)?;
+    let marked = queries::notifications::mark_read(
+        &state.db_pool,
+        user_ref,
+        Some(notification_id),
+    )
+    .await?;
+    if marked == 0
+        && !queries::notifications::exists(
+            &state.db_pool,
+            user_ref,
+            notification_id,
+        )
+        .await?
+    {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+/// Mark all of the session user's notifications as read.
+pub async fn mark_all_read(
+    state: &AppState,
+    session: &Session,
+) -> ApiResult<()> {
+    let user_ref = session_user(session)?;
+    queries::notifications::mark_read(&state.db_pool, user_ref, None).await?;
+    Ok(())
+}
+
+/// Add a notification relayed by the host of a server to the inbox of a
+/// local user.
+pub async fn receive(
+    state: &AppState,
+    new_notification: &NewNotification,
+) -> ApiResult<()> {
+    if state
+        .config
+        .is_remote_host(Some(&new_notification.user.host))
+    {
+        return Err(ApiError::BadRequest(
+            "Notification is not for a user of this host".into(),
+        ));
+    }
+    // Fails with NotFound for unknown users
+    queries::users::get_by_ref(&state.db_pool, new_notification.user.clone())
+        .await?;
+    queries::notifications::insert(&state.db_pool, new_notification).await
+}
+
+/// Remove the notification of a message posted on `server_host` from the
+/// inbox of a local user, once the message was deleted.
+pub async fn withdraw(
+    state: &AppState,
+    server_host: &str,
+    user_ref: &UserRef,
+    message_id: Uuid,
+) -> ApiResult<()> {
+    queries::notifications::delete_by_messages(
+        &state.db_pool,
+        server_host,
+        &[message_id],
+        Some(user_ref),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Auth requirements for notification operations.
+pub mod auth {
+    use crate::auth::Requirement as Req;
+
+    pub fn inbox() -> Req {
+        Req::Client
+    }
+
+    pub mod federated {
+        use super::*;
+
+        /// Only the host of the server a message was posted on may relay
+        /// its mentions.
+        pub fn create(server_host: String) -> Req {
+            Req::FederatedHost(server_host).federated_only()
+        }
+
+        /// Only the host that relayed a mention may withdraw it.
+        pub fn delete(server_host: String) -> Req {
+            Req::FederatedHost(server_host).federated_only()
+        }
+    }
+}
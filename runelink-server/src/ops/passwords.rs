@@ -0,0 +1,147 @@
+use runelink_types::{
+    ChangePasswordRequest, IssuedPasswordReset, NewPasswordReset,
+    PasswordResetRequest, UserRef,
+};
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::audit::{self, AuditAction, AuditTarget},
+    queries, secrets,
+    signup_policy::validate_password,
+    state::AppState,
+};
+
+/// Reset tokens expire after this long unless the admin asks otherwise.
+const DEFAULT_RESET_LIFETIME_HOURS: i64 = 24;
+
+/// Longest an admin can make a reset token last (a week).
+const MAX_RESET_LIFETIME_HOURS: i64 = 7 * 24;
+
+/// Change the caller's password, revoking their other refresh tokens.
+pub async fn change_password(
+    state: &AppState,
+    session: &Session,
+    req: &ChangePasswordRequest,
+) -> ApiResult<()> {
+    let user_ref = session.user_ref.clone().ok_or_else(|| {
+        ApiError::Internal("User reference required to change password".into())
+    })?;
+    let account =
+        queries::accounts::get_by_user(&state.db_pool, user_ref.clone())
+            .await?;
+    secrets::verify_password(&req.current_password, &account.password_hash)?;
+    validate_password(&req.new_password, &user_ref.name)
+        .map_err(ApiError::BadRequest)?;
+    let password_hash = secrets::hash_password(&req.new_password)?;
+    queries::accounts::update_password(
+        &state.db_pool,
+        user_ref.clone(),
+        &password_hash,
+    )
+    .await?;
+    queries::tokens::revoke_all_refresh(
+        &state.db_pool,
+        user_ref.clone(),
+        req.keep_refresh_token.as_deref(),
+    )
+    .await?;
+    queries::password_resets::delete_by_user(&state.db_pool, user_ref).await
+}
+
+/// Mint a one-time password reset token for a local account.
+pub async fn create_reset(
+    state: &AppState,
+    session: &Session,
+    name: &str,
+    new_reset: &NewPasswordReset,
+) -> ApiResult<IssuedPasswordReset> {
+    let created_by = session.user_ref.clone().ok_or_else(|| {
+        ApiError::Internal("User reference required to mint resets".into())
+    })?;
+    let hours = new_reset
+        .expires_in_hours
+        .unwrap_or(DEFAULT_RESET_LIFETIME_HOURS);
+    if !(1..=MAX_RESET_LIFETIME_HOURS).contains(&hours) {
+        return Err(ApiError::BadRequest(format!(
+            "expires_in_hours must be between 1 and {MAX_RESET_LIFETIME_HOURS}"
+        )));
+    }
+    let user_ref = UserRef::new(name.into(), state.config.local_host());
+    // Only local accounts have a password to reset
+    queries::accounts::get_by_user(&state.db_pool, user_ref.clone()).await?;
+    let token = secrets::generate_token();
+    let expires_at = OffsetDateTime::now_utc() + Duration::hours(hours);
+    queries::password_resets::insert(
+        &state.db_pool,
+        &secrets::hash_token(&token),
+        &user_ref,
+        &created_by,
+        expires_at,
+    )
+    .await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::PasswordResetCreate,
+        None,
+        AuditTarget::User(user_ref.clone()),
+    )
+    .await;
+    Ok(IssuedPasswordReset {
+        token,
+        user_ref,
+        expires_at,
+    })
+}
+
+/// Set a new password using a reset token, revoking all refresh tokens.
+pub async fn reset_password(
+    state: &AppState,
+    req: &PasswordResetRequest,
+) -> ApiResult<UserRef> {
+    let token_hash = secrets::hash_token(&req.token);
+    let invalid_token = |e| match e {
+        ApiError::NotFound => ApiError::AuthError(
+            "Reset token is invalid, expired or already used".into(),
+        ),
+        e => e,
+    };
+    // Validate before redeeming so a rejected password doesn't burn the token
+    let user_ref =
+        queries::password_resets::get_user(&state.db_pool, &token_hash)
+            .await
+            .map_err(invalid_token)?;
+    validate_password(&req.new_password, &user_ref.name)
+        .map_err(ApiError::BadRequest)?;
+    let password_hash = secrets::hash_password(&req.new_password)?;
+    let user_ref =
+        queries::password_resets::redeem(&state.db_pool, &token_hash)
+            .await
+            .map_err(invalid_token)?;
+    queries::accounts::update_password(
+        &state.db_pool,
+        user_ref.clone(),
+        &password_hash,
+    )
+    .await?;
+    queries::tokens::revoke_all_refresh(&state.db_pool, user_ref.clone(), None)
+        .await?;
+    queries::password_resets::delete_by_user(&state.db_pool, user_ref.clone())
+        .await?;
+    Ok(user_ref)
+}
+
+/// Auth requirements for password operations.
+pub mod auth {
+    use crate::auth::Requirement as Req;
+
+    pub fn change_password() -> Req {
+        Req::Client
+    }
+
+    pub fn create_reset() -> Req {
+        Req::HostAdmin.client_only()
+    }
+}
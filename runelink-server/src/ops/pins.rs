@@ -0,0 +1,213 @@
+use runelink_client::requests;
+use runelink_types::{PinnedMessage, capabilities};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        check_channel, check_writable, require_capability, session_user,
+    },
+    queries,
+    state::AppState,
+};
+
+/// How many messages can be pinned in a channel.
+pub const MAX_PINS_PER_CHANNEL: i64 = 50;
+
+/// Get the messages pinned in a channel, most recently pinned first.
+pub async fn get_by_channel(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<PinnedMessage>> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        check_channel(state, server_id, channel_id).await?;
+        queries::pins::get_by_channel(&state.db_pool, channel_id).await
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::PINS)?;
+        let user_ref = session_user(session)?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref.clone(),
+        )?;
+        let pins = requests::pins::federated::fetch_by_channel(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!("Failed to fetch pins from {host}: {e}"))
+        })?;
+        Ok(pins)
+    }
+}
+
+/// Pin a message in its channel.
+pub async fn pin(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    message_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<()> {
+    let user_ref = session_user(session)?;
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        check_channel(state, server_id, channel_id).await?;
+        let message =
+            queries::messages::get_by_id(&state.db_pool, message_id).await?;
+        if message.channel_id != channel_id || message.is_deleted() {
+            return Err(ApiError::NotFound);
+        }
+        if queries::pins::exists(&state.db_pool, message_id).await? {
+            return Ok(());
+        }
+        let pinned =
+            queries::pins::count_by_channel(&state.db_pool, channel_id).await?;
+        if pinned >= MAX_PINS_PER_CHANNEL {
+            return Err(ApiError::BadRequest(format!(
+                "A channel can have at most {MAX_PINS_PER_CHANNEL} pinned \
+                 messages"
+            )));
+        }
+        queries::pins::insert(&state.db_pool, channel_id, message_id, user_ref)
+            .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::MessagePin,
+            Some(server_id),
+            AuditTarget::Message(message_id),
+        )
+        .await;
+        Ok(())
+    } else {
+        // Pin on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::PINS)?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref.clone(),
+        )?;
+        requests::pins::federated::pin(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            message_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!("Failed to pin message on {host}: {e}"))
+        })
+    }
+}
+
+/// Unpin a message.
+pub async fn unpin(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    message_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<()> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        check_channel(state, server_id, channel_id).await?;
+        let message =
+            queries::messages::get_by_id(&state.db_pool, message_id).await?;
+        if message.channel_id != channel_id {
+            return Err(ApiError::NotFound);
+        }
+        if !queries::pins::delete(&state.db_pool, message_id).await? {
+            return Err(ApiError::NotFound);
+        }
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::MessageUnpin,
+            Some(server_id),
+            AuditTarget::Message(message_id),
+        )
+        .await;
+        Ok(())
+    } else {
+        // Unpin on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::PINS)?;
+        let user_ref = session_user(session)?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref.clone(),
+        )?;
+        requests::pins::federated::unpin(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            message_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to unpin message on {host}: {e}"
+            ))
+        })
+    }
+}
+
+/// Auth requirements for pin operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn get_by_channel(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().client_only()
+    }
+
+    pub fn pin(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn unpin(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn get_by_channel(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+
+        pub fn pin(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
+        pub fn unpin(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+    }
+}
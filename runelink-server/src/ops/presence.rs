@@ -0,0 +1,271 @@
+use std::time::Instant;
+
+use log::{info, warn};
+use runelink_client::requests;
+use runelink_types::{MemberPresence, PresenceUpdate, UserRef, capabilities};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{check_channel, require_capability, session_user},
+    queries,
+    state::AppState,
+};
+
+/// Record the presence reported by a client of this host, and publish it to
+/// the hosts of the remote servers the user is a member of.
+pub async fn update(
+    state: &AppState,
+    session: &Session,
+    update: &PresenceUpdate,
+) -> ApiResult<PresenceUpdate> {
+    let user_ref = session_user(session)?;
+    let publish =
+        state
+            .presence
+            .set_presence(user_ref, update.status, Instant::now());
+    if publish {
+        let state = state.clone();
+        let user_ref = user_ref.clone();
+        let update = update.clone();
+        tokio::spawn(async move {
+            push_presence(&state, &user_ref, &update).await;
+        });
+    }
+    Ok(update.clone())
+}
+
+/// Send a user's presence to the hosts of their remote servers. Failures
+/// are only logged; the presence expires there if it isn't refreshed.
+async fn push_presence(
+    state: &AppState,
+    user_ref: &UserRef,
+    update: &PresenceUpdate,
+) {
+    let hosts = match queries::memberships::get_remote_server_hosts_for_user(
+        &state.db_pool,
+        user_ref.clone(),
+    )
+    .await
+    {
+        Ok(hosts) => hosts,
+        Err(e) => {
+            warn!("Failed to load remote hosts of {user_ref}: {e}");
+            return;
+        }
+    };
+    for host in hosts {
+        let peer = state.resolve_host(&host).await;
+        if !peer.supports(capabilities::PRESENCE) {
+            info!("Skipping presence on {host}: not supported by peer");
+            continue;
+        }
+        let token = match state.key_manager.issue_federation_jwt_server_only(
+            state.config.api_url(),
+            peer.api_url.clone(),
+        ) {
+            Ok(token) => token,
+            Err(e) => {
+                warn!("Failed to issue federation token for host {host}: {e}");
+                continue;
+            }
+        };
+        let result = requests::presence::federated::update(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            user_ref,
+            update,
+        )
+        .await;
+        if let Err(e) = result {
+            warn!("Failed to publish presence of {user_ref} to {host}: {e}");
+        }
+    }
+}
+
+/// Record the presence of a remote user, as published by their home host.
+pub async fn receive(
+    state: &AppState,
+    user_ref: &UserRef,
+    update: &PresenceUpdate,
+) -> ApiResult<()> {
+    if !state.config.is_remote_host(Some(&user_ref.host)) {
+        return Err(ApiError::BadRequest(
+            "Presence of users of this host is reported by their clients"
+                .into(),
+        ));
+    }
+    state
+        .presence
+        .set_presence(user_ref, update.status, Instant::now());
+    Ok(())
+}
+
+/// Get the presence of every member of a server.
+pub async fn get_by_server(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<MemberPresence>> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let members = queries::memberships::get_members_by_server(
+            &state.db_pool,
+            server_id,
+        )
+        .await?;
+        let now = Instant::now();
+        Ok(members
+            .into_iter()
+            .map(|member| {
+                let user = member.user.as_ref();
+                let status = state.presence.presence(&user, now);
+                MemberPresence { user, status }
+            })
+            .collect())
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::PRESENCE)?;
+        let user_ref = session_user(session)?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref.clone(),
+        )?;
+        let presence = requests::presence::federated::fetch_by_server(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch presence from {host}: {e}"
+            ))
+        })?;
+        Ok(presence)
+    }
+}
+
+/// Show the session's user as typing in a channel for a few seconds.
+pub async fn start_typing(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<()> {
+    let user_ref = session_user(session)?;
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        check_channel(state, server_id, channel_id).await?;
+        state
+            .presence
+            .set_typing(channel_id, user_ref, Instant::now());
+        Ok(())
+    } else {
+        // Relay to remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::PRESENCE)?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref.clone(),
+        )?;
+        requests::presence::federated::start_typing(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to send typing event to {host}: {e}"
+            ))
+        })
+    }
+}
+
+/// Get the users typing in a channel.
+pub async fn get_typing(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<UserRef>> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        check_channel(state, server_id, channel_id).await?;
+        Ok(state.presence.typing(channel_id, Instant::now()))
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::PRESENCE)?;
+        let user_ref = session_user(session)?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref.clone(),
+        )?;
+        let typing = requests::presence::federated::fetch_typing(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch typing users from {host}: {e}"
+            ))
+        })?;
+        Ok(typing)
+    }
+}
+
+/// Auth requirements for presence operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn update() -> Req {
+        Req::Client
+    }
+
+    pub fn get_by_server(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().client_only()
+    }
+
+    pub fn typing(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        /// Only a user's home host may publish their presence.
+        pub fn update(user_host: String) -> Req {
+            Req::FederatedHost(user_host).federated_only()
+        }
+
+        pub fn get_by_server(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+
+        pub fn typing(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use log::{info, warn};
+use runelink_client::requests;
+use runelink_types::{
+    ChannelUnread, NewReadMarker, ReadMarker, UserRef, capabilities,
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{messages, session_user},
+    queries,
+    state::AppState,
+};
+
+/// Get all read markers of the session's user.
+pub async fn get_all(
+    state: &AppState,
+    session: &Session,
+) -> ApiResult<Vec<ReadMarker>> {
+    let user_ref = session_user(session)?;
+    queries::read_markers::get_by_user(&state.db_pool, user_ref).await
+}
+
+/// Mark a channel as read up to a message. The marker is stored on this
+/// host also when the server is on another one, where the message is looked
+/// up.
+pub async fn set(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    new_marker: &NewReadMarker,
+    target_host: Option<&str>,
+) -> ApiResult<ReadMarker> {
+    let user_ref = session_user(session)?;
+    let message = messages::get_by_id(
+        state,
+        session,
+        server_id,
+        channel_id,
+        new_marker.message_id,
+        target_host,
+    )
+    .await?;
+    let server_host = match target_host {
+        Some(host) if state.config.is_remote_host(Some(host)) => {
+            host.to_string()
+        }
+        _ => state.config.local_host(),
+    };
+    queries::read_markers::upsert(
+        &state.db_pool,
+        user_ref,
+        server_id,
+        &server_host,
+        channel_id,
+        message.id,
+        message.created_at,
+    )
+    .await
+}
+
+/// Count the unread messages of a user in every channel of a local server,
+/// given the user's markers in that server.
+async fn count_in_server(
+    state: &AppState,
+    user_ref: &UserRef,
+    server_id: Uuid,
+    markers: &[ReadMarker],
+) -> ApiResult<Vec<ChannelUnread>> {
+    let counts = queries::read_markers::count_unread(
+        &state.db_pool,
+        server_id,
+        user_ref,
+        markers,
+    )
+    .await?;
+    let server_host = state.config.local_host();
+    Ok(counts
+        .into_iter()
+        .map(|count| ChannelUnread {
+            server_id,
+            server_host: server_host.clone(),
+            channel_id: count.channel_id,
+            unread_count: count.unread_count,
+            last_read_message_id: markers
+                .iter()
+                .find(|m| m.channel_id == count.channel_id)
+                .map(|m| m.message_id),
+        })
+        .collect())
+}
+
+/// Count the unread messages of the session's user in every channel of a
+/// local server, for the user's home host.
+pub async fn count_unread(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    markers: &[ReadMarker],
+) -> ApiResult<Vec<ChannelUnread>> {
+    let user_ref = session_user(session)?;
+    count_in_server(state, user_ref, server_id, markers).await
+}
+
+/// Count unread messages on the host of a remote server.
+async fn count_in_remote_server(
+    state: &AppState,
+    user_ref: &UserRef,
+    server_id: Uuid,
+    host: &str,
+    markers: &[ReadMarker],
+) -> ApiResult<Vec<ChannelUnread>> {
+    let peer = state.resolve_host(host).await;
+    if !peer.supports(capabilities::READ_MARKERS) {
+        info!("Skipping unread counts on {host}: not supported by peer");
+        return Ok(Vec::new());
+    }
+    let token = state.key_manager.issue_federation_jwt_delegated(
+        state.config.api_url(),
+        peer.api_url.clone(),
+        user_ref.clone(),
+    )?;
+    requests::read_markers::federated::count_unread(
+        &state.http_client,
+        &peer.federation_url,
+        &token,
+        server_id,
+        markers,
+    )
+    .await
+    .map_err(|e| {
+        ApiError::Internal(format!(
+            "Failed to count unread messages on {host}: {e}"
+        ))
+    })
+}
+
+/// Count the unread messages of the session's user in every channel of
+/// every server they are a member of. Servers whose host can't be reached
+/// are left out.
+pub async fn get_unread(
+    state: &AppState,
+    session: &Session,
+) -> ApiResult<Vec<ChannelUnread>> {
+    let user_ref = session_user(session)?;
+    let memberships =
+        queries::memberships::get_by_user(state, user_ref.clone()).await?;
+    let markers =
+        queries::read_markers::get_by_user(&state.db_pool, user_ref).await?;
+    let mut unread = Vec::new();
+    for membership in memberships {
+        let server = &membership.server;
+        let server_markers: Vec<ReadMarker> = markers
+            .iter()
+            .filter(|m| m.server_id == server.id)
+            .cloned()
+            .collect();
+        if !state.config.is_remote_host(Some(&server.host)) {
+            unread.extend(
+                count_in_server(state, user_ref, server.id, &server_markers)
+                    .await?,
+            );
+            continue;
+        }
+        match count_in_remote_server(
+            state,
+            user_ref,
+            server.id,
+            &server.host,
+            &server_markers,
+        )
+        .await
+        {
+            Ok(counts) => unread.extend(counts),
+            Err(e) => warn!("{e}"),
+        }
+    }
+    Ok(unread)
+}
+
+/// Auth requirements for read marker operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn get_all() -> Req {
+        Req::Client
+    }
+
+    pub fn set(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).client_only()
+    }
+
+    pub fn get_unread() -> Req {
+        Req::Client
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn count_unread(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+    }
+}
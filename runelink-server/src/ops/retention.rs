@@ -0,0 +1,273 @@
+use runelink_client::requests;
+use runelink_types::{RetentionPolicy, RetentionPolicyUpdate, capabilities};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        check_channel, check_writable, require_capability,
+    },
+    queries,
+    state::AppState,
+};
+
+/// The longest age limit, in days (about a century).
+const MAX_AGE_DAYS: i32 = 36_500;
+
+fn validate_limit(
+    name: &str,
+    limit: Option<Option<i32>>,
+    max: i32,
+) -> ApiResult<()> {
+    match limit {
+        Some(Some(value)) if value < 1 => {
+            Err(ApiError::BadRequest(format!("`{name}` must be at least 1")))
+        }
+        Some(Some(value)) if value > max => Err(ApiError::BadRequest(format!(
+            "`{name}` can be at most {max}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn audit_target(server_id: Uuid, channel_id: Option<Uuid>) -> AuditTarget {
+    match channel_id {
+        Some(channel_id) => AuditTarget::Channel(channel_id),
+        None => AuditTarget::Server(server_id),
+    }
+}
+
+/// Get the retention policies of a server: the server-wide one (if any)
+/// first, then those of its channels.
+pub async fn get_by_server(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    target_host: Option<&str>,
+) -> ApiResult<Vec<RetentionPolicy>> {
+    if !state.config.is_remote_host(target_host) {
+        // Handle local case
+        queries::retention::get_by_server(&state.db_pool, server_id).await
+    } else {
+        // Fetch from remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::RETENTION)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated retention fetching"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let policies = requests::retention::federated::fetch_by_server(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to fetch retention policies from {host}: {e}"
+            ))
+        })?;
+        Ok(policies)
+    }
+}
+
+/// Change the retention policy of a server, or of one of its channels when
+/// `channel_id` is given, creating it if needed.
+pub async fn update(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Option<Uuid>,
+    update: &RetentionPolicyUpdate,
+    target_host: Option<&str>,
+) -> ApiResult<RetentionPolicy> {
+    validate_limit("max_age_days", update.max_age_days, MAX_AGE_DAYS)?;
+    validate_limit("max_messages", update.max_messages, i32::MAX)?;
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        if let Some(channel_id) = channel_id {
+            check_channel(state, server_id, channel_id).await?;
+        }
+        let policy = queries::retention::upsert(
+            &state.db_pool,
+            server_id,
+            channel_id,
+            update,
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::RetentionUpdate,
+            Some(server_id),
+            audit_target(server_id, channel_id),
+        )
+        .await;
+        Ok(policy)
+    } else {
+        // Update on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::RETENTION)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated retention update"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let policy = requests::retention::federated::update(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+            update,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to update retention policy on {host}: {e}"
+            ))
+        })?;
+        Ok(policy)
+    }
+}
+
+/// Remove the retention policy of a server, or of one of its channels when
+/// `channel_id` is given. A channel without a policy of its own falls back
+/// to its server's.
+pub async fn delete(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Option<Uuid>,
+    target_host: Option<&str>,
+) -> ApiResult<()> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        if !queries::retention::delete(&state.db_pool, server_id, channel_id)
+            .await?
+        {
+            return Err(ApiError::NotFound);
+        }
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::RetentionDelete,
+            Some(server_id),
+            audit_target(server_id, channel_id),
+        )
+        .await;
+        Ok(())
+    } else {
+        // Delete on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::RETENTION)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated retention deletion"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        requests::retention::federated::delete(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            channel_id,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to delete retention policy on {host}: {e}"
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+/// Auth requirements for retention policy operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn get_by_server(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().client_only()
+    }
+
+    pub fn update(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub fn delete(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn get_by_server(server_id: Uuid) -> Req {
+            Req::ServerMember(server_id).federated_only()
+        }
+
+        pub fn update(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
+        pub fn delete(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_age_limit_is_bounded() {
+        assert!(
+            validate_limit("max_age_days", Some(Some(0)), MAX_AGE_DAYS)
+                .is_err()
+        );
+        assert!(
+            validate_limit("max_age_days", Some(Some(30)), MAX_AGE_DAYS)
+                .is_ok()
+        );
+        assert!(
+            validate_limit(
+                "max_age_days",
+                Some(Some(2_000_000_000)),
+                MAX_AGE_DAYS
+            )
+            .is_err()
+        );
+        assert!(
+            validate_limit("max_age_days", Some(None), MAX_AGE_DAYS).is_ok()
+        );
+    }
+}
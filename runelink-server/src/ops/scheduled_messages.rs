@@ -0,0 +1,266 @@
+use log::{info, warn};
+use runelink_types::{
+    NewMessage, NewScheduledMessage, ScheduledMessage, markup,
+};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    auth::{Principal, Session, authorize},
+    error::{ApiError, ApiResult},
+    ops::{messages, session_user},
+    queries,
+    state::AppState,
+};
+
+/// How far ahead a message can be scheduled.
+const MAX_SCHEDULE_DAYS: i64 = 365;
+
+/// How many messages a user can have waiting to be sent.
+const MAX_PENDING_PER_USER: i64 = 100;
+
+/// How many times delivery is tried before it is given up on.
+const MAX_ATTEMPTS: i32 = 5;
+
+/// How many due messages one delivery run sends at most.
+const DELIVERY_BATCH_SIZE: i64 = 100;
+
+/// How long a delivery run may hold on to the messages it claimed, long
+/// enough for a full batch of timed out requests. Claims older than this
+/// were cut off before they finished.
+const CLAIM_TIMEOUT: Duration = Duration::hours(1);
+
+/// Queue a message to be sent to a channel at a later time. Messages are
+/// kept on their author's home host, which sends them when they are due,
+/// also to servers on other hosts.
+pub async fn create(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    channel_id: Uuid,
+    new_scheduled: &NewScheduledMessage,
+    target_host: Option<&str>,
+) -> ApiResult<ScheduledMessage> {
+    let user_ref = session_user(session)?;
+    let new_message = &new_scheduled.message;
+    if new_message.author != *user_ref {
+        return Err(ApiError::Forbidden(
+            "Messages can only be scheduled as yourself".into(),
+        ));
+    }
+    // Uploads that are not sent within a day are deleted
+    if !new_message.attachment_ids.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Scheduled messages can't have attachments".into(),
+        ));
+    }
+    let body = markup::sanitize(&new_message.body);
+    markup::validate(&markup::parse(&body))
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    if body.is_empty() {
+        return Err(ApiError::BadRequest("Message is empty".into()));
+    }
+    let now = OffsetDateTime::now_utc();
+    if new_scheduled.send_at <= now {
+        return Err(ApiError::BadRequest(
+            "send_at must be in the future".into(),
+        ));
+    }
+    if new_scheduled.send_at > now + Duration::days(MAX_SCHEDULE_DAYS) {
+        return Err(ApiError::BadRequest(format!(
+            "Messages can be scheduled at most {MAX_SCHEDULE_DAYS} days ahead"
+        )));
+    }
+    let pending = queries::scheduled_messages::count_pending_by_author(
+        &state.db_pool,
+        user_ref,
+    )
+    .await?;
+    if pending >= MAX_PENDING_PER_USER {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_PENDING_PER_USER} messages can wait to be sent"
+        )));
+    }
+    let server_host = match target_host {
+        Some(host) if state.config.is_remote_host(Some(host)) => {
+            host.to_string()
+        }
+        _ => state.config.local_host(),
+    };
+    queries::scheduled_messages::insert(
+        &state.db_pool,
+        server_id,
+        &server_host,
+        channel_id,
+        user_ref,
+        &body,
+        new_scheduled.send_at,
+    )
+    .await
+}
+
+/// Get the scheduled messages of the session's user, soonest first.
+pub async fn get_all(
+    state: &AppState,
+    session: &Session,
+) -> ApiResult<Vec<ScheduledMessage>> {
+    let user_ref = session_user(session)?;
+    queries::scheduled_messages::get_by_author(&state.db_pool, user_ref).await
+}
+
+/// Cancel one of the session user's scheduled messages.
+pub async fn cancel(
+    state: &AppState,
+    session: &Session,
+    scheduled_id: Uuid,
+) -> ApiResult<()> {
+    let user_ref = session_user(session)?;
+    let deleted = queries::scheduled_messages::delete_by_author(
+        &state.db_pool,
+        user_ref,
+        scheduled_id,
+    )
+    .await?;
+    if !deleted {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+/// Send a scheduled message as its author, who must still be allowed to
+/// post in the channel.
+async fn deliver(
+    state: &AppState,
+    scheduled: &ScheduledMessage,
+) -> ApiResult<()> {
+    let session = authorize(
+        state,
+        Principal::System(scheduled.author.clone()),
+        auth::deliver(scheduled.server_id),
+    )
+    .await?;
+    let new_message = NewMessage {
+        author: scheduled.author.clone(),
+        body: scheduled.body.clone(),
+        attachment_ids: Vec::new(),
+    };
+    messages::create(
+        state,
+        &session,
+        scheduled.server_id,
+        scheduled.channel_id,
+        &new_message,
+        Some(&scheduled.server_host),
+    )
+    .await?;
+    Ok(())
+}
+
+/// When to try a failed delivery again, if at all. Only errors that may
+/// pass, like an unreachable host, are retried, backing off each time.
+fn retry_at(
+    error: &ApiError,
+    attempts: i32,
+    now: OffsetDateTime,
+) -> Option<OffsetDateTime> {
+    let transient = matches!(
+        error,
+        ApiError::Internal(_)
+            | ApiError::DbConnectionError(_)
+            | ApiError::DatabaseError(_)
+            | ApiError::Client(_)
+            | ApiError::RateLimited { .. }
+    );
+    if !transient || attempts + 1 >= MAX_ATTEMPTS {
+        return None;
+    }
+    Some(now + Duration::minutes(1 << attempts))
+}
+
+/// Send the scheduled messages that are due. Failures are recorded on the
+/// messages, for their authors to see.
+///
+/// Messages are claimed before they are sent, so a message is sent at most
+/// once even if it can't be removed afterwards.
+pub async fn deliver_due(state: &AppState) {
+    let now = OffsetDateTime::now_utc();
+    match queries::scheduled_messages::fail_claimed_before(
+        &state.db_pool,
+        now - CLAIM_TIMEOUT,
+    )
+    .await
+    {
+        Ok(0) => {}
+        Ok(count) => {
+            warn!("Gave up on {count} interrupted scheduled message(s)");
+        }
+        Err(e) => warn!("Failed to clear interrupted scheduled messages: {e}"),
+    }
+    let due = match queries::scheduled_messages::claim_due(
+        &state.db_pool,
+        now,
+        DELIVERY_BATCH_SIZE,
+    )
+    .await
+    {
+        Ok(due) => due,
+        Err(e) => {
+            warn!("Failed to load due scheduled messages: {e}");
+            return;
+        }
+    };
+    for scheduled in due {
+        let result = match deliver(state, &scheduled).await {
+            Ok(()) => {
+                info!(
+                    "Sent scheduled message {} of {}",
+                    scheduled.id, scheduled.author
+                );
+                queries::scheduled_messages::delete(
+                    &state.db_pool,
+                    scheduled.id,
+                )
+                .await
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to send scheduled message {} of {}: {e}",
+                    scheduled.id, scheduled.author
+                );
+                queries::scheduled_messages::record_failure(
+                    &state.db_pool,
+                    scheduled.id,
+                    &e.to_string(),
+                    retry_at(&e, scheduled.attempts, now),
+                )
+                .await
+            }
+        };
+        if let Err(e) = result {
+            warn!("Failed to update scheduled message {}: {e}", scheduled.id);
+        }
+    }
+}
+
+/// Auth requirements for scheduled message operations.
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn create(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).client_only()
+    }
+
+    pub fn get_all() -> Req {
+        Req::Client
+    }
+
+    pub fn cancel() -> Req {
+        Req::Client
+    }
+
+    /// Sent by this host for the author, who must still be allowed to post.
+    pub fn deliver(server_id: Uuid) -> Req {
+        Req::ServerMember(server_id).or_admin().system_only()
+    }
+}
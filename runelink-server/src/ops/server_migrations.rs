@@ -0,0 +1,319 @@
+use std::collections::BTreeSet;
+
+use axum::body::Bytes;
+use futures_util::Stream;
+use log::{info, warn};
+use runelink_client::{requests, resolver::ResolvedHost};
+use runelink_types::{
+    ArchiveImport, ArchivedMember, NewServerMigration, ServerMigration,
+    capabilities,
+};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::{
+        archive::{self, ImportMode},
+        audit::{self, AuditAction, AuditTarget},
+        require_capability, session_user,
+    },
+    queries,
+    state::AppState,
+};
+
+/// Move a server and all its channels, messages, members, bans, timeouts,
+/// retention policies and pins to another host. The new host imports the
+/// server with the same IDs; this host then deletes it, keeps a record
+/// pointing to the new host, and tells the hosts of the server's members
+/// where it went. Servers with attachments can't be moved.
+pub async fn migrate(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    new_migration: &NewServerMigration,
+    target_host: Option<&str>,
+) -> ApiResult<ServerMigration> {
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let to_host = new_migration.to_host.trim();
+        if !state.config.is_remote_host(Some(to_host)) {
+            return Err(ApiError::BadRequest(
+                "The server is already hosted here".into(),
+            ));
+        }
+        queries::servers::get_by_id(state, server_id).await?;
+        let peer = state.resolve_host(to_host).await;
+        require_capability(&peer, to_host, capabilities::SERVER_MIGRATION)?;
+        // Keep the server read-only until it's deleted here, so nothing
+        // written during the export is lost. Writes already under way are
+        // waited out first.
+        let mut tx = state.db_pool.begin().await?;
+        queries::server_migrations::lock_server(&mut tx, server_id, true)
+            .await?;
+        // Archives don't carry attachments, and the blobs left behind would
+        // be deleted along with the server.
+        // TODO: hand attachment blobs over to the new host as well
+        if queries::attachments::exists_in_server(&state.db_pool, server_id)
+            .await?
+        {
+            return Err(ApiError::BadRequest(
+                "Servers with attachments can't be moved yet".into(),
+            ));
+        }
+        if !queries::server_migrations::start_moving(
+            &mut tx, server_id, to_host,
+        )
+        .await?
+        {
+            return Err(ApiError::BadRequest(
+                "The server is already being moved".into(),
+            ));
+        }
+        tx.commit().await?;
+        let imported = match hand_off(state, server_id, &peer, to_host).await {
+            Ok(imported) => imported,
+            Err(e) => {
+                queries::server_migrations::stop_moving(
+                    &state.db_pool,
+                    server_id,
+                )
+                .await?;
+                return Err(e);
+            }
+        };
+        let members =
+            queries::archive::get_members(&state.db_pool, server_id).await?;
+
+        let local_host = state.config.local_host();
+        let migration = ServerMigration {
+            server_id,
+            from_host: local_host.clone(),
+            to_host: imported.server.host.clone(),
+            migrated_at: OffsetDateTime::now_utc(),
+        };
+        let mut tx = state.db_pool.begin().await?;
+        queries::server_migrations::cache_local_memberships(
+            &mut tx,
+            &imported.server,
+            &local_host,
+        )
+        .await?;
+        queries::server_migrations::update_server_host(
+            &mut tx,
+            server_id,
+            &migration.to_host,
+        )
+        .await?;
+        queries::server_migrations::delete_local_server(&mut tx, server_id)
+            .await?;
+        queries::server_migrations::insert(&mut tx, &migration).await?;
+        tx.commit().await?;
+
+        audit::record_with_detail(
+            state,
+            Some(session),
+            AuditAction::ServerMigrate,
+            Some(server_id),
+            AuditTarget::Server(server_id),
+            Some(&format!("to {}", migration.to_host)),
+        )
+        .await;
+        notify_member_hosts(state, &migration, &members).await;
+        Ok(migration)
+    } else {
+        // Ask the server's host to move it, on behalf of its owner
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::SERVER_MIGRATION)?;
+        let user_ref = session_user(session)?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref.clone(),
+        )?;
+        let migration = requests::server_migrations::federated::migrate(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            new_migration,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to migrate server on {host}: {e}"
+            ))
+        })?;
+        Ok(migration)
+    }
+}
+
+/// Export a local server and have another host import it.
+async fn hand_off(
+    state: &AppState,
+    server_id: Uuid,
+    peer: &ResolvedHost,
+    to_host: &str,
+) -> ApiResult<ArchiveImport> {
+    let archive = archive::export(state, server_id).await?;
+    let token = state.key_manager.issue_federation_jwt_server_only(
+        state.config.api_url(),
+        peer.api_url.clone(),
+    )?;
+    requests::server_migrations::federated::hand_off(
+        &state.http_client,
+        &peer.federation_url,
+        &token,
+        server_id,
+        reqwest::Body::wrap_stream(archive),
+    )
+    .await
+    .map_err(|e| {
+        ApiError::Internal(format!(
+            "Failed to hand off server to {to_host}: {e}"
+        ))
+    })
+}
+
+/// Tell the hosts of a moved server's members (other than this host and
+/// the new one) where the server went. Failures are only logged.
+async fn notify_member_hosts(
+    state: &AppState,
+    migration: &ServerMigration,
+    members: &[ArchivedMember],
+) {
+    let hosts: BTreeSet<&str> = members
+        .iter()
+        .map(|m| m.user.host.as_str())
+        .filter(|host| {
+            state.config.is_remote_host(Some(host))
+                && *host != migration.to_host
+        })
+        .collect();
+    for host in hosts {
+        let peer = state.resolve_host(host).await;
+        if !peer.supports(capabilities::SERVER_MIGRATION) {
+            info!(
+                "Skipping server migration notice to {host}: not supported by peer"
+            );
+            continue;
+        }
+        let token = match state.key_manager.issue_federation_jwt_server_only(
+            state.config.api_url(),
+            peer.api_url.clone(),
+        ) {
+            Ok(token) => token,
+            Err(e) => {
+                warn!("Failed to issue federation token for host {host}: {e}");
+                continue;
+            }
+        };
+        let result = requests::server_migrations::federated::notify(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            migration,
+        )
+        .await;
+        if let Err(e) = result {
+            warn!(
+                "Failed to tell {host} that server {} moved: {e}",
+                migration.server_id
+            );
+        }
+    }
+}
+
+/// Take over a server handed off by its old host. Only hosts listed in
+/// `MIGRATION_SOURCE_HOSTS` may move their servers here.
+pub async fn receive<S, E>(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    archive: S,
+) -> ApiResult<ArchiveImport>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let issuer = session
+        .federation
+        .as_ref()
+        .map(|claims| claims.iss.as_str())
+        .ok_or_else(|| {
+            ApiError::Internal("Federation claims required".into())
+        })?;
+    let mut from_host = None;
+    for host in &state.config.migration_source_hosts {
+        if state.api_url_for(host).await == issuer {
+            from_host = Some(host.clone());
+            break;
+        }
+    }
+    let Some(from_host) = from_host else {
+        return Err(ApiError::Forbidden(
+            "This host does not take servers from yours".into(),
+        ));
+    };
+    archive::import_with_mode(
+        state,
+        session,
+        archive,
+        ImportMode::Migration {
+            server_id,
+            from_host,
+        },
+    )
+    .await
+}
+
+/// Point a remote server that moved to its new host.
+pub async fn apply_remote(
+    state: &AppState,
+    migration: &ServerMigration,
+) -> ApiResult<()> {
+    let mut tx = state.db_pool.begin().await?;
+    queries::server_migrations::update_server_host(
+        &mut tx,
+        migration.server_id,
+        &migration.to_host,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub mod auth {
+    use super::*;
+    use crate::auth::Requirement as Req;
+
+    pub fn migrate(server_id: Uuid) -> Req {
+        Req::ServerOwner(server_id).or_admin().client_only()
+    }
+
+    pub mod federated {
+        use super::*;
+
+        pub fn migrate(server_id: Uuid) -> Req {
+            Req::ServerOwner(server_id).federated_only()
+        }
+
+        /// Only hosts this host takes servers from may hand one off.
+        pub fn receive(source_hosts: &[String]) -> Req {
+            Req::Or(
+                source_hosts
+                    .iter()
+                    .map(|host| Req::FederatedHost(host.clone()))
+                    .collect(),
+            )
+            .federated_only()
+        }
+
+        /// Only the old host of a server may say where it moved.
+        pub fn apply(server_host: String) -> Req {
+            Req::FederatedHost(server_host).federated_only()
+        }
+    }
+}
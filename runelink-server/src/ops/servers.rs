@@ -1,18 +1,35 @@
-use runelink_client::{requests, util::get_api_url};
+use runelink_client::requests;
+use std::collections::HashSet;
+
+use log::warn;
 use runelink_types::{
     NewServer, NewServerMembership, Server, ServerMembership, ServerRole,
-    ServerWithChannels,
+    ServerUpdate, ServerWithChannels, UserRef, capabilities,
 };
 use uuid::Uuid;
 
 use crate::{
     auth::Session,
     error::{ApiError, ApiResult},
+    ops::{
+        audit::{self, AuditAction, AuditTarget},
+        check_writable, memberships, require_capability,
+    },
     queries,
     state::AppState,
 };
 
-/// Create a new server and add the creator as admin.
+/// Reject titles that are empty once trimmed.
+pub(crate) fn validate_title(title: Option<&str>) -> ApiResult<()> {
+    match title {
+        Some(title) if title.trim().is_empty() => {
+            Err(ApiError::BadRequest("Title cannot be empty".into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Create a new server and make the creator its owner.
 pub async fn create(
     state: &AppState,
     session: &Session,
@@ -35,15 +52,23 @@ pub async fn create(
             user_ref,
             server_id: server.id,
             server_host: server.host.clone(),
-            role: ServerRole::Admin,
+            role: ServerRole::Owner,
         };
         queries::memberships::insert_local(&state.db_pool, &new_membership)
             .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::ServerCreate,
+            Some(server.id),
+            AuditTarget::Server(server.id),
+        )
+        .await;
         Ok(server)
     } else {
         // Create on remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated server creation"
@@ -52,12 +77,12 @@ pub async fn create(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         )?;
         let server = requests::servers::federated::create(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             new_server,
         )
@@ -67,12 +92,12 @@ pub async fn create(
                 "Failed to create server on {host}: {e}"
             ))
         })?;
-        // Cache the remote server and creator's admin membership on the home server.
+        // Cache the remote server and creator's owner membership on the home server.
         queries::servers::upsert_remote(&state.db_pool, &server).await?;
         let remote_membership = ServerMembership {
             server: server.clone(),
             user_ref,
-            role: ServerRole::Admin,
+            role: ServerRole::Owner,
             joined_at: server.created_at,
             updated_at: server.updated_at,
             synced_at: Some(server.created_at),
@@ -98,7 +123,7 @@ pub async fn get_all(
     } else {
         // Fetch from remote host
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let api_url = state.api_url_for(host).await;
         let servers =
             requests::servers::fetch_all(&state.http_client, &api_url, None)
                 .await
@@ -120,12 +145,22 @@ pub async fn get_by_id(
     if !state.config.is_remote_host(target_host) {
         // Handle local case
         // TODO: separate public and private server objects?
-        let server = queries::servers::get_by_id(state, server_id).await?;
-        Ok(server)
+        match queries::servers::get_by_id(state, server_id).await {
+            // Point to the new host of servers that moved away
+            Err(ApiError::NotFound) => {
+                let migration = queries::server_migrations::get_by_server(
+                    &state.db_pool,
+                    server_id,
+                )
+                .await?;
+                Err(ApiError::ServerMoved(migration.to_host))
+            }
+            result => result,
+        }
     } else {
         // Fetch from remote host
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let api_url = state.api_url_for(host).await;
         let server = requests::servers::fetch_by_id(
             &state.http_client,
             &api_url,
@@ -151,18 +186,20 @@ pub async fn get_with_channels(
 ) -> ApiResult<ServerWithChannels> {
     if !state.config.is_remote_host(target_host) {
         // Handle local case
-        let (server, channels) = tokio::join!(
+        let (server, channels, categories) = tokio::join!(
             queries::servers::get_by_id(state, server_id),
-            queries::channels::get_by_server(&state.db_pool, server_id),
+            queries::channels::get_by_server(&state.db_pool, server_id, false),
+            queries::categories::get_by_server(&state.db_pool, server_id),
         );
         Ok(ServerWithChannels {
             server: server?,
             channels: channels?,
+            categories: categories?,
         })
     } else {
         // Fetch from remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated server fetching"
@@ -171,13 +208,13 @@ pub async fn get_with_channels(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref,
         )?;
         let server_with_channels =
             requests::servers::federated::fetch_with_channels(
                 &state.http_client,
-                &api_url,
+                &peer.federation_url,
                 &token,
                 server_id,
             )
@@ -191,6 +228,103 @@ pub async fn get_with_channels(
     }
 }
 
+/// Update a server's title and/or description.
+pub async fn update(
+    state: &AppState,
+    session: &Session,
+    server_id: Uuid,
+    update: &ServerUpdate,
+    target_host: Option<&str>,
+) -> ApiResult<Server> {
+    validate_title(update.title.as_deref())?;
+    // Handle local case
+    if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
+        let server = queries::servers::update(
+            state,
+            server_id,
+            update.title.as_deref(),
+            update.description.is_some(),
+            update.description.clone().flatten().as_deref(),
+        )
+        .await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::ServerUpdate,
+            Some(server_id),
+            AuditTarget::Server(server_id),
+        )
+        .await;
+        let state = state.clone();
+        tokio::spawn(async move {
+            push_server_update(&state, server_id).await;
+        });
+        Ok(server)
+    } else {
+        // Update on remote host using federation
+        let host = target_host.unwrap();
+        let peer = state.resolve_host(host).await;
+        require_capability(&peer, host, capabilities::METADATA)?;
+        let user_ref = session.user_ref.clone().ok_or_else(|| {
+            ApiError::Internal(
+                "User reference required for federated server update"
+                    .to_string(),
+            )
+        })?;
+        let token = state.key_manager.issue_federation_jwt_delegated(
+            state.config.api_url(),
+            peer.api_url.clone(),
+            user_ref,
+        )?;
+        let server = requests::servers::federated::update(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            server_id,
+            update,
+        )
+        .await
+        .map_err(|e| {
+            ApiError::Internal(format!(
+                "Failed to update server on {host}: {e}"
+            ))
+        })?;
+        // Refresh our cached copy of the remote server.
+        queries::servers::upsert_remote(&state.db_pool, &server).await?;
+        Ok(server)
+    }
+}
+
+/// Refresh the cached copy of a server on the home host of every remote
+/// member, by syncing one membership per host.
+///
+/// Best effort: failures are only logged.
+async fn push_server_update(state: &AppState, server_id: Uuid) {
+    let members = match queries::memberships::get_members_by_server(
+        &state.db_pool,
+        server_id,
+    )
+    .await
+    {
+        Ok(members) => members,
+        Err(e) => {
+            warn!("Failed to load members of {server_id}: {e}");
+            return;
+        }
+    };
+    let mut hosts = HashSet::new();
+    for member in members {
+        let host = member.user.host;
+        if !state.config.is_remote_host(Some(&host)) || hosts.contains(&host) {
+            continue;
+        }
+        let user_ref = UserRef::new(member.user.name, host.clone());
+        memberships::push_membership_update(state, server_id, &user_ref).await;
+        hosts.insert(host);
+    }
+}
+
 /// Delete a server by ID.
 pub async fn delete(
     state: &AppState,
@@ -200,12 +334,21 @@ pub async fn delete(
 ) -> ApiResult<()> {
     // Handle local case
     if !state.config.is_remote_host(target_host) {
+        let _writing = check_writable(state, server_id).await?;
         queries::servers::delete(state, server_id).await?;
+        audit::record(
+            state,
+            Some(session),
+            AuditAction::ServerDelete,
+            Some(server_id),
+            AuditTarget::Server(server_id),
+        )
+        .await;
         Ok(())
     } else {
         // Delete on remote host using federation
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
         let user_ref = session.user_ref.clone().ok_or_else(|| {
             ApiError::Internal(
                 "User reference required for federated server deletion"
@@ -214,12 +357,12 @@ pub async fn delete(
         })?;
         let token = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref,
         )?;
         requests::servers::federated::delete(
             &state.http_client,
-            &api_url,
+            &peer.federation_url,
             &token,
             server_id,
         )
@@ -247,6 +390,10 @@ pub mod auth {
         Req::ServerMember(server_id).or_admin().client_only()
     }
 
+    pub fn update(server_id: Uuid) -> Req {
+        Req::ServerAdmin(server_id).or_admin().client_only()
+    }
+
     pub fn delete(server_id: Uuid) -> Req {
         Req::ServerAdmin(server_id).or_admin().client_only()
     }
@@ -263,6 +410,10 @@ pub mod auth {
             Req::ServerMember(server_id).federated_only()
         }
 
+        pub fn update(server_id: Uuid) -> Req {
+            Req::ServerAdmin(server_id).federated_only()
+        }
+
         pub fn delete(server_id: Uuid) -> Req {
             Req::ServerAdmin(server_id).federated_only()
         }
@@ -0,0 +1,231 @@
+use runelink_types::{
+    IssuedSignupInvite, NewSignupInvite, NewUser, SignupInvite, SignupRequest,
+    User, UserRef, UserRole,
+};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    ops::audit::{self, AuditAction, AuditTarget},
+    queries, secrets,
+    signup_policy::{SignupMode, validate_password, validate_username},
+    state::AppState,
+};
+
+/// Longest an invite can last before expiring (about a year); invites can
+/// also be made to never expire.
+const MAX_INVITE_LIFETIME_HOURS: i64 = 366 * 24;
+
+/// Create a local account, enforcing the host's signup policy.
+pub async fn signup(state: &AppState, req: &SignupRequest) -> ApiResult<User> {
+    let mode = state.config.signup_mode;
+    if mode == SignupMode::Closed {
+        return Err(ApiError::AuthError("Signups are closed".into()));
+    }
+    validate_username(&req.name, &state.config.reserved_usernames)
+        .map_err(ApiError::BadRequest)?;
+    validate_password(&req.password, &req.name)
+        .map_err(ApiError::BadRequest)?;
+
+    let user_ref = UserRef::new(req.name.clone(), state.config.local_host());
+    match queries::users::get_by_ref(&state.db_pool, user_ref.clone()).await {
+        Ok(_) => return Err(ApiError::UniqueViolation),
+        Err(ApiError::NotFound) => {}
+        Err(e) => return Err(e),
+    }
+
+    // Hash before taking an invite use so a hashing failure can't burn it
+    let password_hash = secrets::hash_password(&req.password)?;
+
+    let invite = if mode == SignupMode::Invite {
+        let token = req.invite_token.as_deref().ok_or_else(|| {
+            ApiError::AuthError("An invite token is required to sign up".into())
+        })?;
+        let token_hash = secrets::hash_token(token);
+        let invite = queries::invites::redeem(&state.db_pool, &token_hash)
+            .await
+            .map_err(|e| match e {
+                ApiError::NotFound => ApiError::AuthError(
+                    "Invite token is invalid, expired or used up".into(),
+                ),
+                e => e,
+            })?;
+        Some(invite)
+    } else {
+        None
+    };
+
+    let result = create_account(
+        state,
+        &user_ref,
+        &password_hash,
+        mode != SignupMode::Approval,
+    )
+    .await;
+    if result.is_err()
+        && let Some(invite) = invite
+    {
+        queries::invites::release(&state.db_pool, invite.id).await?;
+    }
+    result
+}
+
+async fn create_account(
+    state: &AppState,
+    user_ref: &UserRef,
+    password_hash: &str,
+    approved: bool,
+) -> ApiResult<User> {
+    let new_user = NewUser {
+        name: user_ref.name.clone(),
+        host: user_ref.host.clone(),
+        role: UserRole::User,
+    };
+    let user = queries::users::insert(&state.db_pool, &new_user).await?;
+    let account_result = queries::accounts::insert(
+        &state.db_pool,
+        user.as_ref(),
+        password_hash,
+        approved,
+    )
+    .await;
+    if let Err(e) = account_result {
+        queries::users::delete(&state.db_pool, user.as_ref()).await?;
+        return Err(e);
+    }
+    Ok(user)
+}
+
+/// Mint a new signup invite.
+pub async fn create_invite(
+    state: &AppState,
+    session: &Session,
+    new_invite: &NewSignupInvite,
+) -> ApiResult<IssuedSignupInvite> {
+    let created_by = session.user_ref.clone().ok_or_else(|| {
+        ApiError::Internal("User reference required to mint invites".into())
+    })?;
+    if new_invite.max_uses.is_some_and(|n| n < 1) {
+        return Err(ApiError::BadRequest("max_uses must be positive".into()));
+    }
+    let expires_at = match new_invite.expires_in_hours {
+        Some(hours) if !(1..=MAX_INVITE_LIFETIME_HOURS).contains(&hours) => {
+            return Err(ApiError::BadRequest(format!(
+                "expires_in_hours must be between 1 and \
+                 {MAX_INVITE_LIFETIME_HOURS}"
+            )));
+        }
+        Some(hours) => Some(OffsetDateTime::now_utc() + Duration::hours(hours)),
+        None => None,
+    };
+    let token = secrets::generate_token();
+    let invite = queries::invites::insert(
+        &state.db_pool,
+        &secrets::hash_token(&token),
+        &created_by,
+        new_invite.note.as_deref(),
+        new_invite.max_uses,
+        expires_at,
+    )
+    .await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::InviteCreate,
+        None,
+        AuditTarget::Invite(invite.id),
+    )
+    .await;
+    Ok(IssuedSignupInvite { token, invite })
+}
+
+/// List all signup invites (without their tokens).
+pub async fn get_invites(
+    state: &AppState,
+    _session: &Session,
+) -> ApiResult<Vec<SignupInvite>> {
+    queries::invites::get_all(&state.db_pool).await
+}
+
+/// Revoke a signup invite.
+pub async fn delete_invite(
+    state: &AppState,
+    session: &Session,
+    invite_id: Uuid,
+) -> ApiResult<()> {
+    queries::invites::delete(&state.db_pool, invite_id).await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::InviteDelete,
+        None,
+        AuditTarget::Invite(invite_id),
+    )
+    .await;
+    Ok(())
+}
+
+/// List accounts waiting for approval.
+pub async fn get_pending(
+    state: &AppState,
+    _session: &Session,
+) -> ApiResult<Vec<User>> {
+    queries::accounts::get_pending(&state.db_pool).await
+}
+
+/// Approve a pending account so it can log in.
+pub async fn approve(
+    state: &AppState,
+    session: &Session,
+    name: &str,
+) -> ApiResult<()> {
+    let user_ref = UserRef::new(name.into(), state.config.local_host());
+    queries::accounts::approve(&state.db_pool, user_ref.clone()).await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::SignupApprove,
+        None,
+        AuditTarget::User(user_ref),
+    )
+    .await;
+    Ok(())
+}
+
+/// Reject a pending account, deleting it.
+pub async fn reject(
+    state: &AppState,
+    session: &Session,
+    name: &str,
+) -> ApiResult<()> {
+    let user_ref = UserRef::new(name.into(), state.config.local_host());
+    let account =
+        queries::accounts::get_by_user(&state.db_pool, user_ref.clone())
+            .await?;
+    if account.approved {
+        return Err(ApiError::BadRequest(
+            "Account is not pending approval".into(),
+        ));
+    }
+    queries::users::delete(&state.db_pool, user_ref.clone()).await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::SignupReject,
+        None,
+        AuditTarget::User(user_ref),
+    )
+    .await;
+    Ok(())
+}
+
+/// Auth requirements for signup administration.
+pub mod auth {
+    use crate::auth::Requirement as Req;
+
+    pub fn manage() -> Req {
+        Req::HostAdmin.client_only()
+    }
+}
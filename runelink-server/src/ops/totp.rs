@@ -0,0 +1,164 @@
+use runelink_types::{
+    RecoveryCodes, TotpConfirmRequest, TotpDisableRequest, TotpEnrollment,
+    TotpStatus, UserRef,
+};
+use time::OffsetDateTime;
+
+use crate::{
+    auth::Session,
+    error::{ApiError, ApiResult},
+    queries, secrets,
+    state::AppState,
+    totp,
+};
+
+fn session_user(session: &Session) -> ApiResult<UserRef> {
+    session.user_ref.clone().ok_or_else(|| {
+        ApiError::Internal("User reference required for 2FA".into())
+    })
+}
+
+/// Whether the caller has 2FA enabled.
+pub async fn status(
+    state: &AppState,
+    session: &Session,
+) -> ApiResult<TotpStatus> {
+    let user_ref = session_user(session)?;
+    let enabled =
+        match queries::totp::get(&state.db_pool, user_ref.clone()).await {
+            Ok(credential) => credential.enabled,
+            Err(ApiError::NotFound) => false,
+            Err(e) => return Err(e),
+        };
+    let recovery_codes_remaining =
+        queries::totp::count_recovery_codes(&state.db_pool, user_ref).await?;
+    Ok(TotpStatus {
+        enabled,
+        recovery_codes_remaining,
+    })
+}
+
+/// Start enrolling the caller in TOTP, replacing any unconfirmed enrollment.
+pub async fn enroll(
+    state: &AppState,
+    session: &Session,
+) -> ApiResult<TotpEnrollment> {
+    let user_ref = session_user(session)?;
+    let secret = totp::generate_secret();
+    queries::totp::upsert_pending(&state.db_pool, user_ref.clone(), &secret)
+        .await
+        .map_err(|e| match e {
+            ApiError::UniqueViolation => {
+                ApiError::BadRequest("2FA is already enabled".into())
+            }
+            e => e,
+        })?;
+    let issuer = format!("RuneLink ({})", state.config.local_host());
+    let otpauth_uri = totp::otpauth_uri(&secret, &issuer, &user_ref.name);
+    Ok(TotpEnrollment {
+        secret,
+        otpauth_uri,
+    })
+}
+
+/// Confirm a pending enrollment with a first code, enabling 2FA and issuing
+/// recovery codes.
+pub async fn confirm(
+    state: &AppState,
+    session: &Session,
+    req: &TotpConfirmRequest,
+) -> ApiResult<RecoveryCodes> {
+    let user_ref = session_user(session)?;
+    let credential = queries::totp::get(&state.db_pool, user_ref.clone())
+        .await
+        .map_err(|e| match e {
+            ApiError::NotFound => {
+                ApiError::BadRequest("No 2FA enrollment in progress".into())
+            }
+            e => e,
+        })?;
+    if credential.enabled {
+        return Err(ApiError::BadRequest("2FA is already enabled".into()));
+    }
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let step = totp::verify(&credential.secret, &req.code, now, None)
+        .ok_or_else(|| ApiError::AuthError("invalid one-time code".into()))?;
+    queries::totp::use_step(&state.db_pool, user_ref.clone(), step).await?;
+
+    let codes = totp::generate_recovery_codes();
+    let code_hashes: Vec<String> = codes
+        .iter()
+        .map(|code| secrets::hash_token(&totp::normalize_recovery_code(code)))
+        .collect();
+    queries::totp::replace_recovery_codes(
+        &state.db_pool,
+        user_ref,
+        &code_hashes,
+    )
+    .await?;
+    Ok(RecoveryCodes { codes })
+}
+
+/// Turn 2FA off. Requires the password and a TOTP or recovery code.
+pub async fn disable(
+    state: &AppState,
+    session: &Session,
+    req: &TotpDisableRequest,
+) -> ApiResult<()> {
+    let user_ref = session_user(session)?;
+    let account =
+        queries::accounts::get_by_user(&state.db_pool, user_ref.clone())
+            .await?;
+    secrets::verify_password(&req.password, &account.password_hash)?;
+    check_second_factor(state, &user_ref, Some(&req.code)).await?;
+    queries::totp::delete(&state.db_pool, user_ref).await
+}
+
+/// Check the second factor of a login. Passes for accounts without 2FA.
+pub async fn check_second_factor(
+    state: &AppState,
+    user_ref: &UserRef,
+    code: Option<&str>,
+) -> ApiResult<()> {
+    let credential =
+        match queries::totp::get(&state.db_pool, user_ref.clone()).await {
+            Ok(credential) if credential.enabled => credential,
+            Ok(_) | Err(ApiError::NotFound) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+    let code = code.ok_or(ApiError::MfaRequired)?;
+    let invalid = || ApiError::AuthError("invalid one-time code".into());
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    if let Some(step) =
+        totp::verify(&credential.secret, code, now, credential.last_used_step)
+    {
+        // Races with a concurrent login using the same code lose here
+        return queries::totp::use_step(&state.db_pool, user_ref.clone(), step)
+            .await
+            .map_err(|e| match e {
+                ApiError::NotFound => invalid(),
+                e => e,
+            });
+    }
+    let code_hash = secrets::hash_token(&totp::normalize_recovery_code(code));
+    queries::totp::use_recovery_code(
+        &state.db_pool,
+        user_ref.clone(),
+        &code_hash,
+    )
+    .await
+    .map_err(|e| match e {
+        ApiError::NotFound => invalid(),
+        e => e,
+    })
+}
+
+/// Auth requirements for 2FA management.
+pub mod auth {
+    use crate::auth::Requirement as Req;
+
+    pub fn manage() -> Req {
+        Req::Client
+    }
+}
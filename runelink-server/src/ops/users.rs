@@ -1,21 +1,106 @@
-use log::warn;
-use runelink_client::{requests, util::get_api_url};
-use runelink_types::{NewUser, User, UserRef};
+use log::{info, warn};
+use runelink_client::requests;
+use runelink_types::{NewUser, User, UserProfileUpdate, UserRef, capabilities};
 
 use crate::{
     auth::Session,
     error::{ApiError, ApiResult},
+    ops::audit::{self, AuditAction, AuditTarget},
     queries,
     state::AppState,
 };
 
+const MAX_DISPLAY_NAME_CHARS: usize = 64;
+const MAX_BIO_CHARS: usize = 1000;
+const MAX_AVATAR_URL_CHARS: usize = 2048;
+const MAX_STATUS_TEXT_CHARS: usize = 128;
+
+/// Whether `c` overrides or isolates text direction, which can make a
+/// name read as something other than what it is.
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Trim a profile field and strip control characters and bidi overrides
+/// (keeping line breaks if `multiline`). A blank value clears the field.
+fn clean_profile_field(
+    value: &Option<Option<String>>,
+    field: &str,
+    max_chars: usize,
+    multiline: bool,
+) -> ApiResult<Option<Option<String>>> {
+    let Some(Some(value)) = value else {
+        return Ok(value.clone());
+    };
+    let cleaned: String = value
+        .chars()
+        .filter(|&c| !is_bidi_control(c))
+        .filter(|&c| !c.is_control() || (multiline && c == '\n'))
+        .collect();
+    let cleaned = cleaned.trim();
+    if cleaned.is_empty() {
+        return Ok(Some(None));
+    }
+    if cleaned.chars().count() > max_chars {
+        return Err(ApiError::BadRequest(format!(
+            "{field} may be at most {max_chars} characters"
+        )));
+    }
+    Ok(Some(Some(cleaned.to_string())))
+}
+
+/// Validate a profile update, returning it with its fields cleaned up.
+fn clean_profile_update(
+    update: &UserProfileUpdate,
+) -> ApiResult<UserProfileUpdate> {
+    let cleaned = UserProfileUpdate {
+        display_name: clean_profile_field(
+            &update.display_name,
+            "Display name",
+            MAX_DISPLAY_NAME_CHARS,
+            false,
+        )?,
+        bio: clean_profile_field(&update.bio, "Bio", MAX_BIO_CHARS, true)?,
+        avatar_url: clean_profile_field(
+            &update.avatar_url,
+            "Avatar URL",
+            MAX_AVATAR_URL_CHARS,
+            false,
+        )?,
+        status_text: clean_profile_field(
+            &update.status_text,
+            "Status text",
+            MAX_STATUS_TEXT_CHARS,
+            false,
+        )?,
+    };
+    if let Some(Some(url)) = &cleaned.avatar_url {
+        let is_web_url =
+            url.starts_with("https://") || url.starts_with("http://");
+        if !is_web_url || url.contains(char::is_whitespace) {
+            return Err(ApiError::BadRequest(
+                "Avatar URL must be an http(s) address".into(),
+            ));
+        }
+    }
+    Ok(cleaned)
+}
+
 /// Create a new user.
 pub async fn create(
     state: &AppState,
-    _session: &Session,
+    session: &Session,
     new_user: &NewUser,
 ) -> ApiResult<User> {
     let user = queries::users::insert(&state.db_pool, new_user).await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::UserCreate,
+        None,
+        AuditTarget::User(user.as_ref()),
+    )
+    .await;
     Ok(user)
 }
 
@@ -29,7 +114,7 @@ pub async fn get_all(
         Ok(users)
     } else {
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let api_url = state.api_url_for(host).await;
         let users =
             requests::users::fetch_all(&state.http_client, &api_url, None)
                 .await
@@ -52,7 +137,7 @@ pub async fn get_by_ref(
         let user = queries::users::get_by_ref(&state.db_pool, user_ref).await?;
         Ok(user)
     } else {
-        let api_url = get_api_url(&user_ref.host);
+        let api_url = state.api_url_for(&user_ref.host).await;
         let host = user_ref.host.clone();
         let user = requests::users::fetch_by_ref(
             &state.http_client,
@@ -70,10 +155,104 @@ pub async fn get_by_ref(
     }
 }
 
+/// Update the profile of a user of this host, and push it to the hosts of
+/// the remote servers they are a member of.
+pub async fn update_profile(
+    state: &AppState,
+    user_ref: &UserRef,
+    update: &UserProfileUpdate,
+) -> ApiResult<User> {
+    if state.config.is_remote_host(Some(&user_ref.host)) {
+        return Err(ApiError::BadRequest(
+            "Profiles can only be edited on the user's home host".into(),
+        ));
+    }
+    let update = clean_profile_update(update)?;
+    let user =
+        queries::users::update_profile(&state.db_pool, user_ref, &update)
+            .await?;
+    let state = state.clone();
+    let pushed = user.clone();
+    tokio::spawn(async move {
+        push_profile_update(&state, &pushed).await;
+    });
+    Ok(user)
+}
+
+/// Send a user's profile to the hosts that cache it. Failures are only
+/// logged.
+async fn push_profile_update(state: &AppState, user: &User) {
+    let hosts = match queries::memberships::get_remote_server_hosts_for_user(
+        &state.db_pool,
+        user.as_ref(),
+    )
+    .await
+    {
+        Ok(hosts) => hosts,
+        Err(e) => {
+            warn!("Failed to load hosts caching {user}: {e}");
+            return;
+        }
+    };
+    for host in hosts {
+        let peer = state.resolve_host(&host).await;
+        if !peer.supports(capabilities::PROFILES) {
+            info!("Skipping profile sync on {host}: not supported by peer");
+            continue;
+        }
+        let token = match state.key_manager.issue_federation_jwt_server_only(
+            state.config.api_url(),
+            peer.api_url.clone(),
+        ) {
+            Ok(token) => token,
+            Err(e) => {
+                warn!("Failed to issue federation token for host {host}: {e}");
+                continue;
+            }
+        };
+        let result = requests::users::federated::sync(
+            &state.http_client,
+            &peer.federation_url,
+            &token,
+            user,
+        )
+        .await;
+        if let Err(e) = result {
+            warn!("Failed to sync profile of {user} to {host}: {e}");
+        }
+    }
+}
+
+/// Update the cached copy of a remote user, as pushed by their home host.
+///
+/// The profile is held to the same rules as local profile updates.
+pub async fn sync_remote(state: &AppState, user: &User) -> ApiResult<()> {
+    if !state.config.is_remote_host(Some(&user.host)) {
+        return Err(ApiError::BadRequest(
+            "Cannot sync users of this host".into(),
+        ));
+    }
+    let profile = clean_profile_update(&UserProfileUpdate {
+        display_name: Some(user.display_name.clone()),
+        bio: Some(user.bio.clone()),
+        avatar_url: Some(user.avatar_url.clone()),
+        status_text: Some(user.status_text.clone()),
+    })?;
+    let user = User {
+        display_name: profile.display_name.flatten(),
+        bio: profile.bio.flatten(),
+        avatar_url: profile.avatar_url.flatten(),
+        status_text: profile.status_text.flatten(),
+        ..user.clone()
+    };
+    queries::users::upsert_remote(&state.db_pool, &user).await?;
+    Ok(())
+}
+
 /// Delete a user from their home server.
 pub async fn delete_home_user(
     state: &AppState,
-    _session: &Session,
+    session: &Session,
     user_ref: &UserRef,
 ) -> ApiResult<()> {
     let user =
@@ -84,25 +263,30 @@ pub async fn delete_home_user(
         ));
     }
 
-    let foreign_hosts =
-        queries::memberships::get_remote_server_hosts_for_user(
-            &state.db_pool,
-            user_ref.clone(),
-        )
-        .await?;
+    let foreign_hosts = queries::memberships::get_remote_server_hosts_for_user(
+        &state.db_pool,
+        user_ref.clone(),
+    )
+    .await?;
 
     for host in &foreign_hosts {
-        let api_url = get_api_url(host);
+        let peer = state.resolve_host(host).await;
+        if !peer.supports(capabilities::USER_DELETION) {
+            // Older peers keep a stale user record until they learn of the
+            // deletion some other way
+            info!("Skipping user deletion on {host}: not supported by peer");
+            continue;
+        }
         let token_result = state.key_manager.issue_federation_jwt_delegated(
             state.config.api_url(),
-            api_url.clone(),
+            peer.api_url.clone(),
             user_ref.clone(),
         );
         match token_result {
             Ok(token) => {
                 let user_result = requests::users::federated::delete(
                     &state.http_client,
-                    &api_url,
+                    &peer.federation_url,
                     &token,
                     user_ref.clone(),
                 )
@@ -122,6 +306,14 @@ pub async fn delete_home_user(
     }
 
     queries::users::delete(&state.db_pool, user_ref.clone()).await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::UserDelete,
+        None,
+        AuditTarget::User(user_ref.clone()),
+    )
+    .await;
     Ok(())
 }
 
@@ -150,7 +342,8 @@ pub async fn delete_remote_user_record(
         ));
     }
 
-    let expected_home_server_url = get_api_url(&session_user_ref.host);
+    let expected_home_server_url =
+        state.api_url_for(&session_user_ref.host).await;
     let federation_claims = session.federation.as_ref().ok_or_else(|| {
         ApiError::AuthError("Federation claims required".into())
     })?;
@@ -162,6 +355,14 @@ pub async fn delete_remote_user_record(
     }
 
     queries::users::delete(&state.db_pool, user_ref.clone()).await?;
+    audit::record(
+        state,
+        Some(session),
+        AuditAction::UserDelete,
+        None,
+        AuditTarget::User(user_ref.clone()),
+    )
+    .await;
     Ok(())
 }
 
@@ -172,15 +373,13 @@ pub async fn get_associated_hosts(
     target_host: Option<&str>,
 ) -> ApiResult<Vec<String>> {
     if !state.config.is_remote_host(target_host) {
-        let hosts = queries::users::get_associated_hosts(
-            &state.db_pool,
-            &user_ref,
-        )
-        .await?;
+        let hosts =
+            queries::users::get_associated_hosts(&state.db_pool, &user_ref)
+                .await?;
         Ok(hosts)
     } else {
         let host = target_host.unwrap();
-        let api_url = get_api_url(host);
+        let api_url = state.api_url_for(host).await;
         let hosts = requests::users::fetch_associated_hosts(
             &state.http_client,
             &api_url,
@@ -210,11 +409,44 @@ pub mod auth {
         Req::User(user_ref).or_admin().client_only()
     }
 
+    pub fn update_profile(user_ref: UserRef) -> Req {
+        Req::User(user_ref).client_only()
+    }
+
     pub mod federated {
         use super::*;
 
+        /// Only a user's home host may update its cached copy.
+        pub fn sync(user_host: String) -> Req {
+            Req::FederatedHost(user_host).federated_only()
+        }
+
         pub fn delete(user_ref: UserRef) -> Req {
             Req::FederatedUser(user_ref).federated_only()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_profile_update_strips_bidi_overrides() {
+        let update = UserProfileUpdate {
+            display_name: Some(Some("\u{202e}nimda\u{202c}".into())),
+            ..Default::default()
+        };
+        let cleaned = clean_profile_update(&update).unwrap();
+        assert_eq!(cleaned.display_name, Some(Some("nimda".into())));
+    }
+
+    #[test]
+    fn test_clean_profile_update_rejects_non_web_avatar() {
+        let update = UserProfileUpdate {
+            avatar_url: Some(Some("javascript:alert(1)".into())),
+            ..Default::default()
+        };
+        assert!(clean_profile_update(&update).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Ephemeral presence and typing state, kept in memory only.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use runelink_types::{PresenceStatus, UserRef};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// How long a presence report holds. Clients report about every 30 seconds,
+/// so a user whose client disconnected goes offline within this time.
+pub const PRESENCE_TTL: Duration = Duration::from_secs(90);
+
+/// How long a typing event holds.
+pub const TYPING_TTL: Duration = Duration::from_secs(8);
+
+/// How often an unchanged presence is published to other hosts again, to
+/// keep it from expiring there.
+const REPUBLISH_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug)]
+struct PresenceEntry {
+    status: PresenceStatus,
+    expires_at: Instant,
+    published_at: Instant,
+}
+
+/// Who is online and who is typing where.
+#[derive(Debug, Default)]
+pub struct PresenceTracker {
+    presence: Mutex<HashMap<UserRef, PresenceEntry>>,
+    typing: Mutex<HashMap<(Uuid, UserRef), Instant>>,
+}
+
+impl PresenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a user's presence. Returns whether it should be published to
+    /// other hosts: when it changed, or when it was last published a while
+    /// ago.
+    pub fn set_presence(
+        &self,
+        user: &UserRef,
+        status: PresenceStatus,
+        now: Instant,
+    ) -> bool {
+        let mut presence = self.presence.lock().unwrap();
+        let previous = presence
+            .get(user)
+            .filter(|entry| entry.expires_at > now)
+            .copied();
+        if status == PresenceStatus::Offline {
+            presence.remove(user);
+            return previous.is_some();
+        }
+        let publish = match previous {
+            Some(entry) => {
+                entry.status != status
+                    || now.saturating_duration_since(entry.published_at)
+                        >= REPUBLISH_INTERVAL
+            }
+            None => true,
+        };
+        let published_at = match previous {
+            Some(entry) if !publish => entry.published_at,
+            _ => now,
+        };
+        presence.insert(
+            user.clone(),
+            PresenceEntry {
+                status,
+                expires_at: now + PRESENCE_TTL,
+                published_at,
+            },
+        );
+        publish
+    }
+
+    /// A user's presence; `Offline` unless reported recently.
+    pub fn presence(&self, user: &UserRef, now: Instant) -> PresenceStatus {
+        let presence = self.presence.lock().unwrap();
+        presence
+            .get(user)
+            .filter(|entry| entry.expires_at > now)
+            .map_or(PresenceStatus::Offline, |entry| entry.status)
+    }
+
+    /// Record that a user is typing in a channel.
+    pub fn set_typing(&self, channel_id: Uuid, user: &UserRef, now: Instant) {
+        let mut typing = self.typing.lock().unwrap();
+        typing.insert((channel_id, user.clone()), now + TYPING_TTL);
+    }
+
+    /// Stop showing a user as typing in a channel, such as once their
+    /// message is sent.
+    pub fn clear_typing(&self, channel_id: Uuid, user: &UserRef) {
+        let mut typing = self.typing.lock().unwrap();
+        typing.remove(&(channel_id, user.clone()));
+    }
+
+    /// The users typing in a channel, sorted.
+    pub fn typing(&self, channel_id: Uuid, now: Instant) -> Vec<UserRef> {
+        let typing = self.typing.lock().unwrap();
+        let mut users: Vec<UserRef> = typing
+            .iter()
+            .filter(|((channel, _), expires_at)| {
+                *channel == channel_id && **expires_at > now
+            })
+            .map(|((_, user), _)| user.clone())
+            .collect();
+        users.sort_by(|a, b| (&a.host, &a.name).cmp(&(&b.host, &b.name)));
+        users
+    }
+
+    /// Forget expired presence and typing events.
+    pub fn prune(&self, now: Instant) {
+        self.presence
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.expires_at > now);
+        self.typing
+            .lock()
+            .unwrap()
+            .retain(|_, expires_at| *expires_at > now);
+    }
+}
+
+/// Spawn a task that periodically forgets expired presence state.
+pub fn spawn_pruner(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            state.presence.prune(Instant::now());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str) -> UserRef {
+        UserRef::new(name.into(), "example.com".into())
+    }
+
+    #[test]
+    fn test_presence_expires() {
+        let tracker = PresenceTracker::new();
+        let now = Instant::now();
+        let alice = user("alice");
+        assert_eq!(tracker.presence(&alice, now), PresenceStatus::Offline);
+        tracker.set_presence(&alice, PresenceStatus::Idle, now);
+        assert_eq!(tracker.presence(&alice, now), PresenceStatus::Idle);
+        let later = now + PRESENCE_TTL;
+        assert_eq!(tracker.presence(&alice, later), PresenceStatus::Offline);
+        tracker.prune(later);
+        assert!(tracker.presence.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_presence_publishes_changes_and_refreshes() {
+        let tracker = PresenceTracker::new();
+        let now = Instant::now();
+        let alice = user("alice");
+        assert!(tracker.set_presence(&alice, PresenceStatus::Online, now));
+        let soon = now + Duration::from_secs(5);
+        assert!(!tracker.set_presence(&alice, PresenceStatus::Online, soon));
+        assert!(tracker.set_presence(&alice, PresenceStatus::Idle, soon));
+        let later = soon + REPUBLISH_INTERVAL;
+        assert!(tracker.set_presence(&alice, PresenceStatus::Idle, later));
+        assert!(tracker.set_presence(&alice, PresenceStatus::Offline, later));
+        assert!(!tracker.set_presence(&alice, PresenceStatus::Offline, later));
+    }
+
+    #[test]
+    fn test_typing_is_per_channel_and_expires() {
+        let tracker = PresenceTracker::new();
+        let now = Instant::now();
+        let (general, random) = (Uuid::new_v4(), Uuid::new_v4());
+        tracker.set_typing(general, &user("bob"), now);
+        tracker.set_typing(general, &user("alice"), now);
+        tracker.set_typing(random, &user("carol"), now);
+        assert_eq!(
+            tracker.typing(general, now),
+            vec![user("alice"), user("bob")]
+        );
+        tracker.clear_typing(general, &user("bob"));
+        assert_eq!(tracker.typing(general, now), vec![user("alice")]);
+        assert!(tracker.typing(general, now + TYPING_TTL).is_empty());
+        assert_eq!(tracker.typing(random, now), vec![user("carol")]);
+    }
+}
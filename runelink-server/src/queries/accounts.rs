@@ -1,22 +1,28 @@
-use runelink_types::{LocalAccount, UserRef};
+use runelink_types::{LocalAccount, User, UserRef, UserRole};
 
-use crate::{db::DbPool, error::ApiResult};
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiResult},
+};
 
 pub async fn insert(
     pool: &DbPool,
     user: UserRef,
     password_hash: &str,
+    approved: bool,
 ) -> ApiResult<LocalAccount> {
     let local_account = sqlx::query_as!(
         LocalAccount,
         r#"
-        INSERT INTO local_accounts (user_name, user_host, password_hash)
-        VALUES ($1, $2, $3)
-        RETURNING user_name, user_host, password_hash, created_at, updated_at;
+        INSERT INTO local_accounts (user_name, user_host, password_hash, approved)
+        VALUES ($1, $2, $3, $4)
+        RETURNING user_name, user_host, password_hash, approved, created_at,
+                  updated_at;
         "#,
         user.name,
         user.host,
         password_hash,
+        approved,
     )
     .fetch_one(pool)
     .await?;
@@ -30,7 +36,8 @@ pub async fn get_by_user(
     let local_account = sqlx::query_as!(
         LocalAccount,
         r#"
-        SELECT user_name, user_host, password_hash, created_at, updated_at
+        SELECT user_name, user_host, password_hash, approved, created_at,
+               updated_at
         FROM local_accounts
         WHERE user_name = $1 AND user_host = $2;
         "#,
@@ -42,6 +49,51 @@ pub async fn get_by_user(
     Ok(local_account)
 }
 
+/// Users whose accounts are waiting for host admin approval.
+pub async fn get_pending(pool: &DbPool) -> ApiResult<Vec<User>> {
+    let users = sqlx::query_as!(
+        User,
+        r#"
+        SELECT
+            u.name,
+            u.host,
+            u.role AS "role: UserRole",
+            u.created_at,
+            u.updated_at,
+            u.synced_at,
+            u.display_name,
+            u.bio,
+            u.avatar_url,
+            u.status_text
+        FROM local_accounts a
+        JOIN users u ON u.name = a.user_name AND u.host = a.user_host
+        WHERE NOT a.approved
+        ORDER BY a.created_at;
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(users)
+}
+
+pub async fn approve(pool: &DbPool, user: UserRef) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE local_accounts
+        SET approved = TRUE
+        WHERE user_name = $1 AND user_host = $2 AND NOT approved;
+        "#,
+        user.name,
+        user.host,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub async fn delete_by_user(pool: &DbPool, user: UserRef) -> ApiResult<()> {
     sqlx::query!(
@@ -53,3 +105,26 @@ pub async fn delete_by_user(pool: &DbPool, user: UserRef) -> ApiResult<()> {
     .await?;
     Ok(())
 }
+
+pub async fn update_password(
+    pool: &DbPool,
+    user: UserRef,
+    password_hash: &str,
+) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE local_accounts
+        SET password_hash = $3
+        WHERE user_name = $1 AND user_host = $2;
+        "#,
+        user.name,
+        user.host,
+        password_hash,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
@@ -0,0 +1,427 @@
+use runelink_types::{
+    ArchivedMember, ArchivedPin, Attachment, Channel, ChannelCategory, Message,
+    RetentionPolicy, Server, ServerBan, ServerRole, ServerTimeout, User,
+    UserRef,
+};
+use sqlx::{PgConnection, types::Json};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult, queries::messages::DbMessage};
+
+/// The members of a local server, longest-standing first.
+pub async fn get_members(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<Vec<ArchivedMember>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT user_name, user_host, role AS "role: ServerRole", created_at
+        FROM server_users
+        WHERE server_id = $1
+        ORDER BY created_at, user_host, user_name;
+        "#,
+        server_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| ArchivedMember {
+            user: UserRef::new(row.user_name, row.user_host),
+            role: row.role,
+            joined_at: row.created_at,
+        })
+        .collect())
+}
+
+/// The pins of a local server's messages, leaving out those of deleted
+/// messages and messages of trashed channels.
+pub async fn get_pins(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<Vec<ArchivedPin>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT p.message_id, p.pinned_by_name, p.pinned_by_host, p.pinned_at
+        FROM message_pins p
+        JOIN messages m ON m.id = p.message_id
+        JOIN channels c ON c.id = p.channel_id
+        WHERE c.server_id = $1
+            AND c.deleted_at IS NULL
+            AND m.deleted_at IS NULL
+        ORDER BY p.pinned_at;
+        "#,
+        server_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| ArchivedPin {
+            message_id: row.message_id,
+            pinned_by: UserRef::new(row.pinned_by_name, row.pinned_by_host),
+            pinned_at: row.pinned_at,
+        })
+        .collect())
+}
+
+/// One page of a server's messages, oldest first, starting after the
+/// message with the given creation time and ID. Deleted messages and
+/// messages of trashed channels are left out.
+pub async fn get_message_page(
+    pool: &DbPool,
+    server_id: Uuid,
+    after: Option<(OffsetDateTime, Uuid)>,
+    limit: i64,
+) -> ApiResult<Vec<Message>> {
+    let (after_created_at, after_id) = after.unzip();
+    let rows = sqlx::query_as!(
+        DbMessage,
+        r#"
+        SELECT
+            m.id,
+            m.channel_id,
+            m.body,
+            m.created_at,
+            m.updated_at,
+            m.deleted_at,
+            m.deleted_by_name,
+            m.deleted_by_host,
+            to_jsonb(a) AS "author: Json<User>",
+            COALESCE(
+                (
+                    SELECT jsonb_agg(
+                        to_jsonb(att) - ARRAY[
+                            'channel_id', 'uploader_name', 'uploader_host'
+                        ]
+                        ORDER BY att.created_at
+                    )
+                    FROM attachments att
+                    WHERE att.message_id = m.id
+                ),
+                '[]'
+            ) AS "attachments!: Json<Vec<Attachment>>"
+        FROM messages m
+        LEFT JOIN users a ON a.name = m.author_name AND a.host = m.author_host
+        JOIN channels c ON c.id = m.channel_id
+        WHERE c.server_id = $1
+            AND c.deleted_at IS NULL
+            AND m.deleted_at IS NULL
+            AND ($2::TIMESTAMPTZ IS NULL OR (m.created_at, m.id) > ($2, $3))
+        ORDER BY m.created_at, m.id
+        LIMIT $4;
+        "#,
+        server_id,
+        after_created_at,
+        after_id,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(Message::from).collect())
+}
+
+/// The `insert_*` functions give the new row the ID passed in, or a new ID
+/// when there is none.
+pub async fn insert_server(
+    conn: &mut PgConnection,
+    id: Option<Uuid>,
+    server: &Server,
+) -> ApiResult<Uuid> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO servers (id, title, description, created_at)
+        VALUES (COALESCE($1, gen_random_uuid()), $2, $3, $4)
+        RETURNING id;
+        "#,
+        id,
+        server.title,
+        server.description,
+        server.created_at,
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(id)
+}
+
+pub async fn insert_category(
+    conn: &mut PgConnection,
+    id: Option<Uuid>,
+    server_id: Uuid,
+    category: &ChannelCategory,
+) -> ApiResult<Uuid> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO channel_categories (
+            id, server_id, title, position, created_at
+        )
+        VALUES (COALESCE($1, gen_random_uuid()), $2, $3, $4, $5)
+        RETURNING id;
+        "#,
+        id,
+        server_id,
+        category.title,
+        category.position,
+        category.created_at,
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(id)
+}
+
+pub async fn insert_channel(
+    conn: &mut PgConnection,
+    id: Option<Uuid>,
+    server_id: Uuid,
+    category_id: Option<Uuid>,
+    channel: &Channel,
+) -> ApiResult<Uuid> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO channels (
+            id, server_id, category_id, title, description, position,
+            created_at, archived_at
+        )
+        VALUES (COALESCE($1, gen_random_uuid()), $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id;
+        "#,
+        id,
+        server_id,
+        category_id,
+        channel.title,
+        channel.description,
+        channel.position,
+        channel.created_at,
+        channel.archived_at,
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(id)
+}
+
+pub async fn user_exists(
+    conn: &mut PgConnection,
+    user_ref: &UserRef,
+) -> ApiResult<bool> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM users WHERE name = $1 AND host = $2
+        ) AS "exists!";
+        "#,
+        user_ref.name,
+        user_ref.host,
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(exists)
+}
+
+/// Make sure a user of another host is known, so messages can be
+/// attributed to them.
+pub async fn ensure_remote_user(
+    conn: &mut PgConnection,
+    user_ref: &UserRef,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO users (name, host, role)
+        VALUES ($1, $2, 'user')
+        ON CONFLICT (name, host) DO NOTHING;
+        "#,
+        user_ref.name,
+        user_ref.host,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_member(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+    member: &ArchivedMember,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO server_users (
+            user_name, user_host, server_id, role, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_name, user_host, server_id) DO NOTHING;
+        "#,
+        member.user.name,
+        member.user.host,
+        server_id,
+        member.role as ServerRole,
+        member.joined_at,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Make `user_ref` the owner of a server that has none, adding them as a
+/// member if needed.
+pub async fn ensure_owner(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+    user_ref: &UserRef,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO server_users (user_name, user_host, server_id, role)
+        SELECT $1, $2, $3, 'owner'
+        WHERE NOT EXISTS (
+            SELECT 1 FROM server_users WHERE server_id = $3 AND role = 'owner'
+        )
+        ON CONFLICT (user_name, user_host, server_id)
+            DO UPDATE SET role = 'owner';
+        "#,
+        user_ref.name,
+        user_ref.host,
+        server_id,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_ban(
+    conn: &mut PgConnection,
+    id: Option<Uuid>,
+    server_id: Uuid,
+    created_by: Option<&UserRef>,
+    ban: &ServerBan,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO server_bans (
+            id, server_id, user_name, user_host, reason, created_by_name,
+            created_by_host, created_at, expires_at
+        )
+        VALUES (
+            COALESCE($1, gen_random_uuid()), $2, $3, $4, $5, $6, $7, $8, $9
+        )
+        ON CONFLICT DO NOTHING;
+        "#,
+        id,
+        server_id,
+        ban.user_name,
+        ban.user_host,
+        ban.reason,
+        created_by.map(|u| u.name.as_str()),
+        created_by.map(|u| u.host.as_str()),
+        ban.created_at,
+        ban.expires_at,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_timeout(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+    created_by: Option<&UserRef>,
+    timeout: &ServerTimeout,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO server_timeouts (
+            server_id, user_name, user_host, reason, created_by_name,
+            created_by_host, created_at, until
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (server_id, user_name, user_host) DO NOTHING;
+        "#,
+        server_id,
+        timeout.user_name,
+        timeout.user_host,
+        timeout.reason,
+        created_by.map(|u| u.name.as_str()),
+        created_by.map(|u| u.host.as_str()),
+        timeout.created_at,
+        timeout.until,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_retention(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+    channel_id: Option<Uuid>,
+    policy: &RetentionPolicy,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO retention_policies (
+            server_id, channel_id, max_age_days, max_messages, created_at
+        )
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (server_id, channel_id) DO NOTHING;
+        "#,
+        server_id,
+        channel_id,
+        policy.max_age_days,
+        policy.max_messages,
+        policy.created_at,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_pin(
+    conn: &mut PgConnection,
+    message_id: Uuid,
+    channel_id: Uuid,
+    pin: &ArchivedPin,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO message_pins (
+            message_id, channel_id, pinned_by_name, pinned_by_host, pinned_at
+        )
+        VALUES ($1, $2, $3, $4, $5);
+        "#,
+        message_id,
+        channel_id,
+        pin.pinned_by.name,
+        pin.pinned_by.host,
+        pin.pinned_at,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_message(
+    conn: &mut PgConnection,
+    id: Option<Uuid>,
+    channel_id: Uuid,
+    author: Option<&UserRef>,
+    message: &Message,
+) -> ApiResult<Uuid> {
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO messages (
+            id, channel_id, author_name, author_host, body, created_at
+        )
+        VALUES (COALESCE($1, gen_random_uuid()), $2, $3, $4, $5, $6)
+        RETURNING id;
+        "#,
+        id,
+        channel_id,
+        author.map(|a| a.name.as_str()),
+        author.map(|a| a.host.as_str()),
+        message.body,
+        message.created_at,
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(id)
+}
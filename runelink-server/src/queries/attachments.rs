@@ -0,0 +1,227 @@
+use runelink_types::{Attachment, UserRef};
+use sqlx::PgConnection;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+#[derive(Debug)]
+struct DbAttachment {
+    id: Uuid,
+    channel_id: Option<Uuid>,
+    message_id: Option<Uuid>,
+    uploader_name: String,
+    uploader_host: String,
+    name: String,
+    mime_type: String,
+    size: i64,
+    sha256: String,
+    created_at: OffsetDateTime,
+}
+
+/// An attachment along with where it was uploaded and by whom.
+#[derive(Clone, Debug)]
+pub struct StoredAttachment {
+    pub attachment: Attachment,
+    /// `None` once the channel is gone
+    pub channel_id: Option<Uuid>,
+    pub uploader: UserRef,
+}
+
+impl From<DbAttachment> for StoredAttachment {
+    fn from(row: DbAttachment) -> Self {
+        StoredAttachment {
+            attachment: Attachment {
+                id: row.id,
+                message_id: row.message_id,
+                name: row.name,
+                mime_type: row.mime_type,
+                size: row.size,
+                sha256: row.sha256,
+                created_at: row.created_at,
+            },
+            channel_id: row.channel_id,
+            uploader: UserRef::new(row.uploader_name, row.uploader_host),
+        }
+    }
+}
+
+pub async fn insert(
+    conn: &mut PgConnection,
+    channel_id: Uuid,
+    uploader: &UserRef,
+    name: &str,
+    mime_type: &str,
+    size: i64,
+    sha256: &str,
+) -> ApiResult<Attachment> {
+    let attachment = sqlx::query_as!(
+        Attachment,
+        r#"
+        INSERT INTO attachments (channel_id, uploader_name, uploader_host,
+                                 name, mime_type, size, sha256)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, message_id, name, mime_type, size, sha256, created_at;
+        "#,
+        channel_id,
+        uploader.name,
+        uploader.host,
+        name,
+        mime_type,
+        size,
+        sha256,
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(attachment)
+}
+
+/// Get an attachment by ID. Attachments of deleted messages are not found.
+pub async fn get_by_id(
+    pool: &DbPool,
+    attachment_id: Uuid,
+) -> ApiResult<StoredAttachment> {
+    let row = sqlx::query_as!(
+        DbAttachment,
+        r#"
+        SELECT a.id, a.channel_id, a.message_id, a.uploader_name,
+               a.uploader_host, a.name, a.mime_type, a.size, a.sha256,
+               a.created_at
+        FROM attachments a
+        LEFT JOIN messages m ON m.id = a.message_id
+        WHERE a.id = $1 AND m.deleted_at IS NULL;
+        "#,
+        attachment_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(row.into())
+}
+
+/// Hold off other uploads by `uploader` until the transaction ends, so that
+/// their quota is checked against a total that can't change underneath.
+pub async fn lock_uploader(
+    conn: &mut PgConnection,
+    uploader: &UserRef,
+) -> ApiResult<()> {
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtextextended($1, 0));",
+        format!("attachments:{uploader}"),
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Total size of everything `uploader` has uploaded, in bytes.
+pub async fn total_size_by_uploader(
+    conn: &mut PgConnection,
+    uploader: &UserRef,
+) -> ApiResult<i64> {
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(size), 0)::BIGINT AS "total!"
+        FROM attachments
+        WHERE uploader_name = $1 AND uploader_host = $2;
+        "#,
+        uploader.name,
+        uploader.host,
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(total)
+}
+
+/// Whether anything was uploaded to a channel of a server.
+pub async fn exists_in_server(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<bool> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM attachments a
+            JOIN channels c ON c.id = a.channel_id
+            WHERE c.server_id = $1
+        ) AS "exists!";
+        "#,
+        server_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(exists)
+}
+
+/// Send attachments with a message. Only attachments that were uploaded to
+/// the channel by `uploader` after `uploaded_after` and were never sent with
+/// a message are affected; returns how many were.
+pub async fn attach(
+    conn: &mut PgConnection,
+    message_id: Uuid,
+    channel_id: Uuid,
+    uploader: &UserRef,
+    attachment_ids: &[Uuid],
+    uploaded_after: OffsetDateTime,
+) -> ApiResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE attachments
+        SET message_id = $1
+        WHERE id = ANY($2)
+            AND channel_id = $3
+            AND uploader_name = $4
+            AND uploader_host = $5
+            AND message_id IS NULL
+            AND created_at > $6;
+        "#,
+        message_id,
+        attachment_ids,
+        channel_id,
+        uploader.name,
+        uploader.host,
+        uploaded_after,
+    )
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Up to `limit` attachments that are not part of any message and were
+/// uploaded before `cutoff`: abandoned uploads, and attachments whose
+/// message was purged.
+pub async fn get_unattached_before(
+    pool: &DbPool,
+    cutoff: OffsetDateTime,
+    limit: i64,
+) -> ApiResult<Vec<Uuid>> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM attachments
+        WHERE message_id IS NULL AND created_at < $1
+        ORDER BY created_at
+        LIMIT $2;
+        "#,
+        cutoff,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids)
+}
+
+/// Delete an attachment unless it was sent with a message in the meantime.
+/// Returns whether it was deleted.
+pub async fn delete_unattached(
+    pool: &DbPool,
+    attachment_id: Uuid,
+) -> ApiResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM attachments WHERE id = $1 AND message_id IS NULL;",
+        attachment_id,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
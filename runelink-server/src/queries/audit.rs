@@ -0,0 +1,70 @@
+use runelink_types::{AuditEntry, AuditLogQuery, UserRef};
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+pub const DEFAULT_LIMIT: i64 = 50;
+pub const MAX_LIMIT: i64 = 500;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn insert(
+    pool: &DbPool,
+    actor: Option<&UserRef>,
+    issuer_host: Option<&str>,
+    action: &str,
+    server_id: Option<Uuid>,
+    target_kind: &str,
+    target_id: &str,
+    detail: Option<&str>,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor_name, actor_host, issuer_host, action,
+                               server_id, target_kind, target_id, detail)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8);
+        "#,
+        actor.map(|a| a.name.as_str()),
+        actor.map(|a| a.host.as_str()),
+        issuer_host,
+        action,
+        server_id,
+        target_kind,
+        target_id,
+        detail,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Entries matching `query`, newest first. Restricted to one server if
+/// `server_id` is given.
+pub async fn get(
+    pool: &DbPool,
+    server_id: Option<Uuid>,
+    query: &AuditLogQuery,
+) -> ApiResult<Vec<AuditEntry>> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let entries = sqlx::query_as!(
+        AuditEntry,
+        r#"
+        SELECT id, actor_name, actor_host, issuer_host, action, server_id,
+               target_kind, target_id, created_at, detail
+        FROM audit_log
+        WHERE ($1::UUID IS NULL OR server_id = $1)
+            AND ($2::TEXT IS NULL OR action = $2)
+            AND ($3::TEXT IS NULL OR actor_host = $3)
+            AND ($4::BIGINT IS NULL OR id < $4)
+        ORDER BY id DESC
+        LIMIT $5;
+        "#,
+        server_id,
+        query.action,
+        query.actor_host,
+        query.before,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(entries)
+}
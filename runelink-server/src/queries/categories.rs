@@ -0,0 +1,119 @@
+use runelink_types::{ChannelCategory, NewChannelCategory};
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+pub async fn insert(
+    pool: &DbPool,
+    server_id: Uuid,
+    new_category: &NewChannelCategory,
+) -> ApiResult<ChannelCategory> {
+    let category = sqlx::query_as!(
+        ChannelCategory,
+        r#"
+        INSERT INTO channel_categories (server_id, title, position)
+        VALUES (
+            $1, $2,
+            (
+                SELECT COALESCE(MAX(position) + 1, 0)
+                FROM channel_categories
+                WHERE server_id = $1
+            )
+        )
+        RETURNING id, server_id, title, position, created_at, updated_at;
+        "#,
+        server_id,
+        new_category.title,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(category)
+}
+
+pub async fn get_by_id(
+    pool: &DbPool,
+    category_id: Uuid,
+) -> ApiResult<ChannelCategory> {
+    let category = sqlx::query_as!(
+        ChannelCategory,
+        r#"
+        SELECT id, server_id, title, position, created_at, updated_at
+        FROM channel_categories
+        WHERE id = $1;
+        "#,
+        category_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(category)
+}
+
+pub async fn get_by_server(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<Vec<ChannelCategory>> {
+    let categories = sqlx::query_as!(
+        ChannelCategory,
+        r#"
+        SELECT id, server_id, title, position, created_at, updated_at
+        FROM channel_categories
+        WHERE server_id = $1
+        ORDER BY position, created_at;
+        "#,
+        server_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(categories)
+}
+
+/// Rename a category; `title: None` keeps the current title.
+pub async fn update(
+    pool: &DbPool,
+    category_id: Uuid,
+    title: Option<&str>,
+) -> ApiResult<ChannelCategory> {
+    let category = sqlx::query_as!(
+        ChannelCategory,
+        r#"
+        UPDATE channel_categories
+        SET title = COALESCE($2, title)
+        WHERE id = $1
+        RETURNING id, server_id, title, position, created_at, updated_at;
+        "#,
+        category_id,
+        title,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(category)
+}
+
+/// Renumber a server's categories to follow `ordered_ids`, starting at 0.
+pub async fn set_positions(
+    pool: &DbPool,
+    server_id: Uuid,
+    ordered_ids: &[Uuid],
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE channel_categories c
+        SET position = (o.pos - 1)::INTEGER
+        FROM unnest($2::uuid[]) WITH ORDINALITY AS o(id, pos)
+        WHERE c.id = o.id AND c.server_id = $1;
+        "#,
+        server_id,
+        ordered_ids,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Delete a category. Its channels stay in the server, uncategorized.
+pub async fn delete(pool: &DbPool, category_id: Uuid) -> ApiResult<()> {
+    sqlx::query!("DELETE FROM channel_categories WHERE id = $1;", category_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
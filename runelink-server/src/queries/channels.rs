@@ -1,4 +1,5 @@
 use runelink_types::{Channel, NewChannel};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{db::DbPool, error::ApiResult};
@@ -11,8 +12,15 @@ pub async fn insert(
     let channel = sqlx::query_as!(
         Channel,
         r#"
-        INSERT INTO channels (server_id, title, description)
-        VALUES ($1, $2, $3)
+        INSERT INTO channels (server_id, title, description, position)
+        VALUES (
+            $1, $2, $3,
+            (
+                SELECT COALESCE(MAX(position) + 1, 0)
+                FROM channels
+                WHERE server_id = $1
+            )
+        )
         RETURNING *;
         "#,
         server_id,
@@ -27,7 +35,7 @@ pub async fn insert(
 pub async fn get_by_id(pool: &DbPool, channel_id: Uuid) -> ApiResult<Channel> {
     let channel = sqlx::query_as!(
         Channel,
-        "SELECT * FROM channels WHERE id = $1;",
+        "SELECT * FROM channels WHERE id = $1 AND deleted_at IS NULL;",
         channel_id,
     )
     .fetch_one(pool)
@@ -36,33 +44,166 @@ pub async fn get_by_id(pool: &DbPool, channel_id: Uuid) -> ApiResult<Channel> {
 }
 
 pub async fn get_all(pool: &DbPool) -> ApiResult<Vec<Channel>> {
-    let channels = sqlx::query_as!(Channel, "SELECT * FROM channels")
-        .fetch_all(pool)
-        .await?;
+    let channels = sqlx::query_as!(
+        Channel,
+        "SELECT * FROM channels WHERE deleted_at IS NULL"
+    )
+    .fetch_all(pool)
+    .await?;
     Ok(channels)
 }
 
+/// Channels of a server in order, leaving out archived channels unless
+/// `include_archived`.
 pub async fn get_by_server(
     pool: &DbPool,
     server_id: Uuid,
+    include_archived: bool,
 ) -> ApiResult<Vec<Channel>> {
     let channels = sqlx::query_as!(
         Channel,
         r#"
         SELECT * FROM channels
         WHERE server_id = $1
-        ORDER BY created_at;
+            AND deleted_at IS NULL
+            AND ($2 OR archived_at IS NULL)
+        ORDER BY position, created_at;
         "#,
         server_id,
+        include_archived,
     )
     .fetch_all(pool)
     .await?;
     Ok(channels)
 }
 
+/// Update a channel's title, description and category. `title: None` keeps
+/// the current title; the description and category are only touched when
+/// `set_description` and `set_category` are set.
+pub async fn update(
+    pool: &DbPool,
+    channel_id: Uuid,
+    title: Option<&str>,
+    set_description: bool,
+    description: Option<&str>,
+    set_category: bool,
+    category_id: Option<Uuid>,
+) -> ApiResult<Channel> {
+    let channel = sqlx::query_as!(
+        Channel,
+        r#"
+        UPDATE channels
+        SET title = COALESCE($2, title),
+            description = CASE WHEN $3 THEN $4 ELSE description END,
+            category_id = CASE WHEN $5 THEN $6 ELSE category_id END
+        WHERE id = $1
+        RETURNING *;
+        "#,
+        channel_id,
+        title,
+        set_description,
+        description,
+        set_category,
+        category_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(channel)
+}
+
+/// Renumber a server's channels to follow `ordered_ids`, starting at 0.
+pub async fn set_positions(
+    pool: &DbPool,
+    server_id: Uuid,
+    ordered_ids: &[Uuid],
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE channels c
+        SET position = (o.pos - 1)::INTEGER
+        FROM unnest($2::uuid[]) WITH ORDINALITY AS o(id, pos)
+        WHERE c.id = o.id AND c.server_id = $1;
+        "#,
+        server_id,
+        ordered_ids,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Archive or unarchive a channel.
+pub async fn set_archived(
+    pool: &DbPool,
+    channel_id: Uuid,
+    archived: bool,
+) -> ApiResult<Channel> {
+    let channel = sqlx::query_as!(
+        Channel,
+        r#"
+        UPDATE channels
+        SET archived_at = CASE
+                WHEN $2 THEN COALESCE(archived_at, NOW())
+                ELSE NULL
+            END
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING *;
+        "#,
+        channel_id,
+        archived,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(channel)
+}
+
+/// Move a channel to the trash. It is purged for good by
+/// [`purge_deleted`] once the trash period has passed.
 pub async fn delete(pool: &DbPool, channel_id: Uuid) -> ApiResult<()> {
-    sqlx::query!("DELETE FROM channels WHERE id = $1;", channel_id)
-        .execute(pool)
-        .await?;
+    sqlx::query!(
+        r#"
+        UPDATE channels
+        SET deleted_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL;
+        "#,
+        channel_id,
+    )
+    .execute(pool)
+    .await?;
     Ok(())
 }
+
+/// Take a channel of the given server back out of the trash.
+pub async fn restore(
+    pool: &DbPool,
+    server_id: Uuid,
+    channel_id: Uuid,
+) -> ApiResult<Channel> {
+    let channel = sqlx::query_as!(
+        Channel,
+        r#"
+        UPDATE channels
+        SET deleted_at = NULL
+        WHERE id = $1 AND server_id = $2 AND deleted_at IS NOT NULL
+        RETURNING *;
+        "#,
+        channel_id,
+        server_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(channel)
+}
+
+/// Delete channels (and with them their messages) that were moved to the
+/// trash before `cutoff`. Returns the number of channels purged.
+pub async fn purge_deleted(
+    pool: &DbPool,
+    cutoff: OffsetDateTime,
+) -> ApiResult<u64> {
+    let result =
+        sqlx::query!("DELETE FROM channels WHERE deleted_at < $1;", cutoff,)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,95 @@
+use runelink_types::{SignupInvite, UserRef};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiResult},
+};
+
+pub async fn insert(
+    pool: &DbPool,
+    token_hash: &str,
+    created_by: &UserRef,
+    note: Option<&str>,
+    max_uses: Option<i32>,
+    expires_at: Option<OffsetDateTime>,
+) -> ApiResult<SignupInvite> {
+    let invite = sqlx::query_as!(
+        SignupInvite,
+        r#"
+        INSERT INTO signup_invites (token_hash, created_by_name, created_by_host,
+                                    note, max_uses, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, note, max_uses, uses, created_at, expires_at;
+        "#,
+        token_hash,
+        created_by.name,
+        created_by.host,
+        note,
+        max_uses,
+        expires_at,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(invite)
+}
+
+pub async fn get_all(pool: &DbPool) -> ApiResult<Vec<SignupInvite>> {
+    let invites = sqlx::query_as!(
+        SignupInvite,
+        r#"
+        SELECT id, note, max_uses, uses, created_at, expires_at
+        FROM signup_invites
+        ORDER BY created_at DESC;
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(invites)
+}
+
+/// Count one use of a valid invite, returning it. Fails with `NotFound` if the
+/// invite doesn't exist, has expired or is used up.
+pub async fn redeem(
+    pool: &DbPool,
+    token_hash: &str,
+) -> ApiResult<SignupInvite> {
+    let invite = sqlx::query_as!(
+        SignupInvite,
+        r#"
+        UPDATE signup_invites
+        SET uses = uses + 1
+        WHERE token_hash = $1
+            AND (expires_at IS NULL OR expires_at > NOW())
+            AND (max_uses IS NULL OR uses < max_uses)
+        RETURNING id, note, max_uses, uses, created_at, expires_at;
+        "#,
+        token_hash,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(invite)
+}
+
+/// Give back a use taken by [`redeem`] when the signup fails afterwards.
+pub async fn release(pool: &DbPool, invite_id: Uuid) -> ApiResult<()> {
+    sqlx::query!(
+        "UPDATE signup_invites SET uses = uses - 1 WHERE id = $1 AND uses > 0;",
+        invite_id,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete(pool: &DbPool, invite_id: Uuid) -> ApiResult<()> {
+    let result =
+        sqlx::query!("DELETE FROM signup_invites WHERE id = $1;", invite_id)
+            .execute(pool)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
@@ -444,3 +444,155 @@ pub async fn delete_remote(
     }
     Ok(())
 }
+
+/// Delete the local memberships of `user_host` users in a server, or of
+/// `user_name` alone when given. Returns the users removed.
+pub async fn delete_local_matching(
+    pool: &DbPool,
+    server_id: Uuid,
+    user_name: Option<&str>,
+    user_host: &str,
+) -> ApiResult<Vec<UserRef>> {
+    let rows = sqlx::query!(
+        r#"
+        DELETE FROM server_users
+        WHERE server_id = $1
+            AND user_host = $3
+            AND ($2::TEXT IS NULL OR user_name = $2)
+        RETURNING user_name, user_host;
+        "#,
+        server_id,
+        user_name,
+        user_host,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| UserRef::new(row.user_name, row.user_host))
+        .collect())
+}
+
+/// Change the role of a local server membership.
+pub async fn update_local_role(
+    pool: &DbPool,
+    server_id: Uuid,
+    user: &UserRef,
+    role: ServerRole,
+) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE server_users
+        SET role = $4
+        WHERE server_id = $1 AND user_name = $2 AND user_host = $3
+        "#,
+        server_id,
+        user.name,
+        user.host,
+        role as ServerRole,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+/// Number of members of a local server with admin rights, owner included.
+pub async fn count_local_admins(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM server_users
+        WHERE server_id = $1 AND role IN ('admin', 'owner')
+        "#,
+        server_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+pub async fn get_local_owner(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<Option<UserRef>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT user_name, user_host
+        FROM server_users
+        WHERE server_id = $1 AND role = 'owner'
+        "#,
+        server_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| UserRef::new(row.user_name, row.user_host)))
+}
+
+/// Make `new_owner` the owner of a local server, demoting the current owner
+/// (if any) to admin. Fails with `NotFound` if `new_owner` isn't a member.
+pub async fn transfer_local_ownership(
+    pool: &DbPool,
+    server_id: Uuid,
+    new_owner: &UserRef,
+) -> ApiResult<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        UPDATE server_users
+        SET role = 'admin'
+        WHERE server_id = $1 AND role = 'owner'
+        "#,
+        server_id,
+    )
+    .execute(&mut *tx)
+    .await?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE server_users
+        SET role = 'owner'
+        WHERE server_id = $1 AND user_name = $2 AND user_host = $3
+        "#,
+        server_id,
+        new_owner.name,
+        new_owner.host,
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Bring a cached remote membership in line with the server's host. Fails
+/// with `NotFound` if the membership isn't cached.
+pub async fn update_remote(
+    pool: &DbPool,
+    membership: &ServerMembership,
+) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE user_remote_server_memberships
+        SET role = $4, remote_updated_at = $5, synced_at = NOW()
+        WHERE user_name = $1 AND user_host = $2 AND remote_server_id = $3
+        "#,
+        membership.user_ref.name,
+        membership.user_ref.host,
+        membership.server.id,
+        membership.role as ServerRole,
+        membership.updated_at,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
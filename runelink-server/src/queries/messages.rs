@@ -1,6 +1,6 @@
-use runelink_types::{Message, NewMessage, User};
+use runelink_types::{Attachment, Message, NewMessage, User, UserRef};
 use serde::{Deserialize, Serialize};
-use sqlx::types::Json;
+use sqlx::{PgConnection, types::Json};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
@@ -16,10 +16,19 @@ pub struct DbMessage {
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
     pub updated_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
+    pub deleted_by_name: Option<String>,
+    pub deleted_by_host: Option<String>,
+    pub attachments: Json<Vec<Attachment>>,
 }
 
 impl From<DbMessage> for Message {
     fn from(msg: DbMessage) -> Self {
+        let deleted_by = match (msg.deleted_by_name, msg.deleted_by_host) {
+            (Some(name), Some(host)) => Some(UserRef::new(name, host)),
+            _ => None,
+        };
         Message {
             id: msg.id,
             channel_id: msg.channel_id,
@@ -27,15 +36,19 @@ impl From<DbMessage> for Message {
             body: msg.body,
             created_at: msg.created_at,
             updated_at: msg.updated_at,
+            deleted_at: msg.deleted_at,
+            deleted_by,
+            attachments: msg.attachments.0,
         }
     }
 }
 
+/// Insert a message, returning its ID.
 pub async fn insert(
-    pool: &DbPool,
+    conn: &mut PgConnection,
     channel_id: Uuid,
     new_message: &NewMessage,
-) -> ApiResult<Message> {
+) -> ApiResult<Uuid> {
     let new_id: Uuid = sqlx::query_scalar!(
         r#"
         INSERT INTO messages (channel_id, author_name, author_host, body)
@@ -47,10 +60,9 @@ pub async fn insert(
         new_message.author.host,
         new_message.body,
     )
-    .fetch_one(pool)
+    .fetch_one(conn)
     .await?;
-    let message = get_by_id(pool, new_id).await?;
-    Ok(message)
+    Ok(new_id)
 }
 
 pub async fn get_all(pool: &DbPool) -> ApiResult<Vec<Message>> {
@@ -63,9 +75,26 @@ pub async fn get_all(pool: &DbPool) -> ApiResult<Vec<Message>> {
             m.body,
             m.created_at,
             m.updated_at,
-            to_jsonb(a) AS "author: Json<User>"
+            m.deleted_at,
+            m.deleted_by_name,
+            m.deleted_by_host,
+            to_jsonb(a) AS "author: Json<User>",
+            COALESCE(
+                (
+                    SELECT jsonb_agg(
+                        to_jsonb(att) - ARRAY[
+                            'channel_id', 'uploader_name', 'uploader_host'
+                        ]
+                        ORDER BY att.created_at
+                    )
+                    FROM attachments att
+                    WHERE att.message_id = m.id AND m.deleted_at IS NULL
+                ),
+                '[]'
+            ) AS "attachments!: Json<Vec<Attachment>>"
         FROM messages m
         LEFT JOIN users a ON a.name = m.author_name AND a.host = m.author_host
+        WHERE m.deleted_at IS NULL
         ORDER BY m.created_at DESC;
         "#
     )
@@ -75,9 +104,12 @@ pub async fn get_all(pool: &DbPool) -> ApiResult<Vec<Message>> {
     Ok(messages)
 }
 
+/// Messages of a server, newest first. Tombstones of deleted messages are
+/// only included when `include_deleted`.
 pub async fn get_by_server(
     pool: &DbPool,
     server_id: Uuid,
+    include_deleted: bool,
 ) -> ApiResult<Vec<Message>> {
     let rows = sqlx::query_as!(
         DbMessage,
@@ -88,14 +120,33 @@ pub async fn get_by_server(
             m.body,
             m.created_at,
             m.updated_at,
-            to_jsonb(a) AS "author: Json<User>"
+            m.deleted_at,
+            m.deleted_by_name,
+            m.deleted_by_host,
+            to_jsonb(a) AS "author: Json<User>",
+            COALESCE(
+                (
+                    SELECT jsonb_agg(
+                        to_jsonb(att) - ARRAY[
+                            'channel_id', 'uploader_name', 'uploader_host'
+                        ]
+                        ORDER BY att.created_at
+                    )
+                    FROM attachments att
+                    WHERE att.message_id = m.id AND m.deleted_at IS NULL
+                ),
+                '[]'
+            ) AS "attachments!: Json<Vec<Attachment>>"
         FROM messages m
         LEFT JOIN users a ON a.name = m.author_name AND a.host = m.author_host
         JOIN channels c ON c.id = m.channel_id
         WHERE c.server_id = $1
+            AND c.deleted_at IS NULL
+            AND ($2 OR m.deleted_at IS NULL)
         ORDER BY m.created_at DESC;
         "#,
         server_id,
+        include_deleted,
     )
     .fetch_all(pool)
     .await?;
@@ -103,9 +154,12 @@ pub async fn get_by_server(
     Ok(messages)
 }
 
+/// Messages of a channel, newest first. Tombstones of deleted messages are
+/// only included when `include_deleted`.
 pub async fn get_by_channel(
     pool: &DbPool,
     channel_id: Uuid,
+    include_deleted: bool,
 ) -> ApiResult<Vec<Message>> {
     let rows = sqlx::query_as!(
         DbMessage,
@@ -116,13 +170,30 @@ pub async fn get_by_channel(
             m.body,
             m.created_at,
             m.updated_at,
-            to_jsonb(a) AS "author: Json<User>"
+            m.deleted_at,
+            m.deleted_by_name,
+            m.deleted_by_host,
+            to_jsonb(a) AS "author: Json<User>",
+            COALESCE(
+                (
+                    SELECT jsonb_agg(
+                        to_jsonb(att) - ARRAY[
+                            'channel_id', 'uploader_name', 'uploader_host'
+                        ]
+                        ORDER BY att.created_at
+                    )
+                    FROM attachments att
+                    WHERE att.message_id = m.id AND m.deleted_at IS NULL
+                ),
+                '[]'
+            ) AS "attachments!: Json<Vec<Attachment>>"
         FROM messages m
         LEFT JOIN users a ON a.name = m.author_name AND a.host = m.author_host
-        WHERE m.channel_id = $1
+        WHERE m.channel_id = $1 AND ($2 OR m.deleted_at IS NULL)
         ORDER BY m.created_at DESC;
         "#,
         channel_id,
+        include_deleted,
     )
     .fetch_all(pool)
     .await?;
@@ -130,6 +201,7 @@ pub async fn get_by_channel(
     Ok(messages)
 }
 
+/// Get a message by ID, or its tombstone if it was deleted.
 pub async fn get_by_id(pool: &DbPool, msg_id: Uuid) -> ApiResult<Message> {
     let db_message = sqlx::query_as!(
         DbMessage,
@@ -140,7 +212,23 @@ pub async fn get_by_id(pool: &DbPool, msg_id: Uuid) -> ApiResult<Message> {
             m.body,
             m.created_at,
             m.updated_at,
-            to_jsonb(a) AS "author: Json<User>"
+            m.deleted_at,
+            m.deleted_by_name,
+            m.deleted_by_host,
+            to_jsonb(a) AS "author: Json<User>",
+            COALESCE(
+                (
+                    SELECT jsonb_agg(
+                        to_jsonb(att) - ARRAY[
+                            'channel_id', 'uploader_name', 'uploader_host'
+                        ]
+                        ORDER BY att.created_at
+                    )
+                    FROM attachments att
+                    WHERE att.message_id = m.id AND m.deleted_at IS NULL
+                ),
+                '[]'
+            ) AS "attachments!: Json<Vec<Attachment>>"
         FROM messages m
         LEFT JOIN users a ON a.name = m.author_name AND a.host = m.author_host
         WHERE m.id = $1;
@@ -152,9 +240,183 @@ pub async fn get_by_id(pool: &DbPool, msg_id: Uuid) -> ApiResult<Message> {
     Ok(db_message.into())
 }
 
-pub async fn delete(pool: &DbPool, message_id: Uuid) -> ApiResult<()> {
-    sqlx::query!("DELETE FROM messages WHERE id = $1;", message_id)
-        .execute(pool)
-        .await?;
+/// Replace a message with a tombstone: its body is cleared and the deletion
+/// recorded. Its attachments stay with the tombstone, hidden, until it is
+/// purged.
+pub async fn delete(
+    pool: &DbPool,
+    message_id: Uuid,
+    deleted_by: Option<&UserRef>,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE messages
+        SET body = '',
+            deleted_at = NOW(),
+            deleted_by_name = $2,
+            deleted_by_host = $3
+        WHERE id = $1 AND deleted_at IS NULL;
+        "#,
+        message_id,
+        deleted_by.map(|u| u.name.as_str()),
+        deleted_by.map(|u| u.host.as_str()),
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Replace every message posted in a server by `author_host` users, or by
+/// `author_name` alone when given, with a tombstone. Returns the IDs of the
+/// deleted messages.
+pub async fn delete_by_author_in_server(
+    pool: &DbPool,
+    server_id: Uuid,
+    author_name: Option<&str>,
+    author_host: &str,
+    deleted_by: Option<&UserRef>,
+) -> ApiResult<Vec<Uuid>> {
+    let deleted = sqlx::query_scalar!(
+        r#"
+        UPDATE messages m
+        SET body = '',
+            deleted_at = NOW(),
+            deleted_by_name = $4,
+            deleted_by_host = $5
+        FROM channels c
+        WHERE c.id = m.channel_id
+            AND c.server_id = $1
+            AND m.author_host = $3
+            AND ($2::TEXT IS NULL OR m.author_name = $2)
+            AND m.deleted_at IS NULL
+        RETURNING m.id;
+        "#,
+        server_id,
+        author_name,
+        author_host,
+        deleted_by.map(|u| u.name.as_str()),
+        deleted_by.map(|u| u.host.as_str()),
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(deleted)
+}
+
+/// Remove tombstones of messages deleted before `cutoff` for good. Returns
+/// how many were removed.
+pub async fn purge_deleted(
+    pool: &DbPool,
+    cutoff: OffsetDateTime,
+) -> ApiResult<u64> {
+    let result =
+        sqlx::query!("DELETE FROM messages WHERE deleted_at < $1;", cutoff)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
+
+/// Remove up to `limit` messages of a channel posted before `cutoff`, along
+/// with their tombstones. Returns how many were removed.
+pub async fn purge_older_than(
+    pool: &DbPool,
+    channel_id: Uuid,
+    cutoff: OffsetDateTime,
+    limit: i64,
+) -> ApiResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM messages
+        WHERE id IN (
+            SELECT id FROM messages
+            WHERE channel_id = $1 AND created_at < $2
+            LIMIT $3
+        );
+        "#,
+        channel_id,
+        cutoff,
+        limit,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Remove up to `limit` messages of a channel that are older than its
+/// `keep` newest messages, along with their tombstones. Returns how many
+/// were removed.
+pub async fn purge_beyond_newest(
+    pool: &DbPool,
+    channel_id: Uuid,
+    keep: i32,
+    limit: i64,
+) -> ApiResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM messages
+        WHERE id IN (
+            SELECT id FROM messages
+            WHERE channel_id = $1
+                AND created_at < (
+                    SELECT created_at FROM messages
+                    WHERE channel_id = $1 AND deleted_at IS NULL
+                    ORDER BY created_at DESC
+                    OFFSET $2 - 1
+                    LIMIT 1
+                )
+            LIMIT $3
+        );
+        "#,
+        channel_id,
+        keep,
+        limit,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Record the users a message mentions.
+pub async fn insert_mentions(
+    pool: &DbPool,
+    message_id: Uuid,
+    users: &[UserRef],
+) -> ApiResult<()> {
+    let names: Vec<&str> = users.iter().map(|u| u.name.as_str()).collect();
+    let hosts: Vec<&str> = users.iter().map(|u| u.host.as_str()).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO message_mentions (message_id, user_name, user_host)
+        SELECT $1, name, host
+        FROM UNNEST($2::TEXT[], $3::TEXT[]) AS u(name, host)
+        ON CONFLICT DO NOTHING;
+        "#,
+        message_id,
+        &names as &[&str],
+        &hosts as &[&str],
+    )
+    .execute(pool)
+    .await?;
     Ok(())
 }
+
+/// The users mentioned in any of the messages, along with the message that
+/// mentions them.
+pub async fn get_mentions(
+    pool: &DbPool,
+    message_ids: &[Uuid],
+) -> ApiResult<Vec<(Uuid, UserRef)>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT message_id, user_name, user_host
+        FROM message_mentions
+        WHERE message_id = ANY($1);
+        "#,
+        message_ids,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.message_id, UserRef::new(r.user_name, r.user_host)))
+        .collect())
+}
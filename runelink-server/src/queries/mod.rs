@@ -1,7 +1,22 @@
 pub mod accounts;
+pub mod archive;
+pub mod attachments;
+pub mod audit;
+pub mod categories;
 pub mod channels;
+pub mod invites;
 pub mod memberships;
 pub mod messages;
+pub mod moderation;
+pub mod notifications;
+pub mod password_resets;
+pub mod pins;
+pub mod rate_limits;
+pub mod read_markers;
+pub mod retention;
+pub mod scheduled_messages;
+pub mod server_migrations;
 pub mod servers;
 pub mod tokens;
+pub mod totp;
 pub mod users;
@@ -0,0 +1,224 @@
+use runelink_types::{ServerBan, ServerTimeout, UserRef};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiResult},
+};
+
+/// Ban a user, or a whole host when `user_name` is `None`. Banning the same
+/// target again replaces the earlier ban.
+pub async fn insert_ban(
+    pool: &DbPool,
+    server_id: Uuid,
+    user_name: Option<&str>,
+    user_host: &str,
+    reason: Option<&str>,
+    created_by: Option<&UserRef>,
+    expires_at: Option<OffsetDateTime>,
+) -> ApiResult<ServerBan> {
+    let ban = sqlx::query_as!(
+        ServerBan,
+        r#"
+        INSERT INTO server_bans (server_id, user_name, user_host, reason,
+                                 created_by_name, created_by_host, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (server_id, user_host, COALESCE(user_name, ''))
+        DO UPDATE SET
+            reason = EXCLUDED.reason,
+            created_by_name = EXCLUDED.created_by_name,
+            created_by_host = EXCLUDED.created_by_host,
+            created_at = NOW(),
+            expires_at = EXCLUDED.expires_at
+        RETURNING id, server_id, user_name, user_host, reason,
+                  created_by_name, created_by_host, created_at, expires_at;
+        "#,
+        server_id,
+        user_name,
+        user_host,
+        reason,
+        created_by.map(|u| u.name.as_str()),
+        created_by.map(|u| u.host.as_str()),
+        expires_at,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(ban)
+}
+
+/// Bans in a server that haven't expired, newest first.
+pub async fn get_bans(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<Vec<ServerBan>> {
+    let bans = sqlx::query_as!(
+        ServerBan,
+        r#"
+        SELECT id, server_id, user_name, user_host, reason,
+               created_by_name, created_by_host, created_at, expires_at
+        FROM server_bans
+        WHERE server_id = $1
+            AND (expires_at IS NULL OR expires_at > NOW())
+        ORDER BY created_at DESC;
+        "#,
+        server_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(bans)
+}
+
+/// The unexpired ban covering a user in a server, if any. A ban on the user
+/// wins over a ban on their host.
+pub async fn get_active_ban(
+    pool: &DbPool,
+    server_id: Uuid,
+    user: &UserRef,
+) -> ApiResult<Option<ServerBan>> {
+    let ban = sqlx::query_as!(
+        ServerBan,
+        r#"
+        SELECT id, server_id, user_name, user_host, reason,
+               created_by_name, created_by_host, created_at, expires_at
+        FROM server_bans
+        WHERE server_id = $1
+            AND user_host = $3
+            AND (user_name IS NULL OR user_name = $2)
+            AND (expires_at IS NULL OR expires_at > NOW())
+        ORDER BY user_name NULLS LAST
+        LIMIT 1;
+        "#,
+        server_id,
+        user.name,
+        user.host,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(ban)
+}
+
+pub async fn delete_ban(
+    pool: &DbPool,
+    server_id: Uuid,
+    ban_id: Uuid,
+) -> ApiResult<ServerBan> {
+    let ban = sqlx::query_as!(
+        ServerBan,
+        r#"
+        DELETE FROM server_bans
+        WHERE server_id = $1 AND id = $2
+        RETURNING id, server_id, user_name, user_host, reason,
+                  created_by_name, created_by_host, created_at, expires_at;
+        "#,
+        server_id,
+        ban_id,
+    )
+    .fetch_optional(pool)
+    .await?;
+    ban.ok_or(ApiError::NotFound)
+}
+
+/// Time a user out, replacing any earlier timeout in the server.
+pub async fn upsert_timeout(
+    pool: &DbPool,
+    server_id: Uuid,
+    user: &UserRef,
+    reason: Option<&str>,
+    created_by: Option<&UserRef>,
+    until: OffsetDateTime,
+) -> ApiResult<ServerTimeout> {
+    let timeout = sqlx::query_as!(
+        ServerTimeout,
+        r#"
+        INSERT INTO server_timeouts (server_id, user_name, user_host, reason,
+                                     created_by_name, created_by_host, until)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (server_id, user_name, user_host)
+        DO UPDATE SET
+            reason = EXCLUDED.reason,
+            created_by_name = EXCLUDED.created_by_name,
+            created_by_host = EXCLUDED.created_by_host,
+            created_at = NOW(),
+            until = EXCLUDED.until
+        RETURNING server_id, user_name, user_host, reason,
+                  created_by_name, created_by_host, created_at, until;
+        "#,
+        server_id,
+        user.name,
+        user.host,
+        reason,
+        created_by.map(|u| u.name.as_str()),
+        created_by.map(|u| u.host.as_str()),
+        until,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(timeout)
+}
+
+/// Timeouts in a server that are still running, ending soonest first.
+pub async fn get_timeouts(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<Vec<ServerTimeout>> {
+    let timeouts = sqlx::query_as!(
+        ServerTimeout,
+        r#"
+        SELECT server_id, user_name, user_host, reason,
+               created_by_name, created_by_host, created_at, until
+        FROM server_timeouts
+        WHERE server_id = $1 AND until > NOW()
+        ORDER BY until;
+        "#,
+        server_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(timeouts)
+}
+
+pub async fn get_active_timeout(
+    pool: &DbPool,
+    server_id: Uuid,
+    user: &UserRef,
+) -> ApiResult<Option<ServerTimeout>> {
+    let timeout = sqlx::query_as!(
+        ServerTimeout,
+        r#"
+        SELECT server_id, user_name, user_host, reason,
+               created_by_name, created_by_host, created_at, until
+        FROM server_timeouts
+        WHERE server_id = $1 AND user_name = $2 AND user_host = $3
+            AND until > NOW();
+        "#,
+        server_id,
+        user.name,
+        user.host,
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(timeout)
+}
+
+pub async fn delete_timeout(
+    pool: &DbPool,
+    server_id: Uuid,
+    user: &UserRef,
+) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM server_timeouts
+        WHERE server_id = $1 AND user_name = $2 AND user_host = $3;
+        "#,
+        server_id,
+        user.name,
+        user.host,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use runelink_types::{NewNotification, Notification, UserRef};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+#[derive(Debug)]
+struct DbNotification {
+    id: Uuid,
+    server_id: Uuid,
+    server_host: String,
+    channel_id: Uuid,
+    message_id: Uuid,
+    author_name: String,
+    author_host: String,
+    excerpt: String,
+    created_at: OffsetDateTime,
+    read_at: Option<OffsetDateTime>,
+}
+
+impl From<DbNotification> for Notification {
+    fn from(row: DbNotification) -> Self {
+        Notification {
+            id: row.id,
+            server_id: row.server_id,
+            server_host: row.server_host,
+            channel_id: row.channel_id,
+            message_id: row.message_id,
+            author: UserRef::new(row.author_name, row.author_host),
+            excerpt: row.excerpt,
+            created_at: row.created_at,
+            read_at: row.read_at,
+        }
+    }
+}
+
+/// Add a notification to a user's inbox. A message notifies each user at
+/// most once.
+pub async fn insert(
+    pool: &DbPool,
+    new_notification: &NewNotification,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (user_name, user_host, server_id,
+                                   server_host, channel_id, message_id,
+                                   author_name, author_host, excerpt)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (user_name, user_host, message_id) DO NOTHING;
+        "#,
+        new_notification.user.name,
+        new_notification.user.host,
+        new_notification.server_id,
+        new_notification.server_host,
+        new_notification.channel_id,
+        new_notification.message_id,
+        new_notification.author.name,
+        new_notification.author.host,
+        new_notification.excerpt,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// A user's notifications, newest first. Read ones are only included when
+/// `include_read`.
+pub async fn get_by_user(
+    pool: &DbPool,
+    user_ref: &UserRef,
+    include_read: bool,
+) -> ApiResult<Vec<Notification>> {
+    let rows = sqlx::query_as!(
+        DbNotification,
+        r#"
+        SELECT id, server_id, server_host, channel_id, message_id,
+               author_name, author_host, excerpt, created_at, read_at
+        FROM notifications
+        WHERE user_name = $1
+            AND user_host = $2
+            AND ($3 OR read_at IS NULL)
+        ORDER BY created_at DESC;
+        "#,
+        user_ref.name,
+        user_ref.host,
+        include_read,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(Notification::from).collect())
+}
+
+/// Mark one of a user's notifications, or all of them when
+/// `notification_id` is `None`, as read. Returns how many were unread.
+pub async fn mark_read(
+    pool: &DbPool,
+    user_ref: &UserRef,
+    notification_id: Option<Uuid>,
+) -> ApiResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET read_at = NOW()
+        WHERE user_name = $1
+            AND user_host = $2
+            AND ($3::UUID IS NULL OR id = $3)
+            AND read_at IS NULL;
+        "#,
+        user_ref.name,
+        user_ref.host,
+        notification_id,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// The host of the server whose message notified a user.
+pub async fn get_server_host(
+    pool: &DbPool,
+    user_ref: &UserRef,
+    message_id: Uuid,
+) -> ApiResult<String> {
+    let server_host = sqlx::query_scalar!(
+        r#"
+        SELECT server_host FROM notifications
+        WHERE message_id = $1 AND user_name = $2 AND user_host = $3;
+        "#,
+        message_id,
+        user_ref.name,
+        user_ref.host,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(server_host)
+}
+
+/// Remove the notifications of messages posted on `server_host`, only from
+/// the inbox of `user_ref` when given. Returns how many were removed.
+pub async fn delete_by_messages(
+    pool: &DbPool,
+    server_host: &str,
+    message_ids: &[Uuid],
+    user_ref: Option<&UserRef>,
+) -> ApiResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM notifications
+        WHERE server_host = $1
+            AND message_id = ANY($2)
+            AND ($3::TEXT IS NULL OR user_name = $3)
+            AND ($4::TEXT IS NULL OR user_host = $4);
+        "#,
+        server_host,
+        message_ids,
+        user_ref.map(|u| u.name.as_str()),
+        user_ref.map(|u| u.host.as_str()),
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Whether a user has a notification with this ID.
+pub async fn exists(
+    pool: &DbPool,
+    user_ref: &UserRef,
+    notification_id: Uuid,
+) -> ApiResult<bool> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM notifications
+            WHERE id = $1 AND user_name = $2 AND user_host = $3
+        ) AS "exists!";
+        "#,
+        notification_id,
+        user_ref.name,
+        user_ref.host,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(exists)
+}
@@ -0,0 +1,78 @@
+use runelink_types::UserRef;
+use time::OffsetDateTime;
+
+use crate::{db::DbPool, error::ApiResult};
+
+pub async fn insert(
+    pool: &DbPool,
+    token_hash: &str,
+    user: &UserRef,
+    created_by: &UserRef,
+    expires_at: OffsetDateTime,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO password_resets (token_hash, user_name, user_host,
+                                     created_by_name, created_by_host,
+                                     expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6);
+        "#,
+        token_hash,
+        user.name,
+        user.host,
+        created_by.name,
+        created_by.host,
+        expires_at,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The account a valid (unused, unexpired) reset token belongs to.
+pub async fn get_user(pool: &DbPool, token_hash: &str) -> ApiResult<UserRef> {
+    let row = sqlx::query!(
+        r#"
+        SELECT user_name, user_host
+        FROM password_resets
+        WHERE token_hash = $1
+            AND used_at IS NULL
+            AND expires_at > NOW();
+        "#,
+        token_hash,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(UserRef::new(row.user_name, row.user_host))
+}
+
+/// Mark a valid reset token as used, returning the account it resets. Fails
+/// with `NotFound` if the token doesn't exist, has expired or was used.
+pub async fn redeem(pool: &DbPool, token_hash: &str) -> ApiResult<UserRef> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE password_resets
+        SET used_at = NOW()
+        WHERE token_hash = $1
+            AND used_at IS NULL
+            AND expires_at > NOW()
+        RETURNING user_name, user_host;
+        "#,
+        token_hash,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(UserRef::new(row.user_name, row.user_host))
+}
+
+/// Drop all reset tokens of a user, e.g. once their password has changed.
+pub async fn delete_by_user(pool: &DbPool, user: UserRef) -> ApiResult<()> {
+    sqlx::query!(
+        "DELETE FROM password_resets WHERE user_name = $1 AND user_host = $2;",
+        user.name,
+        user.host,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
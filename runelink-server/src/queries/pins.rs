@@ -0,0 +1,163 @@
+use runelink_types::{Attachment, PinnedMessage, User, UserRef};
+use sqlx::types::Json;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult, queries::messages::DbMessage};
+
+#[derive(Debug)]
+struct DbPinnedMessage {
+    id: Uuid,
+    channel_id: Uuid,
+    author: Option<Json<User>>,
+    body: String,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+    deleted_at: Option<OffsetDateTime>,
+    deleted_by_name: Option<String>,
+    deleted_by_host: Option<String>,
+    attachments: Json<Vec<Attachment>>,
+    pinned_by_name: String,
+    pinned_by_host: String,
+    pinned_at: OffsetDateTime,
+}
+
+impl From<DbPinnedMessage> for PinnedMessage {
+    fn from(row: DbPinnedMessage) -> Self {
+        let message = DbMessage {
+            id: row.id,
+            channel_id: row.channel_id,
+            author: row.author,
+            body: row.body,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            deleted_at: row.deleted_at,
+            deleted_by_name: row.deleted_by_name,
+            deleted_by_host: row.deleted_by_host,
+            attachments: row.attachments,
+        };
+        PinnedMessage {
+            message: message.into(),
+            pinned_by: UserRef::new(row.pinned_by_name, row.pinned_by_host),
+            pinned_at: row.pinned_at,
+        }
+    }
+}
+
+/// Pin a message in its channel. Pinning a pinned message keeps the
+/// original pin.
+pub async fn insert(
+    pool: &DbPool,
+    channel_id: Uuid,
+    message_id: Uuid,
+    pinned_by: &UserRef,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO message_pins
+            (message_id, channel_id, pinned_by_name, pinned_by_host)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (message_id) DO NOTHING;
+        "#,
+        message_id,
+        channel_id,
+        pinned_by.name,
+        pinned_by.host,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Messages pinned in a channel, most recently pinned first. Pins of
+/// deleted messages are left out.
+pub async fn get_by_channel(
+    pool: &DbPool,
+    channel_id: Uuid,
+) -> ApiResult<Vec<PinnedMessage>> {
+    let rows = sqlx::query_as!(
+        DbPinnedMessage,
+        r#"
+        SELECT
+            m.id,
+            m.channel_id,
+            m.body,
+            m.created_at,
+            m.updated_at,
+            m.deleted_at,
+            m.deleted_by_name,
+            m.deleted_by_host,
+            to_jsonb(a) AS "author: Json<User>",
+            COALESCE(
+                (
+                    SELECT jsonb_agg(
+                        to_jsonb(att) - ARRAY[
+                            'channel_id', 'uploader_name', 'uploader_host'
+                        ]
+                        ORDER BY att.created_at
+                    )
+                    FROM attachments att
+                    WHERE att.message_id = m.id
+                ),
+                '[]'
+            ) AS "attachments!: Json<Vec<Attachment>>",
+            p.pinned_by_name,
+            p.pinned_by_host,
+            p.pinned_at
+        FROM message_pins p
+        JOIN messages m ON m.id = p.message_id
+        LEFT JOIN users a ON a.name = m.author_name AND a.host = m.author_host
+        WHERE p.channel_id = $1 AND m.deleted_at IS NULL
+        ORDER BY p.pinned_at DESC;
+        "#,
+        channel_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(PinnedMessage::from).collect())
+}
+
+/// How many messages are pinned in a channel, not counting deleted ones.
+pub async fn count_by_channel(
+    pool: &DbPool,
+    channel_id: Uuid,
+) -> ApiResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM message_pins p
+        JOIN messages m ON m.id = p.message_id
+        WHERE p.channel_id = $1 AND m.deleted_at IS NULL;
+        "#,
+        channel_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Whether a message is pinned.
+pub async fn exists(pool: &DbPool, message_id: Uuid) -> ApiResult<bool> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM message_pins WHERE message_id = $1
+        ) AS "exists!";
+        "#,
+        message_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(exists)
+}
+
+/// Unpin a message. Returns whether it was pinned.
+pub async fn delete(pool: &DbPool, message_id: Uuid) -> ApiResult<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM message_pins WHERE message_id = $1;",
+        message_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,39 @@
+use time::OffsetDateTime;
+
+use crate::{db::DbPool, error::ApiResult};
+
+/// Count a hit against a fixed window, returning the hits so far.
+pub async fn hit(
+    pool: &DbPool,
+    bucket_key: &str,
+    window_start: OffsetDateTime,
+) -> ApiResult<i32> {
+    let hits = sqlx::query_scalar!(
+        r#"
+        INSERT INTO rate_limit_counters (bucket_key, window_start, hits)
+        VALUES ($1, $2, 1)
+        ON CONFLICT (bucket_key, window_start)
+        DO UPDATE SET hits = rate_limit_counters.hits + 1
+        RETURNING hits
+        "#,
+        bucket_key,
+        window_start,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(hits)
+}
+
+/// Drop counters for windows that started before `before`.
+pub async fn delete_expired(
+    pool: &DbPool,
+    before: OffsetDateTime,
+) -> ApiResult<u64> {
+    let result = sqlx::query!(
+        "DELETE FROM rate_limit_counters WHERE window_start < $1;",
+        before,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,113 @@
+use runelink_types::{ReadMarker, UserRef};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+/// Unread messages in one channel of a local server.
+#[derive(Debug)]
+pub struct UnreadCount {
+    pub channel_id: Uuid,
+    pub unread_count: i64,
+}
+
+/// All of a user's read markers.
+pub async fn get_by_user(
+    pool: &DbPool,
+    user_ref: &UserRef,
+) -> ApiResult<Vec<ReadMarker>> {
+    let markers = sqlx::query_as!(
+        ReadMarker,
+        r#"
+        SELECT server_id, server_host, channel_id, message_id,
+               message_created_at, updated_at
+        FROM read_markers
+        WHERE user_name = $1 AND user_host = $2
+        ORDER BY server_host, server_id, channel_id;
+        "#,
+        user_ref.name,
+        user_ref.host,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(markers)
+}
+
+/// Move a user's read marker in a channel to a message.
+pub async fn upsert(
+    pool: &DbPool,
+    user_ref: &UserRef,
+    server_id: Uuid,
+    server_host: &str,
+    channel_id: Uuid,
+    message_id: Uuid,
+    message_created_at: OffsetDateTime,
+) -> ApiResult<ReadMarker> {
+    let marker = sqlx::query_as!(
+        ReadMarker,
+        r#"
+        INSERT INTO read_markers (user_name, user_host, server_id,
+                                  server_host, channel_id, message_id,
+                                  message_created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (user_name, user_host, channel_id) DO UPDATE
+        SET server_id = EXCLUDED.server_id,
+            server_host = EXCLUDED.server_host,
+            message_id = EXCLUDED.message_id,
+            message_created_at = EXCLUDED.message_created_at,
+            updated_at = NOW()
+        RETURNING server_id, server_host, channel_id, message_id,
+                  message_created_at, updated_at;
+        "#,
+        user_ref.name,
+        user_ref.host,
+        server_id,
+        server_host,
+        channel_id,
+        message_id,
+        message_created_at,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(marker)
+}
+
+/// Count the messages by others in each channel of a local server that were
+/// sent after the reader's marker in that channel. Channels without a marker
+/// count all their messages by others; channels in the trash are left out.
+pub async fn count_unread(
+    pool: &DbPool,
+    server_id: Uuid,
+    reader: &UserRef,
+    markers: &[ReadMarker],
+) -> ApiResult<Vec<UnreadCount>> {
+    let channel_ids: Vec<Uuid> = markers.iter().map(|m| m.channel_id).collect();
+    let read_up_to: Vec<OffsetDateTime> =
+        markers.iter().map(|m| m.message_created_at).collect();
+    let counts = sqlx::query_as!(
+        UnreadCount,
+        r#"
+        SELECT c.id AS channel_id, COUNT(m.id) AS "unread_count!"
+        FROM channels c
+        LEFT JOIN UNNEST($4::UUID[], $5::TIMESTAMPTZ[])
+            AS r(channel_id, read_up_to) ON r.channel_id = c.id
+        LEFT JOIN messages m
+            ON m.channel_id = c.id
+            AND m.deleted_at IS NULL
+            AND (r.read_up_to IS NULL OR m.created_at > r.read_up_to)
+            AND (m.author_name IS DISTINCT FROM $2
+                OR m.author_host IS DISTINCT FROM $3)
+        WHERE c.server_id = $1 AND c.deleted_at IS NULL
+        GROUP BY c.id
+        ORDER BY c.id;
+        "#,
+        server_id,
+        reader.name,
+        reader.host,
+        &channel_ids,
+        &read_up_to,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(counts)
+}
@@ -0,0 +1,116 @@
+use runelink_types::{RetentionPolicy, RetentionPolicyUpdate};
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+/// The retention limits that apply to one channel.
+#[derive(Clone, Debug)]
+pub struct ChannelRetention {
+    pub server_id: Uuid,
+    pub channel_id: Uuid,
+    pub max_age_days: Option<i32>,
+    pub max_messages: Option<i32>,
+}
+
+/// The server-wide policy of a server (if any) followed by its channels'
+/// policies.
+pub async fn get_by_server(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<Vec<RetentionPolicy>> {
+    let policies = sqlx::query_as!(
+        RetentionPolicy,
+        r#"
+        SELECT server_id, channel_id, max_age_days, max_messages,
+               created_at, updated_at
+        FROM retention_policies
+        WHERE server_id = $1
+        ORDER BY channel_id NULLS FIRST;
+        "#,
+        server_id,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(policies)
+}
+
+/// Apply `update` to the policy of a server (`channel_id` of `None`) or
+/// channel, creating the policy if there is none yet.
+pub async fn upsert(
+    pool: &DbPool,
+    server_id: Uuid,
+    channel_id: Option<Uuid>,
+    update: &RetentionPolicyUpdate,
+) -> ApiResult<RetentionPolicy> {
+    let policy = sqlx::query_as!(
+        RetentionPolicy,
+        r#"
+        INSERT INTO retention_policies
+            (server_id, channel_id, max_age_days, max_messages)
+        VALUES ($1, $2, $4, $6)
+        ON CONFLICT (server_id, channel_id) DO UPDATE
+        SET max_age_days = CASE WHEN $3 THEN $4
+                                ELSE retention_policies.max_age_days END,
+            max_messages = CASE WHEN $5 THEN $6
+                                ELSE retention_policies.max_messages END
+        RETURNING server_id, channel_id, max_age_days, max_messages,
+                  created_at, updated_at;
+        "#,
+        server_id,
+        channel_id,
+        update.max_age_days.is_some(),
+        update.max_age_days.flatten(),
+        update.max_messages.is_some(),
+        update.max_messages.flatten(),
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(policy)
+}
+
+/// Remove the policy of a server (`channel_id` of `None`) or channel.
+/// Returns whether there was one.
+pub async fn delete(
+    pool: &DbPool,
+    server_id: Uuid,
+    channel_id: Option<Uuid>,
+) -> ApiResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM retention_policies
+        WHERE server_id = $1 AND channel_id IS NOT DISTINCT FROM $2;
+        "#,
+        server_id,
+        channel_id,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Every channel with retention limits, each with the policy that applies
+/// to it: its own if it has one, otherwise its server's.
+pub async fn get_effective(pool: &DbPool) -> ApiResult<Vec<ChannelRetention>> {
+    let rows = sqlx::query_as!(
+        ChannelRetention,
+        r#"
+        SELECT c.server_id, c.id AS channel_id,
+               p.max_age_days AS "max_age_days?",
+               p.max_messages AS "max_messages?"
+        FROM channels c
+        JOIN LATERAL (
+            SELECT r.max_age_days, r.max_messages
+            FROM retention_policies r
+            WHERE r.server_id = c.server_id
+                AND (r.channel_id = c.id OR r.channel_id IS NULL)
+            ORDER BY r.channel_id NULLS LAST
+            LIMIT 1
+        ) p ON TRUE
+        WHERE c.deleted_at IS NULL
+            AND (p.max_age_days IS NOT NULL OR p.max_messages IS NOT NULL);
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
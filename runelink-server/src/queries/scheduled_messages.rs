@@ -0,0 +1,231 @@
+use runelink_types::{ScheduledMessage, UserRef};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+#[derive(Debug)]
+struct DbScheduledMessage {
+    id: Uuid,
+    server_id: Uuid,
+    server_host: String,
+    channel_id: Uuid,
+    author_name: String,
+    author_host: String,
+    body: String,
+    send_at: OffsetDateTime,
+    created_at: OffsetDateTime,
+    attempts: i32,
+    last_error: Option<String>,
+    failed_at: Option<OffsetDateTime>,
+}
+
+impl From<DbScheduledMessage> for ScheduledMessage {
+    fn from(row: DbScheduledMessage) -> Self {
+        ScheduledMessage {
+            id: row.id,
+            server_id: row.server_id,
+            server_host: row.server_host,
+            channel_id: row.channel_id,
+            author: UserRef::new(row.author_name, row.author_host),
+            body: row.body,
+            send_at: row.send_at,
+            created_at: row.created_at,
+            attempts: row.attempts,
+            last_error: row.last_error,
+            failed_at: row.failed_at,
+        }
+    }
+}
+
+pub async fn insert(
+    pool: &DbPool,
+    server_id: Uuid,
+    server_host: &str,
+    channel_id: Uuid,
+    author: &UserRef,
+    body: &str,
+    send_at: OffsetDateTime,
+) -> ApiResult<ScheduledMessage> {
+    let row = sqlx::query_as!(
+        DbScheduledMessage,
+        r#"
+        INSERT INTO scheduled_messages (
+            server_id, server_host, channel_id, author_name, author_host,
+            body, send_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING
+            id, server_id, server_host, channel_id, author_name, author_host,
+            body, send_at, created_at, attempts, last_error, failed_at;
+        "#,
+        server_id,
+        server_host,
+        channel_id,
+        author.name,
+        author.host,
+        body,
+        send_at,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(row.into())
+}
+
+/// A user's scheduled messages, including failed ones, soonest first.
+pub async fn get_by_author(
+    pool: &DbPool,
+    author: &UserRef,
+) -> ApiResult<Vec<ScheduledMessage>> {
+    let rows = sqlx::query_as!(
+        DbScheduledMessage,
+        r#"
+        SELECT
+            id, server_id, server_host, channel_id, author_name, author_host,
+            body, send_at, created_at, attempts, last_error, failed_at
+        FROM scheduled_messages
+        WHERE author_name = $1 AND author_host = $2
+        ORDER BY send_at ASC;
+        "#,
+        author.name,
+        author.host,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(ScheduledMessage::from).collect())
+}
+
+/// How many messages a user has waiting to be sent.
+pub async fn count_pending_by_author(
+    pool: &DbPool,
+    author: &UserRef,
+) -> ApiResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM scheduled_messages
+        WHERE author_name = $1 AND author_host = $2 AND failed_at IS NULL;
+        "#,
+        author.name,
+        author.host,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Claim up to `limit` messages due by `now` that were not given up on or
+/// claimed already, oldest first. Claimed messages are left alone by other
+/// delivery runs until they are deleted or their failure is recorded.
+pub async fn claim_due(
+    pool: &DbPool,
+    now: OffsetDateTime,
+    limit: i64,
+) -> ApiResult<Vec<ScheduledMessage>> {
+    let rows = sqlx::query_as!(
+        DbScheduledMessage,
+        r#"
+        UPDATE scheduled_messages
+        SET claimed_at = $1
+        WHERE id IN (
+            SELECT id
+            FROM scheduled_messages
+            WHERE send_at <= $1
+                AND failed_at IS NULL
+                AND claimed_at IS NULL
+            ORDER BY send_at ASC
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING
+            id, server_id, server_host, channel_id, author_name, author_host,
+            body, send_at, created_at, attempts, last_error, failed_at;
+        "#,
+        now,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut due: Vec<ScheduledMessage> =
+        rows.into_iter().map(ScheduledMessage::from).collect();
+    due.sort_by_key(|scheduled| scheduled.send_at);
+    Ok(due)
+}
+
+/// Give up on messages claimed before `cutoff`. Their delivery was cut off
+/// (say, by a restart) and may or may not have gone through, so they are
+/// not tried again. Returns how many there were.
+pub async fn fail_claimed_before(
+    pool: &DbPool,
+    cutoff: OffsetDateTime,
+) -> ApiResult<u64> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE scheduled_messages
+        SET attempts = attempts + 1,
+            last_error = 'Delivery was interrupted',
+            failed_at = NOW(),
+            claimed_at = NULL
+        WHERE claimed_at < $1;
+        "#,
+        cutoff,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Record a failed delivery attempt. The message is tried again at
+/// `retry_at`, or given up on when there is none.
+pub async fn record_failure(
+    pool: &DbPool,
+    id: Uuid,
+    error: &str,
+    retry_at: Option<OffsetDateTime>,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_messages
+        SET attempts = attempts + 1,
+            last_error = $2,
+            send_at = COALESCE($3, send_at),
+            failed_at = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN NOW() END,
+            claimed_at = NULL
+        WHERE id = $1;
+        "#,
+        id,
+        error,
+        retry_at,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Remove a scheduled message once it was sent.
+pub async fn delete(pool: &DbPool, id: Uuid) -> ApiResult<()> {
+    sqlx::query!("DELETE FROM scheduled_messages WHERE id = $1;", id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Cancel one of a user's scheduled messages. Returns whether it existed.
+pub async fn delete_by_author(
+    pool: &DbPool,
+    author: &UserRef,
+    id: Uuid,
+) -> ApiResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM scheduled_messages
+        WHERE id = $1 AND author_name = $2 AND author_host = $3;
+        "#,
+        id,
+        author.name,
+        author.host,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,271 @@
+use runelink_types::{Server, ServerMigration};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::ApiResult};
+
+/// Record that a server moved away from this host.
+pub async fn insert(
+    conn: &mut PgConnection,
+    migration: &ServerMigration,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO server_migrations (
+            server_id, from_host, to_host, migrated_at
+        )
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (server_id) DO UPDATE
+            SET from_host = EXCLUDED.from_host,
+                to_host = EXCLUDED.to_host,
+                migrated_at = EXCLUDED.migrated_at;
+        "#,
+        migration.server_id,
+        migration.from_host,
+        migration.to_host,
+        migration.migrated_at,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_by_server(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<ServerMigration> {
+    let migration = sqlx::query_as!(
+        ServerMigration,
+        r#"
+        SELECT server_id, from_host, to_host, migrated_at
+        FROM server_migrations
+        WHERE server_id = $1;
+        "#,
+        server_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(migration)
+}
+
+/// Forget that a server moved away, for when it moves back.
+pub async fn delete(conn: &mut PgConnection, server_id: Uuid) -> ApiResult<()> {
+    sqlx::query!(
+        "DELETE FROM server_migrations WHERE server_id = $1;",
+        server_id,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Turn the memberships of local users in a local server that moved to
+/// another host into memberships in a remote server.
+pub async fn cache_local_memberships(
+    conn: &mut PgConnection,
+    server: &Server,
+    local_host: &str,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO cached_remote_servers (
+            id, host, title, description, remote_created_at,
+            remote_updated_at, synced_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, NOW())
+        ON CONFLICT (id) DO UPDATE
+            SET host = EXCLUDED.host,
+                title = EXCLUDED.title,
+                description = EXCLUDED.description,
+                remote_created_at = EXCLUDED.remote_created_at,
+                remote_updated_at = EXCLUDED.remote_updated_at,
+                synced_at = NOW();
+        "#,
+        server.id,
+        server.host,
+        server.title,
+        server.description,
+        server.created_at,
+        server.updated_at,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO user_remote_server_memberships (
+            user_name, user_host, remote_server_id, role, remote_created_at,
+            remote_updated_at, synced_at
+        )
+        SELECT user_name, user_host, server_id, role, created_at,
+            updated_at, NOW()
+        FROM server_users
+        WHERE server_id = $1 AND user_host = $2
+        ON CONFLICT (user_name, user_host, remote_server_id) DO UPDATE
+            SET role = EXCLUDED.role,
+                remote_updated_at = EXCLUDED.remote_updated_at,
+                synced_at = NOW();
+        "#,
+        server.id,
+        local_host,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Drop the cached copy of a remote server, along with the memberships of
+/// local users in it.
+pub async fn delete_cached_remote(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+) -> ApiResult<()> {
+    sqlx::query!(
+        "DELETE FROM cached_remote_servers WHERE id = $1;",
+        server_id,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// The host of a cached remote server.
+pub async fn get_cached_remote_host(
+    pool: &DbPool,
+    server_id: Uuid,
+) -> ApiResult<String> {
+    let host = sqlx::query_scalar!(
+        "SELECT host FROM cached_remote_servers WHERE id = $1;",
+        server_id,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(host)
+}
+
+/// Point everything this host keeps about a server at its new host.
+pub async fn update_server_host(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+    host: &str,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE cached_remote_servers SET host = $2, synced_at = NOW()
+        WHERE id = $1;
+        "#,
+        server_id,
+        host,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE read_markers SET server_host = $2 WHERE server_id = $1;",
+        server_id,
+        host,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE notifications SET server_host = $2 WHERE server_id = $1;",
+        server_id,
+        host,
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        r#"
+        UPDATE scheduled_messages SET server_host = $2
+        WHERE server_id = $1 AND failed_at IS NULL;
+        "#,
+        server_id,
+        host,
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Delete a local server that moved to another host, along with its
+/// channels, messages and memberships.
+pub async fn delete_local_server(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+) -> ApiResult<()> {
+    sqlx::query!("DELETE FROM servers WHERE id = $1;", server_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Wait out writes to a local server (exclusively) or hold off a move of
+/// it (shared) until the transaction ends. Gives up after `lock_timeout`
+/// rather than waiting forever.
+pub async fn lock_server(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+    exclusive: bool,
+) -> ApiResult<()> {
+    sqlx::query!("SET LOCAL lock_timeout = '10s';")
+        .execute(&mut *conn)
+        .await?;
+    let key = format!("server:{server_id}");
+    if exclusive {
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock(hashtextextended($1, 0));",
+            key,
+        )
+        .execute(conn)
+        .await?;
+    } else {
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock_shared(hashtextextended($1, 0));",
+            key,
+        )
+        .execute(conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Mark a local server as moving to another host. Returns false if it is
+/// already being moved.
+pub async fn start_moving(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+    to_host: &str,
+) -> ApiResult<bool> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO server_moves (server_id, to_host)
+        VALUES ($1, $2)
+        ON CONFLICT (server_id) DO NOTHING;
+        "#,
+        server_id,
+        to_host,
+    )
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Clear the moving mark of a server whose move failed.
+pub async fn stop_moving(pool: &DbPool, server_id: Uuid) -> ApiResult<()> {
+    sqlx::query!("DELETE FROM server_moves WHERE server_id = $1;", server_id,)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The host a local server is being moved to, if it is being moved.
+pub async fn get_moving_to(
+    conn: &mut PgConnection,
+    server_id: Uuid,
+) -> ApiResult<Option<String>> {
+    let to_host = sqlx::query_scalar!(
+        "SELECT to_host FROM server_moves WHERE server_id = $1;",
+        server_id,
+    )
+    .fetch_optional(conn)
+    .await?;
+    Ok(to_host)
+}
@@ -3,7 +3,10 @@ use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{
-    config::ServerConfig, db::DbPool, error::ApiResult, state::AppState,
+    config::ServerConfig,
+    db::DbPool,
+    error::{ApiError, ApiResult},
+    state::AppState,
 };
 
 #[derive(sqlx::FromRow, Debug)]
@@ -75,6 +78,33 @@ pub async fn upsert_remote(pool: &DbPool, server: &Server) -> ApiResult<()> {
     .await?;
     Ok(())
 }
+
+/// Refresh the cached copy of a remote server. The host is never changed
+/// here; only a server migration moves a cached server to another host.
+pub async fn update_remote(pool: &DbPool, server: &Server) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE cached_remote_servers
+        SET title = $3,
+            description = $4,
+            remote_updated_at = $5,
+            synced_at = NOW()
+        WHERE id = $1 AND host = $2;
+        "#,
+        server.id,
+        server.host,
+        server.title,
+        server.description,
+        server.updated_at,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
 pub async fn get_by_id(state: &AppState, server_id: Uuid) -> ApiResult<Server> {
     let row = sqlx::query_as!(
         LocalServerRow,
@@ -97,6 +127,34 @@ pub async fn get_all(state: &AppState) -> ApiResult<Vec<Server>> {
     Ok(servers)
 }
 
+/// Update a server's title and description. `title: None` keeps the
+/// current title; the description is only touched when `set_description`.
+pub async fn update(
+    state: &AppState,
+    server_id: Uuid,
+    title: Option<&str>,
+    set_description: bool,
+    description: Option<&str>,
+) -> ApiResult<Server> {
+    let row = sqlx::query_as!(
+        LocalServerRow,
+        r#"
+        UPDATE servers
+        SET title = COALESCE($2, title),
+            description = CASE WHEN $3 THEN $4 ELSE description END
+        WHERE id = $1
+        RETURNING *;
+        "#,
+        server_id,
+        title,
+        set_description,
+        description,
+    )
+    .fetch_one(state.db_pool.as_ref())
+    .await?;
+    Ok(row.into_server(&state.config))
+}
+
 pub async fn delete(state: &AppState, server_id: Uuid) -> ApiResult<()> {
     sqlx::query!("DELETE FROM servers WHERE id = $1;", server_id)
         .execute(state.db_pool.as_ref())
@@ -1,4 +1,4 @@
-use runelink_types::RefreshToken;
+use runelink_types::{RefreshToken, UserRef};
 
 use crate::{db::DbPool, error::ApiResult};
 
@@ -59,3 +59,26 @@ pub async fn revoke_refresh(pool: &DbPool, token_str: &str) -> ApiResult<()> {
     .await?;
     Ok(())
 }
+
+/// Revoke every refresh token of a user, except `keep` if given.
+pub async fn revoke_all_refresh(
+    pool: &DbPool,
+    user: UserRef,
+    keep: Option<&str>,
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked = TRUE
+        WHERE user_name = $1 AND user_host = $2
+            AND NOT revoked
+            AND ($3::TEXT IS NULL OR token <> $3)
+        "#,
+        user.name,
+        user.host,
+        keep,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
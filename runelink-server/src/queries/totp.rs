@@ -0,0 +1,180 @@
+use runelink_types::UserRef;
+
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiResult},
+};
+
+/// A local account's TOTP secret. Never leaves the server.
+#[derive(Clone, sqlx::FromRow)]
+pub struct TotpCredential {
+    pub secret: String,
+    pub enabled: bool,
+    pub last_used_step: Option<i64>,
+}
+
+pub async fn get(pool: &DbPool, user: UserRef) -> ApiResult<TotpCredential> {
+    let credential = sqlx::query_as!(
+        TotpCredential,
+        r#"
+        SELECT secret, enabled, last_used_step
+        FROM totp_credentials
+        WHERE user_name = $1 AND user_host = $2;
+        "#,
+        user.name,
+        user.host,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(credential)
+}
+
+/// Store a new, not yet confirmed secret. Fails with `UniqueViolation` if
+/// TOTP is already enabled.
+pub async fn upsert_pending(
+    pool: &DbPool,
+    user: UserRef,
+    secret: &str,
+) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO totp_credentials (user_name, user_host, secret)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_name, user_host) DO UPDATE
+        SET secret = EXCLUDED.secret, last_used_step = NULL,
+            created_at = NOW()
+        WHERE NOT totp_credentials.enabled;
+        "#,
+        user.name,
+        user.host,
+        secret,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::UniqueViolation);
+    }
+    Ok(())
+}
+
+/// Record a code's time step as used, enabling TOTP if it was pending.
+/// Fails with `NotFound` if the step was already used (a replayed code).
+pub async fn use_step(
+    pool: &DbPool,
+    user: UserRef,
+    step: i64,
+) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE totp_credentials
+        SET enabled = TRUE, last_used_step = $3
+        WHERE user_name = $1 AND user_host = $2
+            AND (last_used_step IS NULL OR last_used_step < $3);
+        "#,
+        user.name,
+        user.host,
+        step,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+/// Remove TOTP and all recovery codes from an account.
+pub async fn delete(pool: &DbPool, user: UserRef) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM totp_recovery_codes
+        WHERE user_name = $1 AND user_host = $2;
+        "#,
+        user.name,
+        user.host,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM totp_credentials WHERE user_name = $1 AND user_host = $2;",
+        user.name,
+        user.host,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Replace all recovery codes of an account.
+pub async fn replace_recovery_codes(
+    pool: &DbPool,
+    user: UserRef,
+    code_hashes: &[String],
+) -> ApiResult<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM totp_recovery_codes
+        WHERE user_name = $1 AND user_host = $2;
+        "#,
+        user.name,
+        user.host,
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO totp_recovery_codes (user_name, user_host, code_hash)
+        SELECT $1, $2, code_hash FROM UNNEST($3::TEXT[]) AS code_hash;
+        "#,
+        user.name,
+        user.host,
+        code_hashes,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Mark an unused recovery code as used. Fails with `NotFound` if there is
+/// no such unused code.
+pub async fn use_recovery_code(
+    pool: &DbPool,
+    user: UserRef,
+    code_hash: &str,
+) -> ApiResult<()> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE totp_recovery_codes
+        SET used_at = NOW()
+        WHERE user_name = $1 AND user_host = $2 AND code_hash = $3
+            AND used_at IS NULL;
+        "#,
+        user.name,
+        user.host,
+        code_hash,
+    )
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(())
+}
+
+pub async fn count_recovery_codes(
+    pool: &DbPool,
+    user: UserRef,
+) -> ApiResult<i64> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM totp_recovery_codes
+        WHERE user_name = $1 AND user_host = $2 AND used_at IS NULL;
+        "#,
+        user.name,
+        user.host,
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
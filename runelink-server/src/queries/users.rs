@@ -1,4 +1,4 @@
-use runelink_types::{NewUser, User, UserRef, UserRole};
+use runelink_types::{NewUser, User, UserProfileUpdate, UserRef, UserRole};
 use time::OffsetDateTime;
 
 use crate::{db::DbPool, error::ApiResult};
@@ -15,7 +15,11 @@ pub async fn insert(pool: &DbPool, new_user: &NewUser) -> ApiResult<User> {
             role AS "role: UserRole",
             created_at,
             updated_at,
-            synced_at;
+            synced_at,
+            display_name,
+            bio,
+            avatar_url,
+            status_text;
         "#,
         new_user.name,
         new_user.host,
@@ -33,19 +37,28 @@ pub async fn upsert_remote(
     let user = sqlx::query_as!(
         User,
         r#"
-        INSERT INTO users (name, host, role, created_at, updated_at, synced_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO users (name, host, role, created_at, updated_at, synced_at,
+                           display_name, bio, avatar_url, status_text)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         ON CONFLICT (name, host) DO UPDATE SET
             role = EXCLUDED.role,
             updated_at = EXCLUDED.updated_at,
-            synced_at = EXCLUDED.synced_at
+            synced_at = EXCLUDED.synced_at,
+            display_name = EXCLUDED.display_name,
+            bio = EXCLUDED.bio,
+            avatar_url = EXCLUDED.avatar_url,
+            status_text = EXCLUDED.status_text
         RETURNING
             name,
             host,
             role AS "role: UserRole",
             created_at,
             updated_at,
-            synced_at;
+            synced_at,
+            display_name,
+            bio,
+            avatar_url,
+            status_text;
         "#,
         remote_user.name,
         remote_user.host,
@@ -53,6 +66,10 @@ pub async fn upsert_remote(
         remote_user.created_at,
         remote_user.updated_at,
         OffsetDateTime::now_utc(),
+        remote_user.display_name,
+        remote_user.bio,
+        remote_user.avatar_url,
+        remote_user.status_text,
     )
     .fetch_one(pool)
     .await?;
@@ -69,7 +86,11 @@ pub async fn get_all(pool: &DbPool) -> ApiResult<Vec<User>> {
             role AS "role: UserRole",
             created_at,
             updated_at,
-            synced_at
+            synced_at,
+            display_name,
+            bio,
+            avatar_url,
+            status_text
         FROM users;
         "#
     )
@@ -94,7 +115,11 @@ pub async fn ensure_exists(
             role AS "role: UserRole",
             created_at,
             updated_at,
-            synced_at;
+            synced_at,
+            display_name,
+            bio,
+            avatar_url,
+            status_text;
         "#,
         user_ref.name,
         user_ref.host,
@@ -114,7 +139,11 @@ pub async fn get_by_ref(pool: &DbPool, user_ref: UserRef) -> ApiResult<User> {
             role AS "role: UserRole",
             created_at,
             updated_at,
-            synced_at
+            synced_at,
+            display_name,
+            bio,
+            avatar_url,
+            status_text
         FROM users
         WHERE name = $1 AND host = $2;
         "#,
@@ -126,6 +155,51 @@ pub async fn get_by_ref(pool: &DbPool, user_ref: UserRef) -> ApiResult<User> {
     Ok(user)
 }
 
+/// Apply changes to a user's profile. Fields left out of the update keep
+/// their values.
+pub async fn update_profile(
+    pool: &DbPool,
+    user_ref: &UserRef,
+    update: &UserProfileUpdate,
+) -> ApiResult<User> {
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        UPDATE users SET
+            display_name = CASE WHEN $3 THEN $4 ELSE display_name END,
+            bio = CASE WHEN $5 THEN $6 ELSE bio END,
+            avatar_url = CASE WHEN $7 THEN $8 ELSE avatar_url END,
+            status_text = CASE WHEN $9 THEN $10 ELSE status_text END,
+            updated_at = NOW()
+        WHERE name = $1 AND host = $2
+        RETURNING
+            name,
+            host,
+            role AS "role: UserRole",
+            created_at,
+            updated_at,
+            synced_at,
+            display_name,
+            bio,
+            avatar_url,
+            status_text;
+        "#,
+        user_ref.name,
+        user_ref.host,
+        update.display_name.is_some(),
+        update.display_name.clone().flatten(),
+        update.bio.is_some(),
+        update.bio.clone().flatten(),
+        update.avatar_url.is_some(),
+        update.avatar_url.clone().flatten(),
+        update.status_text.is_some(),
+        update.status_text.clone().flatten(),
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(user)
+}
+
 pub async fn delete(pool: &DbPool, user_ref: UserRef) -> ApiResult<()> {
     sqlx::query!(
         "DELETE FROM users WHERE name = $1 AND host = $2;",
@@ -137,7 +211,6 @@ pub async fn delete(pool: &DbPool, user_ref: UserRef) -> ApiResult<()> {
     Ok(())
 }
 
-
 pub async fn get_associated_hosts(
     pool: &DbPool,
     user_ref: &UserRef,
@@ -0,0 +1,527 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use log::{debug, warn};
+use runelink_types::{FEDERATION_LEGACY_PATH, FEDERATION_V1_PATH};
+use time::OffsetDateTime;
+
+use crate::{
+    bearer_auth::{ClientAuth, FederationAuth},
+    config::ServerConfig,
+    error::{ApiError, ApiResult},
+    queries,
+    state::AppState,
+};
+
+/// How many requests are allowed per period.
+///
+/// Parsed from strings like `10/min`, `5/hour` or `off`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quota {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub const fn new(burst: u32, period: Duration) -> Self {
+        Self { burst, period }
+    }
+
+    pub const fn per_minute(burst: u32) -> Self {
+        Self::new(burst, Duration::from_secs(60))
+    }
+
+    pub const fn per_hour(burst: u32) -> Self {
+        Self::new(burst, Duration::from_secs(60 * 60))
+    }
+
+    /// Tokens regained per second
+    fn refill_rate(&self) -> f64 {
+        self.burst as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl FromStr for Quota {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (count, unit) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("expected `<count>/<unit>`, got `{s}`"))?;
+        let burst = count
+            .trim()
+            .parse::<u32>()
+            .map_err(|e| format!("invalid count `{count}`: {e}"))?;
+        if burst == 0 {
+            return Err("count must be positive (use `off` to disable)".into());
+        }
+        let secs = match unit.trim() {
+            "s" | "sec" | "second" => 1,
+            "m" | "min" | "minute" => 60,
+            "h" | "hour" => 60 * 60,
+            "d" | "day" => 24 * 60 * 60,
+            other => return Err(format!("unknown unit `{other}`")),
+        };
+        Ok(Self::new(burst, Duration::from_secs(secs)))
+    }
+}
+
+/// Parse a quota setting, where `off` (or `0`) disables the limit.
+pub fn parse_quota_setting(s: &str) -> Result<Option<Quota>, String> {
+    match s.trim() {
+        "off" | "0" | "" => Ok(None),
+        other => other.parse().map(Some),
+    }
+}
+
+/// Where rate limit counters are kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Per-process token buckets
+    Memory,
+    /// Fixed-window counters shared by every instance using the database
+    Postgres,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    pub backend: Backend,
+    /// Password and refresh token grants, per client IP
+    pub auth: Option<Quota>,
+    /// Account signups, per client IP
+    pub signup: Option<Quota>,
+    /// Message posts, per user
+    pub messages: Option<Quota>,
+    /// Federated requests that are rate limited, per peer host
+    pub federation: Option<Quota>,
+    /// Take the client IP from `X-Forwarded-For` (only when behind a proxy
+    /// that sets it)
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            backend: Backend::Memory,
+            auth: Some(Quota::per_minute(10)),
+            signup: Some(Quota::per_hour(5)),
+            messages: Some(Quota::per_minute(30)),
+            federation: Some(Quota::per_minute(600)),
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+/// Which quota a rule draws from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Limit {
+    Auth,
+    Signup,
+    Messages,
+    Federation,
+}
+
+impl Limit {
+    const ALL: [Limit; 4] = [
+        Limit::Auth,
+        Limit::Signup,
+        Limit::Messages,
+        Limit::Federation,
+    ];
+
+    fn quota(self, config: &RateLimitConfig) -> Option<Quota> {
+        match self {
+            Limit::Auth => config.auth,
+            Limit::Signup => config.signup,
+            Limit::Messages => config.messages,
+            Limit::Federation => config.federation,
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Limit::Auth => "auth",
+            Limit::Signup => "signup",
+            Limit::Messages => "messages",
+            Limit::Federation => "federation",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a rule counts requests by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyBy {
+    /// Client IP address
+    Ip,
+    /// Authenticated user (client or delegated federated user), falling back
+    /// to the client IP for unauthenticated requests
+    User,
+    /// Federation token issuer, falling back to the client IP
+    Peer,
+}
+
+type Rule = (Limit, KeyBy);
+
+/// Rules applying to a route, keyed by its matched path.
+fn rules_for(method: &Method, path: &str) -> &'static [Rule] {
+    const MESSAGES_PATH: &str =
+        "/servers/{server_id}/channels/{channel_id}/messages";
+
+    if *method != Method::POST {
+        return &[];
+    }
+    let federated_path = path
+        .strip_prefix(FEDERATION_V1_PATH)
+        .or_else(|| path.strip_prefix(FEDERATION_LEGACY_PATH));
+    if let Some(path) = federated_path {
+        return match path {
+            MESSAGES_PATH => &[
+                (Limit::Federation, KeyBy::Peer),
+                (Limit::Messages, KeyBy::User),
+            ],
+            _ => &[],
+        };
+    }
+    match path {
+        "/auth/token"
+        | "/auth/password"
+        | "/auth/password/reset"
+        | "/auth/totp/confirm"
+        | "/auth/totp/disable" => &[(Limit::Auth, KeyBy::Ip)],
+        "/auth/signup" => &[(Limit::Signup, KeyBy::Ip)],
+        MESSAGES_PATH => &[(Limit::Messages, KeyBy::User)],
+        _ => &[],
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(quota: Quota, now: Instant) -> Self {
+        Self {
+            tokens: quota.burst as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, quota: Quota, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.tokens = (self.tokens
+            + elapsed.as_secs_f64() * quota.refill_rate())
+        .min(quota.burst as f64);
+        self.updated = now;
+    }
+
+    /// Take one token, or return how long until one is available.
+    fn take(&mut self, quota: Quota, now: Instant) -> Result<(), Duration> {
+        self.refill(quota, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - self.tokens;
+            Err(Duration::from_secs_f64(missing / quota.refill_rate()))
+        }
+    }
+}
+
+/// Request throttling state shared by all handlers.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(Limit, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request against `limit` for `key`.
+    pub async fn check(
+        &self,
+        state: &AppState,
+        limit: Limit,
+        key: &str,
+    ) -> ApiResult<()> {
+        let Some(quota) = limit.quota(&self.config) else {
+            return Ok(());
+        };
+        let result = match self.config.backend {
+            Backend::Memory => self.take_memory(limit, key, quota),
+            Backend::Postgres => {
+                take_postgres(state, limit, key, quota).await?
+            }
+        };
+        result.map_err(|retry_after| {
+            debug!("rate limited {limit} for {key}");
+            ApiError::RateLimited {
+                scope: limit.to_string(),
+                retry_after,
+            }
+        })
+    }
+
+    fn take_memory(
+        &self,
+        limit: Limit,
+        key: &str,
+        quota: Quota,
+    ) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets
+            .entry((limit, key.to_string()))
+            .or_insert_with(|| Bucket::full(quota, now))
+            .take(quota, now)
+    }
+
+    /// Forget buckets that have refilled completely and expired counters.
+    pub async fn prune(&self, state: &AppState) {
+        match self.config.backend {
+            Backend::Memory => {
+                let now = Instant::now();
+                let mut buckets = self.buckets.lock().unwrap();
+                buckets.retain(|(limit, _), bucket| {
+                    let Some(quota) = limit.quota(&self.config) else {
+                        return false;
+                    };
+                    bucket.refill(quota, now);
+                    bucket.tokens < quota.burst as f64
+                });
+            }
+            Backend::Postgres => {
+                let longest = Limit::ALL
+                    .into_iter()
+                    .filter_map(|limit| limit.quota(&self.config))
+                    .map(|quota| quota.period)
+                    .max()
+                    .unwrap_or_default();
+                let before = OffsetDateTime::now_utc() - longest;
+                if let Err(e) =
+                    queries::rate_limits::delete_expired(&state.db_pool, before)
+                        .await
+                {
+                    warn!("Failed to prune rate limit counters: {e}");
+                }
+            }
+        }
+    }
+}
+
+async fn take_postgres(
+    state: &AppState,
+    limit: Limit,
+    key: &str,
+    quota: Quota,
+) -> ApiResult<Result<(), Duration>> {
+    let now = OffsetDateTime::now_utc();
+    let period = quota.period.as_secs().max(1) as i64;
+    let window_secs = now.unix_timestamp().div_euclid(period) * period;
+    let window_start = OffsetDateTime::from_unix_timestamp(window_secs)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let bucket_key = format!("{limit}:{key}");
+    let hits =
+        queries::rate_limits::hit(&state.db_pool, &bucket_key, window_start)
+            .await?;
+    if hits as u32 <= quota.burst {
+        Ok(Ok(()))
+    } else {
+        let window_end = window_start + quota.period;
+        Ok(Err((window_end - now).unsigned_abs()))
+    }
+}
+
+/// Spawn a task that periodically prunes rate limiter state.
+pub fn spawn_pruner(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5 * 60));
+        loop {
+            interval.tick().await;
+            state.rate_limiter.prune(&state).await;
+        }
+    });
+}
+
+fn client_ip(request: &Request, config: &ServerConfig) -> Option<IpAddr> {
+    if config.rate_limits.trust_forwarded_for
+        && let Some(ip) = forwarded_for(request.headers())
+    {
+        return Some(ip);
+    }
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// The address the closest proxy saw the request come from.
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get("x-forwarded-for")?
+        .to_str()
+        .ok()?
+        .rsplit(',')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+async fn request_key(
+    state: &AppState,
+    headers: &HeaderMap,
+    ip: Option<IpAddr>,
+    key_by: KeyBy,
+) -> String {
+    match key_by {
+        KeyBy::Ip => {}
+        KeyBy::User => {
+            if let Ok(auth) = ClientAuth::from_headers(headers, state) {
+                return format!("user:{}", auth.claims.sub);
+            }
+            if let Ok(auth) = FederationAuth::from_headers(headers, state).await
+                && let Some(user_ref) = auth.claims.user_ref
+            {
+                return format!("user:{user_ref}");
+            }
+        }
+        KeyBy::Peer => {
+            if let Ok(auth) = FederationAuth::from_headers(headers, state).await
+            {
+                return format!("peer:{}", auth.claims.iss);
+            }
+        }
+    }
+    match ip {
+        Some(ip) => format!("ip:{ip}"),
+        None => "ip:unknown".into(),
+    }
+}
+
+/// Middleware enforcing the rate limits configured for the matched route.
+pub async fn middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    let rules = match request.extensions().get::<MatchedPath>() {
+        Some(path) => rules_for(request.method(), path.as_str()),
+        None => &[],
+    };
+    if !rules.is_empty() {
+        let headers = request.headers().clone();
+        let ip = client_ip(&request, &state.config);
+        for (limit, key_by) in rules {
+            let key = request_key(&state, &headers, ip, *key_by).await;
+            state.rate_limiter.check(&state, *limit, &key).await?;
+        }
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quota() {
+        assert_eq!("10/min".parse(), Ok(Quota::per_minute(10)));
+        assert_eq!(" 5 / hour ".parse(), Ok(Quota::per_hour(5)));
+        assert_eq!("2/s".parse(), Ok(Quota::new(2, Duration::from_secs(1))));
+        assert!("10".parse::<Quota>().is_err());
+        assert!("0/min".parse::<Quota>().is_err());
+        assert!("10/fortnight".parse::<Quota>().is_err());
+    }
+
+    #[test]
+    fn test_parse_quota_setting_off() {
+        assert_eq!(parse_quota_setting("off"), Ok(None));
+        assert_eq!(parse_quota_setting("0"), Ok(None));
+        assert_eq!(
+            parse_quota_setting("3/day"),
+            Ok(Some(Quota::new(3, Duration::from_secs(24 * 60 * 60))))
+        );
+    }
+
+    #[test]
+    fn test_bucket_allows_burst_then_limits() {
+        let quota = Quota::per_minute(3);
+        let now = Instant::now();
+        let mut bucket = Bucket::full(quota, now);
+        for _ in 0..3 {
+            assert!(bucket.take(quota, now).is_ok());
+        }
+        let retry_after = bucket.take(quota, now).unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let quota = Quota::per_minute(2);
+        let start = Instant::now();
+        let mut bucket = Bucket::full(quota, start);
+        assert!(bucket.take(quota, start).is_ok());
+        assert!(bucket.take(quota, start).is_ok());
+        assert!(bucket.take(quota, start).is_err());
+        let later = start + Duration::from_secs(30);
+        assert!(bucket.take(quota, later).is_ok());
+        assert!(bucket.take(quota, later).is_err());
+        // Never refills past the burst size
+        let much_later = later + Duration::from_secs(60 * 60);
+        bucket.refill(quota, much_later);
+        assert_eq!(bucket.tokens, 2.0);
+    }
+
+    #[test]
+    fn test_rules_for_routes() {
+        assert_eq!(
+            rules_for(&Method::POST, "/auth/token"),
+            &[(Limit::Auth, KeyBy::Ip)]
+        );
+        assert!(rules_for(&Method::GET, "/auth/token").is_empty());
+        assert_eq!(
+            rules_for(&Method::POST, "/auth/password/reset"),
+            &[(Limit::Auth, KeyBy::Ip)]
+        );
+        assert_eq!(
+            rules_for(
+                &Method::POST,
+                "/servers/{server_id}/channels/{channel_id}/messages"
+            ),
+            &[(Limit::Messages, KeyBy::User)]
+        );
+        assert_eq!(
+            rules_for(
+                &Method::POST,
+                "/federation/v1/servers/{server_id}/channels/{channel_id}/messages"
+            )
+            .len(),
+            2
+        );
+        assert!(rules_for(&Method::POST, "/servers").is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+use argon2::{
+    Argon2, PasswordHasher, PasswordVerifier,
+    password_hash::{PasswordHash, SaltString},
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use rand::{RngCore, rngs::OsRng};
+use sha2::{Digest, Sha256};
+
+use crate::error::{ApiError, ApiResult};
+
+/// Generate a random 256-bit token for handing out to users.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hash a generated token for storage.
+///
+/// Tokens carry enough entropy that a fast unsalted hash is sufficient; only
+/// the hash is ever written to the database.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.trim().as_bytes()))
+}
+
+/// Hash a user-chosen password for storage.
+pub fn hash_password(password: &str) -> ApiResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| ApiError::Internal(format!("hashing error: {e}")))?;
+    Ok(hash.to_string())
+}
+
+/// Check a password against a stored hash.
+pub fn verify_password(password: &str, password_hash: &str) -> ApiResult<()> {
+    let parsed_hash = PasswordHash::new(password_hash)
+        .map_err(|_| ApiError::AuthError("invalid password hash".into()))?;
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .map_err(|_| ApiError::AuthError("invalid credentials".into()))
+}
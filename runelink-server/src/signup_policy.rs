@@ -0,0 +1,155 @@
+use std::str::FromStr;
+
+/// Who may create an account on this host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignupMode {
+    /// Anyone may sign up
+    Open,
+    /// Nobody may sign up
+    Closed,
+    /// Signups require an invite token minted by a host admin
+    Invite,
+    /// Anyone may sign up, but can't log in until a host admin approves them
+    Approval,
+}
+
+impl FromStr for SignupMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "open" => Ok(SignupMode::Open),
+            "closed" => Ok(SignupMode::Closed),
+            "invite" => Ok(SignupMode::Invite),
+            "approval" => Ok(SignupMode::Approval),
+            other => Err(format!(
+                "expected one of `open`, `closed`, `invite`, `approval`, got \
+                `{other}`"
+            )),
+        }
+    }
+}
+
+pub const USERNAME_MIN_LEN: usize = 3;
+pub const USERNAME_MAX_LEN: usize = 32;
+pub const PASSWORD_MIN_LEN: usize = 8;
+/// Upper bound so hashing stays cheap
+pub const PASSWORD_MAX_LEN: usize = 256;
+
+/// Names nobody may sign up with, on top of `RESERVED_USERNAMES`.
+const RESERVED_USERNAMES: &[&str] = &[
+    "admin",
+    "administrator",
+    "root",
+    "system",
+    "support",
+    "moderator",
+    "staff",
+    "security",
+    "runelink",
+    "host",
+    "server",
+    "everyone",
+    "here",
+    "me",
+    "null",
+    "undefined",
+];
+
+/// Check that a username is well-formed and not reserved.
+pub fn validate_username(
+    name: &str,
+    extra_reserved: &[String],
+) -> Result<(), String> {
+    let len = name.chars().count();
+    if !(USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len) {
+        return Err(format!(
+            "username must be {USERNAME_MIN_LEN} to {USERNAME_MAX_LEN} \
+            characters long"
+        ));
+    }
+    if !name.starts_with(|c: char| c.is_ascii_lowercase()) {
+        return Err("username must start with a lowercase letter".into());
+    }
+    if let Some(c) = name.chars().find(|c| {
+        !(c.is_ascii_lowercase() || c.is_ascii_digit() || "_.-".contains(*c))
+    }) {
+        return Err(format!(
+            "username may only contain lowercase letters, digits, `_`, `.` \
+            and `-` (found `{c}`)"
+        ));
+    }
+    let reserved = RESERVED_USERNAMES
+        .iter()
+        .copied()
+        .chain(extra_reserved.iter().map(String::as_str));
+    if reserved.into_iter().any(|r| r.eq_ignore_ascii_case(name)) {
+        return Err(format!("username `{name}` is reserved"));
+    }
+    Ok(())
+}
+
+/// Check that a password is acceptable for the given username.
+pub fn validate_password(password: &str, name: &str) -> Result<(), String> {
+    let len = password.chars().count();
+    if len < PASSWORD_MIN_LEN {
+        return Err(format!(
+            "password must be at least {PASSWORD_MIN_LEN} characters long"
+        ));
+    }
+    if len > PASSWORD_MAX_LEN {
+        return Err(format!(
+            "password must be at most {PASSWORD_MAX_LEN} characters long"
+        ));
+    }
+    if password.eq_ignore_ascii_case(name) {
+        return Err("password must not match the username".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signup_mode() {
+        assert_eq!("open".parse(), Ok(SignupMode::Open));
+        assert_eq!(" Invite ".parse(), Ok(SignupMode::Invite));
+        assert!("sometimes".parse::<SignupMode>().is_err());
+    }
+
+    #[test]
+    fn test_valid_usernames() {
+        for name in ["alice", "bob_2", "c.d-e", "abc"] {
+            assert!(validate_username(name, &[]).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_usernames() {
+        for name in ["ab", "Alice", "1abc", "_abc", "al ice", "a@b.c", "bob/x"]
+        {
+            assert!(validate_username(name, &[]).is_err(), "{name}");
+        }
+        let too_long = "a".repeat(USERNAME_MAX_LEN + 1);
+        assert!(validate_username(&too_long, &[]).is_err());
+    }
+
+    #[test]
+    fn test_reserved_usernames() {
+        assert!(validate_username("admin", &[]).is_err());
+        let extra = vec!["Ops".to_string()];
+        assert!(validate_username("ops", &extra).is_err());
+        assert!(validate_username("ops", &[]).is_ok());
+    }
+
+    #[test]
+    fn test_passwords() {
+        assert!(validate_password("correct horse", "alice").is_ok());
+        assert!(validate_password("short", "alice").is_err());
+        assert!(validate_password("alicealice", "alicealice").is_err());
+        let too_long = "x".repeat(PASSWORD_MAX_LEN + 1);
+        assert!(validate_password(&too_long, "alice").is_err());
+    }
+}
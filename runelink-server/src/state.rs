@@ -1,6 +1,12 @@
 use std::sync::Arc;
 
-use crate::{config::ServerConfig, db::DbPool, key_manager::KeyManager};
+use runelink_client::resolver::{HostResolver, ResolvedHost};
+
+use crate::{
+    blobs::BlobStore, config::ServerConfig, db::DbPool,
+    key_manager::KeyManager, presence::PresenceTracker,
+    rate_limit::RateLimiter,
+};
 
 pub type JwksCache =
     std::collections::HashMap<String, crate::jwks_resolver::CachedJwks>;
@@ -13,4 +19,20 @@ pub struct AppState {
     pub key_manager: KeyManager,
     #[allow(dead_code)]
     pub jwks_cache: Arc<tokio::sync::RwLock<JwksCache>>,
+    pub host_resolver: HostResolver,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub blob_store: Arc<BlobStore>,
+    pub presence: Arc<PresenceTracker>,
+}
+
+impl AppState {
+    /// API base URL of `host`, resolved through its discovery document
+    pub async fn api_url_for(&self, host: &str) -> String {
+        self.host_resolver.api_url(&self.http_client, host).await
+    }
+
+    /// Resolve how to reach a peer host's APIs and what it supports
+    pub async fn resolve_host(&self, host: &str) -> ResolvedHost {
+        self.host_resolver.resolve(&self.http_client, host).await
+    }
 }
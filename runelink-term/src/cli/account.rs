@@ -1,5 +1,8 @@
-use runelink_client::{requests, util::get_api_url};
-use runelink_types::{SignupRequest, UserRef};
+use runelink_client::requests;
+use runelink_types::{
+    ChangePasswordRequest, MFA_REQUIRED, PasswordResetRequest, SignupRequest,
+    TotpConfirmRequest, TotpDisableRequest, UserRef,
+};
 use uuid::Uuid;
 
 use crate::{
@@ -25,13 +28,19 @@ pub enum AccountCommands {
     /// List accounts
     List,
     /// Create a new account
-    Create(NameAndHostArgs),
+    Create(CreateAccountArgs),
     /// Login to an account (store authentication tokens)
     Login(LoginArgs),
     /// Logout from an account (remove authentication tokens)
     Logout(LogoutArgs),
     /// Show authentication status for an account
     Status(StatusArgs),
+    /// Change an account's password (logs out other sessions)
+    Passwd(PasswdArgs),
+    /// Set a new password using a reset token from a host admin
+    ResetPassword(ResetPasswordArgs),
+    /// Manage two-factor authentication (TOTP)
+    Totp(TotpArgs),
     /// Delete an account (deletes the underlying user)
     Delete(DeleteAccountArgs),
     /// Manage default account
@@ -39,13 +48,16 @@ pub enum AccountCommands {
 }
 
 #[derive(clap::Args, Debug)]
-pub struct NameAndHostArgs {
+pub struct CreateAccountArgs {
     /// The account's username
     #[clap(long)]
     pub name: Option<String>,
     /// The host name of the account's host
     #[clap(long)]
     pub host: Option<String>,
+    /// Invite token, for hosts with invite-only signups
+    #[clap(long)]
+    pub invite: Option<String>,
 }
 
 #[derive(clap::Args, Debug)]
@@ -78,6 +90,48 @@ pub struct StatusArgs {
     pub host: Option<String>,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct PasswdArgs {
+    /// The account's username
+    #[clap(long)]
+    pub name: Option<String>,
+    /// The host name of the account's host
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ResetPasswordArgs {
+    /// The host name of the account's host
+    #[clap(long)]
+    pub host: Option<String>,
+    /// The reset token handed out by a host admin
+    #[clap(long)]
+    pub token: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TotpArgs {
+    #[clap(subcommand)]
+    pub command: TotpCommands,
+    /// The account's username
+    #[clap(long)]
+    pub name: Option<String>,
+    /// The host name of the account's host
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum TotpCommands {
+    /// Show whether two-factor authentication is enabled
+    Status,
+    /// Enroll an authenticator app
+    Enable,
+    /// Turn two-factor authentication off
+    Disable,
+}
+
 #[derive(clap::Args, Debug)]
 pub struct DeleteAccountArgs {
     #[clap(long)]
@@ -107,14 +161,17 @@ pub async fn handle_account_commands(
         }
 
         AccountCommands::Create(create_args) => {
-            let host =
-                unwrap_or_prompt(create_args.host.clone(), "Host")?;
+            let host = unwrap_or_prompt(create_args.host.clone(), "Host")?;
             let name = unwrap_or_prompt(create_args.name.clone(), "Name")?;
             let password = read_input("Password: ")?.ok_or_else(|| {
                 CliError::InvalidArgument("Password is required.".into())
             })?;
-            let api_url = get_api_url(&host);
-            let signup_req = SignupRequest { name, password };
+            let api_url = ctx.api_url_for(&host).await;
+            let signup_req = SignupRequest {
+                name,
+                password,
+                invite_token: create_args.invite.clone(),
+            };
             let user =
                 requests::auth::signup(ctx.client, &api_url, &signup_req)
                     .await?;
@@ -136,7 +193,7 @@ pub async fn handle_account_commands(
                     acc
                 } else {
                     // Account doesn't exist, fetch it from server
-                    let api_url = get_api_url(host);
+                    let api_url = ctx.api_url_for(host).await;
                     let user = requests::users::fetch_by_ref(
                         ctx.client,
                         &api_url,
@@ -160,16 +217,38 @@ pub async fn handle_account_commands(
             // TODO: Don't generate a random client_id for each session
             let client_id = Uuid::new_v4().to_string();
 
-            let api_url = get_api_url(&account.user_ref.host);
-            let token_response = requests::auth::token_password(
+            let api_url = ctx.api_url_for(&account.user_ref.host).await;
+            let token_response = match requests::auth::token_password(
                 ctx.client,
                 &api_url,
                 &account.user_ref.name,
                 &password,
                 None,
+                None,
                 Some(&client_id),
             )
-            .await?;
+            .await
+            {
+                Err(e) if e.code().as_deref() == Some(MFA_REQUIRED) => {
+                    let otp = read_input("One-time or recovery code: ")?
+                        .ok_or_else(|| {
+                            CliError::InvalidArgument(
+                                "A one-time code is required.".into(),
+                            )
+                        })?;
+                    requests::auth::token_password(
+                        ctx.client,
+                        &api_url,
+                        &account.user_ref.name,
+                        &password,
+                        Some(&otp),
+                        None,
+                        Some(&client_id),
+                    )
+                    .await?
+                }
+                result => result?,
+            };
 
             // Store auth data
             let account_auth = AccountAuth {
@@ -258,6 +337,90 @@ pub async fn handle_account_commands(
             }
         }
 
+        AccountCommands::Passwd(passwd_args) => {
+            let user_ref = if let (Some(name), Some(host)) =
+                (&passwd_args.name, &passwd_args.host)
+            {
+                UserRef::new(name.clone(), host.clone())
+            } else {
+                ctx.account
+                    .ok_or(CliError::MissingAccount)?
+                    .user_ref
+                    .clone()
+            };
+            let current_password = read_input("Current password: ")?
+                .ok_or_else(|| {
+                    CliError::InvalidArgument("Password is required.".into())
+                })?;
+            let new_password = read_new_password()?;
+
+            let api_url = ctx.api_url_for(&user_ref.host).await;
+            let access_token =
+                ctx.get_access_token_for(&user_ref, &api_url).await?;
+            let keep_refresh_token = ctx
+                .auth_cache
+                .get(&user_ref)
+                .map(|auth| auth.refresh_token.clone());
+            let req = ChangePasswordRequest {
+                current_password,
+                new_password,
+                keep_refresh_token,
+            };
+            requests::auth::change_password(
+                ctx.client,
+                &api_url,
+                &access_token,
+                &req,
+            )
+            .await?;
+            println!("Changed password for: {user_ref}");
+        }
+
+        AccountCommands::ResetPassword(reset_args) => {
+            let host = unwrap_or_prompt(reset_args.host.clone(), "Host")?;
+            let token =
+                unwrap_or_prompt(reset_args.token.clone(), "Reset token")?;
+            let new_password = read_new_password()?;
+
+            let api_url = ctx.api_url_for(&host).await;
+            let req = PasswordResetRequest {
+                token,
+                new_password,
+            };
+            let user_ref =
+                requests::auth::reset_password(ctx.client, &api_url, &req)
+                    .await?;
+            // Any stored tokens were revoked by the reset
+            if ctx.auth_cache.remove(&user_ref).is_some() {
+                ctx.auth_cache.save()?;
+            }
+            println!("Reset password for: {user_ref}");
+            println!("Use 'rune account login' to log in again.");
+        }
+
+        AccountCommands::Totp(totp_args) => {
+            let user_ref = if let (Some(name), Some(host)) =
+                (&totp_args.name, &totp_args.host)
+            {
+                UserRef::new(name.clone(), host.clone())
+            } else {
+                ctx.account
+                    .ok_or(CliError::MissingAccount)?
+                    .user_ref
+                    .clone()
+            };
+            let api_url = ctx.api_url_for(&user_ref.host).await;
+            let access_token =
+                ctx.get_access_token_for(&user_ref, &api_url).await?;
+            handle_totp_command(
+                ctx,
+                &totp_args.command,
+                &api_url,
+                &access_token,
+            )
+            .await?;
+        }
+
         AccountCommands::Delete(delete_args) => {
             if ctx.config.accounts.is_empty() {
                 return Err(CliError::InvalidArgument(
@@ -291,7 +454,7 @@ pub async fn handle_account_commands(
                 account.user_ref.clone()
             };
 
-            let api_url = get_api_url(&user_ref.host);
+            let api_url = ctx.api_url_for(&user_ref.host).await;
             let access_token =
                 ctx.get_access_token_for(&user_ref, &api_url).await?;
 
@@ -320,3 +483,92 @@ pub async fn handle_account_commands(
     }
     Ok(())
 }
+
+/// Prompt for a new password twice, failing if the entries differ.
+fn read_new_password() -> Result<String, CliError> {
+    let password = read_input("New password: ")?.ok_or_else(|| {
+        CliError::InvalidArgument("Password is required.".into())
+    })?;
+    let confirmation = read_input("Repeat new password: ")?;
+    if confirmation.as_deref() != Some(password.as_str()) {
+        return Err(CliError::InvalidArgument(
+            "Passwords do not match.".into(),
+        ));
+    }
+    Ok(password)
+}
+
+async fn handle_totp_command(
+    ctx: &CliContext<'_>,
+    command: &TotpCommands,
+    api_url: &str,
+    access_token: &str,
+) -> Result<(), CliError> {
+    match command {
+        TotpCommands::Status => {
+            let status =
+                requests::auth::totp_status(ctx.client, api_url, access_token)
+                    .await?;
+            if status.enabled {
+                println!("Two-factor authentication: enabled");
+                println!(
+                    "Recovery codes remaining: {}",
+                    status.recovery_codes_remaining
+                );
+            } else {
+                println!("Two-factor authentication: disabled");
+            }
+        }
+
+        TotpCommands::Enable => {
+            let enrollment =
+                requests::auth::totp_enroll(ctx.client, api_url, access_token)
+                    .await?;
+            println!("Scan this QR code with your authenticator app:\n");
+            if let Some(qr) = util::render_qr_code(&enrollment.otpauth_uri) {
+                println!("{qr}");
+            }
+            println!("Or enter this secret manually: {}\n", enrollment.secret);
+            let code = read_input("Code from the app: ")?.ok_or_else(|| {
+                CliError::InvalidArgument("A one-time code is required.".into())
+            })?;
+            let recovery = requests::auth::totp_confirm(
+                ctx.client,
+                api_url,
+                access_token,
+                &TotpConfirmRequest { code },
+            )
+            .await?;
+            println!("Two-factor authentication enabled.");
+            println!(
+                "Store these recovery codes somewhere safe. Each can be used \
+                once instead of a code, and they won't be shown again:"
+            );
+            for code in recovery.codes {
+                println!("  {code}");
+            }
+        }
+
+        TotpCommands::Disable => {
+            let password = read_input("Password: ")?.ok_or_else(|| {
+                CliError::InvalidArgument("Password is required.".into())
+            })?;
+            let code = read_input("One-time or recovery code: ")?.ok_or_else(
+                || {
+                    CliError::InvalidArgument(
+                        "A one-time code is required.".into(),
+                    )
+                },
+            )?;
+            requests::auth::totp_disable(
+                ctx.client,
+                api_url,
+                access_token,
+                &TotpDisableRequest { password, code },
+            )
+            .await?;
+            println!("Two-factor authentication disabled.");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use runelink_client::requests;
+use runelink_types::{AuditLogQuery, NewPasswordReset, NewSignupInvite};
+use uuid::Uuid;
+
+use crate::{error::CliError, util::format_audit_entry};
+
+use super::context::CliContext;
+
+#[derive(clap::Args, Debug)]
+pub struct AdminArgs {
+    #[clap(subcommand)]
+    pub command: AdminCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum AdminCommands {
+    /// Manage signup invites
+    Invite(InviteArgs),
+    /// Manage signups waiting for approval
+    Signup(SignupArgs),
+    /// Issue a one-time password reset token for a local account
+    ResetPassword(ResetPasswordArgs),
+    /// Show the host's audit log
+    AuditLog(AuditLogArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct InviteArgs {
+    #[clap(subcommand)]
+    pub command: InviteCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum InviteCommands {
+    /// Create a new signup invite
+    Create(InviteCreateArgs),
+    /// List signup invites
+    List,
+    /// Revoke a signup invite
+    Revoke(InviteRevokeArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct InviteCreateArgs {
+    /// A note to remember the invite by
+    #[clap(long)]
+    pub note: Option<String>,
+    /// How many accounts the invite may create (unlimited if not provided)
+    #[clap(long)]
+    pub max_uses: Option<i32>,
+    /// Hours until the invite expires (never if not provided)
+    #[clap(long)]
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct InviteRevokeArgs {
+    /// The ID of the invite
+    #[clap(long)]
+    pub invite_id: Uuid,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SignupArgs {
+    #[clap(subcommand)]
+    pub command: SignupCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum SignupCommands {
+    /// List accounts waiting for approval
+    List,
+    /// Approve a pending account
+    Approve(SignupNameArgs),
+    /// Reject a pending account (deletes it)
+    Reject(SignupNameArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SignupNameArgs {
+    /// The username of the pending account
+    #[clap(long)]
+    pub name: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ResetPasswordArgs {
+    /// The username of the local account
+    #[clap(long)]
+    pub name: String,
+    /// Hours until the token expires (defaults to 24)
+    #[clap(long)]
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct AuditLogArgs {
+    /// Only show entries for this server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// Only show this action (e.g. user.delete)
+    #[clap(long)]
+    pub action: Option<String>,
+    /// Only show actions by users from this host
+    #[clap(long)]
+    pub actor_host: Option<String>,
+    /// Only show entries older than this entry ID
+    #[clap(long)]
+    pub before: Option<i64>,
+    /// Maximum number of entries to show
+    #[clap(long)]
+    pub limit: Option<i64>,
+}
+
+pub async fn handle_admin_commands(
+    ctx: &mut CliContext<'_>,
+    admin_args: &AdminArgs,
+) -> Result<(), CliError> {
+    let api_url = ctx.home_api_url().await?;
+    let access_token = ctx.get_access_token().await?;
+    match &admin_args.command {
+        AdminCommands::Invite(invite_args) => match &invite_args.command {
+            InviteCommands::Create(create_args) => {
+                let new_invite = NewSignupInvite {
+                    note: create_args.note.clone(),
+                    max_uses: create_args.max_uses,
+                    expires_in_hours: create_args.expires_in_hours,
+                };
+                let issued = requests::signups::create_invite(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    &new_invite,
+                )
+                .await?;
+                println!("Created invite {}", issued.invite.id);
+                println!("Token (shown only once): {}", issued.token);
+            }
+            InviteCommands::List => {
+                let invites = requests::signups::fetch_invites(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                )
+                .await?;
+                if invites.is_empty() {
+                    println!("No invites.");
+                }
+                for invite in invites {
+                    let uses = match invite.max_uses {
+                        Some(max) => format!("{}/{max}", invite.uses),
+                        None => invite.uses.to_string(),
+                    };
+                    let note = invite.note.as_deref().unwrap_or("");
+                    println!("{} (uses: {uses}) {note}", invite.id);
+                }
+            }
+            InviteCommands::Revoke(revoke_args) => {
+                requests::signups::delete_invite(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    revoke_args.invite_id,
+                )
+                .await?;
+                println!("Revoked invite {}", revoke_args.invite_id);
+            }
+        },
+
+        AdminCommands::Signup(signup_args) => match &signup_args.command {
+            SignupCommands::List => {
+                let users = requests::signups::fetch_pending(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                )
+                .await?;
+                if users.is_empty() {
+                    println!("No pending signups.");
+                }
+                for user in users {
+                    println!("{user}");
+                }
+            }
+            SignupCommands::Approve(name_args) => {
+                requests::signups::approve(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    &name_args.name,
+                )
+                .await?;
+                println!("Approved {}", name_args.name);
+            }
+            SignupCommands::Reject(name_args) => {
+                requests::signups::reject(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    &name_args.name,
+                )
+                .await?;
+                println!("Rejected {}", name_args.name);
+            }
+        },
+
+        AdminCommands::ResetPassword(reset_args) => {
+            let new_reset = NewPasswordReset {
+                expires_in_hours: reset_args.expires_in_hours,
+            };
+            let issued = requests::auth::create_password_reset(
+                ctx.client,
+                &api_url,
+                &access_token,
+                &reset_args.name,
+                &new_reset,
+            )
+            .await?;
+            println!(
+                "Reset token for {} (shown only once, expires {}): {}",
+                issued.user_ref, issued.expires_at, issued.token
+            );
+            println!(
+                "Redeem it with 'rune account reset-password --host {}'",
+                issued.user_ref.host
+            );
+        }
+
+        AdminCommands::AuditLog(audit_args) => {
+            let query = AuditLogQuery {
+                action: audit_args.action.clone(),
+                actor_host: audit_args.actor_host.clone(),
+                before: audit_args.before,
+                limit: audit_args.limit,
+            };
+            let entries = requests::audit::fetch_all(
+                ctx.client,
+                &api_url,
+                &access_token,
+                audit_args.server_id,
+                &query,
+            )
+            .await?;
+            if entries.is_empty() {
+                println!("No audit log entries.");
+            }
+            for entry in entries {
+                println!("{}", format_audit_entry(&entry));
+            }
+        }
+    }
+    Ok(())
+}
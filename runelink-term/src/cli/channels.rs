@@ -1,5 +1,10 @@
+use log::warn;
 use runelink_client::requests;
-use runelink_types::NewChannel;
+use runelink_types::{
+    Channel, ChannelCategoryUpdate, ChannelUpdate, NewChannel,
+    NewChannelCategory, NewReadMarker, ServerWithChannels,
+};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::{
@@ -8,6 +13,7 @@ use crate::{
         get_server_selection,
     },
     error::CliError,
+    util::format_pinned_message,
 };
 
 use super::{
@@ -29,8 +35,84 @@ pub enum ChannelCommands {
     Get(ChannelGetArgs),
     /// Create a new channel
     Create(ChannelCreateArgs),
-    /// Delete a channel
+    /// Rename, describe or move a channel
+    Edit(ChannelEditArgs),
+    /// Delete a channel (it stays in the trash for a while and can be
+    /// restored)
     Delete(ChannelDeleteArgs),
+    /// Archive a channel, making it read-only and hiding it from listings
+    Archive(ChannelArchiveArgs),
+    /// Make an archived channel writable and listed again
+    Unarchive(ChannelIdArgs),
+    /// Take a deleted channel back out of the trash
+    Restore(ChannelIdArgs),
+    /// Mark a channel as read
+    MarkRead(ChannelMarkReadArgs),
+    /// List the messages pinned in a channel
+    Pins(ChannelPinsArgs),
+    /// Manage the channel categories of a server
+    Category(ChannelCategoryArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ChannelCategoryArgs {
+    #[clap(subcommand)]
+    pub command: ChannelCategoryCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum ChannelCategoryCommands {
+    /// List the categories of a server
+    List(CategoryServerArgs),
+    /// Create a new category at the end of the list
+    Create(CategoryCreateArgs),
+    /// Rename or move a category
+    Edit(CategoryEditArgs),
+    /// Delete a category (its channels are kept, uncategorized)
+    Delete(CategoryDeleteArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CategoryServerArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CategoryCreateArgs {
+    #[clap(flatten)]
+    pub server: CategoryServerArgs,
+    /// The title of the category
+    #[clap(long)]
+    pub title: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CategoryEditArgs {
+    #[clap(flatten)]
+    pub server: CategoryServerArgs,
+    /// The ID of the category
+    #[clap(long)]
+    pub category_id: Uuid,
+    /// The new title of the category
+    #[clap(long)]
+    pub title: Option<String>,
+    /// Move the category to this position in the list (0 is the top)
+    #[clap(long)]
+    pub position: Option<i32>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CategoryDeleteArgs {
+    #[clap(flatten)]
+    pub server: CategoryServerArgs,
+    /// The ID of the category
+    #[clap(long)]
+    pub category_id: Uuid,
 }
 
 #[derive(clap::Args, Debug)]
@@ -44,6 +126,9 @@ pub struct ChannelListArgs {
     /// The host of host or server
     #[clap(long)]
     pub host: Option<String>,
+    /// Also list archived channels
+    #[clap(long)]
+    pub archived: bool,
 }
 
 #[derive(clap::Args, Debug)]
@@ -78,6 +163,159 @@ pub struct ChannelCreateArgs {
     pub host: Option<String>,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct ChannelEditArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The ID of the channel to edit
+    #[clap(long)]
+    pub channel_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+    /// The new title of the channel
+    #[clap(long)]
+    pub title: Option<String>,
+    /// The new description of the channel
+    #[clap(long, conflicts_with = "clear_description")]
+    pub description: Option<String>,
+    /// Remove the channel's description
+    #[clap(long)]
+    pub clear_description: bool,
+    /// Move the channel to this position in the list (0 is the top)
+    #[clap(long)]
+    pub position: Option<i32>,
+    /// Move the channel into this category
+    #[clap(long, conflicts_with = "no_category")]
+    pub category_id: Option<Uuid>,
+    /// Take the channel out of its category
+    #[clap(long)]
+    pub no_category: bool,
+}
+
+/// The server a category command acts on, prompting for one of the user's
+/// servers if no ID was given.
+async fn category_server(
+    ctx: &mut CliContext<'_>,
+    args: &CategoryServerArgs,
+) -> Result<(Uuid, Option<String>), CliError> {
+    if let Some(server_id) = args.server_id {
+        Ok((server_id, args.host.clone()))
+    } else {
+        let server =
+            get_server_selection(ctx, ServerSelectionType::MemberOnly).await?;
+        Ok((server.id, Some(server.host)))
+    }
+}
+
+/// Unread message counts by channel. Listings still work without them, so
+/// failures are only logged.
+async fn fetch_unread_counts(
+    ctx: &CliContext<'_>,
+    api_url: &str,
+    access_token: &str,
+) -> HashMap<Uuid, i64> {
+    match requests::read_markers::fetch_unread(
+        ctx.client,
+        api_url,
+        access_token,
+    )
+    .await
+    {
+        Ok(unread) => unread
+            .into_iter()
+            .map(|channel| (channel.channel_id, channel.unread_count))
+            .collect(),
+        Err(e) => {
+            warn!("Failed to fetch unread counts: {e}");
+            HashMap::new()
+        }
+    }
+}
+
+/// A channel's listing line, with a badge if it has unread messages.
+fn with_unread_badge(channel: &Channel, unread: &HashMap<Uuid, i64>) -> String {
+    match unread.get(&channel.id) {
+        Some(&count) if count > 0 => {
+            format!("{} [{count} unread]", channel.verbose())
+        }
+        _ => channel.verbose(),
+    }
+}
+
+/// Print a server's channels under their categories.
+fn print_grouped(server: &ServerWithChannels, unread: &HashMap<Uuid, i64>) {
+    for (category, channels) in server.grouped() {
+        let indent = match category {
+            Some(category) => {
+                println!("{}", category.verbose());
+                "    "
+            }
+            None => "",
+        };
+        for channel in channels {
+            println!("{indent}{}", with_unread_badge(channel, unread));
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ChannelArchiveArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The ID of the channel to archive
+    #[clap(long)]
+    pub channel_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ChannelIdArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Uuid,
+    /// The ID of the channel
+    #[clap(long)]
+    pub channel_id: Uuid,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ChannelMarkReadArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The ID of the channel
+    #[clap(long)]
+    pub channel_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+    /// Mark the channel as read up to this message (the newest if not
+    /// provided)
+    #[clap(long)]
+    pub message_id: Option<Uuid>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ChannelPinsArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The ID of the channel
+    #[clap(long)]
+    pub channel_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
 #[derive(clap::Args, Debug)]
 pub struct ChannelDeleteArgs {
     /// The ID of the server
@@ -98,32 +336,35 @@ pub async fn handle_channel_commands(
     match &channel_args.command {
         ChannelCommands::List(list_args) => {
             ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
-            let channels = match (list_args.server_id, list_args.all) {
+            let (server_id, host) = match (list_args.server_id, list_args.all) {
                 (Some(_server_id), true) => {
                     return Err(CliError::InvalidArgument(
                         "Cannot use --all with --server-id.".into(),
                     ));
                 }
-                (Some(server_id), false) => {
-                    requests::channels::fetch_by_server(
-                        ctx.client,
-                        &api_url,
-                        &access_token,
-                        server_id,
-                        list_args.host.as_deref(),
-                    )
-                    .await?
-                }
+                (Some(server_id), false) => (server_id, list_args.host.clone()),
                 (None, true) => {
-                    requests::channels::fetch_all(
+                    let channels = requests::channels::fetch_all(
                         ctx.client,
                         &api_url,
                         &access_token,
                         list_args.host.as_deref(),
                     )
-                    .await?
+                    .await?;
+                    if channels.is_empty() {
+                        println!(
+                            "No channels available.\n\
+                            For more information, try `rune channel --help`."
+                        )
+                    }
+                    let unread =
+                        fetch_unread_counts(ctx, &api_url, &access_token).await;
+                    for channel in channels {
+                        println!("{}", with_unread_badge(&channel, &unread));
+                    }
+                    return Ok(());
                 }
                 (None, false) => {
                     let server = get_server_selection(
@@ -131,30 +372,42 @@ pub async fn handle_channel_commands(
                         ServerSelectionType::MemberOnly,
                     )
                     .await?;
-                    requests::channels::fetch_by_server(
-                        ctx.client,
-                        &api_url,
-                        &access_token,
-                        server.id,
-                        Some(server.host.as_str()),
-                    )
-                    .await?
+                    (server.id, Some(server.host))
                 }
             };
-            if channels.is_empty() {
+            let mut server = requests::servers::fetch_with_channels(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                host.as_deref(),
+            )
+            .await?;
+            if list_args.archived {
+                server.channels = requests::channels::fetch_by_server(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    server_id,
+                    true,
+                    host.as_deref(),
+                )
+                .await?;
+            }
+            if server.channels.is_empty() && server.categories.is_empty() {
                 println!(
                     "No channels available.\n\
                     For more information, try `rune channel --help`."
                 )
             }
-            for channel in channels {
-                println!("{}", channel.verbose());
-            }
+            let unread =
+                fetch_unread_counts(ctx, &api_url, &access_token).await;
+            print_grouped(&server, &unread);
         }
 
         ChannelCommands::Get(get_args) => {
             ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let channel = requests::channels::fetch_by_id(
                 ctx.client,
@@ -170,7 +423,7 @@ pub async fn handle_channel_commands(
 
         ChannelCommands::Create(create_args) => {
             let account = ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let server = match create_args.server_id {
                 Some(server_id) => {
@@ -217,9 +470,64 @@ pub async fn handle_channel_commands(
             println!("Created channel: {}", channel.verbose());
         }
 
+        ChannelCommands::Edit(edit_args) => {
+            let _account = ctx.account.ok_or(CliError::MissingAccount)?;
+            let description = if edit_args.clear_description {
+                Some(None)
+            } else {
+                edit_args.description.clone().map(Some)
+            };
+            let category_id = if edit_args.no_category {
+                Some(None)
+            } else {
+                edit_args.category_id.map(Some)
+            };
+            let update = ChannelUpdate {
+                title: edit_args.title.clone(),
+                description,
+                position: edit_args.position,
+                category_id,
+            };
+            if update == ChannelUpdate::default() {
+                println!("Nothing to change.");
+                return Ok(());
+            }
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, channel_id, server_host) =
+                match (edit_args.server_id, edit_args.channel_id) {
+                    (Some(server_id), Some(channel_id)) => {
+                        (server_id, channel_id, None)
+                    }
+                    _ => {
+                        let (server, channel) =
+                            get_channel_selection_with_inputs(
+                                ctx,
+                                edit_args.channel_id,
+                                edit_args.server_id,
+                            )
+                            .await?;
+                        (server.id, channel.id, Some(server.host.clone()))
+                    }
+                };
+            let target_host =
+                server_host.as_deref().or(edit_args.host.as_deref());
+            let channel = requests::channels::update(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                channel_id,
+                &update,
+                target_host,
+            )
+            .await?;
+            println!("Updated channel: {}", channel.verbose());
+        }
+
         ChannelCommands::Delete(delete_args) => {
             let _account = ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let (server_id, channel_id, server_host) =
                 match (delete_args.server_id, delete_args.channel_id) {
@@ -239,9 +547,8 @@ pub async fn handle_channel_commands(
                         (server.id, channel.id, Some(server.host.clone()))
                     }
                 };
-            let target_host = server_host
-                .as_deref()
-                .or_else(|| delete_args.host.as_deref());
+            let target_host =
+                server_host.as_deref().or(delete_args.host.as_deref());
             requests::channels::delete(
                 ctx.client,
                 &api_url,
@@ -251,8 +558,261 @@ pub async fn handle_channel_commands(
                 target_host,
             )
             .await?;
-            println!("Deleted channel: {channel_id}");
+            println!(
+                "Deleted channel: {channel_id}\n\
+                It can be restored with `rune channel restore` until it is \
+                purged."
+            );
+        }
+
+        ChannelCommands::Archive(archive_args) => {
+            let _account = ctx.account.ok_or(CliError::MissingAccount)?;
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, channel_id, server_host) =
+                match (archive_args.server_id, archive_args.channel_id) {
+                    (Some(server_id), Some(channel_id)) => {
+                        (server_id, channel_id, None)
+                    }
+                    _ => {
+                        let (server, channel) =
+                            get_channel_selection_with_inputs(
+                                ctx,
+                                archive_args.channel_id,
+                                archive_args.server_id,
+                            )
+                            .await?;
+                        (server.id, channel.id, Some(server.host.clone()))
+                    }
+                };
+            let target_host =
+                server_host.as_deref().or(archive_args.host.as_deref());
+            let channel = requests::channels::archive(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                channel_id,
+                target_host,
+            )
+            .await?;
+            println!("Archived channel: {}", channel.verbose());
+        }
+
+        ChannelCommands::MarkRead(mark_args) => {
+            let _account = ctx.account.ok_or(CliError::MissingAccount)?;
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, channel_id, server_host) =
+                match (mark_args.server_id, mark_args.channel_id) {
+                    (Some(server_id), Some(channel_id)) => {
+                        (server_id, channel_id, None)
+                    }
+                    _ => {
+                        let (server, channel) =
+                            get_channel_selection_with_inputs(
+                                ctx,
+                                mark_args.channel_id,
+                                mark_args.server_id,
+                            )
+                            .await?;
+                        (server.id, channel.id, Some(server.host.clone()))
+                    }
+                };
+            let target_host =
+                server_host.as_deref().or(mark_args.host.as_deref());
+            let message_id = match mark_args.message_id {
+                Some(message_id) => message_id,
+                None => {
+                    let messages = requests::messages::fetch_by_channel(
+                        ctx.client,
+                        &api_url,
+                        &access_token,
+                        server_id,
+                        channel_id,
+                        false,
+                        target_host,
+                    )
+                    .await?;
+                    // Messages come newest first
+                    let Some(newest) = messages.first() else {
+                        println!("No messages to mark as read.");
+                        return Ok(());
+                    };
+                    newest.id
+                }
+            };
+            requests::read_markers::set(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                channel_id,
+                &NewReadMarker { message_id },
+                target_host,
+            )
+            .await?;
+            println!("Marked channel {channel_id} as read");
+        }
+
+        ChannelCommands::Pins(pins_args) => {
+            let _account = ctx.account.ok_or(CliError::MissingAccount)?;
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, channel_id, server_host) =
+                match (pins_args.server_id, pins_args.channel_id) {
+                    (Some(server_id), Some(channel_id)) => {
+                        (server_id, channel_id, None)
+                    }
+                    _ => {
+                        let (server, channel) =
+                            get_channel_selection_with_inputs(
+                                ctx,
+                                pins_args.channel_id,
+                                pins_args.server_id,
+                            )
+                            .await?;
+                        (server.id, channel.id, Some(server.host.clone()))
+                    }
+                };
+            let target_host =
+                server_host.as_deref().or(pins_args.host.as_deref());
+            let pins = requests::pins::fetch_by_channel(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                channel_id,
+                target_host,
+            )
+            .await?;
+            if pins.is_empty() {
+                println!("No pinned messages.");
+            }
+            for pin in pins {
+                println!("{}", format_pinned_message(&pin));
+            }
+        }
+
+        ChannelCommands::Unarchive(id_args) => {
+            let _account = ctx.account.ok_or(CliError::MissingAccount)?;
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let channel = requests::channels::unarchive(
+                ctx.client,
+                &api_url,
+                &access_token,
+                id_args.server_id,
+                id_args.channel_id,
+                id_args.host.as_deref(),
+            )
+            .await?;
+            println!("Unarchived channel: {}", channel.verbose());
+        }
+
+        ChannelCommands::Restore(id_args) => {
+            let _account = ctx.account.ok_or(CliError::MissingAccount)?;
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let channel = requests::channels::restore(
+                ctx.client,
+                &api_url,
+                &access_token,
+                id_args.server_id,
+                id_args.channel_id,
+                id_args.host.as_deref(),
+            )
+            .await?;
+            println!("Restored channel: {}", channel.verbose());
+        }
+
+        ChannelCommands::Category(category_args) => {
+            handle_category_commands(ctx, category_args).await?;
         }
     };
     Ok(())
 }
+
+async fn handle_category_commands(
+    ctx: &mut CliContext<'_>,
+    category_args: &ChannelCategoryArgs,
+) -> Result<(), CliError> {
+    let api_url = ctx.home_api_url().await?;
+    let access_token = ctx.get_access_token().await?;
+    match &category_args.command {
+        ChannelCategoryCommands::List(list_args) => {
+            let (server_id, host) = category_server(ctx, list_args).await?;
+            let categories = requests::categories::fetch_by_server(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                host.as_deref(),
+            )
+            .await?;
+            if categories.is_empty() {
+                println!("No categories.");
+            }
+            for category in categories {
+                println!("{}", category.verbose());
+            }
+        }
+
+        ChannelCategoryCommands::Create(create_args) => {
+            let (server_id, host) =
+                category_server(ctx, &create_args.server).await?;
+            let title =
+                unwrap_or_prompt(create_args.title.clone(), "Category Title")?;
+            let category = requests::categories::create(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                &NewChannelCategory { title },
+                host.as_deref(),
+            )
+            .await?;
+            println!("Created category: {}", category.verbose());
+        }
+
+        ChannelCategoryCommands::Edit(edit_args) => {
+            let update = ChannelCategoryUpdate {
+                title: edit_args.title.clone(),
+                position: edit_args.position,
+            };
+            if update == ChannelCategoryUpdate::default() {
+                println!("Nothing to change.");
+                return Ok(());
+            }
+            let (server_id, host) =
+                category_server(ctx, &edit_args.server).await?;
+            let category = requests::categories::update(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                edit_args.category_id,
+                &update,
+                host.as_deref(),
+            )
+            .await?;
+            println!("Updated category: {}", category.verbose());
+        }
+
+        ChannelCategoryCommands::Delete(delete_args) => {
+            let (server_id, host) =
+                category_server(ctx, &delete_args.server).await?;
+            requests::categories::delete(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                delete_args.category_id,
+                host.as_deref(),
+            )
+            .await?;
+            println!("Deleted category: {}", delete_args.category_id);
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use reqwest::Client;
-use runelink_client::requests;
+use runelink_client::{requests, resolver::HostResolver};
 use runelink_types::UserRef;
 use time::OffsetDateTime;
 
@@ -12,16 +12,23 @@ pub struct CliContext<'a> {
     pub config: &'a mut AppConfig,
     pub auth_cache: &'a mut AuthCache,
     pub account: Option<&'a AccountConfig>,
+    pub resolver: HostResolver,
 }
 
 impl<'a> CliContext<'a> {
-    pub fn home_api_url(&self) -> Result<String, CliError> {
-        self.account.try_get_api_url()
+    pub async fn home_api_url(&self) -> Result<String, CliError> {
+        let host = self.account.try_get_host()?;
+        Ok(self.api_url_for(host).await)
+    }
+
+    /// API base URL of `host`, resolved through its discovery document
+    pub async fn api_url_for(&self, host: &str) -> String {
+        self.resolver.api_url(self.client, host).await
     }
 
     pub async fn get_access_token(&mut self) -> Result<String, CliError> {
         let account = self.account.ok_or(CliError::MissingAccount)?;
-        let api_url = self.home_api_url()?;
+        let api_url = self.home_api_url().await?;
         self.get_access_token_for(&account.user_ref, &api_url).await
     }
 
@@ -1,8 +1,14 @@
+use std::path::{Path, PathBuf};
+
 use runelink_client::requests;
-use runelink_types::NewMessage;
+use runelink_types::{AttachmentUpload, NewMessage, NewScheduledMessage};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
-use crate::error::CliError;
+use crate::{
+    error::CliError,
+    util::{format_message, format_scheduled_message, parse_send_at},
+};
 
 use super::{
     context::CliContext, input::unwrap_or_prompt,
@@ -25,6 +31,16 @@ pub enum MessageCommands {
     Send(MessageSendArgs),
     /// Delete a message
     Delete(MessageDeleteArgs),
+    /// Download an attachment of a message
+    Download(MessageDownloadArgs),
+    /// Pin a message in its channel
+    Pin(MessagePinArgs),
+    /// Unpin a message
+    Unpin(MessagePinArgs),
+    /// List your scheduled messages
+    Scheduled,
+    /// Cancel a scheduled message
+    Cancel(MessageCancelArgs),
 }
 
 #[derive(clap::Args, Debug)]
@@ -35,6 +51,9 @@ pub struct MessageListArgs {
     /// Optional: Filter messages by Channel ID
     #[clap(long)]
     pub channel_id: Option<Uuid>,
+    /// Also list tombstones of deleted messages
+    #[clap(long)]
+    pub deleted: bool,
 }
 
 #[derive(clap::Args, Debug)]
@@ -67,6 +86,13 @@ pub struct MessageSendArgs {
     /// The host of the server
     #[clap(long)]
     pub host: Option<String>,
+    /// A file to attach; can be given more than once
+    #[clap(long = "attach", value_name = "PATH")]
+    pub attachments: Vec<PathBuf>,
+    /// Send the message later instead: an RFC 3339 time, or a delay such
+    /// as +30m (s, m, h or d)
+    #[clap(long, value_name = "TIME", value_parser = parse_send_at)]
+    pub at: Option<OffsetDateTime>,
 }
 
 #[derive(clap::Args, Debug)]
@@ -85,6 +111,65 @@ pub struct MessageDeleteArgs {
     pub host: Option<String>,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct MessageCancelArgs {
+    /// The ID of the scheduled message
+    #[clap(long)]
+    pub scheduled_id: Uuid,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MessagePinArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Uuid,
+    /// The ID of the channel
+    #[clap(long)]
+    pub channel_id: Uuid,
+    /// The ID of the message
+    #[clap(long)]
+    pub message_id: Uuid,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MessageDownloadArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Uuid,
+    /// The ID of the channel
+    #[clap(long)]
+    pub channel_id: Uuid,
+    /// The ID of the attachment to download
+    #[clap(long)]
+    pub attachment_id: Uuid,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+    /// Where to save the file [default: the attachment's name]
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Read a file to upload, guessing its MIME type from its extension.
+fn read_attachment(path: &Path) -> Result<AttachmentUpload, CliError> {
+    let data = std::fs::read(path)?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mime_type = mime_guess::from_path(path)
+        .first_or_octet_stream()
+        .to_string();
+    Ok(AttachmentUpload {
+        name,
+        mime_type,
+        data,
+    })
+}
+
 pub async fn handle_message_commands(
     ctx: &mut CliContext<'_>,
     message_args: &MessageArgs,
@@ -99,7 +184,7 @@ pub async fn handle_message_commands(
                 list_args.server_id,
             )
             .await?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let target_host = if server.host != account.user_ref.host {
                 Some(server.host.as_str())
@@ -112,17 +197,18 @@ pub async fn handle_message_commands(
                 &access_token,
                 server.id,
                 channel.id,
+                list_args.deleted,
                 target_host,
             )
             .await?;
             for message in messages.iter().rev() {
-                println!("{message}");
+                println!("{}", format_message(message));
             }
         }
 
         MessageCommands::Get(get_args) => {
             ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let message = requests::messages::fetch_by_id(
                 ctx.client,
@@ -134,7 +220,7 @@ pub async fn handle_message_commands(
                 get_args.host.as_deref(),
             )
             .await?;
-            println!("{message}");
+            println!("{}", format_message(&message));
         }
 
         MessageCommands::Send(send_args) => {
@@ -145,13 +231,14 @@ pub async fn handle_message_commands(
                 send_args.server_id,
             )
             .await?;
-            let body = unwrap_or_prompt(send_args.body.clone(), "Message")?;
-            let api_url = ctx.home_api_url()?;
-            let access_token = ctx.get_access_token().await?;
-            let new_message = NewMessage {
-                author: account.user_ref.clone(),
-                body,
+            // A message with attachments needs no body
+            let body = if send_args.attachments.is_empty() {
+                unwrap_or_prompt(send_args.body.clone(), "Message")?
+            } else {
+                send_args.body.clone().unwrap_or_default()
             };
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
             let target_host = send_args.host.as_deref().or_else(|| {
                 if server.host != account.user_ref.host {
                     Some(server.host.as_str())
@@ -159,6 +246,56 @@ pub async fn handle_message_commands(
                     None
                 }
             });
+            if let Some(send_at) = send_args.at {
+                if !send_args.attachments.is_empty() {
+                    return Err(CliError::InvalidArgument(
+                        "Scheduled messages can't have attachments.".into(),
+                    ));
+                }
+                let new_scheduled = NewScheduledMessage {
+                    message: NewMessage {
+                        author: account.user_ref.clone(),
+                        body,
+                        attachment_ids: Vec::new(),
+                    },
+                    send_at,
+                };
+                let scheduled = requests::scheduled_messages::create(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    server.id,
+                    channel.id,
+                    &new_scheduled,
+                    target_host,
+                )
+                .await?;
+                println!(
+                    "Scheduled message: {}",
+                    format_scheduled_message(&scheduled)
+                );
+                return Ok(());
+            }
+            let mut attachment_ids = Vec::new();
+            for path in &send_args.attachments {
+                let upload = read_attachment(path)?;
+                let attachment = requests::attachments::upload(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    server.id,
+                    channel.id,
+                    &upload,
+                    target_host,
+                )
+                .await?;
+                attachment_ids.push(attachment.id);
+            }
+            let new_message = NewMessage {
+                author: account.user_ref.clone(),
+                body,
+                attachment_ids,
+            };
             let message = requests::messages::create(
                 ctx.client,
                 &api_url,
@@ -169,12 +306,12 @@ pub async fn handle_message_commands(
                 target_host,
             )
             .await?;
-            println!("Sent message: {}", message.body);
+            println!("Sent message: {message}");
         }
 
         MessageCommands::Delete(delete_args) => {
             // TODO: Interactive message selection
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             requests::messages::delete(
                 ctx.client,
@@ -188,6 +325,105 @@ pub async fn handle_message_commands(
             .await?;
             println!("Deleted message: {}", delete_args.message_id);
         }
+
+        MessageCommands::Download(download_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let attachment = requests::attachments::fetch_by_id(
+                ctx.client,
+                &api_url,
+                &access_token,
+                download_args.server_id,
+                download_args.channel_id,
+                download_args.attachment_id,
+                download_args.host.as_deref(),
+            )
+            .await?;
+            let data = requests::attachments::fetch_content(
+                ctx.client,
+                &api_url,
+                &access_token,
+                download_args.server_id,
+                download_args.channel_id,
+                download_args.attachment_id,
+                download_args.host.as_deref(),
+            )
+            .await?;
+            // Never let a remote name point outside the current directory
+            let output = download_args.output.clone().unwrap_or_else(|| {
+                Path::new(&attachment.name)
+                    .file_name()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| attachment.id.to_string().into())
+            });
+            std::fs::write(&output, data)?;
+            println!("Saved {attachment} to {}", output.display());
+        }
+
+        MessageCommands::Scheduled => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let scheduled = requests::scheduled_messages::fetch_all(
+                ctx.client,
+                &api_url,
+                &access_token,
+            )
+            .await?;
+            if scheduled.is_empty() {
+                println!("No scheduled messages.");
+            }
+            for scheduled in scheduled {
+                println!("{}", format_scheduled_message(&scheduled));
+            }
+        }
+
+        MessageCommands::Cancel(cancel_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            requests::scheduled_messages::cancel(
+                ctx.client,
+                &api_url,
+                &access_token,
+                cancel_args.scheduled_id,
+            )
+            .await?;
+            println!(
+                "Cancelled scheduled message: {}",
+                cancel_args.scheduled_id
+            );
+        }
+
+        MessageCommands::Pin(pin_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            requests::pins::pin(
+                ctx.client,
+                &api_url,
+                &access_token,
+                pin_args.server_id,
+                pin_args.channel_id,
+                pin_args.message_id,
+                pin_args.host.as_deref(),
+            )
+            .await?;
+            println!("Pinned message: {}", pin_args.message_id);
+        }
+
+        MessageCommands::Unpin(pin_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            requests::pins::unpin(
+                ctx.client,
+                &api_url,
+                &access_token,
+                pin_args.server_id,
+                pin_args.channel_id,
+                pin_args.message_id,
+                pin_args.host.as_deref(),
+            )
+            .await?;
+            println!("Unpinned message: {}", pin_args.message_id);
+        }
     };
     Ok(())
 }
@@ -3,16 +3,20 @@ use clap_complete::Shell;
 use context::CliContext;
 use log::LevelFilter;
 use reqwest::Client;
+use runelink_client::{resolver::HostResolver, util::Scheme};
 use runelink_types::UserRef;
 
 use crate::{error::CliError, storage::AppConfig, storage_auth::AuthCache};
 
 pub mod account;
+pub mod admin;
 pub mod channels;
 pub mod config;
 pub mod context;
 pub mod input;
 pub mod messages;
+pub mod notifications;
+pub mod presence;
 pub mod select;
 pub mod servers;
 pub mod users;
@@ -33,6 +37,10 @@ pub struct Cli {
     /// Increase logging verbosity (-v, -vv, -vvv)
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
+    /// Talk to hosts over plaintext HTTP (local development only).
+    /// Can also be enabled with RUNELINK_INSECURE_HTTP=1.
+    #[clap(long, global = true)]
+    pub insecure_http: bool,
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -43,12 +51,18 @@ pub enum Commands {
     Channel(channels::ChannelArgs),
     /// Manage messages
     Message(messages::MessageArgs),
+    /// List and dismiss mentions of you
+    Notifications(notifications::NotificationArgs),
+    /// Report presence and see who is online or typing
+    Presence(presence::PresenceArgs),
     /// Manage servers
     Server(servers::ServerArgs),
     /// Manage users
     User(users::UserArgs),
     /// Manage config
     Config(config::ConfigArgs),
+    /// Host administration
+    Admin(admin::AdminArgs),
     /// Generate shell completion scripts
     Completions(CompletionsArgs),
 }
@@ -77,6 +91,13 @@ pub async fn handle_cli(
     auth_cache: &mut AuthCache,
 ) -> Result<(), CliError> {
     init_logging(cli.verbose);
+    let insecure_env = std::env::var("RUNELINK_INSECURE_HTTP")
+        .is_ok_and(|v| matches!(v.as_str(), "1" | "true"));
+    let scheme = if cli.insecure_http || insecure_env {
+        Scheme::Http
+    } else {
+        Scheme::Https
+    };
     let account_owned = match (&cli.name, &cli.host) {
         (Some(name), Some(host)) => {
             let user_ref = UserRef::new(name.clone(), host.clone());
@@ -89,6 +110,7 @@ pub async fn handle_cli(
         config,
         auth_cache,
         account: account_owned.as_ref(),
+        resolver: HostResolver::new().with_scheme(scheme),
     };
     let ctx = &mut ctx_owned;
 
@@ -102,6 +124,12 @@ pub async fn handle_cli(
         Commands::Message(args) => {
             messages::handle_message_commands(ctx, args).await?;
         }
+        Commands::Notifications(args) => {
+            notifications::handle_notification_commands(ctx, args).await?;
+        }
+        Commands::Presence(args) => {
+            presence::handle_presence_commands(ctx, args).await?;
+        }
         Commands::Server(args) => {
             servers::handle_server_commands(ctx, args).await?;
         }
@@ -111,6 +139,9 @@ pub async fn handle_cli(
         Commands::Config(args) => {
             config::handle_config_commands(ctx, args).await?;
         }
+        Commands::Admin(args) => {
+            admin::handle_admin_commands(ctx, args).await?;
+        }
         Commands::Completions(args) => {
             let mut cmd = Cli::command();
             let cmd_name = cmd.get_name().to_string();
@@ -0,0 +1,75 @@
+use runelink_client::requests;
+use uuid::Uuid;
+
+use crate::{error::CliError, util::format_notification};
+
+use super::context::CliContext;
+
+#[derive(clap::Args, Debug)]
+pub struct NotificationArgs {
+    #[clap(subcommand)]
+    pub command: Option<NotificationCommands>,
+    /// Also list notifications that were already read
+    #[clap(long)]
+    pub all: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum NotificationCommands {
+    /// Mark notifications as read
+    Read(NotificationReadArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct NotificationReadArgs {
+    /// The ID of the notification (all of them if not provided)
+    #[clap(long)]
+    pub id: Option<Uuid>,
+}
+
+pub async fn handle_notification_commands(
+    ctx: &mut CliContext<'_>,
+    notification_args: &NotificationArgs,
+) -> Result<(), CliError> {
+    let api_url = ctx.home_api_url().await?;
+    let access_token = ctx.get_access_token().await?;
+    match &notification_args.command {
+        None => {
+            let notifications = requests::notifications::fetch(
+                ctx.client,
+                &api_url,
+                &access_token,
+                notification_args.all,
+            )
+            .await?;
+            if notifications.is_empty() {
+                println!("No notifications.");
+            }
+            for notification in notifications {
+                println!("{}", format_notification(&notification));
+            }
+        }
+        Some(NotificationCommands::Read(read_args)) => match read_args.id {
+            Some(id) => {
+                requests::notifications::mark_read(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    id,
+                )
+                .await?;
+                println!("Marked notification {id} as read");
+            }
+            None => {
+                requests::notifications::mark_all_read(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                )
+                .await?;
+                println!("Marked all notifications as read");
+            }
+        },
+    }
+    Ok(())
+}
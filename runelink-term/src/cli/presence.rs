@@ -0,0 +1,175 @@
+use runelink_client::requests;
+use runelink_types::{PresenceStatus, PresenceUpdate};
+use uuid::Uuid;
+
+use crate::{
+    cli::select::{
+        ServerSelectionType, get_channel_selection_with_inputs,
+        get_server_selection,
+    },
+    error::CliError,
+};
+
+use super::context::CliContext;
+
+#[derive(clap::Args, Debug)]
+pub struct PresenceArgs {
+    #[clap(subcommand)]
+    pub command: PresenceCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum PresenceCommands {
+    /// Report your presence (it expires unless reported again)
+    Set(PresenceSetArgs),
+    /// List the presence of a server's members
+    List(PresenceListArgs),
+    /// Show who is typing in a channel
+    Typing(PresenceTypingArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct PresenceSetArgs {
+    /// The status to report
+    #[clap(value_enum)]
+    pub status: PresenceStatusArg,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum PresenceStatusArg {
+    Online,
+    Idle,
+    Offline,
+}
+
+impl From<PresenceStatusArg> for PresenceStatus {
+    fn from(status: PresenceStatusArg) -> Self {
+        match status {
+            PresenceStatusArg::Online => PresenceStatus::Online,
+            PresenceStatusArg::Idle => PresenceStatus::Idle,
+            PresenceStatusArg::Offline => PresenceStatus::Offline,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct PresenceListArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct PresenceTypingArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The ID of the channel
+    #[clap(long)]
+    pub channel_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+    /// Show yourself as typing instead
+    #[clap(long)]
+    pub start: bool,
+}
+
+pub async fn handle_presence_commands(
+    ctx: &mut CliContext<'_>,
+    presence_args: &PresenceArgs,
+) -> Result<(), CliError> {
+    ctx.account.ok_or(CliError::MissingAccount)?;
+    let api_url = ctx.home_api_url().await?;
+    let access_token = ctx.get_access_token().await?;
+    match &presence_args.command {
+        PresenceCommands::Set(set_args) => {
+            let presence = requests::presence::update(
+                ctx.client,
+                &api_url,
+                &access_token,
+                &PresenceUpdate {
+                    status: set_args.status.into(),
+                },
+            )
+            .await?;
+            println!("Presence set to {}", presence.status);
+        }
+        PresenceCommands::List(list_args) => {
+            let (server_id, host) = match list_args.server_id {
+                Some(server_id) => (server_id, list_args.host.clone()),
+                None => {
+                    let server = get_server_selection(
+                        ctx,
+                        ServerSelectionType::MemberOnly,
+                    )
+                    .await?;
+                    (server.id, Some(server.host))
+                }
+            };
+            let presence = requests::presence::fetch_by_server(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                host.as_deref(),
+            )
+            .await?;
+            for member in presence {
+                println!("{member}");
+            }
+        }
+        PresenceCommands::Typing(typing_args) => {
+            let (server_id, channel_id, server_host) =
+                match (typing_args.server_id, typing_args.channel_id) {
+                    (Some(server_id), Some(channel_id)) => {
+                        (server_id, channel_id, None)
+                    }
+                    _ => {
+                        let (server, channel) =
+                            get_channel_selection_with_inputs(
+                                ctx,
+                                typing_args.channel_id,
+                                typing_args.server_id,
+                            )
+                            .await?;
+                        (server.id, channel.id, Some(server.host.clone()))
+                    }
+                };
+            let target_host =
+                server_host.as_deref().or(typing_args.host.as_deref());
+            if typing_args.start {
+                requests::presence::start_typing(
+                    ctx.client,
+                    &api_url,
+                    &access_token,
+                    server_id,
+                    channel_id,
+                    target_host,
+                )
+                .await?;
+                println!("Shown as typing in channel {channel_id}");
+                return Ok(());
+            }
+            let typing = requests::presence::fetch_typing(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                channel_id,
+                target_host,
+            )
+            .await?;
+            if typing.is_empty() {
+                println!("Nobody is typing.");
+            }
+            for user in typing {
+                println!("{user} is typing…");
+            }
+        }
+    }
+    Ok(())
+}
@@ -137,7 +137,7 @@ pub async fn get_server_selection(
     ctx: &CliContext<'_>,
     selection_type: ServerSelectionType<'_>,
 ) -> Result<Server, CliError> {
-    let api_url = ctx.home_api_url()?;
+    let api_url = ctx.home_api_url().await?;
     let account = ctx.account.ok_or(CliError::MissingAccount)?;
     let servers = match selection_type {
         ServerSelectionType::All { host } => {
@@ -170,11 +170,7 @@ pub async fn get_server_selection(
                 None
             };
             let (all_servers_result, member_servers_result) = tokio::join!(
-                requests::servers::fetch_all(
-                    ctx.client,
-                    &api_url,
-                    target_host
-                ),
+                requests::servers::fetch_all(ctx.client, &api_url, target_host),
                 requests::servers::fetch_by_user(
                     ctx.client,
                     &api_url,
@@ -214,13 +210,14 @@ pub async fn get_channel_selection(
     server_id: Uuid,
     server_host: Option<&str>,
 ) -> Result<Channel, CliError> {
-    let api_url = ctx.home_api_url()?;
+    let api_url = ctx.home_api_url().await?;
     let access_token = ctx.get_access_token().await?;
     let channels = requests::channels::fetch_by_server(
         ctx.client,
         &api_url,
         &access_token,
         server_id,
+        false,
         server_host,
     )
     .await?;
@@ -243,7 +240,7 @@ pub async fn get_channel_selection_with_inputs(
     channel_id: Option<Uuid>,
     server_id: Option<Uuid>,
 ) -> Result<(Server, Channel), CliError> {
-    let api_url = ctx.home_api_url()?;
+    let api_url = ctx.home_api_url().await?;
     match (channel_id, server_id) {
         (Some(channel_id), Some(server_id)) => {
             let access_token = ctx.get_access_token().await?;
@@ -2,11 +2,27 @@
 #![allow(unused_variables)]
 #![allow(unreachable_code)]
 
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
 use runelink_client::requests;
-use runelink_types::{NewServer, NewServerMembership, ServerRole};
+use runelink_types::{
+    AuditLogQuery, NewServer, NewServerBan, NewServerMembership,
+    NewServerMigration, NewServerTimeout, OwnershipTransfer, RetentionPolicy,
+    RetentionPolicyUpdate, ServerRole, ServerRoleUpdate, ServerUpdate, UserRef,
+};
 use uuid::Uuid;
 
-use crate::{error::CliError, util::group_memberships_by_host};
+use crate::{
+    error::CliError,
+    util::{
+        format_audit_entry, format_ban, format_timeout,
+        group_memberships_by_host,
+    },
+};
 
 use super::{
     context::CliContext,
@@ -28,12 +44,146 @@ pub enum ServerCommands {
     Get(ServerGetArg),
     /// Create a new server
     Create(ServerCreateArgs),
-    /// Create a new server
+    /// Rename a server or change its description
+    Edit(ServerEditArgs),
+    /// Join a server
     Join(ServerJoinArgs),
     /// Leave a server
     Leave(ServerLeaveArgs),
     /// Delete a server
     Delete(ServerDeleteArgs),
+    /// Show a server's audit log (server admins only)
+    AuditLog(ServerAuditLogArgs),
+    /// Ban a user, or every user of a host, from a server
+    Ban(ServerBanArgs),
+    /// Lift a ban
+    Unban(ServerUnbanArgs),
+    /// List the bans of a server
+    Bans(ServerModerationArgs),
+    /// Stop a user from posting in a server for a while
+    Timeout(ServerTimeoutArgs),
+    /// End a user's timeout early
+    Untimeout(ServerUserArgs),
+    /// List the running timeouts of a server
+    Timeouts(ServerModerationArgs),
+    /// Delete every message a user posted in a server
+    PurgeMessages(ServerUserArgs),
+    /// Manage the members of a server
+    Member(ServerMemberArgs),
+    /// Manage how long a server keeps its messages
+    Retention(ServerRetentionArgs),
+    /// Write an archive of a server hosted by your home host to stdout
+    Export(ServerExportArgs),
+    /// Recreate a server from an archive (host admins only)
+    Import(ServerImportArgs),
+    /// Move a server to another host (server owners only)
+    Migrate(ServerMigrateArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerMigrateArgs {
+    #[clap(flatten)]
+    pub server: ServerModerationArgs,
+    /// The host to move the server to
+    #[clap(long)]
+    pub to_host: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerExportArgs {
+    /// The ID of the server to export
+    #[clap(long)]
+    pub server_id: Uuid,
+    /// Write the archive to this file instead of stdout
+    #[clap(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerImportArgs {
+    /// The archive to import
+    pub file: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerRetentionArgs {
+    #[clap(subcommand)]
+    pub command: ServerRetentionCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum ServerRetentionCommands {
+    /// Show the retention policies of a server and its channels
+    Show(ServerModerationArgs),
+    /// Change the retention policy of a server or channel
+    Set(ServerRetentionSetArgs),
+    /// Remove the retention policy of a server or channel
+    Clear(ServerRetentionTargetArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerRetentionTargetArgs {
+    #[clap(flatten)]
+    pub server: ServerModerationArgs,
+    /// Apply to this channel instead of the whole server
+    #[clap(long)]
+    pub channel_id: Option<Uuid>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerRetentionSetArgs {
+    #[clap(flatten)]
+    pub target: ServerRetentionTargetArgs,
+    /// Purge messages older than this many days
+    #[clap(long, conflicts_with = "no_max_age")]
+    pub max_age_days: Option<i32>,
+    /// Lift the age limit
+    #[clap(long)]
+    pub no_max_age: bool,
+    /// Keep only this many of the newest messages of each channel
+    #[clap(long, conflicts_with = "no_max_messages")]
+    pub max_messages: Option<i32>,
+    /// Lift the message count limit
+    #[clap(long)]
+    pub no_max_messages: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerMemberArgs {
+    #[clap(subcommand)]
+    pub command: ServerMemberCommands,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum ServerMemberCommands {
+    /// Promote a member to admin or demote an admin
+    Role(ServerMemberRoleArgs),
+    /// Make another member the owner of a server (you stay an admin)
+    TransferOwnership(ServerUserArgs),
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum RoleArg {
+    Member,
+    Admin,
+}
+
+impl From<RoleArg> for ServerRole {
+    fn from(role: RoleArg) -> Self {
+        match role {
+            RoleArg::Member => ServerRole::Member,
+            RoleArg::Admin => ServerRole::Admin,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerMemberRoleArgs {
+    #[clap(flatten)]
+    pub user: ServerUserArgs,
+    /// The new role
+    #[clap(long, value_enum)]
+    pub role: RoleArg,
 }
 
 #[derive(clap::Args, Debug)]
@@ -69,6 +219,21 @@ pub struct ServerCreateArgs {
     pub host: Option<String>,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct ServerEditArgs {
+    #[clap(flatten)]
+    pub server: ServerModerationArgs,
+    /// The new title of the server
+    #[clap(long)]
+    pub title: Option<String>,
+    /// The new description of the server
+    #[clap(long, conflicts_with = "clear_description")]
+    pub description: Option<String>,
+    /// Remove the server's description
+    #[clap(long)]
+    pub clear_description: bool,
+}
+
 #[derive(clap::Args, Debug)]
 pub struct ServerJoinArgs {
     /// The ID of the server
@@ -99,13 +264,117 @@ pub struct ServerDeleteArgs {
     pub host: Option<String>,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct ServerAuditLogArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+    /// Only show this action (e.g. channel.delete)
+    #[clap(long)]
+    pub action: Option<String>,
+    /// Only show entries older than this entry ID
+    #[clap(long)]
+    pub before: Option<i64>,
+    /// Maximum number of entries to show
+    #[clap(long)]
+    pub limit: Option<i64>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerModerationArgs {
+    /// The ID of the server
+    #[clap(long)]
+    pub server_id: Option<Uuid>,
+    /// The host of the server
+    #[clap(long)]
+    pub host: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerBanArgs {
+    #[clap(flatten)]
+    pub server: ServerModerationArgs,
+    /// The name of the user to ban (leave out to ban the whole host)
+    #[clap(long)]
+    pub user_name: Option<String>,
+    /// The host of the user to ban
+    #[clap(long)]
+    pub user_host: String,
+    /// Why the user is banned
+    #[clap(long)]
+    pub reason: Option<String>,
+    /// Lift the ban after this many hours (permanent if not provided)
+    #[clap(long)]
+    pub expires_in_hours: Option<i64>,
+    /// Also delete the banned users' messages in the server
+    #[clap(long)]
+    pub delete_messages: bool,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerUnbanArgs {
+    #[clap(flatten)]
+    pub server: ServerModerationArgs,
+    /// The ID of the ban to lift
+    #[clap(long)]
+    pub ban_id: Uuid,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerUserArgs {
+    #[clap(flatten)]
+    pub server: ServerModerationArgs,
+    /// The name of the user
+    #[clap(long)]
+    pub user_name: String,
+    /// The host of the user
+    #[clap(long)]
+    pub user_host: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServerTimeoutArgs {
+    #[clap(flatten)]
+    pub user: ServerUserArgs,
+    /// How many minutes the user can't post for
+    #[clap(long)]
+    pub minutes: i64,
+    /// Why the user is timed out
+    #[clap(long)]
+    pub reason: Option<String>,
+}
+
+impl ServerUserArgs {
+    fn user_ref(&self) -> UserRef {
+        UserRef::new(self.user_name.clone(), self.user_host.clone())
+    }
+}
+
+/// The server a moderation command acts on, prompting for one of the
+/// user's servers if no ID was given.
+async fn moderated_server(
+    ctx: &mut CliContext<'_>,
+    args: &ServerModerationArgs,
+) -> Result<(Uuid, Option<String>), CliError> {
+    if let Some(server_id) = args.server_id {
+        Ok((server_id, args.host.clone()))
+    } else {
+        let server =
+            get_server_selection(ctx, ServerSelectionType::MemberOnly).await?;
+        Ok((server.id, Some(server.host)))
+    }
+}
+
 pub async fn handle_server_commands(
     ctx: &mut CliContext<'_>,
     server_args: &ServerArgs,
 ) -> Result<(), CliError> {
     match &server_args.command {
         ServerCommands::List(list_args) => {
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
 
             if let Some(host) = &list_args.host {
                 // List all servers in the specified host
@@ -150,10 +419,10 @@ pub async fn handle_server_commands(
                     for membership in memberships {
                         let server = &membership.server;
                         print!("    {}", server.verbose());
-                        if membership.role == ServerRole::Admin {
-                            println!(" - admin");
-                        } else {
+                        if membership.role == ServerRole::Member {
                             println!();
+                        } else {
+                            println!(" - {}", membership.role);
                         }
                     }
                 }
@@ -161,7 +430,7 @@ pub async fn handle_server_commands(
         }
 
         ServerCommands::Get(get_args) => {
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let server = requests::servers::fetch_by_id(
                 ctx.client,
                 &api_url,
@@ -179,7 +448,7 @@ pub async fn handle_server_commands(
 
         ServerCommands::Create(create_args) => {
             let account = ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let title =
                 unwrap_or_prompt(create_args.title.clone(), "Server Title")?;
@@ -204,7 +473,7 @@ pub async fn handle_server_commands(
 
         ServerCommands::Join(join_args) => {
             let account = ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let server = if let Some(server_id) = join_args.server_id {
                 requests::servers::fetch_by_id(
@@ -243,7 +512,7 @@ pub async fn handle_server_commands(
 
         ServerCommands::Leave(leave_args) => {
             let account = ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let server = if let Some(server_id) = leave_args.server_id {
                 requests::servers::fetch_by_id(
@@ -275,7 +544,7 @@ pub async fn handle_server_commands(
 
         ServerCommands::Delete(delete_args) => {
             let account = ctx.account.ok_or(CliError::MissingAccount)?;
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let access_token = ctx.get_access_token().await?;
             let server_id = if let Some(server_id) = delete_args.server_id {
                 server_id
@@ -298,6 +567,432 @@ pub async fn handle_server_commands(
             .await?;
             println!("Deleted server: {server_id}");
         }
+
+        ServerCommands::Edit(edit_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, &edit_args.server).await?;
+            let description = if edit_args.clear_description {
+                Some(None)
+            } else {
+                edit_args.description.clone().map(Some)
+            };
+            let update = ServerUpdate {
+                title: edit_args.title.clone(),
+                description,
+            };
+            if update == ServerUpdate::default() {
+                println!("Nothing to change.");
+                return Ok(());
+            }
+            let server = requests::servers::update(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                &update,
+                host.as_deref(),
+            )
+            .await?;
+            println!("Updated server: {}", server.verbose());
+        }
+
+        ServerCommands::AuditLog(audit_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) = if let Some(server_id) =
+                audit_args.server_id
+            {
+                (server_id, audit_args.host.clone())
+            } else {
+                let server =
+                    get_server_selection(ctx, ServerSelectionType::MemberOnly)
+                        .await?;
+                (server.id, Some(server.host))
+            };
+            let query = AuditLogQuery {
+                action: audit_args.action.clone(),
+                actor_host: None,
+                before: audit_args.before,
+                limit: audit_args.limit,
+            };
+            let entries = requests::audit::fetch_by_server(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                &query,
+                host.as_deref(),
+            )
+            .await?;
+            if entries.is_empty() {
+                println!("No audit log entries.");
+            }
+            for entry in entries {
+                println!("{}", format_audit_entry(&entry));
+            }
+        }
+
+        ServerCommands::Ban(ban_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, &ban_args.server).await?;
+            let new_ban = NewServerBan {
+                user_name: ban_args.user_name.clone(),
+                user_host: ban_args.user_host.clone(),
+                reason: ban_args.reason.clone(),
+                expires_in_hours: ban_args.expires_in_hours,
+                delete_messages: ban_args.delete_messages,
+            };
+            let ban = requests::moderation::create_ban(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                &new_ban,
+                host.as_deref(),
+            )
+            .await?;
+            println!("Banned: {}", format_ban(&ban));
+        }
+
+        ServerCommands::Unban(unban_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, &unban_args.server).await?;
+            requests::moderation::delete_ban(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                unban_args.ban_id,
+                host.as_deref(),
+            )
+            .await?;
+            println!("Lifted ban: {}", unban_args.ban_id);
+        }
+
+        ServerCommands::Bans(bans_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) = moderated_server(ctx, bans_args).await?;
+            let bans = requests::moderation::fetch_bans(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                host.as_deref(),
+            )
+            .await?;
+            if bans.is_empty() {
+                println!("No bans.");
+            }
+            for ban in bans {
+                println!("{}", format_ban(&ban));
+            }
+        }
+
+        ServerCommands::Timeout(timeout_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, &timeout_args.user.server).await?;
+            let new_timeout = NewServerTimeout {
+                user_ref: timeout_args.user.user_ref(),
+                duration_minutes: timeout_args.minutes,
+                reason: timeout_args.reason.clone(),
+            };
+            let timeout = requests::moderation::create_timeout(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                &new_timeout,
+                host.as_deref(),
+            )
+            .await?;
+            println!("Timed out: {}", format_timeout(&timeout));
+        }
+
+        ServerCommands::Untimeout(user_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, &user_args.server).await?;
+            let user_ref = user_args.user_ref();
+            requests::moderation::delete_timeout(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                user_ref.clone(),
+                host.as_deref(),
+            )
+            .await?;
+            println!("Ended timeout: {user_ref}");
+        }
+
+        ServerCommands::Timeouts(timeouts_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, timeouts_args).await?;
+            let timeouts = requests::moderation::fetch_timeouts(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                host.as_deref(),
+            )
+            .await?;
+            if timeouts.is_empty() {
+                println!("No timeouts.");
+            }
+            for timeout in timeouts {
+                println!("{}", format_timeout(&timeout));
+            }
+        }
+
+        ServerCommands::PurgeMessages(user_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, &user_args.server).await?;
+            let user_ref = user_args.user_ref();
+            let result = requests::moderation::delete_messages_by_user(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                user_ref.clone(),
+                host.as_deref(),
+            )
+            .await?;
+            println!("Deleted {} messages by {user_ref}", result.deleted);
+        }
+
+        ServerCommands::Member(member_args) => {
+            handle_member_commands(ctx, member_args).await?;
+        }
+
+        ServerCommands::Retention(retention_args) => {
+            handle_retention_commands(ctx, retention_args).await?;
+        }
+
+        ServerCommands::Export(export_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let mut response = requests::archive::export(
+                ctx.client,
+                &api_url,
+                &access_token,
+                export_args.server_id,
+            )
+            .await?;
+            let mut out: Box<dyn Write> = match &export_args.output {
+                Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(std::io::stdout().lock()),
+            };
+            while let Some(chunk) = response.chunk().await? {
+                out.write_all(&chunk)?;
+            }
+            out.flush()?;
+            if let Some(path) = &export_args.output {
+                println!("Exported server to {}", path.display());
+            }
+        }
+
+        ServerCommands::Import(import_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let archive = std::fs::read(&import_args.file)?;
+            let summary = requests::archive::import(
+                ctx.client,
+                &api_url,
+                &access_token,
+                archive,
+            )
+            .await?;
+            println!(
+                "Imported server: {} ({})",
+                summary.server.title, summary.server.id
+            );
+            println!(
+                "{} categories, {} channels, {} members, {} messages",
+                summary.categories,
+                summary.channels,
+                summary.members,
+                summary.messages
+            );
+            if !summary.skipped_members.is_empty() {
+                println!("Members who have to join again:");
+                for user_ref in &summary.skipped_members {
+                    println!("  {user_ref}");
+                }
+            }
+        }
+
+        ServerCommands::Migrate(migrate_args) => {
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let (server_id, host) =
+                moderated_server(ctx, &migrate_args.server).await?;
+            let new_migration = NewServerMigration {
+                to_host: migrate_args.to_host.clone(),
+            };
+            let migration = requests::server_migrations::migrate(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                &new_migration,
+                host.as_deref(),
+            )
+            .await?;
+            println!(
+                "Moved server {} from {} to {}",
+                migration.server_id, migration.from_host, migration.to_host
+            );
+        }
+    }
+    Ok(())
+}
+
+fn format_retention_policy(policy: &RetentionPolicy) -> String {
+    match policy.channel_id {
+        Some(channel_id) => {
+            format!("channel {channel_id}: {}", policy.describe())
+        }
+        None => format!("server: {}", policy.describe()),
+    }
+}
+
+async fn handle_retention_commands(
+    ctx: &mut CliContext<'_>,
+    retention_args: &ServerRetentionArgs,
+) -> Result<(), CliError> {
+    let api_url = ctx.home_api_url().await?;
+    let access_token = ctx.get_access_token().await?;
+    match &retention_args.command {
+        ServerRetentionCommands::Show(server_args) => {
+            let (server_id, host) = moderated_server(ctx, server_args).await?;
+            let policies = requests::retention::fetch_by_server(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                host.as_deref(),
+            )
+            .await?;
+            if policies.is_empty() {
+                println!("No retention policies, messages are kept forever.");
+            }
+            for policy in policies {
+                println!("{}", format_retention_policy(&policy));
+            }
+        }
+
+        ServerRetentionCommands::Set(set_args) => {
+            let (server_id, host) =
+                moderated_server(ctx, &set_args.target.server).await?;
+            let max_age_days = if set_args.no_max_age {
+                Some(None)
+            } else {
+                set_args.max_age_days.map(Some)
+            };
+            let max_messages = if set_args.no_max_messages {
+                Some(None)
+            } else {
+                set_args.max_messages.map(Some)
+            };
+            let update = RetentionPolicyUpdate {
+                max_age_days,
+                max_messages,
+            };
+            if update == RetentionPolicyUpdate::default() {
+                println!("Nothing to change.");
+                return Ok(());
+            }
+            let policy = requests::retention::update(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                set_args.target.channel_id,
+                &update,
+                host.as_deref(),
+            )
+            .await?;
+            println!(
+                "Updated retention policy: {}",
+                format_retention_policy(&policy)
+            );
+        }
+
+        ServerRetentionCommands::Clear(clear_args) => {
+            let (server_id, host) =
+                moderated_server(ctx, &clear_args.server).await?;
+            requests::retention::delete(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                clear_args.channel_id,
+                host.as_deref(),
+            )
+            .await?;
+            println!("Removed retention policy.");
+        }
+    }
+    Ok(())
+}
+
+async fn handle_member_commands(
+    ctx: &mut CliContext<'_>,
+    member_args: &ServerMemberArgs,
+) -> Result<(), CliError> {
+    let api_url = ctx.home_api_url().await?;
+    let access_token = ctx.get_access_token().await?;
+    match &member_args.command {
+        ServerMemberCommands::Role(role_args) => {
+            let (server_id, host) =
+                moderated_server(ctx, &role_args.user.server).await?;
+            let update = ServerRoleUpdate {
+                role: role_args.role.into(),
+            };
+            let member = requests::memberships::update_role(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                role_args.user.user_ref(),
+                &update,
+                host.as_deref(),
+            )
+            .await?;
+            println!("{} is now {}", member.user.as_ref(), member.role);
+        }
+
+        ServerMemberCommands::TransferOwnership(user_args) => {
+            let (server_id, host) =
+                moderated_server(ctx, &user_args.server).await?;
+            let transfer = OwnershipTransfer {
+                new_owner: user_args.user_ref(),
+            };
+            let member = requests::memberships::transfer_ownership(
+                ctx.client,
+                &api_url,
+                &access_token,
+                server_id,
+                &transfer,
+                host.as_deref(),
+            )
+            .await?;
+            println!("{} is now the owner", member.user.as_ref());
+        }
     }
     Ok(())
 }
@@ -1,8 +1,8 @@
 use runelink_client::requests;
-use runelink_types::UserRef;
+use runelink_types::{UserProfileUpdate, UserRef};
 use uuid::Uuid;
 
-use crate::error::CliError;
+use crate::{error::CliError, util::format_profile};
 
 use super::context::CliContext;
 
@@ -18,6 +18,48 @@ pub enum UserCommands {
     List(UserListArgs),
     /// Get a user by ID
     Get(UserGetArgs),
+    /// Edit your profile
+    Edit(UserEditArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct UserEditArgs {
+    /// The name to show instead of your username
+    #[clap(long, conflicts_with = "clear_display_name")]
+    pub display_name: Option<String>,
+    /// Remove your display name
+    #[clap(long)]
+    pub clear_display_name: bool,
+    /// A few words about yourself
+    #[clap(long, conflicts_with = "clear_bio")]
+    pub bio: Option<String>,
+    /// Remove your bio
+    #[clap(long)]
+    pub clear_bio: bool,
+    /// The http(s) address of your avatar image
+    #[clap(long, conflicts_with = "clear_avatar_url")]
+    pub avatar_url: Option<String>,
+    /// Remove your avatar
+    #[clap(long)]
+    pub clear_avatar_url: bool,
+    /// Custom status text, such as "on vacation"
+    #[clap(long, conflicts_with = "clear_status")]
+    pub status: Option<String>,
+    /// Remove your status text
+    #[clap(long)]
+    pub clear_status: bool,
+}
+
+/// A field of a profile update: `Some(None)` when it is cleared.
+fn profile_field(
+    value: &Option<String>,
+    clear: bool,
+) -> Option<Option<String>> {
+    if clear {
+        Some(None)
+    } else {
+        value.clone().map(Some)
+    }
 }
 
 #[derive(clap::Args, Debug)]
@@ -46,7 +88,7 @@ pub async fn handle_user_commands(
 ) -> Result<(), CliError> {
     match &user_args.command {
         UserCommands::List(list_args) => {
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let users;
             if let Some(server_id) = list_args.server_id {
                 // Fetch members of the server, then extract users
@@ -74,11 +116,45 @@ pub async fn handle_user_commands(
         UserCommands::Get(get_args) => {
             let user_ref =
                 UserRef::new(get_args.name.clone(), get_args.host.clone());
-            let api_url = ctx.home_api_url()?;
+            let api_url = ctx.home_api_url().await?;
             let user =
                 requests::users::fetch_by_ref(ctx.client, &api_url, user_ref)
                     .await?;
-            println!("{user}");
+            println!("{}", format_profile(&user));
+        }
+
+        UserCommands::Edit(edit_args) => {
+            let account = ctx.account.ok_or(CliError::MissingAccount)?;
+            let update = UserProfileUpdate {
+                display_name: profile_field(
+                    &edit_args.display_name,
+                    edit_args.clear_display_name,
+                ),
+                bio: profile_field(&edit_args.bio, edit_args.clear_bio),
+                avatar_url: profile_field(
+                    &edit_args.avatar_url,
+                    edit_args.clear_avatar_url,
+                ),
+                status_text: profile_field(
+                    &edit_args.status,
+                    edit_args.clear_status,
+                ),
+            };
+            if update == UserProfileUpdate::default() {
+                println!("Nothing to change.");
+                return Ok(());
+            }
+            let api_url = ctx.home_api_url().await?;
+            let access_token = ctx.get_access_token().await?;
+            let user = requests::users::update_profile(
+                ctx.client,
+                &api_url,
+                &access_token,
+                account.user_ref.clone(),
+                &update,
+            )
+            .await?;
+            println!("Updated profile:\n{}", format_profile(&user));
         }
     }
     Ok(())
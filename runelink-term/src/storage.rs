@@ -1,5 +1,4 @@
 use directories::ProjectDirs;
-use runelink_client::util::get_api_url;
 use runelink_types::UserRef;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -86,7 +85,6 @@ impl AppConfig {
 
 pub trait TryGetHost {
     fn try_get_host(&self) -> Result<&str, CliError>;
-    fn try_get_api_url(&self) -> Result<String, CliError>;
 }
 
 impl TryGetHost for Option<&AccountConfig> {
@@ -94,10 +92,6 @@ impl TryGetHost for Option<&AccountConfig> {
         self.map(|ac| ac.user_ref.host.as_str())
             .ok_or(CliError::MissingAccount)
     }
-
-    fn try_get_api_url(&self) -> Result<String, CliError> {
-        self.try_get_host().map(get_api_url)
-    }
 }
 
 pub fn get_data_dir() -> Result<PathBuf, CliError> {
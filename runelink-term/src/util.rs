@@ -1,5 +1,12 @@
-use runelink_types::ServerMembership;
-use std::collections::HashMap;
+use crossterm::style::{ContentStyle, Stylize};
+use qrcode::{QrCode, render::unicode::Dense1x2};
+use runelink_types::{
+    AuditEntry, Message, Notification, PinnedMessage, ScheduledMessage,
+    ServerBan, ServerMembership, ServerTimeout, User,
+    markup::{self, Block, Inline},
+};
+use std::{collections::HashMap, io::IsTerminal};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 
 /// Returns the prefix for a list item given an optional default value
 pub fn get_prefix<T: PartialEq>(
@@ -13,11 +20,7 @@ pub fn get_prefix<T: PartialEq>(
     let Some(default) = default else {
         return "";
     };
-    if val == default {
-        "* "
-    } else {
-        "  "
-    }
+    if val == default { "* " } else { "  " }
 }
 
 pub fn group_memberships_by_host<'a>(
@@ -30,3 +33,232 @@ pub fn group_memberships_by_host<'a>(
     }
     map
 }
+
+/// Renders data as a QR code made of unicode blocks for the terminal
+pub fn render_qr_code(data: &str) -> Option<String> {
+    let code = QrCode::new(data.as_bytes()).ok()?;
+    let rendered = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build();
+    Some(rendered)
+}
+
+/// Formats an audit log entry as a single line
+pub fn format_audit_entry(entry: &AuditEntry) -> String {
+    let at = entry
+        .created_at
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| entry.created_at.to_string());
+    let actor = match (entry.actor(), &entry.issuer_host) {
+        (Some(actor), _) => actor.to_string(),
+        (None, Some(issuer)) => format!("({issuer})"),
+        (None, None) => "(host)".into(),
+    };
+    let mut line = format!(
+        "#{id} {at} {actor} {action} {kind}:{target}",
+        id = entry.id,
+        action = entry.action,
+        kind = entry.target_kind,
+        target = entry.target_id,
+    );
+    if let Some(detail) = &entry.detail {
+        line.push_str(&format!(" ({detail})"));
+    }
+    line
+}
+
+/// Formats a user's profile, one field per line, leaving out fields that
+/// are not set
+pub fn format_profile(user: &User) -> String {
+    let mut out = match &user.display_name {
+        Some(display_name) => format!("{display_name} ({user})"),
+        None => user.to_string(),
+    };
+    if let Some(status_text) = &user.status_text {
+        out.push_str(&format!("\nStatus: {status_text}"));
+    }
+    if let Some(bio) = &user.bio {
+        out.push_str(&format!("\nBio: {}", bio.replace('\n', "\n     ")));
+    }
+    if let Some(avatar_url) = &user.avatar_url {
+        out.push_str(&format!("\nAvatar: {avatar_url}"));
+    }
+    out
+}
+
+/// Formats a notification as a single line, marking ones already read
+pub fn format_notification(notification: &Notification) -> String {
+    let mut line = format!(
+        "{id} {at} {notification} (server {server_id} on {host})",
+        id = notification.id,
+        at = format_time(notification.created_at),
+        server_id = notification.server_id,
+        host = notification.server_host,
+    );
+    if notification.is_read() {
+        line.push_str(" [read]");
+    }
+    line
+}
+
+/// Formats a pinned message with who pinned it and when
+pub fn format_pinned_message(pin: &PinnedMessage) -> String {
+    format!(
+        "{} (pinned by {} at {})\n  {}",
+        pin.message.id,
+        pin.pinned_by,
+        format_time(pin.pinned_at),
+        format_message(&pin.message).replace('\n', "\n  "),
+    )
+}
+
+fn format_time(at: OffsetDateTime) -> String {
+    at.format(&Rfc3339).unwrap_or_else(|_| at.to_string())
+}
+
+/// Formats a scheduled message with when it will be sent, or why it
+/// failed
+pub fn format_scheduled_message(scheduled: &ScheduledMessage) -> String {
+    let mut line = format!(
+        "{} {} (channel {} on {}): {}",
+        scheduled.id,
+        format_time(scheduled.send_at),
+        scheduled.channel_id,
+        scheduled.server_host,
+        scheduled.body,
+    );
+    match (&scheduled.failed_at, &scheduled.last_error) {
+        (Some(_), Some(error)) => line.push_str(&format!(" [failed: {error}]")),
+        (Some(_), None) => line.push_str(" [failed]"),
+        (None, Some(error)) => line.push_str(&format!(" [retrying: {error}]")),
+        (None, None) => {}
+    }
+    line
+}
+
+/// Parses when to send a message: an RFC 3339 time, or a delay from now
+/// such as `+30m` (units `s`, `m`, `h` and `d`)
+pub fn parse_send_at(value: &str) -> Result<OffsetDateTime, String> {
+    let Some(delay) = value.strip_prefix('+') else {
+        return OffsetDateTime::parse(value, &Rfc3339).map_err(|e| {
+            format!("expected an RFC 3339 time or a delay like +30m: {e}")
+        });
+    };
+    let split = delay.len().saturating_sub(1);
+    let (amount, unit) = delay.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid delay: {value}"))?;
+    let delay = match unit {
+        "s" => time::Duration::seconds(amount),
+        "m" => time::Duration::minutes(amount),
+        "h" => time::Duration::hours(amount),
+        "d" => time::Duration::days(amount),
+        _ => return Err(format!("unknown delay unit in {value}")),
+    };
+    Ok(OffsetDateTime::now_utc() + delay)
+}
+
+pub fn format_ban(ban: &ServerBan) -> String {
+    let mut line = format!("{} {}", ban.id, ban.target());
+    match ban.expires_at {
+        Some(expires_at) => {
+            line.push_str(&format!(" until {}", format_time(expires_at)))
+        }
+        None => line.push_str(" permanently"),
+    }
+    if let Some(reason) = &ban.reason {
+        line.push_str(&format!(" - {reason}"));
+    }
+    line
+}
+
+pub fn format_timeout(timeout: &ServerTimeout) -> String {
+    let mut line = format!(
+        "{} until {}",
+        timeout.user_ref(),
+        format_time(timeout.until)
+    );
+    if let Some(reason) = &timeout.reason {
+        line.push_str(&format!(" - {reason}"));
+    }
+    line
+}
+
+fn render_inlines(out: &mut String, inlines: &[Inline], style: ContentStyle) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(&style.apply(text).to_string()),
+            Inline::LineBreak => out.push('\n'),
+            Inline::Bold(inner) => render_inlines(out, inner, style.bold()),
+            Inline::Italic(inner) => render_inlines(out, inner, style.italic()),
+            Inline::Code(code) => {
+                out.push_str(&style.dark_yellow().apply(code).to_string())
+            }
+            Inline::Link { text, url } => {
+                render_inlines(out, text, style.cyan().underlined());
+                let label =
+                    markup::to_plain_text(&[Block::Paragraph(text.clone())]);
+                if label != *url {
+                    let url = format!(" ({url})");
+                    out.push_str(&style.dark_grey().apply(url).to_string());
+                }
+            }
+            Inline::Mention(user_ref) => {
+                let mention = format!("@{user_ref}");
+                out.push_str(&style.magenta().bold().apply(mention).to_string())
+            }
+            Inline::ChannelLink(id) => {
+                out.push_str(&style.blue().apply(format!("#{id}")).to_string())
+            }
+        }
+    }
+}
+
+/// Renders a message body's markup with terminal styling
+pub fn render_markup(body: &str) -> String {
+    let mut out = String::new();
+    for (i, block) in markup::parse(body).iter().enumerate() {
+        if i > 0 {
+            out.push_str("\n\n");
+        }
+        match block {
+            Block::Paragraph(inlines) => {
+                render_inlines(&mut out, inlines, ContentStyle::new())
+            }
+            Block::CodeBlock { code, .. } => {
+                let lines: Vec<String> = code
+                    .lines()
+                    .map(|line| format!("    {}", line.dark_yellow()))
+                    .collect();
+                out.push_str(&lines.join("\n"));
+            }
+        }
+    }
+    out
+}
+
+/// Formats a message with its attachments, styling its markup when
+/// printing to a terminal
+pub fn format_message(message: &Message) -> String {
+    if message.is_deleted() || !std::io::stdout().is_terminal() {
+        return message.to_string();
+    }
+    let author = message
+        .author
+        .as_ref()
+        .map(|u| u.shown_name())
+        .unwrap_or("anon");
+    let mut out =
+        format!("{}: {}", author.bold(), render_markup(&message.body));
+    for attachment in &message.attachments {
+        out.push_str(&format!(
+            "\n  [attachment {}] {attachment}",
+            attachment.id
+        ));
+    }
+    out
+}
@@ -0,0 +1,110 @@
+//! Server archives, for exporting a server from one host and importing it
+//! on another (or the same) host.
+//!
+//! An archive is newline-delimited JSON: one [`ArchiveRecord`] per line,
+//! starting with a [`ArchiveRecord::Header`], then the server, its
+//! categories, channels, members, bans, timeouts, retention policies, pins
+//! and messages, and ending with [`ArchiveRecord::End`] so truncated
+//! archives can be told apart. Attachments are not part of archives.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    Channel, ChannelCategory, Message, RetentionPolicy, Server, ServerBan,
+    ServerRole, ServerTimeout, UserRef,
+};
+
+/// The archive format version written by this crate.
+pub const ARCHIVE_VERSION: u32 = 2;
+
+/// The oldest archive format version that can still be read. Version 1
+/// archives have no bans, timeouts, retention policies or pins.
+pub const MIN_ARCHIVE_VERSION: u32 = 1;
+
+/// Media type of archives.
+pub const ARCHIVE_CONTENT_TYPE: &str = "application/x-ndjson";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    Header(ArchiveHeader),
+    Server(Server),
+    Category(ChannelCategory),
+    Channel(Channel),
+    Member(ArchivedMember),
+    Ban(ServerBan),
+    Timeout(ServerTimeout),
+    Retention(RetentionPolicy),
+    Pin(ArchivedPin),
+    Message(Box<Message>),
+    End,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchiveHeader {
+    pub version: u32,
+    /// The host the server was exported from
+    pub host: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchivedMember {
+    pub user: UserRef,
+    pub role: ServerRole,
+    #[serde(with = "time::serde::rfc3339")]
+    pub joined_at: OffsetDateTime,
+}
+
+/// A pinned message. Pins come before the messages they pin.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchivedPin {
+    pub message_id: Uuid,
+    pub pinned_by: UserRef,
+    #[serde(with = "time::serde::rfc3339")]
+    pub pinned_at: OffsetDateTime,
+}
+
+/// What importing an archive recreated. The imported server, categories,
+/// channels and messages get new IDs.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArchiveImport {
+    pub server: Server,
+    pub categories: u64,
+    pub channels: u64,
+    pub members: u64,
+    /// Members that are not users of the importing host, who have to join
+    /// the imported server again
+    pub skipped_members: Vec<UserRef>,
+    pub messages: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_are_tagged_with_their_type() {
+        let header = ArchiveRecord::Header(ArchiveHeader {
+            version: ARCHIVE_VERSION,
+            host: "example.com".into(),
+            exported_at: OffsetDateTime::UNIX_EPOCH,
+        });
+        let line = serde_json::to_string(&header).unwrap();
+        assert_eq!(
+            line,
+            r#"{"type":"header","version":2,"host":"example.com","exported_at":"1970-01-01T00:00:00Z"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ArchiveRecord>(&line).unwrap(),
+            header
+        );
+        assert_eq!(
+            serde_json::from_str::<ArchiveRecord>(r#"{"type":"end"}"#).unwrap(),
+            ArchiveRecord::End
+        );
+    }
+}
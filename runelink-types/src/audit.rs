@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::UserRef;
+
+/// An entry in a host's append-only audit log of privileged actions.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct AuditEntry {
+    pub id: i64,
+    /// The acting user, unless the host acted on its own
+    pub actor_name: Option<String>,
+    pub actor_host: Option<String>,
+    /// Host that issued the credentials the action was authorized with
+    pub issuer_host: Option<String>,
+    /// What happened, e.g. `channel.delete`
+    pub action: String,
+    /// The server the action happened in, if any
+    pub server_id: Option<Uuid>,
+    /// What was acted on, e.g. `channel`, and its ID
+    pub target_kind: String,
+    pub target_id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// Further details, e.g. how many messages a purge removed
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn actor(&self) -> Option<UserRef> {
+        match (&self.actor_name, &self.actor_host) {
+            (Some(name), Some(host)) => {
+                Some(UserRef::new(name.clone(), host.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Filters for reading the audit log. Entries come newest first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditLogQuery {
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub actor_host: Option<String>,
+    /// Only entries older than this entry ID, for paging
+    #[serde(default)]
+    pub before: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
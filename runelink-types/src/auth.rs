@@ -2,15 +2,22 @@ use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use rand::{RngCore, rngs::OsRng};
 use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
 
 use crate::UserRef;
 
+/// Error `code` returned by the password grant when the account has 2FA
+/// enabled and no `otp` was sent.
+pub const MFA_REQUIRED: &str = "mfa_required";
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct LocalAccount {
     pub user_name: String,
     pub user_host: String,
     pub password_hash: String,
+    /// False while waiting for a host admin to approve the signup
+    pub approved: bool,
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
@@ -21,6 +28,109 @@ pub struct LocalAccount {
 pub struct SignupRequest {
     pub name: String,
     pub password: String,
+    /// Required by hosts that only allow signups by invite
+    #[serde(default)]
+    pub invite_token: Option<String>,
+}
+
+/// An invite allowing accounts to be created on an invite-only host.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct SignupInvite {
+    pub id: Uuid,
+    pub note: Option<String>,
+    /// `None` for unlimited uses
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+/// A freshly minted invite. The token is only ever shown once.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssuedSignupInvite {
+    pub token: String,
+    #[serde(flatten)]
+    pub invite: SignupInvite,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewSignupInvite {
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub max_uses: Option<i32>,
+    #[serde(default)]
+    pub expires_in_hours: Option<i64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+    /// Refresh token of the calling session, left valid while every other
+    /// refresh token of the account is revoked
+    #[serde(default)]
+    pub keep_refresh_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewPasswordReset {
+    /// Defaults to 24 hours
+    #[serde(default)]
+    pub expires_in_hours: Option<i64>,
+}
+
+/// A freshly minted one-time password reset token, handed to the account
+/// owner out of band. The token is only ever shown once.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssuedPasswordReset {
+    pub token: String,
+    pub user_ref: UserRef,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PasswordResetRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TotpStatus {
+    pub enabled: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+/// A pending TOTP enrollment. It takes effect once confirmed with a code.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TotpEnrollment {
+    /// Base32 shared secret, for authenticator apps without QR scanning
+    pub secret: String,
+    /// `otpauth://` URI, usually shown as a QR code
+    pub otpauth_uri: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TotpConfirmRequest {
+    pub code: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TotpDisableRequest {
+    pub password: String,
+    /// TOTP or recovery code
+    pub code: String,
+}
+
+/// One-time recovery codes, each usable once in place of a TOTP code. They
+/// are only ever shown once.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RecoveryCodes {
+    pub codes: Vec<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -45,6 +155,9 @@ pub struct TokenRequest {
     pub refresh_token: Option<String>, // refresh_token grant
     pub scope: Option<String>,
     pub client_id: Option<String>,
+    /// TOTP or recovery code, for password grants on accounts with 2FA
+    #[serde(default)]
+    pub otp: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -215,6 +328,7 @@ impl std::fmt::Debug for LocalAccount {
             .field("user_name", &self.user_name)
             .field("user_host", &self.user_host)
             .field("password_hash", &"[REDACTED]")
+            .field("approved", &self.approved)
             .field("created_at", &self.created_at)
             .field("updated_at", &self.updated_at)
             .finish()
@@ -223,9 +337,85 @@ impl std::fmt::Debug for LocalAccount {
 
 impl std::fmt::Debug for SignupRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let invite_token = self.invite_token.as_ref().map(|_| "[REDACTED]");
         f.debug_struct("SignupRequest")
             .field("name", &self.name)
             .field("password", &"[REDACTED]")
+            .field("invite_token", &invite_token)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for IssuedSignupInvite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IssuedSignupInvite")
+            .field("token", &"[REDACTED]")
+            .field("invite", &self.invite)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for ChangePasswordRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keep_refresh_token =
+            self.keep_refresh_token.as_ref().map(|_| "[REDACTED]");
+        f.debug_struct("ChangePasswordRequest")
+            .field("current_password", &"[REDACTED]")
+            .field("new_password", &"[REDACTED]")
+            .field("keep_refresh_token", &keep_refresh_token)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for IssuedPasswordReset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IssuedPasswordReset")
+            .field("token", &"[REDACTED]")
+            .field("user_ref", &self.user_ref)
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for PasswordResetRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordResetRequest")
+            .field("token", &"[REDACTED]")
+            .field("new_password", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for TotpEnrollment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TotpEnrollment")
+            .field("secret", &"[REDACTED]")
+            .field("otpauth_uri", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for TotpConfirmRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TotpConfirmRequest")
+            .field("code", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for TotpDisableRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TotpDisableRequest")
+            .field("password", &"[REDACTED]")
+            .field("code", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for RecoveryCodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecoveryCodes")
+            .field("codes", &format_args!("[{} REDACTED]", self.codes.len()))
             .finish()
     }
 }
@@ -247,6 +437,7 @@ impl std::fmt::Debug for TokenRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let password = self.password.as_ref().map(|_| "[REDACTED]");
         let refresh_token = self.refresh_token.as_ref().map(|_| "[REDACTED]");
+        let otp = self.otp.as_ref().map(|_| "[REDACTED]");
         f.debug_struct("TokenRequest")
             .field("grant_type", &self.grant_type)
             .field("username", &self.username)
@@ -254,6 +445,7 @@ impl std::fmt::Debug for TokenRequest {
             .field("refresh_token", &refresh_token)
             .field("scope", &self.scope)
             .field("client_id", &self.client_id)
+            .field("otp", &otp)
             .finish()
     }
 }
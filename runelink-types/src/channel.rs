@@ -3,6 +3,8 @@ use std::fmt;
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+use crate::server::deserialize_some;
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct Channel {
@@ -10,19 +12,101 @@ pub struct Channel {
     pub server_id: Uuid,
     pub title: String,
     pub description: Option<String>,
+    /// Sort order within the server, starting at 0
+    #[serde(default)]
+    pub position: i32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+    /// `None` for channels outside of any category
+    #[serde(default)]
+    pub category_id: Option<Uuid>,
+    /// Set while the channel is archived (read-only)
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub archived_at: Option<OffsetDateTime>,
+    /// Set while the channel is in the trash, waiting to be purged
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+/// A named group of channels within a server.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ChannelCategory {
+    pub id: Uuid,
+    pub server_id: Uuid,
+    pub title: String,
+    /// Sort order within the server, starting at 0
+    pub position: i32,
     #[serde(with = "time::serde::rfc3339")]
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
     pub updated_at: OffsetDateTime,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewChannelCategory {
+    pub title: String,
+}
+
+/// Changes to a channel category. Fields left out stay as they are.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChannelCategoryUpdate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Move the category to this position; the categories after it shift
+    /// down
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NewChannel {
     pub title: String,
     pub description: Option<String>,
 }
 
+/// Changes to a channel. Fields left out stay as they are.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChannelUpdate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// `Some(None)` (JSON `null`) clears the description
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<Option<String>>,
+    /// Move the channel to this position; the channels after it shift down
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i32>,
+    /// Move the channel into a category; `Some(None)` (JSON `null`) takes
+    /// it out of its category
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub category_id: Option<Option<Uuid>>,
+}
+
 impl Channel {
+    pub fn verbose(&self) -> String {
+        if self.is_archived() {
+            format!("{} ({}) [archived]", self.title, self.id)
+        } else {
+            format!("{} ({})", self.title, self.id)
+        }
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+}
+
+impl ChannelCategory {
     pub fn verbose(&self) -> String {
         format!("{} ({})", self.title, self.id)
     }
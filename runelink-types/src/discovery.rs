@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+/// Path at which every host serves its [`HostDiscovery`] document.
+pub const WELL_KNOWN_RUNELINK_PATH: &str = "/.well-known/runelink";
+
+/// Protocol version implemented by this crate.
+pub const PROTOCOL_VERSION: &str = "1";
+
+/// Prefix of the versioned federation API.
+pub const FEDERATION_V1_PATH: &str = "/federation/v1";
+
+/// Unversioned federation prefix used by hosts that predate versioning.
+pub const FEDERATION_LEGACY_PATH: &str = "/federation";
+
+/// Capability names advertised in [`HostDiscovery::capabilities`].
+///
+/// Capabilities are plain strings so that documents from newer hosts still
+/// parse; unknown names are ignored.
+pub mod capabilities {
+    /// Joining and leaving servers on the host
+    pub const MEMBERSHIPS: &str = "memberships";
+    /// Creating, reading and deleting servers
+    pub const SERVERS: &str = "servers";
+    /// Creating, reading and deleting channels
+    pub const CHANNELS: &str = "channels";
+    /// Creating, reading and deleting messages
+    pub const MESSAGES: &str = "messages";
+    /// Receiving deletions of remote users
+    pub const USER_DELETION: &str = "user-deletion";
+    /// Reading a server's audit log
+    pub const AUDIT_LOG: &str = "audit-log";
+    /// Managing bans and timeouts in a server
+    pub const MODERATION: &str = "moderation";
+    /// Changing member roles and transferring server ownership
+    pub const ROLES: &str = "roles";
+    /// Receiving role changes of users' memberships in remote servers
+    pub const MEMBERSHIP_SYNC: &str = "membership-sync";
+    /// Editing server and channel metadata
+    pub const METADATA: &str = "metadata";
+    /// Channel categories
+    pub const CATEGORIES: &str = "categories";
+    /// Archiving channels and restoring them from the trash
+    pub const ARCHIVING: &str = "archiving";
+    /// Message retention policies
+    pub const RETENTION: &str = "retention";
+    /// Message attachments
+    pub const ATTACHMENTS: &str = "attachments";
+    /// Relaying mentions to the home hosts of the users mentioned
+    pub const NOTIFICATIONS: &str = "notifications";
+    /// Counting unread messages for users of other hosts
+    pub const READ_MARKERS: &str = "read-markers";
+    /// Pushing profile changes to the hosts caching a user
+    pub const PROFILES: &str = "profiles";
+    /// Presence and typing indicators
+    pub const PRESENCE: &str = "presence";
+    /// Pinned messages
+    pub const PINS: &str = "pins";
+    /// Moving servers between hosts
+    pub const SERVER_MIGRATION: &str = "server-migration";
+
+    /// Capabilities assumed for hosts that predate capability negotiation
+    pub const BASELINE: &[&str] =
+        &[MEMBERSHIPS, SERVERS, CHANNELS, MESSAGES, USER_DELETION];
+
+    /// Everything this crate implements
+    pub const ALL: &[&str] = &[
+        MEMBERSHIPS,
+        SERVERS,
+        CHANNELS,
+        MESSAGES,
+        USER_DELETION,
+        AUDIT_LOG,
+        MODERATION,
+        ROLES,
+        MEMBERSHIP_SYNC,
+        METADATA,
+        CATEGORIES,
+        ARCHIVING,
+        RETENTION,
+        ATTACHMENTS,
+        NOTIFICATIONS,
+        READ_MARKERS,
+        PROFILES,
+        PRESENCE,
+        PINS,
+        SERVER_MIGRATION,
+    ];
+}
+
+/// Discovery document describing how to reach a host's APIs.
+///
+/// Lets a deployment live behind a reverse proxy on a different port or path
+/// than the `host:7000` default.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HostDiscovery {
+    /// Host this document describes (e.g. "example.com")
+    pub host: String,
+    /// Base URL of the client API, also used as the host's token issuer
+    /// (e.g. "https://example.com/runelink")
+    pub api_base_url: String,
+    /// Base URL of the server-to-server federation API
+    pub federation_url: String,
+    /// Protocol versions this host speaks
+    #[serde(default)]
+    pub protocol_versions: Vec<String>,
+    /// Optional federation features this host supports (see
+    /// [`capabilities`]); empty for hosts that predate negotiation
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub software: Option<SoftwareInfo>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SoftwareInfo {
+    pub name: String,
+    pub version: String,
+}
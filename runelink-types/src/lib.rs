@@ -1,11 +1,28 @@
+pub mod archive;
+pub mod audit;
 pub mod auth;
 pub mod channel;
+pub mod discovery;
+pub mod markup;
 pub mod message;
+pub mod moderation;
+pub mod notification;
+pub mod presence;
+pub mod read_marker;
+pub mod retention;
 pub mod server;
 pub mod user;
 
+pub use archive::*;
+pub use audit::*;
 pub use auth::*;
 pub use channel::*;
+pub use discovery::*;
 pub use message::*;
+pub use moderation::*;
+pub use notification::*;
+pub use presence::*;
+pub use read_marker::*;
+pub use retention::*;
 pub use server::*;
 pub use user::*;
@@ -0,0 +1,651 @@
+//! RuneLink markup, the formatting subset understood in message bodies:
+//!
+//! - `**bold**` (or `__bold__`) and `*italics*` (or `_italics_`)
+//! - `` `inline code` `` and fenced code blocks, opened by a line starting
+//!   with ```` ``` ```` (optionally followed by a language) and closed by a
+//!   line that is just ```` ``` ````
+//! - `[text](https://example.com)` links; only `http`, `https` and `mailto`
+//!   links are valid
+//! - `@name@host` (or `<@name@host>`) user mentions and `<#channel-id>`
+//!   channel links
+//! - a backslash before punctuation, such as `\*`, keeps it literal
+//!
+//! Markers that are not closed are kept as text. Clients render the
+//! [`Block`]s returned by [`parse`]; [`to_plain_text`] is the rendering for
+//! places without styling.
+
+use std::{collections::HashMap, fmt};
+
+use uuid::Uuid;
+
+use crate::UserRef;
+
+/// How deeply emphasis and links may nest; deeper markers are kept as text.
+const MAX_DEPTH: usize = 8;
+/// Longest `<...>` mention or channel link recognized, in bytes.
+const MAX_TAG_LEN: usize = 256;
+const LINK_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// Consecutive non-blank lines
+    Paragraph(Vec<Inline>),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    LineBreak,
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Code(String),
+    Link { text: Vec<Inline>, url: String },
+    Mention(UserRef),
+    ChannelLink(Uuid),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkupError {
+    /// A link to something other than a web page or email address
+    UnsafeLink(String),
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkupError::UnsafeLink(url) => write!(
+                f,
+                "Links must start with http://, https:// or mailto: ({url})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+/// Parse a message body into blocks.
+pub fn parse(body: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph = Vec::new();
+    let mut lines = body.lines();
+    while let Some(line) = lines.next() {
+        let fence = line
+            .trim_start()
+            .strip_prefix("```")
+            .filter(|info| !info.contains('`'));
+        if let Some(info) = fence {
+            push_paragraph(&mut blocks, &mut paragraph);
+            let mut code = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim() == "```" {
+                    break;
+                }
+                code.push(line);
+            }
+            let language = info.trim();
+            blocks.push(Block::CodeBlock {
+                language: (!language.is_empty()).then(|| language.to_string()),
+                code: code.join("\n"),
+            });
+        } else if line.trim().is_empty() {
+            push_paragraph(&mut blocks, &mut paragraph);
+        } else {
+            paragraph.push(line);
+        }
+    }
+    push_paragraph(&mut blocks, &mut paragraph);
+    blocks
+}
+
+fn push_paragraph(blocks: &mut Vec<Block>, lines: &mut Vec<&str>) {
+    if !lines.is_empty() {
+        blocks.push(Block::Paragraph(parse_inlines(&lines.join("\n"), 0)));
+        lines.clear();
+    }
+}
+
+fn parse_inlines(src: &str, depth: usize) -> Vec<Inline> {
+    let mut parser = InlineParser {
+        src,
+        depth,
+        unclosed: HashMap::new(),
+        dead_links_before: 0,
+        inlines: Vec::new(),
+        text: String::new(),
+    };
+    parser.run();
+    parser.inlines
+}
+
+struct InlineParser<'a> {
+    src: &'a str,
+    depth: usize,
+    /// Delimiters with no closing match at or after the given position,
+    /// so each is only searched for once
+    unclosed: HashMap<&'a str, usize>,
+    /// `[` before this position can't start a link
+    dead_links_before: usize,
+    inlines: Vec<Inline>,
+    text: String,
+}
+
+impl<'a> InlineParser<'a> {
+    fn run(&mut self) {
+        let mut i = 0;
+        while let Some(c) = self.src[i..].chars().next() {
+            let consumed = match c {
+                '\\' => self.escape(i),
+                '\n' => {
+                    self.push(Inline::LineBreak);
+                    Some(1)
+                }
+                '`' => Some(self.code(i)),
+                '*' | '_' if self.depth < MAX_DEPTH => self.emphasis(i, c),
+                '[' if self.depth < MAX_DEPTH => self.link(i),
+                '<' => self.tag(i),
+                '@' => self.mention(i),
+                _ => None,
+            };
+            match consumed {
+                Some(len) => i += len,
+                None => {
+                    self.text.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+        self.flush_text();
+    }
+
+    fn flush_text(&mut self) {
+        if !self.text.is_empty() {
+            let text = std::mem::take(&mut self.text);
+            self.inlines.push(Inline::Text(text));
+        }
+    }
+
+    fn push(&mut self, inline: Inline) {
+        self.flush_text();
+        self.inlines.push(inline);
+    }
+
+    fn is_unclosed(&self, delim: &str, at: usize) -> bool {
+        self.unclosed.get(delim).is_some_and(|&from| at >= from)
+    }
+
+    fn escape(&mut self, i: usize) -> Option<usize> {
+        let next = self.src[i + 1..].chars().next()?;
+        if !next.is_ascii_punctuation() {
+            return None;
+        }
+        self.text.push(next);
+        Some(1 + next.len_utf8())
+    }
+
+    /// Inline code is delimited by equally long runs of backticks, so code
+    /// containing a backtick can be wrapped in two.
+    fn code(&mut self, i: usize) -> usize {
+        let src = self.src;
+        let len = src[i..].bytes().take_while(|&b| b == b'`').count();
+        let delim = &src[i..i + len];
+        let start = i + len;
+        let mut j = start;
+        let close = loop {
+            if self.is_unclosed(delim, i) {
+                break None;
+            }
+            let Some(offset) = src[j..].find('`') else {
+                self.unclosed.insert(delim, i);
+                break None;
+            };
+            let run_start = j + offset;
+            let run =
+                src[run_start..].bytes().take_while(|&b| b == b'`').count();
+            if run == len {
+                break Some(run_start);
+            }
+            j = run_start + run;
+        };
+        let Some(end) = close else {
+            // The whole run is text, or a shorter part of it could match
+            self.text.push_str(delim);
+            return len;
+        };
+        let mut code = &src[start..end];
+        if code.len() > 2
+            && code.starts_with(' ')
+            && code.ends_with(' ')
+            && !code.trim().is_empty()
+        {
+            code = &code[1..code.len() - 1];
+        }
+        self.push(Inline::Code(code.to_string()));
+        end + len - i
+    }
+
+    fn emphasis(&mut self, i: usize, c: char) -> Option<usize> {
+        let src = self.src;
+        let doubled = src[i + 1..].starts_with(c);
+        let len = if doubled { 2 } else { 1 };
+        let delim = &src[i..i + len];
+        let start = i + len;
+        // `snake_case` is not emphasis
+        if c == '_'
+            && src[..i]
+                .chars()
+                .next_back()
+                .is_some_and(char::is_alphanumeric)
+        {
+            return None;
+        }
+        if src[start..].chars().next().is_none_or(char::is_whitespace) {
+            return None;
+        }
+        let Some(end) = self.find_closing(start, delim) else {
+            self.text.push_str(delim);
+            return Some(len);
+        };
+        let inner = parse_inlines(&src[start..end], self.depth + 1);
+        self.push(if doubled {
+            Inline::Bold(inner)
+        } else {
+            Inline::Italic(inner)
+        });
+        Some(end + len - i)
+    }
+
+    /// Position of the first unescaped `delim` at or after `from` that can
+    /// close emphasis.
+    fn find_closing(&mut self, from: usize, delim: &'a str) -> Option<usize> {
+        if self.is_unclosed(delim, from) {
+            return None;
+        }
+        let src = self.src;
+        let c = delim.as_bytes()[0];
+        let mut j = from;
+        while j < src.len() {
+            let b = src.as_bytes()[j];
+            if b == b'\\' {
+                j += 2;
+                continue;
+            }
+            if b != c {
+                j += 1;
+                continue;
+            }
+            let run = src[j..].bytes().take_while(|&b| b == c).count();
+            // A single delimiter is never closed by part of a double one,
+            // and a run of three closes the inner one of the pair first
+            let usable = if delim.len() == 1 { run != 2 } else { run >= 2 };
+            let preceded_by_space =
+                src[..j].chars().next_back().is_none_or(char::is_whitespace);
+            let followed_by_word = src[j + run..]
+                .chars()
+                .next()
+                .is_some_and(char::is_alphanumeric);
+            if usable
+                && j > from
+                && !preceded_by_space
+                && !(c == b'_' && followed_by_word)
+            {
+                return Some(j + run - delim.len());
+            }
+            j += run;
+        }
+        self.unclosed.insert(delim, from);
+        None
+    }
+
+    fn link(&mut self, i: usize) -> Option<usize> {
+        let src = self.src;
+        if i < self.dead_links_before || self.is_unclosed("]", i) {
+            return None;
+        }
+        let mut j = i + 1;
+        let text_end = loop {
+            match src.as_bytes().get(j) {
+                None => {
+                    self.unclosed.insert("]", i);
+                    return None;
+                }
+                Some(b'\\') => j += 2,
+                Some(b']') => break j,
+                Some(_) => j += 1,
+            }
+        };
+        let url = src[text_end + 1..]
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .map(|(url, _)| url)
+            .filter(|url| {
+                !url.is_empty() && !url.contains(char::is_whitespace)
+            });
+        let Some(url) = url else {
+            // Every `[` up to here would find the same `]`
+            self.dead_links_before = text_end;
+            return None;
+        };
+        let text = parse_inlines(&src[i + 1..text_end], self.depth + 1);
+        self.push(Inline::Link {
+            text,
+            url: url.to_string(),
+        });
+        Some(text_end + 1 + url.len() + 2 - i)
+    }
+
+    /// A mention written without angle brackets, like `@ann@example.com`.
+    /// The host may have a port; a trailing `.` or `:` ends the sentence
+    /// rather than the host.
+    fn mention(&mut self, i: usize) -> Option<usize> {
+        let src = self.src;
+        let is_name_char =
+            |c: char| c.is_ascii_alphanumeric() || "_.-".contains(c);
+        if src[..i].chars().next_back().is_some_and(is_name_char) {
+            return None;
+        }
+        let bounded = |from: usize, accept: &dyn Fn(char) -> bool| {
+            src[from..]
+                .char_indices()
+                .take_while(|&(at, c)| at < MAX_TAG_LEN && accept(c))
+                .last()
+                .map_or(0, |(at, c)| at + c.len_utf8())
+        };
+        let name_start = i + 1;
+        let name_len = bounded(name_start, &is_name_char);
+        let host_start = name_start + name_len + 1;
+        if name_len == 0 || !src[name_start + name_len..].starts_with('@') {
+            return None;
+        }
+        let host_len = bounded(host_start, &|c: char| {
+            c.is_ascii_alphanumeric() || ".-:".contains(c)
+        });
+        let host = src[host_start..host_start + host_len]
+            .trim_end_matches(['.', ':', '-']);
+        if host.is_empty() {
+            return None;
+        }
+        self.push(Inline::Mention(UserRef::new(
+            src[name_start..name_start + name_len].to_string(),
+            host.to_string(),
+        )));
+        Some(host_start + host.len() - i)
+    }
+
+    fn tag(&mut self, i: usize) -> Option<usize> {
+        let rest = &self.src[i + 1..];
+        let end = rest
+            .char_indices()
+            .take_while(|(at, c)| *at < MAX_TAG_LEN && !c.is_whitespace())
+            .find(|(_, c)| *c == '>')?
+            .0;
+        let tag = &rest[..end];
+        let inline = if let Some(subject) = tag.strip_prefix('@') {
+            Inline::Mention(UserRef::parse_subject(subject)?)
+        } else if let Some(id) = tag.strip_prefix('#') {
+            Inline::ChannelLink(Uuid::parse_str(id).ok()?)
+        } else {
+            return None;
+        };
+        self.push(inline);
+        Some(end + 2)
+    }
+}
+
+/// Collect the users mentioned in parsed markup, without duplicates.
+pub fn mentions(blocks: &[Block]) -> Vec<UserRef> {
+    fn collect(inlines: &[Inline], out: &mut Vec<UserRef>) {
+        for inline in inlines {
+            match inline {
+                Inline::Bold(inner) | Inline::Italic(inner) => {
+                    collect(inner, out)
+                }
+                Inline::Link { text, .. } => collect(text, out),
+                Inline::Mention(user_ref) if !out.contains(user_ref) => {
+                    out.push(user_ref.clone())
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for block in blocks {
+        if let Block::Paragraph(inlines) = block {
+            collect(inlines, &mut out);
+        }
+    }
+    out
+}
+
+/// Normalize a message body before it is stored: line breaks become `\n`,
+/// and control characters (other than tabs) and bidirectional overrides,
+/// which could make the text display differently from what it says, are
+/// removed, as are trailing whitespace and leading blank lines.
+pub fn sanitize(body: &str) -> String {
+    let body = body.replace("\r\n", "\n").replace('\r', "\n");
+    let body: String = body
+        .chars()
+        .filter(|&c| {
+            (!c.is_control() || c == '\n' || c == '\t')
+                && !matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+        })
+        .collect();
+    body.trim_end()
+        .trim_start_matches(['\n', ' ', '\t'])
+        .to_string()
+}
+
+/// Check that parsed markup is safe to render.
+pub fn validate(blocks: &[Block]) -> Result<(), MarkupError> {
+    fn check(inlines: &[Inline]) -> Result<(), MarkupError> {
+        for inline in inlines {
+            match inline {
+                Inline::Bold(inner) | Inline::Italic(inner) => check(inner)?,
+                Inline::Link { text, url } => {
+                    let lower = url.to_ascii_lowercase();
+                    if !LINK_SCHEMES.iter().any(|s| lower.starts_with(s)) {
+                        return Err(MarkupError::UnsafeLink(url.clone()));
+                    }
+                    check(text)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+    for block in blocks {
+        if let Block::Paragraph(inlines) = block {
+            check(inlines)?;
+        }
+    }
+    Ok(())
+}
+
+/// Render markup without styling: markers are dropped, links show their
+/// address after their text, and blocks are separated by blank lines.
+pub fn to_plain_text(blocks: &[Block]) -> String {
+    fn push_inlines(out: &mut String, inlines: &[Inline]) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) | Inline::Code(text) => out.push_str(text),
+                Inline::LineBreak => out.push('\n'),
+                Inline::Bold(inner) | Inline::Italic(inner) => {
+                    push_inlines(out, inner)
+                }
+                Inline::Link { text, url } => {
+                    let mut label = String::new();
+                    push_inlines(&mut label, text);
+                    if label == *url {
+                        out.push_str(url);
+                    } else {
+                        out.push_str(&format!("{label} ({url})"));
+                    }
+                }
+                Inline::Mention(user_ref) => {
+                    out.push_str(&format!("@{user_ref}"))
+                }
+                Inline::ChannelLink(id) => out.push_str(&format!("#{id}")),
+            }
+        }
+    }
+    let mut out = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\n\n");
+        }
+        match block {
+            Block::Paragraph(inlines) => push_inlines(&mut out, inlines),
+            Block::CodeBlock { code, .. } => out.push_str(code),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Inline {
+        Inline::Text(s.into())
+    }
+
+    fn inlines(body: &str) -> Vec<Inline> {
+        match parse(body).as_slice() {
+            [Block::Paragraph(inlines)] => inlines.clone(),
+            blocks => panic!("expected one paragraph, got {blocks:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parses_emphasis_and_code() {
+        assert_eq!(
+            inlines("a **b *c*** `d*`"),
+            vec![
+                text("a "),
+                Inline::Bold(vec![text("b "), Inline::Italic(vec![text("c")])]),
+                text(" "),
+                Inline::Code("d*".into()),
+            ]
+        );
+        assert_eq!(
+            inlines("__b__ _i_"),
+            vec![
+                Inline::Bold(vec![text("b")]),
+                text(" "),
+                Inline::Italic(vec![text("i")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keeps_unclosed_and_escaped_markers_as_text() {
+        assert_eq!(inlines("2 * 3 * 4"), vec![text("2 * 3 * 4")]);
+        assert_eq!(inlines("**open"), vec![text("**open")]);
+        assert_eq!(inlines(r"\*not\*"), vec![text("*not*")]);
+        assert_eq!(inlines("snake_case_name"), vec![text("snake_case_name")]);
+        assert_eq!(inlines("`` a`b ``"), vec![Inline::Code("a`b".into())]);
+    }
+
+    #[test]
+    fn test_parses_links_mentions_and_channel_links() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            inlines(&format!(
+                "[**docs**](https://example.com) <@ann@example.com> <#{id}>"
+            )),
+            vec![
+                Inline::Link {
+                    text: vec![Inline::Bold(vec![text("docs")])],
+                    url: "https://example.com".into(),
+                },
+                text(" "),
+                Inline::Mention(UserRef::new(
+                    "ann".into(),
+                    "example.com".into()
+                )),
+                text(" "),
+                Inline::ChannelLink(id),
+            ]
+        );
+        assert_eq!(inlines("[x] <not a tag>"), vec![text("[x] <not a tag>")]);
+    }
+
+    #[test]
+    fn test_parses_bare_mentions() {
+        let ann = || {
+            Inline::Mention(UserRef::new("ann".into(), "example.com".into()))
+        };
+        assert_eq!(
+            inlines("hi @ann@example.com. and @bob@localhost:7000"),
+            vec![
+                text("hi "),
+                ann(),
+                text(". and "),
+                Inline::Mention(UserRef::new(
+                    "bob".into(),
+                    "localhost:7000".into()
+                )),
+            ]
+        );
+        assert_eq!(
+            inlines("mail bob@example.com or @ann"),
+            vec![text("mail bob@example.com or @ann")]
+        );
+        assert_eq!(
+            mentions(&parse("@ann@example.com **<@ann@example.com>**")),
+            vec![UserRef::new("ann".into(), "example.com".into())]
+        );
+    }
+
+    #[test]
+    fn test_parses_blocks() {
+        assert_eq!(
+            parse("one\ntwo\n\n```rust\nlet x = *y;\n```\nthree"),
+            vec![
+                Block::Paragraph(vec![
+                    text("one"),
+                    Inline::LineBreak,
+                    text("two")
+                ]),
+                Block::CodeBlock {
+                    language: Some("rust".into()),
+                    code: "let x = *y;".into(),
+                },
+                Block::Paragraph(vec![text("three")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_survives_pathological_input() {
+        let nested = "*_".repeat(5000) + "x" + &"_*".repeat(5000);
+        assert!(!parse(&nested).is_empty());
+        let unclosed = "**[`<@".repeat(20000);
+        assert!(!parse(&unclosed).is_empty());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_links() {
+        assert!(
+            validate(&parse("[a](https://x.org) [b](mailto:b@x.org)")).is_ok()
+        );
+        assert_eq!(
+            validate(&parse("**[a](javascript:alert(1))**")),
+            Err(MarkupError::UnsafeLink("javascript:alert(1".into()))
+        );
+    }
+
+    #[test]
+    fn test_sanitizes_and_renders_plain_text() {
+        assert_eq!(sanitize("\n\n hi\r\nthere\u{202E}\u{7}  \n"), "hi\nthere");
+        assert_eq!(
+            to_plain_text(&parse(
+                "**hi** <@ann@example.com>, see [docs](https://x.org)"
+            )),
+            "hi @ann@example.com, see docs (https://x.org)"
+        );
+    }
+}
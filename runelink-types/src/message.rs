@@ -1,4 +1,4 @@
-use crate::{UserRef, user::User};
+use crate::{UserRef, markup, user::User};
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -15,24 +15,136 @@ pub struct Message {
     pub created_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
     pub updated_at: OffsetDateTime,
+    /// Set when the message was deleted; its body is then empty
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
+    /// Who deleted the message, when known
+    #[serde(default)]
+    pub deleted_by: Option<UserRef>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+impl Message {
+    /// Whether this is the tombstone of a deleted message.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NewMessage {
     pub author: UserRef,
     pub body: String,
+    /// Uploaded attachments to send with the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachment_ids: Vec<Uuid>,
+}
+
+/// A message to send at a later time.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewScheduledMessage {
+    pub message: NewMessage,
+    #[serde(with = "time::serde::rfc3339")]
+    pub send_at: OffsetDateTime,
+}
+
+/// A message waiting on its author's home host to be sent.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScheduledMessage {
+    pub id: Uuid,
+    pub server_id: Uuid,
+    pub server_host: String,
+    pub channel_id: Uuid,
+    pub author: UserRef,
+    pub body: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub send_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// Failed delivery attempts so far
+    pub attempts: i32,
+    /// Why the last delivery attempt failed
+    pub last_error: Option<String>,
+    /// Set when delivery was given up on
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub failed_at: Option<OffsetDateTime>,
+}
+
+impl ScheduledMessage {
+    /// Whether delivery was given up on.
+    pub fn is_failed(&self) -> bool {
+        self.failed_at.is_some()
+    }
+}
+
+/// A message pinned in its channel.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PinnedMessage {
+    pub message: Message,
+    pub pinned_by: UserRef,
+    #[serde(with = "time::serde::rfc3339")]
+    pub pinned_at: OffsetDateTime,
+}
+
+/// A file attached to a message.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct Attachment {
+    pub id: Uuid,
+    /// `None` until the attachment is sent with a message
+    pub message_id: Option<Uuid>,
+    pub name: String,
+    pub mime_type: String,
+    /// Size in bytes
+    pub size: i64,
+    /// Hex-encoded SHA-256 digest of the contents
+    pub sha256: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+/// A file to upload as an attachment.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AttachmentUpload {
+    pub name: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl fmt::Debug for AttachmentUpload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttachmentUpload")
+            .field("name", &self.name)
+            .field("mime_type", &self.mime_type)
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+impl fmt::Display for Attachment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}, {} bytes)", self.name, self.mime_type, self.size)
+    }
 }
 
 impl fmt::Display for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_deleted() {
+            return write!(f, "[deleted message]");
+        }
         write!(
             f,
             "{}: {}",
             self.author
                 .as_ref()
-                .map(|u| u.name.as_str())
+                .map(|u| u.shown_name())
                 .unwrap_or("anon"),
-            self.body
-        )
+            markup::to_plain_text(&markup::parse(&self.body))
+        )?;
+        for attachment in &self.attachments {
+            write!(f, "\n  [attachment {}] {attachment}", attachment.id)?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::UserRef;
+
+/// A user, or every user of a host, barred from joining and posting in a
+/// server.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ServerBan {
+    pub id: Uuid,
+    pub server_id: Uuid,
+    /// `None` when the whole host is banned
+    pub user_name: Option<String>,
+    pub user_host: String,
+    pub reason: Option<String>,
+    pub created_by_name: Option<String>,
+    pub created_by_host: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// `None` for a permanent ban
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+impl ServerBan {
+    /// The banned user, unless the whole host is banned.
+    pub fn user_ref(&self) -> Option<UserRef> {
+        self.user_name
+            .as_ref()
+            .map(|name| UserRef::new(name.clone(), self.user_host.clone()))
+    }
+
+    /// Who created the ban, if they are still known.
+    pub fn created_by(&self) -> Option<UserRef> {
+        match (&self.created_by_name, &self.created_by_host) {
+            (Some(name), Some(host)) => {
+                Some(UserRef::new(name.clone(), host.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Human-readable description of who is banned.
+    pub fn target(&self) -> String {
+        match self.user_ref() {
+            Some(user_ref) => user_ref.to_string(),
+            None => format!("*@{}", self.user_host),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewServerBan {
+    /// Leave out to ban every user of `user_host`
+    #[serde(default)]
+    pub user_name: Option<String>,
+    pub user_host: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Leave out for a permanent ban
+    #[serde(default)]
+    pub expires_in_hours: Option<i64>,
+    /// Also delete every message the banned users posted in the server
+    #[serde(default)]
+    pub delete_messages: bool,
+}
+
+/// A member temporarily barred from posting in a server.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ServerTimeout {
+    pub server_id: Uuid,
+    pub user_name: String,
+    pub user_host: String,
+    pub reason: Option<String>,
+    pub created_by_name: Option<String>,
+    pub created_by_host: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub until: OffsetDateTime,
+}
+
+impl ServerTimeout {
+    pub fn user_ref(&self) -> UserRef {
+        UserRef::new(self.user_name.clone(), self.user_host.clone())
+    }
+
+    /// Who created the timeout, if they are still known.
+    pub fn created_by(&self) -> Option<UserRef> {
+        match (&self.created_by_name, &self.created_by_host) {
+            (Some(name), Some(host)) => {
+                Some(UserRef::new(name.clone(), host.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewServerTimeout {
+    pub user_ref: UserRef,
+    /// How long the user can't post for
+    pub duration_minutes: i64,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Result of deleting a user's messages in bulk.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeletedMessages {
+    pub deleted: u64,
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::UserRef;
+
+/// A mention of a user in a message, kept in the inbox on the user's home
+/// host whichever host the message was posted on.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Notification {
+    pub id: Uuid,
+    pub server_id: Uuid,
+    pub server_host: String,
+    pub channel_id: Uuid,
+    pub message_id: Uuid,
+    pub author: UserRef,
+    /// The start of the message, as plain text
+    pub excerpt: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub read_at: Option<OffsetDateTime>,
+}
+
+impl Notification {
+    pub fn is_read(&self) -> bool {
+        self.read_at.is_some()
+    }
+}
+
+impl fmt::Display for Notification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} mentioned you: {}", self.author, self.excerpt)
+    }
+}
+
+/// A mention relayed by the host of a server to the home host of the user
+/// mentioned. Only the server's host may relay it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewNotification {
+    /// Who was mentioned
+    pub user: UserRef,
+    pub server_id: Uuid,
+    pub server_host: String,
+    pub channel_id: Uuid,
+    pub message_id: Uuid,
+    pub author: UserRef,
+    pub excerpt: String,
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::UserRef;
+
+/// Whether a user is around. Clients report `online` or `idle` regularly;
+/// users whose client stops reporting are `offline` after a short while.
+#[derive(
+    Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceStatus {
+    Online,
+    Idle,
+    #[default]
+    Offline,
+}
+
+impl fmt::Display for PresenceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresenceStatus::Online => write!(f, "online"),
+            PresenceStatus::Idle => write!(f, "idle"),
+            PresenceStatus::Offline => write!(f, "offline"),
+        }
+    }
+}
+
+/// A presence report from a client to its home host, or from the home host
+/// to the hosts of the user's servers.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PresenceUpdate {
+    pub status: PresenceStatus,
+}
+
+/// The presence of one member of a server.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MemberPresence {
+    pub user: UserRef,
+    pub status: PresenceStatus,
+}
+
+impl fmt::Display for MemberPresence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.user, self.status)
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// The last message a user has read in a channel. Markers are kept on the
+/// user's home host, also for channels of servers on other hosts.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ReadMarker {
+    pub server_id: Uuid,
+    pub server_host: String,
+    pub channel_id: Uuid,
+    /// The last message read
+    pub message_id: Uuid,
+    /// When the last message read was sent; later messages are unread
+    #[serde(with = "time::serde::rfc3339")]
+    pub message_created_at: OffsetDateTime,
+    /// When the marker was last moved
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Marks a channel as read up to and including a message.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewReadMarker {
+    pub message_id: Uuid,
+}
+
+/// How many messages of a channel a user has not read yet.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChannelUnread {
+    pub server_id: Uuid,
+    pub server_host: String,
+    pub channel_id: Uuid,
+    /// Messages by others sent after the last message read; every message by
+    /// others if the channel was never read
+    pub unread_count: i64,
+    #[serde(default)]
+    pub last_read_message_id: Option<Uuid>,
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::server::deserialize_some;
+
+/// Limits on how long a server or channel keeps its messages. The host
+/// purges messages that fall outside either limit. A channel's policy
+/// replaces its server's.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct RetentionPolicy {
+    pub server_id: Uuid,
+    /// `None` for the server-wide policy
+    pub channel_id: Option<Uuid>,
+    /// Messages older than this many days are purged
+    pub max_age_days: Option<i32>,
+    /// Only this many of the newest messages of each channel are kept
+    pub max_messages: Option<i32>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+impl RetentionPolicy {
+    /// Human-readable summary of the limits.
+    pub fn describe(&self) -> String {
+        let mut limits = Vec::new();
+        if let Some(days) = self.max_age_days {
+            limits.push(format!("purge messages after {days} day(s)"));
+        }
+        if let Some(count) = self.max_messages {
+            limits.push(format!("keep the last {count} message(s)"));
+        }
+        if limits.is_empty() {
+            "keep messages forever".into()
+        } else {
+            limits.join(", ")
+        }
+    }
+}
+
+/// Changes to a retention policy, creating it if needed. Fields left out
+/// stay as they are.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetentionPolicyUpdate {
+    /// `Some(None)` (JSON `null`) lifts the age limit
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_age_days: Option<Option<i32>>,
+    /// `Some(None)` (JSON `null`) lifts the message count limit
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_messages: Option<Option<i32>>,
+}
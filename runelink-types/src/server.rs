@@ -1,7 +1,10 @@
 use crate::user::User;
-use crate::{UserRef, channel::Channel};
+use crate::{
+    UserRef,
+    channel::{Channel, ChannelCategory},
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
 use time::OffsetDateTime;
 use uuid::Uuid;
@@ -25,10 +28,89 @@ pub struct NewServer {
     pub description: Option<String>,
 }
 
+/// Changes to a server's metadata. Fields left out stay as they are.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServerUpdate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// `Some(None)` (JSON `null`) clears the description
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<Option<String>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewServerMigration {
+    /// The host to move the server to
+    pub to_host: String,
+}
+
+/// A server that moved from one host to another. The old host keeps it so
+/// requests for the server can be pointed to its new host.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ServerMigration {
+    pub server_id: Uuid,
+    pub from_host: String,
+    pub to_host: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub migrated_at: OffsetDateTime,
+}
+
+/// Deserialize a present field as `Some`, so that with `#[serde(default)]`
+/// a missing field (`None`) can be told apart from an explicit `null`
+/// (`Some(None)`).
+pub(crate) fn deserialize_some<'de, T, D>(
+    deserializer: D,
+) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ServerWithChannels {
     pub server: Server,
+    /// Every channel of the server, in order
     pub channels: Vec<Channel>,
+    /// The server's categories, in order
+    #[serde(default)]
+    pub categories: Vec<ChannelCategory>,
+}
+
+impl ServerWithChannels {
+    /// Group the channels by category: uncategorized channels first (under
+    /// `None`), then each category in order, including empty ones.
+    pub fn grouped(&self) -> Vec<(Option<&ChannelCategory>, Vec<&Channel>)> {
+        let mut groups = Vec::with_capacity(self.categories.len() + 1);
+        // Channels whose category we don't know about count as uncategorized
+        let uncategorized: Vec<&Channel> = self
+            .channels
+            .iter()
+            .filter(|c| {
+                c.category_id.is_none_or(|id| {
+                    !self.categories.iter().any(|cat| cat.id == id)
+                })
+            })
+            .collect();
+        if !uncategorized.is_empty() {
+            groups.push((None, uncategorized));
+        }
+        for category in &self.categories {
+            let channels = self
+                .channels
+                .iter()
+                .filter(|c| c.category_id == Some(category.id))
+                .collect();
+            groups.push((Some(category), channels));
+        }
+        groups
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -41,6 +123,8 @@ pub struct ServerWithChannels {
 pub enum ServerRole {
     Member,
     Admin,
+    /// The one admin who can hand the server over to someone else
+    Owner,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -79,6 +163,19 @@ pub struct ServerMember {
     pub updated_at: OffsetDateTime,
 }
 
+/// Promote or demote a member. Ownership changes hands through
+/// [`OwnershipTransfer`] instead.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServerRoleUpdate {
+    pub role: ServerRole,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OwnershipTransfer {
+    /// The member who becomes the new owner; the old owner stays an admin
+    pub new_owner: UserRef,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NewServerMembership {
     pub user_ref: UserRef,
@@ -87,6 +184,24 @@ pub struct NewServerMembership {
     pub role: ServerRole,
 }
 
+impl ServerRole {
+    /// Whether the role carries admin rights (owners are admins too).
+    pub fn is_admin(self) -> bool {
+        matches!(self, ServerRole::Admin | ServerRole::Owner)
+    }
+}
+
+impl fmt::Display for ServerRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ServerRole::Member => "member",
+            ServerRole::Admin => "admin",
+            ServerRole::Owner => "owner",
+        };
+        write!(f, "{name}")
+    }
+}
+
 impl Server {
     pub fn verbose(&self) -> String {
         format!("{} ({})", self.title, self.id)
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use time::OffsetDateTime;
 
+use crate::server::deserialize_some;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 #[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
@@ -26,6 +28,17 @@ pub struct User {
     pub updated_at: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339::option")]
     pub synced_at: Option<OffsetDateTime>,
+    /// Name to show instead of the username
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub bio: Option<String>,
+    /// Address of the user's avatar image
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    /// Custom status text, such as "on vacation"
+    #[serde(default)]
+    pub status_text: Option<String>,
 }
 
 /// User identity: (name, host) pair used for identification and authorization.
@@ -35,6 +48,36 @@ pub struct UserRef {
     pub host: String,
 }
 
+/// Changes to a user's profile. Fields left out stay as they are;
+/// `Some(None)` (JSON `null`) clears a field.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UserProfileUpdate {
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub display_name: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub bio: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub avatar_url: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_some",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub status_text: Option<Option<String>>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NewUser {
     pub name: String,
@@ -49,6 +92,11 @@ impl User {
             host: self.host.clone(),
         }
     }
+
+    /// The display name if the user set one, otherwise the username.
+    pub fn shown_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
 }
 
 impl fmt::Display for User {